@@ -4,6 +4,7 @@ use rg3d::{
         algebra::{UnitQuaternion, Vector2, Vector3},
         math::aabb::AxisAlignedBoundingBox,
         pool::Handle,
+        visitor::{Visit, VisitResult, Visitor},
     },
     gui::message::{KeyCode, MouseButton},
     scene::{
@@ -16,6 +17,38 @@ use std::{
     hash::{Hash, Hasher},
 };
 
+/// Editor camera pose, it is stored in scene file in editor-only region, so the
+/// editor could restore viewpoint when the scene is opened again.
+#[derive(Default, Copy, Clone, Debug, PartialEq)]
+pub struct CameraPose {
+    pub position: Vector3<f32>,
+    pub yaw: f32,
+    pub pitch: f32,
+}
+
+impl Visit for CameraPose {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.position.visit("Position", visitor)?;
+        self.yaw.visit("Yaw", visitor)?;
+        self.pitch.visit("Pitch", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+impl CameraPose {
+    pub const REGION_NAME: &'static str = "EditorCameraPose";
+
+    /// Reads pose from a saved scene, scenes saved without the pose will give `None`.
+    pub fn load(visitor: &mut Visitor) -> Option<Self> {
+        let mut pose = Self::default();
+        pose.visit(Self::REGION_NAME, visitor).ok()?;
+        Some(pose)
+    }
+}
+
 pub struct CameraController {
     pub pivot: Handle<Node>,
     pub camera: Handle<Node>,
@@ -77,6 +110,22 @@ impl CameraController {
         }
     }
 
+    pub fn pose(&self, graph: &Graph) -> CameraPose {
+        CameraPose {
+            position: **graph[self.pivot].local_transform().position(),
+            yaw: self.yaw,
+            pitch: self.pitch,
+        }
+    }
+
+    pub fn set_pose(&mut self, graph: &mut Graph, pose: CameraPose) {
+        graph[self.pivot]
+            .local_transform_mut()
+            .set_position(pose.position);
+        self.yaw = pose.yaw;
+        self.pitch = pose.pitch;
+    }
+
     pub fn on_mouse_move(&mut self, delta: Vector2<f32>) {
         if self.rotate {
             self.yaw -= delta.x as f32 * 0.01;
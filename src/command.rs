@@ -30,12 +30,24 @@ pub trait Command<'a> {
     fn execute(&mut self, context: &mut Self::Context);
     fn revert(&mut self, context: &mut Self::Context);
     fn finalize(&mut self, _: &mut Self::Context) {}
+
+    /// Merges `next` command, which was executed right after this one, into this one, so they
+    /// are undone at once (for example a color that is changed continuously while dragging).
+    /// Returns `false` if commands can't be merged, `next` is put in the stack as usual then.
+    fn merge(&mut self, _next: &mut Self, _context: &mut Self::Context) -> bool {
+        false
+    }
 }
 
 pub struct CommandStack<C> {
     commands: Vec<C>,
     top: Option<usize>,
     debug: bool,
+    max_depth: Option<usize>,
+    // Executed commands of open transaction, see `begin_transaction`.
+    transaction: Vec<C>,
+    // Index of first command of every open (nested) transaction in `transaction`.
+    transaction_starts: Vec<usize>,
 }
 
 impl<C> CommandStack<C> {
@@ -44,10 +56,70 @@ impl<C> CommandStack<C> {
             commands: Default::default(),
             top: None,
             debug,
+            max_depth: None,
+            transaction: Default::default(),
+            transaction_starts: Default::default(),
         }
     }
 
+    /// Limits amount of commands in the stack, `None` means no limit. Oldest commands are
+    /// finalized and dropped when the limit is exceeded.
+    pub fn with_max_depth(mut self, max_depth: Option<usize>) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    pub fn max_depth(&self) -> Option<usize> {
+        self.max_depth
+    }
+
+    pub fn top_command_name<'a, Ctx>(&mut self, context: &Ctx) -> Option<String>
+    where
+        C: Command<'a, Context = Ctx>,
+    {
+        let top = self.top?;
+        self.commands.get_mut(top).map(|c| c.name(context))
+    }
+
     pub fn do_command<'a, Ctx>(&mut self, mut command: C, mut context: Ctx)
+    where
+        C: Command<'a, Context = Ctx> + Debug,
+    {
+        // Only a command on top of the stack which was not undone can absorb the new one.
+        let merge_target = if self.is_in_transaction() {
+            None
+        } else {
+            self.top.filter(|&top| top + 1 == self.commands.len())
+        };
+
+        if !self.is_in_transaction() {
+            self.advance_top(&mut context);
+        }
+
+        if self.debug {
+            println!("Executing command {:?}", command);
+        }
+
+        command.execute(&mut context);
+
+        if let Some(merge_target) = merge_target {
+            if self.commands[merge_target].merge(&mut command, &mut context) {
+                if self.debug {
+                    println!("Merged command {:?}", command);
+                }
+                self.top = Some(merge_target);
+                return;
+            }
+        }
+
+        if self.is_in_transaction() {
+            self.transaction.push(command);
+        } else {
+            self.push_executed(command, &mut context);
+        }
+    }
+
+    fn advance_top<'a, Ctx>(&mut self, context: &mut Ctx)
     where
         C: Command<'a, Context = Ctx> + Debug,
     {
@@ -66,18 +138,90 @@ impl<C> CommandStack<C> {
                     if self.debug {
                         println!("Finalizing command {:?}", dropped_command);
                     }
-                    dropped_command.finalize(&mut context);
+                    dropped_command.finalize(context);
                 }
             }
         }
+    }
 
-        if self.debug {
-            println!("Executing command {:?}", command);
+    fn push_executed<'a, Ctx>(&mut self, command: C, context: &mut Ctx)
+    where
+        C: Command<'a, Context = Ctx> + Debug,
+    {
+        self.commands.push(command);
+
+        // Evict oldest commands, they must be finalized because they can hold
+        // tickets of reserved entries.
+        if let Some(max_depth) = self.max_depth {
+            while self.commands.len() > max_depth.max(1) {
+                let mut evicted_command = self.commands.remove(0);
+                if self.debug {
+                    println!("Finalizing evicted command {:?}", evicted_command);
+                }
+                evicted_command.finalize(context);
+                if let Some(top) = self.top.as_mut() {
+                    *top -= 1;
+                }
+            }
         }
+    }
 
-        command.execute(&mut context);
+    /// Starts a transaction: commands done until the transaction is committed are executed
+    /// as usual, but they're put in the stack as a single command when the transaction is
+    /// committed, so they're undone at once. Transactions could be nested, nested ones are
+    /// flattened into the outermost transaction when committed.
+    pub fn begin_transaction(&mut self) {
+        self.transaction_starts.push(self.transaction.len());
+    }
 
-        self.commands.push(command);
+    pub fn is_in_transaction(&self) -> bool {
+        !self.transaction_starts.is_empty()
+    }
+
+    /// Commits innermost transaction. When outermost transaction is committed, its commands
+    /// are combined into a single command by `make_group` and put in the stack (without
+    /// execution, they're executed already). Empty transactions leave the stack untouched.
+    pub fn commit_transaction<'a, Ctx, F>(&mut self, mut context: Ctx, make_group: F)
+    where
+        C: Command<'a, Context = Ctx> + Debug,
+        F: FnOnce(Vec<C>) -> C,
+    {
+        if self.transaction_starts.pop().is_some()
+            && self.transaction_starts.is_empty()
+            && !self.transaction.is_empty()
+        {
+            let group = make_group(self.transaction.drain(..).collect());
+            if self.debug {
+                println!("Committing transaction {:?}", group);
+            }
+            self.advance_top(&mut context);
+            self.push_executed(group, &mut context);
+        }
+    }
+
+    /// Ends innermost transaction without a context, when there is nothing the commands could
+    /// be committed to or reverted in (the scene was closed). Commands of the transaction are
+    /// dropped as is.
+    pub fn discard_transaction(&mut self) {
+        if let Some(start) = self.transaction_starts.pop() {
+            self.transaction.truncate(start);
+        }
+    }
+
+    /// Cancels innermost transaction, every command done in it is reverted and dropped.
+    pub fn cancel_transaction<'a, Ctx>(&mut self, mut context: Ctx)
+    where
+        C: Command<'a, Context = Ctx> + Debug,
+    {
+        if let Some(start) = self.transaction_starts.pop() {
+            for mut command in self.transaction.drain(start..).rev() {
+                if self.debug {
+                    println!("Cancelling command {:?}", command);
+                }
+                command.revert(&mut context);
+                command.finalize(&mut context);
+            }
+        }
     }
 
     pub fn undo<'a, Ctx>(&mut self, mut context: Ctx)
@@ -135,12 +279,23 @@ impl<C> CommandStack<C> {
     where
         C: Command<'a, Context = Ctx> + Debug,
     {
+        // Commands of open transaction are executed, but they're not in the stack, so they
+        // must be reverted to not leave their changes without a way to undo them.
+        for mut command in self.transaction.drain(..).rev() {
+            if self.debug {
+                println!("Cancelling command {:?}", command);
+            }
+            command.revert(&mut context);
+            command.finalize(&mut context);
+        }
+        self.transaction_starts.clear();
         for mut dropped_command in self.commands.drain(..) {
             if self.debug {
                 println!("Finalizing command {:?}", dropped_command);
             }
             dropped_command.finalize(&mut context);
         }
+        self.top = None;
     }
 }
 
@@ -318,3 +473,230 @@ impl CommandStackViewer {
         );
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::command::{Command, CommandStack};
+
+    #[derive(Debug)]
+    struct TestCommand {
+        name: String,
+        children: Vec<TestCommand>,
+    }
+
+    impl TestCommand {
+        fn new(name: &str) -> Self {
+            Self {
+                name: name.to_owned(),
+                children: Vec::new(),
+            }
+        }
+
+        fn group(name: String, children: Vec<TestCommand>) -> Self {
+            Self { name, children }
+        }
+    }
+
+    impl<'a> Command<'a> for TestCommand {
+        type Context = &'a mut Vec<String>;
+
+        fn name(&mut self, _context: &Self::Context) -> String {
+            self.name.clone()
+        }
+
+        fn execute(&mut self, context: &mut Self::Context) {
+            if self.children.is_empty() {
+                context.push(format!("do {}", self.name));
+            }
+            for child in self.children.iter_mut() {
+                child.execute(context);
+            }
+        }
+
+        fn revert(&mut self, context: &mut Self::Context) {
+            if self.children.is_empty() {
+                context.push(format!("revert {}", self.name));
+            }
+            for child in self.children.iter_mut().rev() {
+                child.revert(context);
+            }
+        }
+
+        fn finalize(&mut self, context: &mut Self::Context) {
+            if self.children.is_empty() {
+                context.push(format!("finalize {}", self.name));
+            }
+            for child in self.children.iter_mut() {
+                child.finalize(context);
+            }
+        }
+    }
+
+    fn take(log: &mut Vec<String>) -> Vec<String> {
+        std::mem::take(log)
+    }
+
+    #[test]
+    fn test_commit_transaction() {
+        let mut log = Vec::new();
+        let mut stack = CommandStack::new(false);
+
+        stack.begin_transaction();
+        stack.do_command(TestCommand::new("a"), &mut log);
+        stack.do_command(TestCommand::new("b"), &mut log);
+        assert!(stack.commands.is_empty());
+        stack.commit_transaction(&mut log, |commands| {
+            TestCommand::group("Extrude".to_owned(), commands)
+        });
+
+        assert!(!stack.is_in_transaction());
+        assert_eq!(take(&mut log), ["do a", "do b"]);
+        assert_eq!(stack.commands.len(), 1);
+        assert_eq!(stack.commands[0].name, "Extrude");
+
+        stack.undo(&mut log);
+        assert_eq!(take(&mut log), ["revert b", "revert a"]);
+        stack.redo(&mut log);
+        assert_eq!(take(&mut log), ["do a", "do b"]);
+    }
+
+    #[test]
+    fn test_cancel_transaction() {
+        let mut log = Vec::new();
+        let mut stack = CommandStack::new(false);
+
+        stack.do_command(TestCommand::new("x"), &mut log);
+        stack.begin_transaction();
+        stack.do_command(TestCommand::new("a"), &mut log);
+        stack.do_command(TestCommand::new("b"), &mut log);
+        stack.cancel_transaction(&mut log);
+
+        assert!(!stack.is_in_transaction());
+        assert_eq!(
+            take(&mut log),
+            [
+                "do x",
+                "do a",
+                "do b",
+                "revert b",
+                "finalize b",
+                "revert a",
+                "finalize a",
+            ]
+        );
+        assert_eq!(stack.commands.len(), 1);
+
+        // Commands before the transaction are not affected.
+        stack.undo(&mut log);
+        assert_eq!(take(&mut log), ["revert x"]);
+    }
+
+    #[test]
+    fn test_nested_transactions() {
+        let mut log = Vec::new();
+        let mut stack = CommandStack::new(false);
+
+        stack.begin_transaction();
+        stack.do_command(TestCommand::new("a"), &mut log);
+
+        stack.begin_transaction();
+        stack.do_command(TestCommand::new("b"), &mut log);
+        stack.commit_transaction(&mut log, |commands| {
+            TestCommand::group("Inner".to_owned(), commands)
+        });
+        // Nested transaction is flattened into outer one.
+        assert!(stack.is_in_transaction());
+        assert!(stack.commands.is_empty());
+
+        stack.begin_transaction();
+        stack.do_command(TestCommand::new("c"), &mut log);
+        stack.cancel_transaction(&mut log);
+
+        stack.commit_transaction(&mut log, |commands| {
+            TestCommand::group("Outer".to_owned(), commands)
+        });
+
+        assert!(!stack.is_in_transaction());
+        assert_eq!(
+            take(&mut log),
+            ["do a", "do b", "do c", "revert c", "finalize c"]
+        );
+        assert_eq!(stack.commands.len(), 1);
+        assert_eq!(stack.commands[0].name, "Outer");
+        assert_eq!(stack.commands[0].children.len(), 2);
+
+        stack.undo(&mut log);
+        assert_eq!(take(&mut log), ["revert b", "revert a"]);
+    }
+
+    #[test]
+    fn test_discard_and_clear_transaction() {
+        let mut log = Vec::new();
+        let mut stack = CommandStack::new(false);
+
+        stack.begin_transaction();
+        stack.do_command(TestCommand::new("a"), &mut log);
+        stack.discard_transaction();
+        assert!(!stack.is_in_transaction());
+        assert!(stack.transaction.is_empty());
+
+        stack.begin_transaction();
+        stack.do_command(TestCommand::new("b"), &mut log);
+        stack.clear(&mut log);
+        assert!(!stack.is_in_transaction());
+        assert_eq!(take(&mut log), ["do a", "do b", "revert b", "finalize b"]);
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut log = Vec::new();
+        let mut stack = CommandStack::new(false);
+
+        stack.do_command(TestCommand::new("a"), &mut log);
+        stack.do_command(TestCommand::new("b"), &mut log);
+        assert_eq!(take(&mut log), ["do a", "do b"]);
+
+        stack.undo(&mut log);
+        stack.undo(&mut log);
+        // Nothing left to undo.
+        stack.undo(&mut log);
+        assert_eq!(take(&mut log), ["revert b", "revert a"]);
+
+        stack.redo(&mut log);
+        assert_eq!(take(&mut log), ["do a"]);
+
+        // New command drops (and finalizes) everything that could be redone.
+        stack.do_command(TestCommand::new("c"), &mut log);
+        stack.redo(&mut log);
+        assert_eq!(take(&mut log), ["finalize b", "do c"]);
+        assert_eq!(stack.commands.len(), 2);
+        assert_eq!(stack.commands[1].name, "c");
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let mut log = Vec::new();
+        let mut stack = CommandStack::new(false).with_max_depth(Some(2));
+
+        stack.do_command(TestCommand::new("a"), &mut log);
+        stack.do_command(TestCommand::new("b"), &mut log);
+        assert_eq!(take(&mut log), ["do a", "do b"]);
+
+        // Oldest command is finalized when it falls out of the stack, so it can forget
+        // its tickets.
+        stack.do_command(TestCommand::new("c"), &mut log);
+        assert_eq!(take(&mut log), ["do c", "finalize a"]);
+        assert_eq!(stack.commands.len(), 2);
+        assert_eq!(stack.commands[0].name, "b");
+
+        // Evicted command can't be undone.
+        stack.undo(&mut log);
+        stack.undo(&mut log);
+        stack.undo(&mut log);
+        assert_eq!(take(&mut log), ["revert c", "revert b"]);
+
+        stack.redo(&mut log);
+        stack.redo(&mut log);
+        assert_eq!(take(&mut log), ["do b", "do c"]);
+    }
+}
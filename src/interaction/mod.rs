@@ -2,8 +2,9 @@ use crate::{
     gui::UiNode,
     interaction::navmesh::EditNavmeshMode,
     scene::{
-        ChangeSelectionCommand, CommandGroup, EditorScene, GraphSelection, MoveNodeCommand,
-        RotateNodeCommand, ScaleNodeCommand, SceneCommand, Selection,
+        rotate_in_space, ChangeSelectionCommand, CommandGroup, EditorScene, GraphSelection,
+        MoveNodeCommand, RotateNodeCommand, ScaleNodeCommand, SceneCommand, Selection,
+        TransformSpace,
     },
     GameEngine, Message,
 };
@@ -430,8 +431,14 @@ impl MoveGizmo {
         graph: &mut Graph,
         selection: &GraphSelection,
         scale: Vector3<f32>,
+        space: TransformSpace,
     ) {
         if let Some((rotation, position)) = selection.global_rotation_position(graph) {
+            // Gizmo is aligned with world axes in world space.
+            let rotation = match space {
+                TransformSpace::Local => rotation,
+                TransformSpace::World => UnitQuaternion::identity(),
+            };
             graph[self.origin]
                 .set_visibility(true)
                 .local_transform_mut()
@@ -455,6 +462,7 @@ pub struct MoveInteractionMode {
     move_gizmo: MoveGizmo,
     interacting: bool,
     message_sender: Sender<Message>,
+    pub transform_space: TransformSpace,
 }
 
 impl MoveInteractionMode {
@@ -468,6 +476,7 @@ impl MoveInteractionMode {
             move_gizmo: MoveGizmo::new(editor_scene, engine),
             interacting: false,
             message_sender,
+            transform_space: Default::default(),
         }
     }
 }
@@ -612,7 +621,8 @@ impl InteractionModeTrait for MoveInteractionMode {
             if !editor_scene.selection.is_empty() {
                 let graph = &mut engine.scenes[editor_scene.scene].graph;
                 let scale = calculate_gizmo_distance_scaling(graph, camera, self.move_gizmo.origin);
-                self.move_gizmo.sync_transform(graph, selection, scale);
+                self.move_gizmo
+                    .sync_transform(graph, selection, scale, self.transform_space);
                 self.move_gizmo.set_visible(graph, true);
             } else {
                 let graph = &mut engine.scenes[editor_scene.scene].graph;
@@ -1289,8 +1299,14 @@ impl RotationGizmo {
         graph: &mut Graph,
         selection: &GraphSelection,
         scale: Vector3<f32>,
+        space: TransformSpace,
     ) {
         if let Some((rotation, position)) = selection.global_rotation_position(graph) {
+            // Gizmo is aligned with world axes in world space.
+            let rotation = match space {
+                TransformSpace::Local => rotation,
+                TransformSpace::World => UnitQuaternion::identity(),
+            };
             graph[self.origin]
                 .set_visibility(true)
                 .local_transform_mut()
@@ -1310,6 +1326,7 @@ pub struct RotateInteractionMode {
     rotation_gizmo: RotationGizmo,
     interacting: bool,
     message_sender: Sender<Message>,
+    pub transform_space: TransformSpace,
 }
 
 impl RotateInteractionMode {
@@ -1323,6 +1340,7 @@ impl RotateInteractionMode {
             rotation_gizmo: RotationGizmo::new(editor_scene, engine),
             interacting: false,
             message_sender,
+            transform_space: Default::default(),
         }
     }
 }
@@ -1451,11 +1469,17 @@ impl InteractionModeTrait for RotateInteractionMode {
                     engine,
                     frame_size,
                 );
+                let graph = &mut engine.scenes[editor_scene.scene].graph;
                 for &node in selection.nodes().iter() {
-                    let transform =
-                        engine.scenes[editor_scene.scene].graph[node].local_transform_mut();
-                    let rotation = **transform.rotation();
-                    transform.set_rotation(rotation * rotation_delta);
+                    let rotation = **graph[node].local_transform().rotation();
+                    let rotation = rotate_in_space(
+                        graph,
+                        node,
+                        rotation,
+                        rotation_delta,
+                        self.transform_space,
+                    );
+                    graph[node].local_transform_mut().set_rotation(rotation);
                 }
             }
         }
@@ -1472,7 +1496,8 @@ impl InteractionModeTrait for RotateInteractionMode {
                 let graph = &mut engine.scenes[editor_scene.scene].graph;
                 let scale =
                     calculate_gizmo_distance_scaling(graph, camera, self.rotation_gizmo.origin);
-                self.rotation_gizmo.sync_transform(graph, selection, scale);
+                self.rotation_gizmo
+                    .sync_transform(graph, selection, scale, self.transform_space);
                 self.rotation_gizmo.set_visible(graph, true);
             } else {
                 let graph = &mut engine.scenes[editor_scene.scene].graph;
@@ -1,5 +1,7 @@
 use rg3d::core::algebra::Vector3;
+use rg3d::core::math::aabb::AxisAlignedBoundingBox;
 use rg3d::core::pool::{Handle, Pool};
+use std::collections::{hash_map::Entry, HashMap};
 
 #[derive(Debug, Clone)]
 pub struct NavmeshVertex {
@@ -11,6 +13,8 @@ pub struct NavmeshTriangle {
     pub a: Handle<NavmeshVertex>,
     pub b: Handle<NavmeshVertex>,
     pub c: Handle<NavmeshVertex>,
+    /// Gameplay tag of the triangle (water, lava, etc.), meaning of the value is up to game.
+    pub region: u8,
 }
 
 #[derive(PartialEq, Copy, Clone, Debug, Eq)]
@@ -73,12 +77,303 @@ impl Navmesh {
 
         let mut triangles = Pool::new();
 
-        let _ = triangles.spawn(NavmeshTriangle { a, b, c });
-        let _ = triangles.spawn(NavmeshTriangle { a, b: c, c: d });
+        let _ = triangles.spawn(NavmeshTriangle { a, b, c, region: 0 });
+        let _ = triangles.spawn(NavmeshTriangle {
+            a,
+            b: c,
+            c: d,
+            region: 0,
+        });
+
+        Self {
+            vertices,
+            triangles,
+        }
+    }
+
+    /// Converts engine's navmesh to editor's representation.
+    pub fn from_engine_navmesh(navmesh: &rg3d::utils::navmesh::Navmesh) -> Self {
+        Self {
+            vertices: navmesh
+                .vertices()
+                .iter()
+                .map(|vertex| NavmeshVertex {
+                    position: vertex.position,
+                })
+                .collect(),
+            triangles: navmesh
+                .triangles()
+                .iter()
+                .map(|triangle| NavmeshTriangle {
+                    a: Handle::new(triangle[0], 1),
+                    b: Handle::new(triangle[1], 1),
+                    c: Handle::new(triangle[2], 1),
+                    region: 0,
+                })
+                .collect(),
+        }
+    }
+
+    /// Creates a copy of the navmesh with densely packed vertices, triangles of the copy
+    /// refer to copied vertices.
+    pub fn deep_clone(&self) -> Self {
+        let mut vertices = Pool::new();
+        let mut old_to_new = HashMap::new();
+        for (handle, vertex) in self.vertices.pair_iter() {
+            old_to_new.insert(handle, vertices.spawn(vertex.clone()));
+        }
+
+        let mut triangles = Pool::new();
+        for triangle in self.triangles.iter() {
+            let _ = triangles.spawn(NavmeshTriangle {
+                a: old_to_new[&triangle.a],
+                b: old_to_new[&triangle.b],
+                c: old_to_new[&triangle.c],
+                region: triangle.region,
+            });
+        }
 
         Self {
             vertices,
             triangles,
         }
     }
+
+    /// Assigns regions to triangles in order of their appearance in the pool, this is the
+    /// same order in which triangles are saved.
+    pub fn set_regions(&mut self, regions: &[u8]) {
+        for (triangle, &region) in self.triangles.iter_mut().zip(regions.iter()) {
+            triangle.region = region;
+        }
+    }
+
+    /// Returns walkable area of the navmesh. Triangles that refer to missing vertices are
+    /// skipped.
+    pub fn total_area(&self) -> f32 {
+        self.triangles
+            .iter()
+            .filter_map(|triangle| {
+                let a = self.vertices.try_borrow(triangle.a)?.position;
+                let b = self.vertices.try_borrow(triangle.b)?.position;
+                let c = self.vertices.try_borrow(triangle.c)?.position;
+                Some((b - a).cross(&(c - a)).norm() * 0.5)
+            })
+            .sum()
+    }
+
+    /// Returns bounding box of all vertices of the navmesh, empty navmesh gives default
+    /// bounding box.
+    pub fn bounds(&self) -> AxisAlignedBoundingBox {
+        let points = self
+            .vertices
+            .iter()
+            .map(|vertex| vertex.position)
+            .collect::<Vec<_>>();
+        if points.is_empty() {
+            AxisAlignedBoundingBox::default()
+        } else {
+            AxisAlignedBoundingBox::from_points(&points)
+        }
+    }
+
+    /// Returns true if every triangle of the navmesh could be reached from any other triangle.
+    pub fn is_connected(&self) -> bool {
+        self.connected_components().len() <= 1
+    }
+
+    /// Splits triangles into islands. Triangles are connected only if they share an edge,
+    /// triangles that share a single vertex are not connected because agents can't pass
+    /// through a point.
+    pub fn connected_components(&self) -> Vec<Vec<Handle<NavmeshTriangle>>> {
+        fn find(parents: &mut [usize], mut i: usize) -> usize {
+            while parents[i] != i {
+                parents[i] = parents[parents[i]];
+                i = parents[i];
+            }
+            i
+        }
+
+        let triangles = self.triangles.pair_iter().collect::<Vec<_>>();
+        let mut parents = (0..triangles.len()).collect::<Vec<_>>();
+
+        // Map each edge (with sorted vertices) to triangles that use it.
+        let mut edge_owners = HashMap::new();
+        for (i, (_, triangle)) in triangles.iter().enumerate() {
+            for edge in triangle.edges().iter() {
+                let key = if edge.begin.index() < edge.end.index() {
+                    (edge.begin, edge.end)
+                } else {
+                    (edge.end, edge.begin)
+                };
+                match edge_owners.entry(key) {
+                    Entry::Vacant(entry) => {
+                        entry.insert(i);
+                    }
+                    Entry::Occupied(entry) => {
+                        let a = find(&mut parents, *entry.get());
+                        let b = find(&mut parents, i);
+                        parents[a] = b;
+                    }
+                }
+            }
+        }
+
+        let mut components: HashMap<usize, Vec<Handle<NavmeshTriangle>>> = HashMap::new();
+        for (i, (handle, _)) in triangles.iter().enumerate() {
+            let root = find(&mut parents, i);
+            components.entry(root).or_default().push(*handle);
+        }
+
+        components.into_iter().map(|(_, c)| c).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn add_triangle(navmesh: &mut Navmesh, a: Vector3<f32>, b: Vector3<f32>, c: Vector3<f32>) {
+        let a = navmesh.vertices.spawn(NavmeshVertex { position: a });
+        let b = navmesh.vertices.spawn(NavmeshVertex { position: b });
+        let c = navmesh.vertices.spawn(NavmeshVertex { position: c });
+        let _ = navmesh
+            .triangles
+            .spawn(NavmeshTriangle { a, b, c, region: 0 });
+    }
+
+    #[test]
+    fn test_connected_components() {
+        let mut navmesh = Navmesh::new();
+        assert!(navmesh.is_connected());
+        assert_eq!(navmesh.connected_components().len(), 1);
+
+        // Triangle that touches the quad only by a vertex is an island.
+        let corner = navmesh
+            .vertices
+            .pair_iter()
+            .find(|(_, v)| v.position == Vector3::new(1.0, 0.0, 1.0))
+            .map(|(h, _)| h)
+            .unwrap();
+        let b = navmesh.vertices.spawn(NavmeshVertex {
+            position: Vector3::new(2.0, 0.0, 1.0),
+        });
+        let c = navmesh.vertices.spawn(NavmeshVertex {
+            position: Vector3::new(2.0, 0.0, 2.0),
+        });
+        let _ = navmesh.triangles.spawn(NavmeshTriangle {
+            a: corner,
+            b,
+            c,
+            region: 0,
+        });
+        assert!(!navmesh.is_connected());
+
+        add_triangle(
+            &mut navmesh,
+            Vector3::new(10.0, 0.0, 10.0),
+            Vector3::new(11.0, 0.0, 10.0),
+            Vector3::new(11.0, 0.0, 11.0),
+        );
+
+        let mut sizes = navmesh
+            .connected_components()
+            .iter()
+            .map(|c| c.len())
+            .collect::<Vec<_>>();
+        sizes.sort_unstable();
+        assert_eq!(sizes, [1, 1, 2]);
+    }
+
+    #[test]
+    fn test_deep_clone() {
+        let mut navmesh = Navmesh::new();
+        let unused = navmesh.vertices.spawn(NavmeshVertex {
+            position: Vector3::new(5.0, 5.0, 5.0),
+        });
+        add_triangle(
+            &mut navmesh,
+            Vector3::new(3.0, 0.0, 0.0),
+            Vector3::new(4.0, 0.0, 0.0),
+            Vector3::new(4.0, 0.0, 1.0),
+        );
+        navmesh.vertices.free(unused);
+        for (i, triangle) in navmesh.triangles.iter_mut().enumerate() {
+            triangle.region = i as u8 + 1;
+        }
+
+        let copy = navmesh.deep_clone();
+        assert_eq!(copy.vertices.iter().count(), 7);
+        assert_eq!(copy.triangles.iter().count(), 3);
+        for triangle in copy.triangles.iter() {
+            for &vertex in triangle.vertices().iter() {
+                assert!(copy.vertices.is_valid_handle(vertex));
+            }
+        }
+        assert_eq!(
+            copy.triangles.iter().map(|t| t.region).collect::<Vec<_>>(),
+            [1, 2, 3]
+        );
+        for (original, copied) in navmesh.triangles.iter().zip(copy.triangles.iter()) {
+            assert_eq!(
+                navmesh.vertices[original.b].position,
+                copy.vertices[copied.b].position
+            );
+        }
+    }
+
+    #[test]
+    fn test_set_regions() {
+        let mut navmesh = Navmesh::new();
+
+        // Extra regions are ignored, missing ones leave triangles untouched.
+        navmesh.set_regions(&[7]);
+        navmesh.set_regions(&[]);
+        assert_eq!(
+            navmesh
+                .triangles
+                .iter()
+                .map(|t| t.region)
+                .collect::<Vec<_>>(),
+            [7, 0]
+        );
+
+        navmesh.set_regions(&[1, 2, 3]);
+        assert_eq!(
+            navmesh
+                .triangles
+                .iter()
+                .map(|t| t.region)
+                .collect::<Vec<_>>(),
+            [1, 2]
+        );
+    }
+
+    #[test]
+    fn test_total_area_and_bounds() {
+        let mut navmesh = Navmesh::new();
+        assert!((navmesh.total_area() - 4.0).abs() < f32::EPSILON);
+
+        let bounds = navmesh.bounds();
+        assert_eq!(bounds.min, Vector3::new(-1.0, 0.0, -1.0));
+        assert_eq!(bounds.max, Vector3::new(1.0, 0.0, 1.0));
+
+        // Triangle with a missing vertex does not count.
+        let a = navmesh.vertices.spawn(NavmeshVertex {
+            position: Vector3::new(0.0, 0.0, 5.0),
+        });
+        let b = navmesh.vertices.spawn(NavmeshVertex {
+            position: Vector3::new(1.0, 0.0, 5.0),
+        });
+        let c = navmesh.vertices.spawn(NavmeshVertex {
+            position: Vector3::new(1.0, 0.0, 6.0),
+        });
+        let _ = navmesh
+            .triangles
+            .spawn(NavmeshTriangle { a, b, c, region: 0 });
+        assert!((navmesh.total_area() - 4.5).abs() < f32::EPSILON);
+        navmesh.vertices.free(c);
+        assert!((navmesh.total_area() - 4.0).abs() < f32::EPSILON);
+
+        assert_eq!(Navmesh::default().total_area(), 0.0);
+    }
 }
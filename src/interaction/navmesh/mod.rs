@@ -9,7 +9,7 @@ use crate::{
     },
     scene::{
         AddNavmeshCommand, AddNavmeshEdgeCommand, ChangeSelectionCommand, CommandGroup,
-        DeleteNavmeshCommand, DeleteNavmeshVertexCommand, EditorScene, MoveNavmeshVertexCommand,
+        DeleteNavmeshCommand, DeleteNavmeshVerticesCommand, EditorScene, MoveNavmeshVertexCommand,
         SceneCommand, Selection,
     },
     send_sync_message, GameEngine, Message, MSG_SYNC_FLAG,
@@ -710,20 +710,22 @@ impl InteractionModeTrait for EditNavmeshMode {
                 if editor_scene.navmeshes.is_valid_handle(self.navmesh) {
                     if let Selection::Navmesh(navmesh_selection) = &mut editor_scene.selection {
                         if !navmesh_selection.is_empty() {
-                            let mut commands = Vec::new();
-
-                            for &vertex in navmesh_selection.unique_vertices() {
-                                commands.push(SceneCommand::DeleteNavmeshVertex(
-                                    DeleteNavmeshVertexCommand::new(self.navmesh, vertex),
-                                ));
-                            }
-
-                            commands.push(SceneCommand::ChangeSelection(
-                                ChangeSelectionCommand::new(
+                            let commands = vec![
+                                SceneCommand::DeleteNavmeshVertices(
+                                    DeleteNavmeshVerticesCommand::new(
+                                        self.navmesh,
+                                        navmesh_selection
+                                            .unique_vertices()
+                                            .iter()
+                                            .cloned()
+                                            .collect(),
+                                    ),
+                                ),
+                                SceneCommand::ChangeSelection(ChangeSelectionCommand::new(
                                     Selection::Navmesh(NavmeshSelection::empty(self.navmesh)),
                                     editor_scene.selection.clone(),
-                                ),
-                            ));
+                                )),
+                            ];
 
                             self.message_sender
                                 .send(Message::DoSceneCommand(SceneCommand::CommandGroup(
@@ -18,32 +18,30 @@ pub mod menu;
 pub mod physics;
 pub mod preview;
 pub mod scene;
+pub mod scene_diff;
 pub mod settings;
 pub mod sidebar;
 pub mod world_outliner;
 
 use crate::{
     asset::{AssetBrowser, AssetKind},
-    camera::CameraController,
+    camera::{CameraController, CameraPose},
     command::{CommandStack, CommandStackViewer},
     configurator::Configurator,
     gui::{BuildContext, EditorUiMessage, EditorUiNode, Ui, UiMessage, UiNode},
     interaction::{
-        navmesh::{
-            data_model::{Navmesh, NavmeshTriangle, NavmeshVertex},
-            EditNavmeshMode, NavmeshPanel,
-        },
+        navmesh::{data_model::Navmesh, EditNavmeshMode, NavmeshPanel},
         InteractionMode, InteractionModeKind, InteractionModeTrait, MoveInteractionMode,
         RotateInteractionMode, ScaleInteractionMode, SelectInteractionMode,
     },
     light::LightPanel,
     log::Log,
     menu::{Menu, MenuContext},
-    physics::Physics,
+    physics::{CollisionLayers, Physics},
     scene::{
-        make_delete_selection_command, EditorScene, LoadModelCommand, PasteCommand, SceneCommand,
-        SceneContext, Selection, SetMeshTextureCommand, SetParticleSystemTextureCommand,
-        SetSpriteTextureCommand,
+        make_cut_selection_command, make_delete_selection_command, CommandGroup, EditorScene,
+        LoadModelCommand, PasteCommand, SceneCommand, SceneContext, Selection,
+        SetMeshTextureCommand, SetParticleSystemTextureCommand, SetSpriteTextureCommand,
     },
     settings::Settings,
     sidebar::SideBar,
@@ -54,8 +52,9 @@ use rg3d::{
         algebra::{Point3, Vector2},
         color::Color,
         math::aabb::AxisAlignedBoundingBox,
-        pool::{Handle, Pool},
+        pool::{ErasedHandle, Handle, Pool},
         scope_profile,
+        visitor::Visitor,
     },
     dpi::LogicalSize,
     engine::resource_manager::ResourceManager,
@@ -82,12 +81,16 @@ use rg3d::{
         window::{WindowBuilder, WindowTitle},
         Thickness,
     },
-    resource::texture::{Texture, TextureKind, TextureState},
+    resource::{
+        model::Model,
+        texture::{Texture, TextureKind, TextureState},
+    },
     scene::{base::BaseBuilder, graph::Graph, node::Node, Line, Scene, SceneDrawingContext},
     utils::{into_gui_texture, translate_cursor_icon, translate_event},
 };
 use std::{
     cell::RefCell,
+    collections::HashMap,
     fs::File,
     io::Write,
     path::{Path, PathBuf},
@@ -395,6 +398,28 @@ pub enum Message {
     Exit {
         force: bool,
     },
+    /// Sent when nodes are pasted, maps handles of copied nodes, bodies and colliders to
+    /// handles of pasted ones.
+    NodesPasted {
+        mapping: HashMap<ErasedHandle, ErasedHandle>,
+    },
+    /// Starts a transaction, scene commands done until the transaction is committed will be
+    /// undone at once. Transactions could be nested, see `CommandStack::begin_transaction`.
+    BeginTransaction,
+    /// Commits innermost transaction, outermost transaction is put in command stack as a
+    /// single command with given name.
+    CommitTransaction {
+        name: String,
+    },
+    /// Reverts every command done in innermost transaction.
+    CancelTransaction,
+    /// Sent when a model requested by `EditorScene::request_model` is loaded.
+    ModelLoaded {
+        // Scene which requested the model.
+        scene: Handle<Scene>,
+        path: PathBuf,
+        model: Model,
+    },
 }
 
 pub fn make_scene_file_filter() -> Rc<RefCell<Filter>> {
@@ -441,6 +466,8 @@ struct Editor {
     validation_message_box: Handle<UiNode>,
     navmesh_panel: NavmeshPanel,
     settings: Settings,
+    // Time since last autosave, see `Settings::autosave`.
+    autosave_timer: f32,
 }
 
 impl Editor {
@@ -624,7 +651,7 @@ impl Editor {
             sidebar: node_editor,
             preview,
             scene: None,
-            command_stack: CommandStack::new(false),
+            command_stack: CommandStack::new(false).with_max_depth(settings.max_undo_depth),
             message_sender,
             message_receiver,
             interaction_modes: Default::default(),
@@ -642,6 +669,7 @@ impl Editor {
             command_stack_viewer,
             validation_message_box,
             settings,
+            autosave_timer: 0.0,
         };
 
         editor.set_interaction_mode(Some(InteractionModeKind::Move), engine);
@@ -653,7 +681,13 @@ impl Editor {
         if let Some(previous_editor_scene) = self.scene.as_ref() {
             engine.scenes.remove(previous_editor_scene.scene);
         }
-        self.scene = None;
+        // Clipboard outlives the scene, so copied nodes could be pasted into another scene.
+        let mut clipboard = self
+            .scene
+            .take()
+            .map(|previous_editor_scene| previous_editor_scene.clipboard)
+            .unwrap_or_default();
+        clipboard.forget_source();
         self.sync_to_model(engine);
         poll_ui_messages(self, engine);
 
@@ -670,30 +704,31 @@ impl Editor {
 
         let root = BaseBuilder::new().build(&mut scene.graph);
 
+        // Editor-only data is stored in its own regions of the scene file, read the file
+        // once and let every table pick its region.
+        let mut visitor = path.as_ref().and_then(|path| {
+            rg3d::core::futures::executor::block_on(Visitor::load_binary(path)).ok()
+        });
+
         let graph = &mut scene.graph;
-        let camera_controller = CameraController::new(graph, root);
+        let mut camera_controller = CameraController::new(graph, root);
+        if let Some(pose) = visitor.as_mut().and_then(CameraPose::load) {
+            camera_controller.set_pose(graph, pose);
+        }
 
         let mut navmeshes = Pool::new();
 
-        for navmesh in scene.navmeshes.iter() {
-            let _ = navmeshes.spawn(Navmesh {
-                vertices: navmesh
-                    .vertices()
-                    .iter()
-                    .map(|vertex| NavmeshVertex {
-                        position: vertex.position,
-                    })
-                    .collect(),
-                triangles: navmesh
-                    .triangles()
-                    .iter()
-                    .map(|triangle| NavmeshTriangle {
-                        a: Handle::new(triangle[0], 1),
-                        b: Handle::new(triangle[1], 1),
-                        c: Handle::new(triangle[2], 1),
-                    })
-                    .collect(),
-            });
+        let navmesh_regions = visitor
+            .as_mut()
+            .map(EditorScene::load_navmesh_regions)
+            .unwrap_or_default();
+
+        for (i, navmesh) in scene.navmeshes.iter().enumerate() {
+            let mut navmesh = Navmesh::from_engine_navmesh(navmesh);
+            if let Some(regions) = navmesh_regions.get(i) {
+                navmesh.set_regions(regions);
+            }
+            let _ = navmeshes.spawn(navmesh);
         }
 
         let editor_scene = EditorScene {
@@ -701,10 +736,42 @@ impl Editor {
             root,
             camera_controller,
             physics: Physics::new(&scene),
+            collision_layers: visitor
+                .as_mut()
+                .map(CollisionLayers::load)
+                .unwrap_or_default(),
             navmeshes,
+            surface_render_paths: visitor
+                .as_mut()
+                .map(EditorScene::load_surface_render_paths)
+                .unwrap_or_default(),
+            wireframe_nodes: Default::default(),
+            paused_particle_systems: Default::default(),
+            paused_animations: Default::default(),
+            pending_models: Default::default(),
+            emitter_texture_regions: visitor
+                .as_mut()
+                .map(EditorScene::load_emitter_texture_regions)
+                .unwrap_or_default(),
+            emitter_acceleration_overrides: visitor
+                .as_mut()
+                .map(EditorScene::load_emitter_acceleration_overrides)
+                .unwrap_or_default(),
+            blend_shape_weights: visitor
+                .as_mut()
+                .map(EditorScene::load_blend_shape_weights)
+                .unwrap_or_default(),
+            linked_instances: visitor
+                .as_mut()
+                .map(|visitor| EditorScene::load_linked_instances(visitor, &mut scene.graph))
+                .unwrap_or_default(),
+            static_nodes: visitor
+                .as_mut()
+                .map(EditorScene::load_static_nodes)
+                .unwrap_or_default(),
             scene: engine.scenes.add(scene),
             selection: Default::default(),
-            clipboard: Default::default(),
+            clipboard,
         };
 
         self.interaction_modes = vec![
@@ -735,7 +802,9 @@ impl Editor {
             )),
         ];
 
-        self.command_stack = CommandStack::new(false);
+        self.sync_transform_space();
+
+        self.command_stack = CommandStack::new(false).with_max_depth(self.settings.max_undo_depth);
         self.scene = Some(editor_scene);
 
         self.set_interaction_mode(Some(InteractionModeKind::Move), engine);
@@ -768,6 +837,19 @@ impl Editor {
         }
     }
 
+    /// Passes gizmo transform space from settings to interaction modes that use it.
+    fn sync_transform_space(&mut self) {
+        for mode in self.interaction_modes.iter_mut() {
+            match mode {
+                InteractionMode::Move(mode) => mode.transform_space = self.settings.transform_space,
+                InteractionMode::Rotate(mode) => {
+                    mode.transform_space = self.settings.transform_space
+                }
+                _ => (),
+            }
+        }
+    }
+
     pub fn handle_ui_message(&mut self, message: &UiMessage, engine: &mut GameEngine) {
         scope_profile!();
 
@@ -776,6 +858,8 @@ impl Editor {
             return;
         }
 
+        let transform_space = self.settings.transform_space;
+
         self.configurator.handle_ui_message(message, engine);
         self.menu.handle_ui_message(
             message,
@@ -792,6 +876,10 @@ impl Editor {
             },
         );
 
+        if self.settings.transform_space != transform_space {
+            self.sync_transform_space();
+        }
+
         self.log.handle_ui_message(message, engine);
         self.asset_browser.handle_ui_message(message, engine);
         self.command_stack_viewer.handle_ui_message(message);
@@ -959,15 +1047,22 @@ impl Editor {
                                 KeyCode::C
                                     if engine.user_interface.keyboard_modifiers().control =>
                                 {
-                                    if let Selection::Graph(graph_selection) =
-                                        &editor_scene.selection
+                                    editor_scene.copy_selection_to_clipboard(
+                                        &engine.scenes[editor_scene.scene].graph,
+                                    );
+                                }
+                                KeyCode::X
+                                    if engine.user_interface.keyboard_modifiers().control =>
+                                {
+                                    if !editor_scene.selection.is_empty()
+                                        && matches!(editor_scene.selection, Selection::Graph(_))
                                     {
-                                        editor_scene.clipboard.fill_from_selection(
-                                            graph_selection,
-                                            editor_scene.scene,
-                                            &editor_scene.physics,
-                                            engine,
-                                        );
+                                        let graph = &engine.scenes[editor_scene.scene].graph;
+                                        self.message_sender
+                                            .send(Message::DoSceneCommand(
+                                                make_cut_selection_command(editor_scene, graph),
+                                            ))
+                                            .unwrap();
                                     }
                                 }
                                 KeyCode::V
@@ -985,9 +1080,10 @@ impl Editor {
                                     if !editor_scene.selection.is_empty()
                                         && matches!(editor_scene.selection, Selection::Graph(_))
                                     {
+                                        let graph = &engine.scenes[editor_scene.scene].graph;
                                         self.message_sender
                                             .send(Message::DoSceneCommand(
-                                                make_delete_selection_command(editor_scene, engine),
+                                                make_delete_selection_command(editor_scene, graph),
                                             ))
                                             .unwrap();
                                     }
@@ -1006,14 +1102,11 @@ impl Editor {
 
                                     match item.kind {
                                         AssetKind::Model => {
-                                            // Import model.
-                                            self.message_sender
-                                                .send(Message::DoSceneCommand(
-                                                    SceneCommand::LoadModel(LoadModelCommand::new(
-                                                        relative_path,
-                                                    )),
-                                                ))
-                                                .unwrap();
+                                            // Import model, it will be instantiated when loaded.
+                                            editor_scene.request_model(
+                                                relative_path,
+                                                &engine.resource_manager,
+                                            );
                                         }
                                         AssetKind::Texture => {
                                             let cursor_pos =
@@ -1194,6 +1287,16 @@ impl Editor {
                         needs_sync = true;
                     }
                 }
+                Message::UndoSceneCommand | Message::RedoSceneCommand
+                    if self.command_stack.is_in_transaction() =>
+                {
+                    self.message_sender
+                        .send(Message::Log(
+                            "Undo and redo are not available until transaction is finished."
+                                .to_owned(),
+                        ))
+                        .unwrap();
+                }
                 Message::UndoSceneCommand => {
                     if let Some(editor_scene) = self.scene.as_mut() {
                         self.command_stack.undo(SceneContext {
@@ -1227,6 +1330,42 @@ impl Editor {
                         needs_sync = true;
                     }
                 }
+                Message::BeginTransaction => {
+                    self.command_stack.begin_transaction();
+                }
+                Message::CommitTransaction { name } => {
+                    if let Some(editor_scene) = self.scene.as_mut() {
+                        self.command_stack.commit_transaction(
+                            SceneContext {
+                                scene: &mut engine.scenes[editor_scene.scene],
+                                message_sender: self.message_sender.clone(),
+                                editor_scene,
+                                resource_manager: engine.resource_manager.clone(),
+                            },
+                            |commands| {
+                                SceneCommand::CommandGroup(
+                                    CommandGroup::from(commands).with_name(name),
+                                )
+                            },
+                        );
+                        needs_sync = true;
+                    } else {
+                        self.command_stack.discard_transaction();
+                    }
+                }
+                Message::CancelTransaction => {
+                    if let Some(editor_scene) = self.scene.as_mut() {
+                        self.command_stack.cancel_transaction(SceneContext {
+                            scene: &mut engine.scenes[editor_scene.scene],
+                            message_sender: self.message_sender.clone(),
+                            editor_scene,
+                            resource_manager: engine.resource_manager.clone(),
+                        });
+                        needs_sync = true;
+                    } else {
+                        self.command_stack.discard_transaction();
+                    }
+                }
                 Message::SelectionChanged => {
                     self.world_outliner.sync_selection = true;
                 }
@@ -1315,6 +1454,25 @@ impl Editor {
                         ));
                     }
                 }
+                Message::NodesPasted { mapping } => {
+                    if let Some(editor_scene) = self.scene.as_mut() {
+                        editor_scene.copy_pasted_node_data(&mapping);
+                    }
+                }
+                Message::ModelLoaded { scene, path, model } => {
+                    // Model could be requested by a scene which is closed already.
+                    if self
+                        .scene
+                        .as_ref()
+                        .map_or(false, |editor_scene| editor_scene.scene == scene)
+                    {
+                        self.message_sender
+                            .send(Message::DoSceneCommand(SceneCommand::LoadModel(
+                                LoadModelCommand::from_model(path, model),
+                            )))
+                            .unwrap();
+                    }
+                }
                 Message::NewScene => {
                     let mut scene = Scene::new();
 
@@ -1358,6 +1516,21 @@ impl Editor {
             self.sync_to_model(engine);
         }
 
+        if let Some(autosave) = self.settings.autosave.as_ref() {
+            self.autosave_timer += dt;
+            if self.autosave_timer >= autosave.interval() {
+                self.autosave_timer = 0.0;
+                if let Some(editor_scene) = self.scene.as_ref() {
+                    let message = match editor_scene.autosave(engine, &autosave.dir, autosave.keep)
+                    {
+                        Ok(path) => format!("Scene was autosaved to {}.", path.display()),
+                        Err(reason) => format!("Failed to autosave scene! Reason: {}", reason),
+                    };
+                    self.message_sender.send(Message::Log(message)).unwrap();
+                }
+            }
+        }
+
         if let Some(editor_scene) = self.scene.as_mut() {
             // Adjust camera viewport to size of frame.
             let scene = &mut engine.scenes[editor_scene.scene];
@@ -1502,6 +1675,10 @@ impl Editor {
                     .draw(&mut scene.drawing_context, &scene.graph);
             }
 
+            editor_scene.draw_wireframes(&mut scene.drawing_context, &scene.graph);
+
+            editor_scene.poll_pending_models(&self.message_sender);
+
             let graph = &mut scene.graph;
 
             editor_scene.camera_controller.update(graph, dt);
@@ -2,7 +2,11 @@ use crate::settings::Settings;
 use crate::{
     gui::{Ui, UiMessage, UiNode},
     make_save_file_selector, make_scene_file_filter,
-    scene::{AddNodeCommand, EditorScene, PasteCommand, SceneCommand, Selection},
+    scene::{
+        make_add_directional_light_command, make_add_point_light_command,
+        make_add_spot_light_command, make_cut_selection_command, AddNodeCommand, EditorScene,
+        PasteCommand, SceneCommand, Selection,
+    },
     send_sync_message,
     settings::SettingsWindow,
     GameEngine, Message,
@@ -29,7 +33,6 @@ use rg3d::{
     scene::{
         base::BaseBuilder,
         camera::CameraBuilder,
-        light::{BaseLightBuilder, DirectionalLightBuilder, PointLightBuilder, SpotLightBuilder},
         mesh::{Mesh, MeshBuilder},
         node::Node,
         particle_system::{BaseEmitterBuilder, ParticleSystemBuilder, SphereEmitterBuilder},
@@ -48,6 +51,7 @@ pub struct Menu {
     undo: Handle<UiNode>,
     redo: Handle<UiNode>,
     copy: Handle<UiNode>,
+    cut: Handle<UiNode>,
     paste: Handle<UiNode>,
     create_pivot: Handle<UiNode>,
     create_cube: Handle<UiNode>,
@@ -114,6 +118,7 @@ impl Menu {
         let redo;
         let undo;
         let copy;
+        let cut;
         let paste;
         let create_cube;
         let create_cone;
@@ -266,6 +271,12 @@ impl Menu {
                         .build(ctx);
                     copy
                 },
+                {
+                    cut = MenuItemBuilder::new(WidgetBuilder::new().with_min_size(min_size))
+                        .with_content(MenuItemContent::text_with_shortcut("Cut", "Ctrl+X"))
+                        .build(ctx);
+                    cut
+                },
                 {
                     paste = MenuItemBuilder::new(WidgetBuilder::new().with_min_size(min_size))
                         .with_content(MenuItemContent::text_with_shortcut("Paste", "Ctrl+V"))
@@ -443,6 +454,7 @@ impl Menu {
             configure_message,
             light_panel,
             copy,
+            cut,
             paste,
             log_panel,
             create_pivot,
@@ -517,17 +529,9 @@ impl Menu {
                         )))
                         .unwrap();
                 } else if message.destination() == self.create_spot_light {
-                    let node = SpotLightBuilder::new(BaseLightBuilder::new(
-                        BaseBuilder::new().with_name("SpotLight"),
-                    ))
-                    .with_distance(10.0)
-                    .with_hotspot_cone_angle(45.0f32.to_radians())
-                    .with_falloff_angle_delta(2.0f32.to_radians())
-                    .build_node();
-
                     self.message_sender
                         .send(Message::DoSceneCommand(SceneCommand::AddNode(
-                            AddNodeCommand::new(node),
+                            make_add_spot_light_command(),
                         )))
                         .unwrap();
                 } else if message.destination() == self.create_pivot {
@@ -539,26 +543,15 @@ impl Menu {
                         )))
                         .unwrap();
                 } else if message.destination() == self.create_point_light {
-                    let node = PointLightBuilder::new(BaseLightBuilder::new(
-                        BaseBuilder::new().with_name("PointLight"),
-                    ))
-                    .with_radius(10.0)
-                    .build_node();
-
                     self.message_sender
                         .send(Message::DoSceneCommand(SceneCommand::AddNode(
-                            AddNodeCommand::new(node),
+                            make_add_point_light_command(),
                         )))
                         .unwrap();
                 } else if message.destination() == self.create_directional_light {
-                    let node = DirectionalLightBuilder::new(BaseLightBuilder::new(
-                        BaseBuilder::new().with_name("DirectionalLight"),
-                    ))
-                    .build_node();
-
                     self.message_sender
                         .send(Message::DoSceneCommand(SceneCommand::AddNode(
-                            AddNodeCommand::new(node),
+                            make_add_directional_light_command(),
                         )))
                         .unwrap();
                 } else if message.destination() == self.create_cone {
@@ -691,13 +684,22 @@ impl Menu {
                     self.message_sender.send(Message::CloseScene).unwrap();
                 } else if message.destination() == self.copy {
                     if let Some(editor_scene) = ctx.editor_scene {
-                        if let Selection::Graph(selection) = &editor_scene.selection {
-                            editor_scene.clipboard.fill_from_selection(
-                                selection,
-                                editor_scene.scene,
-                                &editor_scene.physics,
-                                ctx.engine,
-                            );
+                        editor_scene.copy_selection_to_clipboard(
+                            &ctx.engine.scenes[editor_scene.scene].graph,
+                        );
+                    }
+                } else if message.destination() == self.cut {
+                    if let Some(editor_scene) = ctx.editor_scene {
+                        if matches!(editor_scene.selection, Selection::Graph(_))
+                            && !editor_scene.selection.is_empty()
+                        {
+                            let graph = &ctx.engine.scenes[editor_scene.scene].graph;
+                            self.message_sender
+                                .send(Message::DoSceneCommand(make_cut_selection_command(
+                                    editor_scene,
+                                    graph,
+                                )))
+                                .unwrap();
                         }
                     }
                 } else if message.destination() == self.paste {
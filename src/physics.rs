@@ -5,6 +5,7 @@ use rg3d::{
         math::aabb::AxisAlignedBoundingBox,
         pool::{ErasedHandle, Handle, Pool},
         uuid::Uuid,
+        visitor::{Visit, VisitResult, Visitor},
         BiDirHashMap,
     },
     engine::{ColliderHandle, JointHandle, RigidBodyHandle},
@@ -12,7 +13,9 @@ use rg3d::{
         graph::Graph,
         node::Node,
         physics::{
-            ColliderDesc, ColliderShapeDesc, JointDesc, JointParamsDesc, PhysicsDesc, RigidBodyDesc,
+            BallDesc, CapsuleDesc, ColliderDesc, ColliderShapeDesc, ConeDesc, CuboidDesc,
+            CylinderDesc, HeightfieldDesc, JointDesc, JointParamsDesc, PhysicsDesc, RigidBodyDesc,
+            RoundCylinderDesc, SegmentDesc, TriangleDesc, TrimeshDesc,
         },
         Line, Scene, SceneDrawingContext,
     },
@@ -46,6 +49,310 @@ pub struct Physics {
     joint_handle_map: HashMap<Handle<Joint>, JointHandle>,
 }
 
+/// Density of a collider without explicitly set density, it is the same as in the engine.
+pub const DEFAULT_COLLIDER_DENSITY: f32 = 1.0;
+
+/// Collision groups of a collider are packed into `u32` - upper 16 bits are memberships and
+/// lower 16 bits are filter, so there is only 16 layers available.
+pub const MAX_COLLISION_LAYERS: u32 = 16;
+
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct CollisionLayer {
+    pub name: String,
+    pub index: u32,
+}
+
+impl Visit for CollisionLayer {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.name.visit("Name", visitor)?;
+        self.index.visit("Index", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+/// Editor-side table of human-readable names for bits of collision groups. Engine knows
+/// nothing about the names, so the table is saved next to the scene in the same file.
+#[derive(Default, Clone, Debug)]
+pub struct CollisionLayers {
+    layers: Vec<CollisionLayer>,
+}
+
+impl Visit for CollisionLayers {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.layers.visit("Layers", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+impl CollisionLayers {
+    /// Reads layer table from a saved scene. Scenes saved without the table will
+    /// give empty table.
+    pub fn load(visitor: &mut Visitor) -> Self {
+        let mut layers = Self::default();
+        if layers.visit("CollisionLayers", visitor).is_err() {
+            layers = Self::default();
+        }
+        layers
+    }
+
+    /// Gives a name to a bit, previous name of the bit (if any) will be replaced. Returns
+    /// false and leaves the table unchanged if index is out of range.
+    pub fn set(&mut self, name: &str, index: u32) -> bool {
+        if index >= MAX_COLLISION_LAYERS {
+            return false;
+        }
+
+        self.layers
+            .retain(|layer| layer.name != name && layer.index != index);
+        self.layers.push(CollisionLayer {
+            name: name.to_owned(),
+            index,
+        });
+        true
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.layers.retain(|layer| layer.name != name);
+    }
+
+    pub fn index_of(&self, name: &str) -> Option<u32> {
+        self.layers
+            .iter()
+            .find(|layer| layer.name == name)
+            .map(|layer| layer.index)
+    }
+
+    pub fn name_of(&self, index: u32) -> Option<&str> {
+        self.layers
+            .iter()
+            .find(|layer| layer.index == index)
+            .map(|layer| layer.name.as_str())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &CollisionLayer> {
+        self.layers.iter()
+    }
+}
+
+/// Sets or clears membership bit of a layer in collision groups, returns previous state of the bit.
+pub fn set_collision_membership(collision_groups: &mut u32, layer: u32, enabled: bool) -> bool {
+    set_collision_groups_bit(collision_groups, layer + 16, enabled)
+}
+
+/// Sets or clears filter bit of a layer in collision groups, returns previous state of the bit.
+pub fn set_collision_filter(collision_groups: &mut u32, layer: u32, enabled: bool) -> bool {
+    set_collision_groups_bit(collision_groups, layer, enabled)
+}
+
+fn set_collision_groups_bit(collision_groups: &mut u32, bit: u32, enabled: bool) -> bool {
+    let mask = 1 << bit;
+    let old = *collision_groups & mask != 0;
+    if enabled {
+        *collision_groups |= mask;
+    } else {
+        *collision_groups &= !mask;
+    }
+    old
+}
+
+/// Creates a shape of given kind (`id` is an id of shape, see [`ColliderShapeDesc::id`]) with
+/// default size, it is used for new colliders.
+pub fn default_collider_shape(id: usize) -> ColliderShapeDesc {
+    match id {
+        0 => ColliderShapeDesc::Ball(BallDesc { radius: 0.5 }),
+        1 => ColliderShapeDesc::Cylinder(CylinderDesc {
+            half_height: 0.5,
+            radius: 0.5,
+        }),
+        2 => ColliderShapeDesc::RoundCylinder(RoundCylinderDesc {
+            half_height: 0.5,
+            radius: 0.5,
+            border_radius: 0.1,
+        }),
+        3 => ColliderShapeDesc::Cone(ConeDesc {
+            half_height: 0.5,
+            radius: 0.5,
+        }),
+        4 => ColliderShapeDesc::Cuboid(CuboidDesc {
+            half_extents: Vector3::new(0.5, 0.5, 0.5),
+        }),
+        5 => ColliderShapeDesc::Capsule(CapsuleDesc {
+            begin: Vector3::new(0.0, 0.0, 0.0),
+            end: Vector3::new(0.0, 1.0, 0.0),
+            radius: 0.5,
+        }),
+        6 => ColliderShapeDesc::Segment(SegmentDesc {
+            begin: Vector3::new(0.0, 0.0, 0.0),
+            end: Vector3::new(1.0, 0.0, 0.0),
+        }),
+        7 => ColliderShapeDesc::Triangle(TriangleDesc {
+            a: Vector3::new(0.0, 0.0, 0.0),
+            b: Vector3::new(1.0, 0.0, 0.0),
+            c: Vector3::new(1.0, 0.0, 1.0),
+        }),
+        8 => ColliderShapeDesc::Trimesh(TrimeshDesc),
+        9 => ColliderShapeDesc::Heightfield(HeightfieldDesc),
+        _ => unreachable!(),
+    }
+}
+
+/// Approximate half-extents of a shape, used to carry size over when shape is converted to
+/// other kind of shape. Trimesh and heightfield shapes have no natural size.
+fn shape_half_extents(shape: &ColliderShapeDesc) -> Option<Vector3<f32>> {
+    Some(match shape {
+        ColliderShapeDesc::Ball(ball) => Vector3::repeat(ball.radius),
+        ColliderShapeDesc::Cylinder(cylinder) => {
+            Vector3::new(cylinder.radius, cylinder.half_height, cylinder.radius)
+        }
+        ColliderShapeDesc::RoundCylinder(round_cylinder) => {
+            let radius = round_cylinder.radius + round_cylinder.border_radius;
+            Vector3::new(
+                radius,
+                round_cylinder.half_height + round_cylinder.border_radius,
+                radius,
+            )
+        }
+        ColliderShapeDesc::Cone(cone) => Vector3::new(cone.radius, cone.half_height, cone.radius),
+        ColliderShapeDesc::Cuboid(cuboid) => cuboid.half_extents,
+        ColliderShapeDesc::Capsule(capsule) => {
+            let half_height = (capsule.end - capsule.begin).norm() * 0.5 + capsule.radius;
+            Vector3::new(capsule.radius, half_height, capsule.radius)
+        }
+        ColliderShapeDesc::Segment(segment) => (segment.end - segment.begin).abs().scale(0.5),
+        ColliderShapeDesc::Triangle(triangle) => {
+            let min = triangle.a.inf(&triangle.b).inf(&triangle.c);
+            let max = triangle.a.sup(&triangle.b).sup(&triangle.c);
+            (max - min).scale(0.5)
+        }
+        ColliderShapeDesc::Trimesh(_) | ColliderShapeDesc::Heightfield(_) => return None,
+    })
+}
+
+/// Creates a shape of other kind (`target` is an id of shape, see [`ColliderShapeDesc::id`])
+/// with size similar to the size of given shape. Default shape is created if there is no
+/// natural mapping, see [`default_collider_shape`].
+pub fn convert_collider_shape(shape: &ColliderShapeDesc, target: usize) -> ColliderShapeDesc {
+    let e = match shape_half_extents(shape) {
+        Some(e) => e,
+        None => return default_collider_shape(target),
+    };
+    let radius = e.x.max(e.z);
+
+    match target {
+        0 => ColliderShapeDesc::Ball(BallDesc {
+            radius: e.x.max(e.y).max(e.z),
+        }),
+        1 => ColliderShapeDesc::Cylinder(CylinderDesc {
+            half_height: e.y,
+            radius,
+        }),
+        2 => {
+            let border_radius = (0.1f32).min(radius * 0.5);
+            ColliderShapeDesc::RoundCylinder(RoundCylinderDesc {
+                half_height: (e.y - border_radius).max(0.0),
+                radius: (radius - border_radius).max(0.0),
+                border_radius,
+            })
+        }
+        3 => ColliderShapeDesc::Cone(ConeDesc {
+            half_height: e.y,
+            radius,
+        }),
+        4 => ColliderShapeDesc::Cuboid(CuboidDesc { half_extents: e }),
+        5 => {
+            let half_segment = (e.y - radius).max(0.0);
+            ColliderShapeDesc::Capsule(CapsuleDesc {
+                begin: Vector3::new(0.0, -half_segment, 0.0),
+                end: Vector3::new(0.0, half_segment, 0.0),
+                radius,
+            })
+        }
+        6 => ColliderShapeDesc::Segment(SegmentDesc {
+            begin: Vector3::new(0.0, -e.y, 0.0),
+            end: Vector3::new(0.0, e.y, 0.0),
+        }),
+        7 => ColliderShapeDesc::Triangle(TriangleDesc {
+            a: Vector3::new(-e.x, 0.0, -e.z),
+            b: Vector3::new(e.x, 0.0, -e.z),
+            c: Vector3::new(e.x, 0.0, e.z),
+        }),
+        _ => default_collider_shape(target),
+    }
+}
+
+/// Multiplies every linear dimension of a shape by given factor. Trimesh and heightfield
+/// shapes are built from scene geometry, so they're left as is.
+pub fn scale_collider_shape(shape: &mut ColliderShapeDesc, factor: f32) {
+    match shape {
+        ColliderShapeDesc::Ball(ball) => ball.radius *= factor,
+        ColliderShapeDesc::Cylinder(cylinder) => {
+            cylinder.half_height *= factor;
+            cylinder.radius *= factor;
+        }
+        ColliderShapeDesc::RoundCylinder(round_cylinder) => {
+            round_cylinder.half_height *= factor;
+            round_cylinder.radius *= factor;
+            round_cylinder.border_radius *= factor;
+        }
+        ColliderShapeDesc::Cone(cone) => {
+            cone.half_height *= factor;
+            cone.radius *= factor;
+        }
+        ColliderShapeDesc::Cuboid(cuboid) => cuboid.half_extents *= factor,
+        ColliderShapeDesc::Capsule(capsule) => {
+            capsule.begin *= factor;
+            capsule.end *= factor;
+            capsule.radius *= factor;
+        }
+        ColliderShapeDesc::Segment(segment) => {
+            segment.begin *= factor;
+            segment.end *= factor;
+        }
+        ColliderShapeDesc::Triangle(triangle) => {
+            triangle.a *= factor;
+            triangle.b *= factor;
+            triangle.c *= factor;
+        }
+        ColliderShapeDesc::Trimesh(_) | ColliderShapeDesc::Heightfield(_) => {}
+    }
+}
+
+/// Volume of a shape. Flat shapes (segment, triangle) have no volume, volume of trimesh and
+/// heightfield shapes is unknown before they're built from scene geometry, so it is zero too.
+pub fn collider_shape_volume(shape: &ColliderShapeDesc) -> f32 {
+    use std::f32::consts::PI;
+
+    match shape {
+        ColliderShapeDesc::Ball(ball) => 4.0 / 3.0 * PI * ball.radius.powi(3),
+        ColliderShapeDesc::Cylinder(cylinder) => {
+            PI * cylinder.radius.powi(2) * 2.0 * cylinder.half_height
+        }
+        ColliderShapeDesc::RoundCylinder(round_cylinder) => {
+            let radius = round_cylinder.radius + round_cylinder.border_radius;
+            let half_height = round_cylinder.half_height + round_cylinder.border_radius;
+            PI * radius.powi(2) * 2.0 * half_height
+        }
+        ColliderShapeDesc::Cone(cone) => PI * cone.radius.powi(2) * 2.0 * cone.half_height / 3.0,
+        ColliderShapeDesc::Cuboid(cuboid) => {
+            8.0 * cuboid.half_extents.x * cuboid.half_extents.y * cuboid.half_extents.z
+        }
+        ColliderShapeDesc::Capsule(capsule) => {
+            let length = (capsule.end - capsule.begin).norm();
+            PI * capsule.radius.powi(2) * (length + 4.0 / 3.0 * capsule.radius)
+        }
+        ColliderShapeDesc::Segment(_)
+        | ColliderShapeDesc::Triangle(_)
+        | ColliderShapeDesc::Trimesh(_)
+        | ColliderShapeDesc::Heightfield(_) => 0.0,
+    }
+}
+
 impl Physics {
     pub fn new(scene: &Scene) -> Self {
         let mut bodies: Pool<RigidBody> = Default::default();
@@ -66,7 +373,7 @@ impl Physics {
                 mass: b.mass(),
                 x_rotation_locked: rotation_locked[0],
                 y_rotation_locked: rotation_locked[1],
-                z_rotation_locked: rotation_locked[1],
+                z_rotation_locked: rotation_locked[2],
                 translation_locked: b.is_translation_locked(),
             });
 
@@ -326,6 +633,79 @@ impl Physics {
         )
     }
 
+    /// Calculates mass of a body from density and volume of its colliders. Sensors do not
+    /// contribute to the mass, colliders without density have default density of the engine.
+    pub fn calculate_body_mass(&self, body: Handle<RigidBody>) -> f32 {
+        self.bodies[body]
+            .colliders
+            .iter()
+            .filter_map(|&collider| self.colliders.try_borrow(collider.into()))
+            .filter(|collider| !collider.is_sensor)
+            .map(|collider| {
+                collider.density.unwrap_or(DEFAULT_COLLIDER_DENSITY)
+                    * collider_shape_volume(&collider.shape)
+            })
+            .sum()
+    }
+
+    /// Returns true if mass of a body is the mass calculated from its colliders, and not
+    /// the one that was set explicitly.
+    pub fn is_body_mass_calculated(&self, body: Handle<RigidBody>) -> bool {
+        let calculated = self.calculate_body_mass(body);
+        // Tolerance is relative, because rounding errors grow with mass.
+        (self.bodies[body].mass - calculated).abs() <= 1.0e-5 * calculated.abs().max(1.0)
+    }
+
+    /// Sets mass of a body to the mass calculated from its colliders and returns previous
+    /// mass.
+    pub fn recompute_body_mass(&mut self, body: Handle<RigidBody>) -> f32 {
+        let mass = self.calculate_body_mass(body);
+        std::mem::replace(&mut self.bodies[body].mass, mass)
+    }
+
+    /// Collects bodies bound to nodes of the sub-tree starting from `root`, colliders of
+    /// these bodies and joints that refer to the bodies (as first or as connected body).
+    pub fn entities_in_subtree(
+        &self,
+        graph: &Graph,
+        root: Handle<Node>,
+    ) -> (
+        Vec<Handle<RigidBody>>,
+        Vec<Handle<Collider>>,
+        Vec<Handle<Joint>>,
+    ) {
+        let mut bodies = Vec::new();
+        let mut colliders = Vec::new();
+
+        let mut stack = vec![root];
+        while let Some(node) = stack.pop() {
+            if let Some(&body) = self.binder.value_of(&node) {
+                bodies.push(body);
+                colliders.extend(
+                    self.bodies[body]
+                        .colliders
+                        .iter()
+                        .map(|&collider| Handle::<Collider>::from(collider)),
+                );
+            }
+            stack.extend_from_slice(graph[node].children());
+        }
+
+        let joints = self
+            .joints
+            .pair_iter()
+            .filter(|(_, joint)| {
+                bodies.iter().any(|&body| {
+                    joint.body1 == ErasedHandle::from(body)
+                        || joint.body2 == ErasedHandle::from(body)
+                })
+            })
+            .map(|(handle, _)| handle)
+            .collect();
+
+        (bodies, colliders, joints)
+    }
+
     /// Searches joint by its **first** body.
     pub fn find_joint(&self, body1: Handle<RigidBody>) -> Handle<Joint> {
         for (handle, joint) in self.joints.pair_iter() {
@@ -336,6 +716,91 @@ impl Physics {
         Handle::NONE
     }
 
+    /// Casts a ray against editor-side colliders and returns nearest collider with distance
+    /// to it. Bodies bound to nodes are placed where their nodes are, like in `draw`.
+    /// Trimesh is tested against meshes of the sub-tree of bound node, the same geometry the
+    /// engine builds it from. Heightfield and segment shapes are not hit-tested, round cylinder
+    /// is approximated by a cylinder of the same outer size. Colliders rejected by `filter`
+    /// are ignored.
+    pub fn raycast<F>(
+        &self,
+        graph: &Graph,
+        origin: Vector3<f32>,
+        dir: Vector3<f32>,
+        mut filter: F,
+    ) -> Option<(Handle<Collider>, f32)>
+    where
+        F: FnMut(Handle<Collider>, &Collider) -> bool,
+    {
+        let dir = dir.try_normalize(f32::EPSILON)?;
+
+        let mut closest: Option<(Handle<Collider>, f32)> = None;
+        for (handle, collider) in self.colliders.pair_iter() {
+            if !filter(handle, collider) {
+                continue;
+            }
+
+            let parent: Handle<RigidBody> = collider.parent.into();
+            let body = match self.bodies.try_borrow(parent) {
+                Some(body) => body,
+                None => continue,
+            };
+
+            let bound_node = self.binder.key_of(&parent).cloned();
+
+            if let ColliderShapeDesc::Trimesh(_) = collider.shape {
+                if let Some(distance) =
+                    bound_node.and_then(|node| ray_mesh_subtree(graph, node, origin, dir))
+                {
+                    if closest.map_or(true, |(_, d)| distance < d) {
+                        closest = Some((handle, distance));
+                    }
+                }
+                continue;
+            }
+
+            // Position of a body is relative to its node's parent, so take global transform
+            // of bound node instead.
+            let body_transform = match bound_node {
+                Some(node) => {
+                    let (rotation, position) = graph.isometric_global_rotation_position(node);
+                    Isometry3 {
+                        rotation,
+                        translation: Translation3 { vector: position },
+                    }
+                }
+                None => Isometry3 {
+                    rotation: body.rotation,
+                    translation: Translation3 {
+                        vector: body.position,
+                    },
+                },
+            };
+
+            let transform = body_transform
+                * Isometry3 {
+                    rotation: collider.rotation,
+                    translation: Translation3 {
+                        vector: collider.translation,
+                    },
+                };
+
+            // Do intersection test in collider's space, isometry preserves distances.
+            let local_origin = transform
+                .inverse_transform_point(&Point3::from(origin))
+                .coords;
+            let local_dir = transform.inverse_transform_vector(&dir);
+
+            if let Some(distance) = ray_shape_intersection(&collider.shape, local_origin, local_dir)
+            {
+                if closest.map_or(true, |(_, d)| distance < d) {
+                    closest = Some((handle, distance));
+                }
+            }
+        }
+        closest
+    }
+
     pub fn draw(&self, context: &mut SceneDrawingContext, graph: &Graph) {
         for body in self.bodies.iter() {
             context.draw_transform(
@@ -516,3 +981,467 @@ impl Physics {
         }
     }
 }
+
+// Ray intersection helpers, all of them expect normalized direction and return distance
+// to nearest intersection in front of ray origin.
+
+fn nearest_root(a: f32, b: f32, c: f32) -> Option<(f32, f32)> {
+    if a.abs() < f32::EPSILON {
+        return None;
+    }
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let sqrt = discriminant.sqrt();
+    let t0 = (-b - sqrt) / (2.0 * a);
+    let t1 = (-b + sqrt) / (2.0 * a);
+    Some((t0.min(t1), t0.max(t1)))
+}
+
+fn min_distance(a: Option<f32>, b: Option<f32>) -> Option<f32> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, None) => a,
+        (None, b) => b,
+    }
+}
+
+fn ray_sphere(
+    origin: Vector3<f32>,
+    dir: Vector3<f32>,
+    center: Vector3<f32>,
+    radius: f32,
+) -> Option<f32> {
+    let oc = origin - center;
+    let (t0, t1) = nearest_root(1.0, 2.0 * oc.dot(&dir), oc.norm_squared() - radius * radius)?;
+    if t1 < 0.0 {
+        None
+    } else {
+        Some(t0.max(0.0))
+    }
+}
+
+fn ray_box(origin: Vector3<f32>, dir: Vector3<f32>, half_extents: Vector3<f32>) -> Option<f32> {
+    let mut t_min = f32::NEG_INFINITY;
+    let mut t_max = f32::INFINITY;
+    for i in 0..3 {
+        if dir[i].abs() < f32::EPSILON {
+            if origin[i].abs() > half_extents[i] {
+                return None;
+            }
+        } else {
+            let t0 = (-half_extents[i] - origin[i]) / dir[i];
+            let t1 = (half_extents[i] - origin[i]) / dir[i];
+            t_min = t_min.max(t0.min(t1));
+            t_max = t_max.min(t0.max(t1));
+        }
+    }
+    if t_max < t_min || t_max < 0.0 {
+        None
+    } else {
+        Some(t_min.max(0.0))
+    }
+}
+
+/// Side surface of a cylinder with axis going from `a` to `b`.
+fn ray_cylinder_side(
+    origin: Vector3<f32>,
+    dir: Vector3<f32>,
+    a: Vector3<f32>,
+    b: Vector3<f32>,
+    radius: f32,
+) -> Option<f32> {
+    let axis = (b - a).try_normalize(f32::EPSILON)?;
+    let length = (b - a).norm();
+    let oa = origin - a;
+    let d = dir - axis.scale(dir.dot(&axis));
+    let o = oa - axis.scale(oa.dot(&axis));
+    let (t0, t1) = nearest_root(
+        d.norm_squared(),
+        2.0 * d.dot(&o),
+        o.norm_squared() - radius * radius,
+    )?;
+    [t0, t1].iter().cloned().filter(|&t| t >= 0.0).find(|&t| {
+        let h = (oa + dir.scale(t)).dot(&axis);
+        h >= 0.0 && h <= length
+    })
+}
+
+/// Disc with given center and radius which lies in XZ plane.
+fn ray_disc(origin: Vector3<f32>, dir: Vector3<f32>, y: f32, radius: f32) -> Option<f32> {
+    if dir.y.abs() < f32::EPSILON {
+        return None;
+    }
+    let t = (y - origin.y) / dir.y;
+    let point = origin + dir.scale(t);
+    if t >= 0.0 && point.x * point.x + point.z * point.z <= radius * radius {
+        Some(t)
+    } else {
+        None
+    }
+}
+
+fn ray_cylinder(
+    origin: Vector3<f32>,
+    dir: Vector3<f32>,
+    half_height: f32,
+    radius: f32,
+) -> Option<f32> {
+    let side = ray_cylinder_side(
+        origin,
+        dir,
+        Vector3::new(0.0, -half_height, 0.0),
+        Vector3::new(0.0, half_height, 0.0),
+        radius,
+    );
+    let caps = min_distance(
+        ray_disc(origin, dir, -half_height, radius),
+        ray_disc(origin, dir, half_height, radius),
+    );
+    min_distance(side, caps)
+}
+
+/// Cone with apex at `half_height` and base at `-half_height`, aligned with Y axis.
+fn ray_cone(origin: Vector3<f32>, dir: Vector3<f32>, half_height: f32, radius: f32) -> Option<f32> {
+    let k = radius / (2.0 * half_height);
+    let k2 = k * k;
+    let h = half_height - origin.y;
+    let side = nearest_root(
+        dir.x * dir.x + dir.z * dir.z - k2 * dir.y * dir.y,
+        2.0 * (origin.x * dir.x + origin.z * dir.z + k2 * h * dir.y),
+        origin.x * origin.x + origin.z * origin.z - k2 * h * h,
+    )
+    .and_then(|(t0, t1)| {
+        [t0, t1].iter().cloned().filter(|&t| t >= 0.0).find(|&t| {
+            let y = origin.y + dir.y * t;
+            y >= -half_height && y <= half_height
+        })
+    });
+    min_distance(side, ray_disc(origin, dir, -half_height, radius))
+}
+
+fn ray_triangle(
+    origin: Vector3<f32>,
+    dir: Vector3<f32>,
+    a: Vector3<f32>,
+    b: Vector3<f32>,
+    c: Vector3<f32>,
+) -> Option<f32> {
+    let ab = b - a;
+    let ac = c - a;
+    let p = dir.cross(&ac);
+    let det = ab.dot(&p);
+    if det.abs() < f32::EPSILON {
+        return None;
+    }
+    let inv_det = 1.0 / det;
+    let s = origin - a;
+    let u = s.dot(&p) * inv_det;
+    if u < 0.0 || u > 1.0 {
+        return None;
+    }
+    let q = s.cross(&ab);
+    let v = dir.dot(&q) * inv_det;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+    let t = ac.dot(&q) * inv_det;
+    if t >= 0.0 {
+        Some(t)
+    } else {
+        None
+    }
+}
+
+/// Casts a ray given in world space against triangles of every mesh in a sub-tree and returns
+/// distance to nearest one.
+fn ray_mesh_subtree(
+    graph: &Graph,
+    root: Handle<Node>,
+    origin: Vector3<f32>,
+    dir: Vector3<f32>,
+) -> Option<f32> {
+    let mut closest = None;
+    let mut stack = vec![root];
+    while let Some(handle) = stack.pop() {
+        let node = &graph[handle];
+        if let Node::Mesh(mesh) = node {
+            let transform = mesh.global_transform();
+            for surface in mesh.surfaces() {
+                let data = surface.data();
+                let data = data.read().unwrap();
+                let vertices = data.get_vertices();
+                let world_position = |index: u32| {
+                    transform
+                        .transform_point(&Point3::from(vertices[index as usize].position))
+                        .coords
+                };
+                for triangle in data.triangles() {
+                    closest = min_distance(
+                        closest,
+                        ray_triangle(
+                            origin,
+                            dir,
+                            world_position(triangle[0]),
+                            world_position(triangle[1]),
+                            world_position(triangle[2]),
+                        ),
+                    );
+                }
+            }
+        }
+        stack.extend_from_slice(node.children());
+    }
+    closest
+}
+
+fn ray_shape_intersection(
+    shape: &ColliderShapeDesc,
+    origin: Vector3<f32>,
+    dir: Vector3<f32>,
+) -> Option<f32> {
+    match shape {
+        ColliderShapeDesc::Ball(ball) => ray_sphere(origin, dir, Vector3::default(), ball.radius),
+        ColliderShapeDesc::Cuboid(cuboid) => ray_box(origin, dir, cuboid.half_extents),
+        ColliderShapeDesc::Capsule(capsule) => min_distance(
+            ray_cylinder_side(origin, dir, capsule.begin, capsule.end, capsule.radius),
+            min_distance(
+                ray_sphere(origin, dir, capsule.begin, capsule.radius),
+                ray_sphere(origin, dir, capsule.end, capsule.radius),
+            ),
+        ),
+        ColliderShapeDesc::Cylinder(cylinder) => {
+            ray_cylinder(origin, dir, cylinder.half_height, cylinder.radius)
+        }
+        ColliderShapeDesc::RoundCylinder(round_cylinder) => ray_cylinder(
+            origin,
+            dir,
+            round_cylinder.half_height + round_cylinder.border_radius,
+            round_cylinder.radius + round_cylinder.border_radius,
+        ),
+        ColliderShapeDesc::Cone(cone) => ray_cone(origin, dir, cone.half_height, cone.radius),
+        ColliderShapeDesc::Triangle(triangle) => {
+            ray_triangle(origin, dir, triangle.a, triangle.b, triangle.c)
+        }
+        // Trimesh is tested in world space by `Physics::raycast`.
+        ColliderShapeDesc::Segment(_)
+        | ColliderShapeDesc::Trimesh(_)
+        | ColliderShapeDesc::Heightfield(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rg3d::{core::algebra::UnitQuaternion, scene::base::BaseBuilder};
+
+    fn cuboid(x: f32, y: f32, z: f32) -> ColliderShapeDesc {
+        ColliderShapeDesc::Cuboid(CuboidDesc {
+            half_extents: Vector3::new(x, y, z),
+        })
+    }
+
+    #[test]
+    fn test_collision_layers() {
+        let mut layers = CollisionLayers::default();
+        assert!(layers.set("Player", 0));
+        assert!(layers.set("Enemy", 1));
+        assert_eq!(layers.index_of("Enemy"), Some(1));
+        assert_eq!(layers.name_of(0), Some("Player"));
+
+        // Bit gets new name, name moves to new bit.
+        assert!(layers.set("Npc", 1));
+        assert_eq!(layers.index_of("Enemy"), None);
+        assert!(layers.set("Player", 2));
+        assert_eq!(layers.name_of(0), None);
+        assert_eq!(layers.index_of("Player"), Some(2));
+
+        assert!(!layers.set("Debris", MAX_COLLISION_LAYERS));
+        assert_eq!(layers.index_of("Debris"), None);
+
+        layers.remove("Npc");
+        assert_eq!(layers.name_of(1), None);
+        assert_eq!(layers.iter().count(), 1);
+    }
+
+    #[test]
+    fn test_collision_groups_bits() {
+        let mut groups = 0;
+        assert!(!set_collision_membership(&mut groups, 2, true));
+        assert!(!set_collision_filter(&mut groups, 2, true));
+        assert_eq!(groups, (1 << 18) | (1 << 2));
+
+        assert!(set_collision_membership(&mut groups, 2, false));
+        assert_eq!(groups, 1 << 2);
+        assert!(set_collision_filter(&mut groups, 2, false));
+        assert_eq!(groups, 0);
+    }
+
+    #[test]
+    fn test_collider_shape_volume() {
+        assert!((collider_shape_volume(&cuboid(1.0, 2.0, 3.0)) - 48.0).abs() < 1.0e-4);
+        let ball = ColliderShapeDesc::Ball(BallDesc { radius: 1.0 });
+        assert!((collider_shape_volume(&ball) - 4.0 / 3.0 * std::f32::consts::PI).abs() < 1.0e-4);
+        let segment = ColliderShapeDesc::Segment(SegmentDesc {
+            begin: Vector3::default(),
+            end: Vector3::new(1.0, 0.0, 0.0),
+        });
+        assert_eq!(collider_shape_volume(&segment), 0.0);
+        assert_eq!(
+            collider_shape_volume(&ColliderShapeDesc::Trimesh(TrimeshDesc)),
+            0.0
+        );
+    }
+
+    #[test]
+    fn test_calculate_body_mass() {
+        let mut physics = Physics::default();
+        let body = physics.bodies.spawn(RigidBody::default());
+        for &(density, is_sensor) in [(2.0, false), (3.0, false), (100.0, true)].iter() {
+            let collider = physics.colliders.spawn(Collider {
+                shape: cuboid(0.5, 0.5, 0.5),
+                density: Some(density),
+                is_sensor,
+                parent: body.into(),
+                ..Default::default()
+            });
+            physics.bodies[body].colliders.push(collider.into());
+        }
+
+        // Unit cubes with density 2 and 3, sensor is ignored.
+        assert!((physics.calculate_body_mass(body) - 5.0).abs() < 1.0e-4);
+        assert!(!physics.is_body_mass_calculated(body));
+
+        physics.bodies[body].mass = 3.0;
+        assert_eq!(physics.recompute_body_mass(body), 3.0);
+        assert!(physics.is_body_mass_calculated(body));
+    }
+
+    #[test]
+    fn test_body_mass_with_default_density() {
+        let mut physics = Physics::default();
+        let body = physics.bodies.spawn(RigidBody::default());
+        let collider = physics.colliders.spawn(Collider {
+            shape: cuboid(10.0, 10.0, 10.0),
+            density: None,
+            parent: body.into(),
+            ..Default::default()
+        });
+        physics.bodies[body].colliders.push(collider.into());
+
+        assert!((physics.calculate_body_mass(body) - 8000.0).abs() < 1.0e-2);
+        physics.recompute_body_mass(body);
+        // Big masses have rounding errors bigger than f32::EPSILON.
+        physics.bodies[body].mass += 1.0e-3;
+        assert!(physics.is_body_mass_calculated(body));
+        physics.bodies[body].mass += 1.0;
+        assert!(!physics.is_body_mass_calculated(body));
+    }
+
+    #[test]
+    fn test_convert_collider_shape() {
+        let ball = ColliderShapeDesc::Ball(BallDesc { radius: 1.0 });
+        let cuboid = convert_collider_shape(&ball, 4);
+        let capsule = convert_collider_shape(&cuboid, 5);
+        match convert_collider_shape(&capsule, 0) {
+            ColliderShapeDesc::Ball(ball) => assert_eq!(ball.radius, 1.0),
+            _ => panic!("must be a ball"),
+        }
+
+        // Trimesh has no natural size, so default shape is made.
+        match convert_collider_shape(&ColliderShapeDesc::Trimesh(TrimeshDesc), 0) {
+            ColliderShapeDesc::Ball(ball) => assert_eq!(ball.radius, 0.5),
+            _ => panic!("must be a ball"),
+        }
+        for id in 0..10 {
+            assert_eq!(default_collider_shape(id).id() as usize, id);
+        }
+    }
+
+    #[test]
+    fn test_raycast() {
+        let mut physics = Physics::default();
+        let mut add = |position: Vector3<f32>, rotation: UnitQuaternion<f32>, shape| {
+            let body = physics.bodies.spawn(RigidBody {
+                position,
+                rotation,
+                ..Default::default()
+            });
+            let collider = physics.colliders.spawn(Collider {
+                shape,
+                parent: body.into(),
+                ..Default::default()
+            });
+            physics.bodies[body].colliders.push(collider.into());
+            collider
+        };
+        let ball = add(
+            Vector3::new(0.0, 0.0, 10.0),
+            UnitQuaternion::identity(),
+            ColliderShapeDesc::Ball(BallDesc { radius: 1.0 }),
+        );
+        // Rotated by 45 degrees, so the ray hits its edge.
+        let cuboid = add(
+            Vector3::new(0.0, 0.0, 5.0),
+            UnitQuaternion::from_axis_angle(&Vector3::y_axis(), std::f32::consts::FRAC_PI_4),
+            cuboid(1.0, 1.0, 1.0),
+        );
+        let graph = Graph::new();
+
+        let (hit, distance) = physics
+            .raycast(&graph, Vector3::default(), Vector3::z(), |_, _| true)
+            .unwrap();
+        assert_eq!(hit, cuboid);
+        assert!((distance - (5.0 - std::f32::consts::SQRT_2)).abs() < 1.0e-4);
+
+        let (hit, distance) = physics
+            .raycast(&graph, Vector3::default(), Vector3::z(), |h, _| h != cuboid)
+            .unwrap();
+        assert_eq!(hit, ball);
+        assert!((distance - 9.0).abs() < 1.0e-4);
+
+        assert!(physics
+            .raycast(&graph, Vector3::default(), Vector3::x(), |_, _| true)
+            .is_none());
+    }
+
+    #[test]
+    fn test_entities_in_subtree() {
+        let mut graph = Graph::new();
+        let mut physics = Physics::default();
+        let mut bind = |graph: &mut Graph, children: &[Handle<Node>]| {
+            let node = BaseBuilder::new().with_children(children).build(graph);
+            let body = physics.bodies.spawn(RigidBody::default());
+            let collider = physics.colliders.spawn(Collider {
+                parent: body.into(),
+                ..Default::default()
+            });
+            physics.bodies[body].colliders.push(collider.into());
+            physics.binder.insert(node, body);
+            (node, body, collider)
+        };
+        let (child, child_body, child_collider) = bind(&mut graph, &[]);
+        let (root, root_body, root_collider) = bind(&mut graph, &[child]);
+        let (_, other_body, _) = bind(&mut graph, &[]);
+        let (_, another_body, _) = bind(&mut graph, &[]);
+        // Joint connects the sub-tree with the rest of the scene, so it is found too.
+        let joint = physics.joints.spawn(Joint {
+            body1: other_body.into(),
+            body2: child_body.into(),
+            ..Default::default()
+        });
+        physics.joints.spawn(Joint {
+            body1: other_body.into(),
+            body2: another_body.into(),
+            ..Default::default()
+        });
+
+        let (mut bodies, mut colliders, joints) = physics.entities_in_subtree(&graph, root);
+        bodies.sort_by_key(|body| body.index());
+        colliders.sort_by_key(|collider| collider.index());
+        assert_eq!(bodies, [child_body, root_body]);
+        assert_eq!(colliders, [child_collider, root_collider]);
+        assert_eq!(joints, [joint]);
+    }
+}
@@ -1,43 +1,71 @@
 use crate::{
-    camera::CameraController,
+    camera::{CameraController, CameraPose},
     command::Command,
     interaction::navmesh::{
         data_model::{Navmesh, NavmeshEdge, NavmeshEntity, NavmeshTriangle, NavmeshVertex},
         selection::NavmeshSelection,
     },
-    physics::{Collider, Joint, Physics, RigidBody},
+    physics::{
+        collider_shape_volume, scale_collider_shape, set_collision_filter,
+        set_collision_membership, Collider, CollisionLayers, Joint, Physics, RigidBody,
+        MAX_COLLISION_LAYERS,
+    },
     GameEngine, Message,
 };
 use rg3d::scene::base::{LevelOfDetail, LodGroup};
 use rg3d::{
-    animation::Animation,
+    animation::{Animation, AnimationContainer},
     core::{
-        algebra::{UnitQuaternion, Vector3},
+        algebra::{
+            Matrix3, Matrix4, Point3, Quaternion, Rotation3, UnitQuaternion, Vector2, Vector3,
+        },
         color::Color,
-        math::Matrix4Ext,
+        math::{aabb::AxisAlignedBoundingBox, frustum::Frustum, Matrix4Ext, Rect},
         numeric_range::NumericRange,
         pool::{ErasedHandle, Handle, Pool, Ticket},
-        visitor::{Visit, Visitor},
+        visitor::{Visit, VisitResult, Visitor},
     },
     engine::resource_manager::ResourceManager,
-    resource::texture::Texture,
+    renderer::surface::{Surface, SurfaceSharedData, Vertex},
+    resource::{
+        model::Model,
+        texture::{Texture, TextureState},
+        ResourceState,
+    },
     scene::{
-        base::PhysicsBinding,
+        base::{BaseBuilder, PhysicsBinding},
+        camera::{CameraBuilder, SkyBox},
         graph::{Graph, SubGraph},
-        mesh::{Mesh, RenderPath},
+        light::{
+            BaseLightBuilder, DirectionalLightBuilder, Light, PointLightBuilder, SpotLightBuilder,
+        },
+        mesh::{Mesh, MeshBuilder, RenderPath},
         node::Node,
-        particle_system::{Emitter, ParticleLimit, ParticleSystem},
-        physics::{ColliderShapeDesc, JointParamsDesc},
-        Scene,
+        particle_system::{
+            BaseEmitter, BaseEmitterBuilder, BoxEmitterBuilder, CylinderEmitterBuilder, Emitter,
+            ParticleLimit, ParticleSystem, SphereEmitterBuilder,
+        },
+        physics::{ColliderShapeDesc, CuboidDesc, JointParamsDesc, TrimeshDesc},
+        transform::{Transform, TransformBuilder},
+        Line, Scene, SceneDrawingContext,
     },
     sound::math::TriangleDefinition,
 };
-use std::{collections::HashMap, fmt::Write, path::PathBuf, sync::mpsc::Sender};
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{Debug, Formatter, Write},
+    path::{Path, PathBuf},
+    sync::{mpsc::Sender, Arc, RwLock},
+};
 
 pub struct Clipboard {
     graph: Graph,
     physics: Physics,
     empty: bool,
+    // Maps handles of copied entities to handles of their copies in clipboard.
+    source_to_clipboard: HashMap<ErasedHandle, ErasedHandle>,
 }
 
 impl Default for Clipboard {
@@ -46,10 +74,17 @@ impl Default for Clipboard {
             graph: Graph::new(),
             physics: Default::default(),
             empty: true,
+            source_to_clipboard: Default::default(),
         }
     }
 }
 
+impl Debug for Clipboard {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Clipboard")
+    }
+}
+
 #[derive(Default, Debug)]
 pub struct DeepCloneResult {
     root_nodes: Vec<Handle<Node>>,
@@ -57,6 +92,8 @@ pub struct DeepCloneResult {
     bodies: Vec<Handle<RigidBody>>,
     joints: Vec<Handle<Joint>>,
     binder: HashMap<Handle<Node>, Handle<RigidBody>>,
+    /// Maps handles of source nodes, bodies, colliders and joints to handles of their copies.
+    pub old_to_new: HashMap<ErasedHandle, ErasedHandle>,
 }
 
 fn deep_clone_nodes(
@@ -83,39 +120,72 @@ fn deep_clone_nodes(
         .map(|n| *old_new_mapping.get(n).unwrap())
         .collect::<Vec<_>>();
 
-    // Copy associated bodies, colliders, joints.
-    for &root_node in root_nodes.iter() {
-        for descendant in source_graph.traverse_handle_iter(root_node) {
-            // Copy body too if we have any.
-            if let Some(&body) = source_physics.binder.value_of(&descendant) {
-                let body = &source_physics.bodies[body];
-                let mut body_clone = body.clone();
-                body_clone.colliders.clear();
-                let body_clone_handle = dest_physics.bodies.spawn(body_clone);
-
-                result.bodies.push(body_clone_handle);
-
-                // Also copy colliders.
-                for &collider in body.colliders.iter() {
-                    let mut collider_clone = source_physics.colliders[collider.into()].clone();
-                    collider_clone.parent = body_clone_handle.into();
-                    let collider_clone_handle = dest_physics.colliders.spawn(collider_clone);
-                    dest_physics.bodies[body_clone_handle]
-                        .colliders
-                        .push(collider_clone_handle.into());
+    for (&old, &new) in old_new_mapping.iter() {
+        result.old_to_new.insert(old.into(), new.into());
+    }
 
-                    result.colliders.push(collider_clone_handle);
-                }
+    // Gather nodes with bodies and sort them by handles of bodies, so copies will have the
+    // same layout of handles no matter in which order nodes were selected.
+    let mut bound_nodes = root_nodes
+        .iter()
+        .flat_map(|&root_node| source_graph.traverse_handle_iter(root_node))
+        .filter_map(|node| {
+            source_physics
+                .binder
+                .value_of(&node)
+                .map(|&body| (node, body))
+        })
+        .collect::<Vec<_>>();
+    bound_nodes.sort_by_key(|&(_, body)| body.index());
 
-                let new_node = *old_new_mapping.get(&descendant).unwrap();
-                result.binder.insert(new_node, body_clone_handle);
-                dest_physics.binder.insert(new_node, body_clone_handle);
-            }
+    // Copy associated bodies, colliders, joints.
+    for (node, body_handle) in bound_nodes {
+        let body = &source_physics.bodies[body_handle];
+        let mut body_clone = body.clone();
+        body_clone.colliders.clear();
+        let body_clone_handle = dest_physics.bodies.spawn(body_clone);
+        result
+            .old_to_new
+            .insert(body_handle.into(), body_clone_handle.into());
+
+        result.bodies.push(body_clone_handle);
+
+        // Also copy colliders.
+        for &collider in body.colliders.iter() {
+            let mut collider_clone = source_physics.colliders[collider.into()].clone();
+            collider_clone.parent = body_clone_handle.into();
+            let collider_clone_handle = dest_physics.colliders.spawn(collider_clone);
+            dest_physics.bodies[body_clone_handle]
+                .colliders
+                .push(collider_clone_handle.into());
+
+            result.colliders.push(collider_clone_handle);
+            result
+                .old_to_new
+                .insert(collider, collider_clone_handle.into());
         }
+
+        let new_node = *old_new_mapping.get(&node).unwrap();
+        result.binder.insert(new_node, body_clone_handle);
+        dest_physics.binder.insert(new_node, body_clone_handle);
     }
 
-    // TODO: Add joints.
     // Joint will be copied only if both of its associated bodies are copied too.
+    for (joint_handle, joint) in source_physics.joints.pair_iter() {
+        if let (Some(&body1), Some(&body2)) = (
+            result.old_to_new.get(&joint.body1),
+            result.old_to_new.get(&joint.body2),
+        ) {
+            let mut joint_clone = joint.clone();
+            joint_clone.body1 = body1;
+            joint_clone.body2 = body2;
+            let joint_clone_handle = dest_physics.joints.spawn(joint_clone);
+            result.joints.push(joint_clone_handle);
+            result
+                .old_to_new
+                .insert(joint_handle.into(), joint_clone_handle.into());
+        }
+    }
 
     result
 }
@@ -124,23 +194,21 @@ impl Clipboard {
     pub fn fill_from_selection(
         &mut self,
         selection: &GraphSelection,
-        scene_handle: Handle<Scene>,
+        graph: &Graph,
         physics: &Physics,
-        engine: &GameEngine,
     ) {
         self.clear();
 
-        let scene = &engine.scenes[scene_handle];
-
-        let root_nodes = selection.root_nodes(&scene.graph);
+        let root_nodes = selection.root_nodes(graph);
 
-        deep_clone_nodes(
+        self.source_to_clipboard = deep_clone_nodes(
             &root_nodes,
-            &scene.graph,
+            graph,
             physics,
             &mut self.graph,
             &mut self.physics,
-        );
+        )
+        .old_to_new;
 
         self.empty = false;
     }
@@ -148,23 +216,268 @@ impl Clipboard {
     pub fn paste(&mut self, dest_graph: &mut Graph, dest_physics: &mut Physics) -> DeepCloneResult {
         assert_ne!(self.empty, true);
 
-        deep_clone_nodes(
+        let mut result = deep_clone_nodes(
             self.graph[self.graph.get_root()].children(),
             &self.graph,
             &self.physics,
             dest_graph,
             dest_physics,
-        )
+        );
+
+        // Map handles of original entities to pasted ones, clipboard is just an intermediate
+        // storage and its handles are meaningless for outer world.
+        result.old_to_new = self
+            .source_to_clipboard
+            .iter()
+            .filter_map(|(source, clipboard)| {
+                result
+                    .old_to_new
+                    .get(clipboard)
+                    .map(|&pasted| (*source, pasted))
+            })
+            .collect();
+
+        result
     }
 
     pub fn is_empty(&self) -> bool {
         self.empty
     }
 
+    /// Forgets handles of copied entities in the source scene. Content of clipboard does not
+    /// depend on the source scene (nodes and physics entities are copied, resources are shared),
+    /// so it can be pasted into any scene, but handles of source scene are meaningless for
+    /// other scenes.
+    pub fn forget_source(&mut self) {
+        self.source_to_clipboard.clear();
+    }
+
     pub fn clear(&mut self) {
         self.empty = true;
         self.graph = Graph::new();
         self.physics = Default::default();
+        self.source_to_clipboard.clear();
+    }
+}
+
+/// UV sub-rectangle of particle system texture used by particles of an emitter. Particles of
+/// the engine have no texture coordinates that could be changed, so regions can't be baked
+/// into the saved scene: they're saved as a separate table, indices in the table refer to
+/// emitters of the saved scene (after `EditorScene::split_overridden_emitters`), so a game
+/// could apply the regions.
+#[derive(Debug, Clone, Default)]
+pub struct EmitterTextureRegion {
+    pub particle_system: Handle<Node>,
+    pub emitter_index: u32,
+    pub rect: Rect<f32>,
+}
+
+impl Visit for EmitterTextureRegion {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.particle_system.visit("ParticleSystem", visitor)?;
+        self.emitter_index.visit("EmitterIndex", visitor)?;
+        self.rect.visit("Rect", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+/// Acceleration (gravity) used by particles of an emitter instead of the acceleration of
+/// whole particle system. The engine has acceleration per particle system only, so on save
+/// overridden emitters are moved into child particle systems with desired acceleration (see
+/// `EditorScene::split_overridden_emitters`). Overrides are saved as a separate table as
+/// well, indices in the table refer to emitters of the saved scene.
+#[derive(Debug, Clone, Default)]
+pub struct EmitterAccelerationOverride {
+    pub particle_system: Handle<Node>,
+    pub emitter_index: u32,
+    pub acceleration: Vector3<f32>,
+}
+
+impl Visit for EmitterAccelerationOverride {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.particle_system.visit("ParticleSystem", visitor)?;
+        self.emitter_index.visit("EmitterIndex", visitor)?;
+        self.acceleration.visit("Acceleration", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+/// Render path override of a surface of a mesh, see [`SetSurfaceRenderPathOverrideCommand`].
+/// Overrides are baked into the scene on save, so this table only lets the editor show them
+/// after the scene is loaded again.
+#[derive(Debug, Clone, Default)]
+pub struct SurfaceRenderPathOverride {
+    pub mesh: Handle<Node>,
+    pub surface_index: u32,
+    pub render_path: RenderPath,
+}
+
+impl Visit for SurfaceRenderPathOverride {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.mesh.visit("Mesh", visitor)?;
+        self.surface_index.visit("SurfaceIndex", visitor)?;
+        self.render_path.visit("RenderPath", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+/// Weight of a blend shape of a mesh. Meshes of the engine have no blend shapes, so weights
+/// are saved as a separate table, which could be applied by a game.
+#[derive(Debug, Clone, Default)]
+pub struct BlendShapeWeight {
+    pub mesh: Handle<Node>,
+    pub shape_index: u32,
+    pub weight: f32,
+}
+
+impl Visit for BlendShapeWeight {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.mesh.visit("Mesh", visitor)?;
+        self.shape_index.visit("ShapeIndex", visitor)?;
+        self.weight.visit("Weight", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+/// Query of [`EditorScene::find_nodes`].
+#[derive(Debug, Clone, Default)]
+pub struct NodeQuery {
+    /// Substring of a name or whole tag of a node.
+    pub text: String,
+    pub case_sensitive: bool,
+}
+
+/// Defines which nodes are selected by [`GraphSelection::from_frustum`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FrustumSelectionMode {
+    /// Node is selected if its bounding box intersects frustum.
+    Intersection,
+    /// Node is selected only if its bounding box is fully inside of frustum.
+    Containment,
+}
+
+/// Calculates bounding box of a node in world coordinates. Nodes without geometry have unit
+/// bounding box.
+pub fn world_aabb(graph: &Graph, handle: Handle<Node>) -> AxisAlignedBoundingBox {
+    let node = &graph[handle];
+    let local_aabb = match node {
+        Node::Mesh(mesh) => mesh.bounding_box(),
+        _ => AxisAlignedBoundingBox::unit(),
+    };
+    let transform = node.global_transform();
+    let corners = local_aabb
+        .corners()
+        .iter()
+        .map(|corner| transform.transform_point(&Point3::from(*corner)).coords)
+        .collect::<Vec<_>>();
+    AxisAlignedBoundingBox::from_points(&corners)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+pub enum NodeKind {
+    Base,
+    Light,
+    Camera,
+    Mesh,
+    Sprite,
+    ParticleSystem,
+}
+
+impl NodeKind {
+    pub fn of(node: &Node) -> Self {
+        match node {
+            Node::Base(_) => Self::Base,
+            Node::Light(_) => Self::Light,
+            Node::Camera(_) => Self::Camera,
+            Node::Mesh(_) => Self::Mesh,
+            Node::Sprite(_) => Self::Sprite,
+            Node::ParticleSystem(_) => Self::ParticleSystem,
+        }
+    }
+}
+
+/// Entry of human-readable scene hierarchy dump, see [`EditorScene::export_hierarchy_ron`].
+/// Nodes are identified by indices in the graph, so nodes with same names could be told
+/// apart.
+#[derive(Debug, Serialize)]
+struct HierarchyEntry {
+    index: u32,
+    name: String,
+    kind: NodeKind,
+    parent: Option<u32>,
+    position: (f32, f32, f32),
+    rotation: (f32, f32, f32, f32),
+    scale: (f32, f32, f32),
+    body: Option<u32>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ValidationSeverity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ValidationSubject {
+    Node(Handle<Node>),
+    Body(Handle<RigidBody>),
+    Collider(Handle<Collider>),
+    Joint(Handle<Joint>),
+    Navmesh(Handle<Navmesh>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationIssue {
+    pub severity: ValidationSeverity,
+    pub subject: ValidationSubject,
+    pub message: String,
+}
+
+/// Summary of a scene content, editor nodes are not counted.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SceneStatistics {
+    pub base_nodes: usize,
+    pub point_lights: usize,
+    pub spot_lights: usize,
+    pub directional_lights: usize,
+    pub cameras: usize,
+    pub meshes: usize,
+    pub sprites: usize,
+    pub particle_systems: usize,
+    pub emitters: usize,
+    pub triangles: usize,
+    pub bodies: usize,
+    pub colliders: usize,
+    pub joints: usize,
+    pub navmesh_triangles: usize,
+    /// Estimated amount of memory (in bytes) occupied by unique loaded textures.
+    pub texture_memory: usize,
+}
+
+impl SceneStatistics {
+    pub fn light_count(&self) -> usize {
+        self.point_lights + self.spot_lights + self.directional_lights
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.base_nodes
+            + self.light_count()
+            + self.cameras
+            + self.meshes
+            + self.sprites
+            + self.particle_systems
     }
 }
 
@@ -179,121 +492,1425 @@ pub struct EditorScene {
     // Editor uses split data model - some parts of scene are editable directly,
     // but some parts are not because of incompatible data model.
     pub physics: Physics,
+    pub collision_layers: CollisionLayers,
     pub navmeshes: Pool<Navmesh>,
+    // Render paths of individual surfaces, see `SetSurfaceRenderPathOverrideCommand`.
+    pub surface_render_paths: HashMap<(Handle<Node>, usize), RenderPath>,
+    // UV sub-rectangles of particle system texture used by emitters, see
+    // `SetEmitterTextureRegionCommand`.
+    pub emitter_texture_regions: HashMap<(Handle<Node>, usize), Rect<f32>>,
+    // Per-emitter accelerations, see `SetEmitterAccelerationOverrideCommand`.
+    pub emitter_acceleration_overrides: HashMap<(Handle<Node>, usize), Vector3<f32>>,
+    // Blend shape weights of meshes, see `SetMeshBlendShapeWeightCommand`.
+    pub blend_shape_weights: HashMap<(Handle<Node>, usize), f32>,
+    // Linked duplicates mapped to their sources, see `make_linked_duplicate_command`.
+    pub linked_instances: HashMap<Handle<Node>, Handle<Node>>,
+    // Nodes marked as static (or explicitly as dynamic), see `SetNodeStaticCommand`.
+    pub static_nodes: HashMap<Handle<Node>, bool>,
+    // Meshes drawn with wireframe overlay, see `SetNodeWireframeCommand`.
+    pub wireframe_nodes: HashSet<Handle<Node>>,
+    // Particle systems that were disabled by global pause, see `set_particle_systems_paused`.
+    pub paused_particle_systems: Vec<Handle<Node>>,
+    // Animations that were disabled by global pause, see `set_animations_paused`.
+    pub paused_animations: Vec<Handle<Animation>>,
+    // Models that are being loaded in background, see `request_model`.
+    pub pending_models: Vec<(PathBuf, Model)>,
+}
+
+/// Editor data of an emitter stored in side tables of [`EditorScene`], it is kept by
+/// [`DeleteEmitterCommand`] to restore the data on undo.
+#[derive(Debug, Default)]
+pub struct EmitterEditorData {
+    texture_region: Option<Rect<f32>>,
+    acceleration_override: Option<Vector3<f32>>,
+}
+
+/// Side tables of emitters are keyed by emitter index, this function changes index of every
+/// key of given particle system with `remap`.
+fn remap_emitter_keys<V>(
+    table: &mut HashMap<(Handle<Node>, usize), V>,
+    particle_system: Handle<Node>,
+    remap: &dyn Fn(usize) -> usize,
+) {
+    let keys = table
+        .keys()
+        .filter(|(node, _)| *node == particle_system)
+        .cloned()
+        .collect::<Vec<_>>();
+    let entries = keys
+        .into_iter()
+        .filter_map(|key| table.remove(&key).map(|value| (key.1, value)))
+        .collect::<Vec<_>>();
+    for (index, value) in entries {
+        table.insert((particle_system, remap(index)), value);
+    }
 }
 
 impl EditorScene {
-    pub fn save(&mut self, path: PathBuf, engine: &mut GameEngine) -> Result<String, String> {
-        let scene = &mut engine.scenes[self.scene];
+    /// Name of a region of saved scene where static flags of nodes are stored.
+    pub const STATIC_NODES_REGION_NAME: &'static str = "StaticNodes";
+
+    /// Reads static flags of nodes from a saved scene. Scenes saved without the flags
+    /// will give empty map.
+    pub fn load_static_nodes(visitor: &mut Visitor) -> HashMap<Handle<Node>, bool> {
+        let mut static_nodes = HashMap::new();
+        if static_nodes
+            .visit(Self::STATIC_NODES_REGION_NAME, visitor)
+            .is_err()
+        {
+            static_nodes.clear();
+        }
+        static_nodes
+    }
 
-        // Validate first.
-        let mut valid = true;
-        let mut reason = "Scene is not saved, because validation failed:\n".to_owned();
+    /// Name of a region of saved scene where links of linked duplicates to their sources
+    /// are stored.
+    pub const LINKED_INSTANCES_REGION_NAME: &'static str = "LinkedInstances";
 
-        for joint in self.physics.joints.iter() {
-            if joint.body1.is_none() || joint.body2.is_none() {
-                let mut associated_node = Handle::NONE;
-                for (&node, &body) in self.physics.binder.forward_map().iter() {
-                    if body == joint.body1.into() {
-                        associated_node = node;
-                        break;
+    /// Reads links of linked duplicates from a saved scene. Scenes saved without links will
+    /// give empty map.
+    ///
+    /// Geometry of loaded meshes is not shared anymore, so surfaces of every duplicate are
+    /// linked to surface data of its source again. Links of nodes that are not meshes anymore
+    /// or have different amount of surfaces than their sources are dropped.
+    pub fn load_linked_instances(
+        visitor: &mut Visitor,
+        graph: &mut Graph,
+    ) -> HashMap<Handle<Node>, Handle<Node>> {
+        let mut linked_instances = HashMap::<Handle<Node>, Handle<Node>>::new();
+        if linked_instances
+            .visit(Self::LINKED_INSTANCES_REGION_NAME, visitor)
+            .is_err()
+        {
+            linked_instances.clear();
+        }
+        linked_instances.retain(|&instance, &mut source| {
+            if !graph.is_valid_handle(source) || !graph.is_valid_handle(instance) {
+                return false;
+            }
+            let source_surfaces = match &graph[source] {
+                Node::Mesh(source) => source.surfaces().to_vec(),
+                _ => return false,
+            };
+            match &mut graph[instance] {
+                Node::Mesh(instance) if instance.surfaces().len() == source_surfaces.len() => {
+                    for (surface, source_surface) in
+                        instance.surfaces_mut().iter_mut().zip(source_surfaces)
+                    {
+                        // Clone of source surface shares its data, keep own material.
+                        let mut linked = source_surface;
+                        linked.set_diffuse_texture(surface.diffuse_texture());
+                        linked.set_normal_texture(surface.normal_texture());
+                        linked.set_color(surface.color());
+                        *surface = linked;
                     }
+                    true
                 }
+                _ => false,
+            }
+        });
+        linked_instances
+    }
 
-                writeln!(
-                    &mut reason,
-                    "Invalid joint on node {} ({}:{}). Associated body is missing!",
-                    scene.graph[associated_node].name(),
-                    associated_node.index(),
-                    associated_node.generation()
+    /// Returns every mesh linked with given one: source of linked duplicates and all its
+    /// valid duplicates, see [`make_linked_duplicate_command`]. If the node is not linked,
+    /// only the node itself is returned.
+    pub fn linked_group(&self, node: Handle<Node>, graph: &Graph) -> Vec<Handle<Node>> {
+        let is_mesh =
+            |handle| graph.is_valid_handle(handle) && matches!(graph[handle], Node::Mesh(_));
+
+        let source = self.linked_instances.get(&node).cloned().unwrap_or(node);
+        if !is_mesh(source) {
+            // Source was deleted, the link is broken.
+            return vec![node];
+        }
+
+        let mut group = vec![source];
+        group.extend(
+            self.linked_instances
+                .iter()
+                .filter(|&(&instance, &instance_source)| {
+                    instance_source == source && instance != source && is_mesh(instance)
+                })
+                .map(|(&instance, _)| instance),
+        );
+        group
+    }
+
+    /// Name of a region of saved scene where regions of navmesh triangles are stored. The
+    /// engine's navmesh has no place for them, so they're stored as a separate table: one
+    /// array per navmesh, indexed by triangle index.
+    pub const NAVMESH_REGIONS_REGION_NAME: &'static str = "NavmeshRegions";
+
+    /// Reads regions of navmesh triangles from a saved scene. Scenes saved without regions
+    /// will give empty table.
+    pub fn load_navmesh_regions(visitor: &mut Visitor) -> Vec<Vec<u8>> {
+        let mut regions = Vec::new();
+        if regions
+            .visit(Self::NAVMESH_REGIONS_REGION_NAME, visitor)
+            .is_err()
+        {
+            regions.clear();
+        }
+        regions
+    }
+
+    /// Pauses (or resumes) simulation of every enabled particle system in preview. This is
+    /// not a command, pause is not a part of the scene and saved scene will have particle
+    /// systems enabled as if there were no pause.
+    pub fn set_particle_systems_paused(&mut self, graph: &mut Graph, paused: bool) {
+        if paused {
+            if self.paused_particle_systems.is_empty() {
+                self.paused_particle_systems = graph
+                    .pair_iter()
+                    .filter_map(|(handle, node)| match node {
+                        Node::ParticleSystem(particle_system) if particle_system.is_enabled() => {
+                            Some(handle)
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                for &handle in self.paused_particle_systems.iter() {
+                    graph[handle].as_particle_system_mut().set_enabled(false);
+                }
+            }
+        } else {
+            for handle in self.paused_particle_systems.drain(..) {
+                if graph.is_valid_handle(handle) {
+                    if let Node::ParticleSystem(particle_system) = &mut graph[handle] {
+                        particle_system.set_enabled(true);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn is_particle_systems_paused(&self) -> bool {
+        !self.paused_particle_systems.is_empty()
+    }
+
+    /// Pauses (or resumes) every enabled animation in preview. Same as particle systems pause,
+    /// it is not a command and saved scene will have animations enabled.
+    pub fn set_animations_paused(&mut self, animations: &mut AnimationContainer, paused: bool) {
+        if paused {
+            if self.paused_animations.is_empty() {
+                self.paused_animations = animations
+                    .pair_iter()
+                    .filter(|(_, animation)| animation.is_enabled())
+                    .map(|(handle, _)| handle)
+                    .collect();
+                for &handle in self.paused_animations.iter() {
+                    animations[handle].set_enabled(false);
+                }
+            }
+        } else {
+            for handle in self.paused_animations.drain(..) {
+                // Animation could be removed while paused.
+                if animations.pair_iter().any(|(h, _)| h == handle) {
+                    animations[handle].set_enabled(true);
+                }
+            }
+        }
+    }
+
+    pub fn is_animations_paused(&self) -> bool {
+        !self.paused_animations.is_empty()
+    }
+
+    /// Copies editor-only data (side tables) of copied nodes to their pasted copies, `mapping`
+    /// is the one from `Message::NodesPasted`. Links of linked duplicates are copied too, a
+    /// pasted instance is linked to pasted copy of its source if the source was pasted as well.
+    pub fn copy_pasted_node_data(&mut self, mapping: &HashMap<ErasedHandle, ErasedHandle>) {
+        let pasted = |node: Handle<Node>| {
+            mapping
+                .get(&ErasedHandle::from(node))
+                .map(|&new_node| Handle::<Node>::from(new_node))
+        };
+
+        fn copy_indexed<V: Clone>(
+            table: &mut HashMap<(Handle<Node>, usize), V>,
+            pasted: &dyn Fn(Handle<Node>) -> Option<Handle<Node>>,
+        ) {
+            let copies = table
+                .iter()
+                .filter_map(|(&(node, index), value)| {
+                    pasted(node).map(|new_node| ((new_node, index), value.clone()))
+                })
+                .collect::<Vec<_>>();
+            table.extend(copies);
+        }
+
+        copy_indexed(&mut self.surface_render_paths, &pasted);
+        copy_indexed(&mut self.emitter_texture_regions, &pasted);
+        copy_indexed(&mut self.emitter_acceleration_overrides, &pasted);
+        copy_indexed(&mut self.blend_shape_weights, &pasted);
+
+        let static_nodes = self
+            .static_nodes
+            .iter()
+            .filter_map(|(&node, &is_static)| pasted(node).map(|new_node| (new_node, is_static)))
+            .collect::<Vec<_>>();
+        self.static_nodes.extend(static_nodes);
+
+        let wireframe_nodes = self
+            .wireframe_nodes
+            .iter()
+            .filter_map(|&node| pasted(node))
+            .collect::<Vec<_>>();
+        self.wireframe_nodes.extend(wireframe_nodes);
+
+        let linked_instances = self
+            .linked_instances
+            .iter()
+            .filter_map(|(&instance, &source)| {
+                pasted(instance)
+                    .map(|new_instance| (new_instance, pasted(source).unwrap_or(source)))
+            })
+            .collect::<Vec<_>>();
+        self.linked_instances.extend(linked_instances);
+    }
+
+    /// Starts loading of a model in background, so large models won't freeze the editor.
+    /// When the model is loaded, `Message::ModelLoaded` is sent by `poll_pending_models`
+    /// and the model is instantiated by `LoadModelCommand` created from the loaded model.
+    ///
+    /// Loading of a model does not put anything in command stack until the model is loaded,
+    /// so undo while a model is loading reverts previous command as usual and the model is
+    /// still added when it is loaded.
+    pub fn request_model(&mut self, path: PathBuf, resource_manager: &ResourceManager) {
+        let model = resource_manager.request_model(&path);
+        self.pending_models.push((path, model));
+    }
+
+    /// Checks state of pending models, sends `Message::ModelLoaded` for every loaded model
+    /// and reports models that failed to load. Messages are tagged with the scene, so models
+    /// loaded after the scene was closed won't be added to other scene.
+    pub fn poll_pending_models(&mut self, sender: &Sender<Message>) {
+        poll_models(&mut self.pending_models, self.scene, sender);
+    }
+
+    /// Moves editor data of an emitter together with the emitter, see [`MoveEmitterCommand`].
+    pub fn move_emitter_data(&mut self, particle_system: Handle<Node>, from: usize, to: usize) {
+        let remap = |i: usize| {
+            if i == from {
+                to
+            } else if from < to && i > from && i <= to {
+                i - 1
+            } else if to < from && i >= to && i < from {
+                i + 1
+            } else {
+                i
+            }
+        };
+        remap_emitter_keys(&mut self.emitter_texture_regions, particle_system, &remap);
+        remap_emitter_keys(
+            &mut self.emitter_acceleration_overrides,
+            particle_system,
+            &remap,
+        );
+    }
+
+    /// Removes editor data of an emitter which is being deleted, indices of data of next
+    /// emitters are shifted, see [`DeleteEmitterCommand`].
+    pub fn remove_emitter_data(
+        &mut self,
+        particle_system: Handle<Node>,
+        index: usize,
+    ) -> EmitterEditorData {
+        let key = (particle_system, index);
+        let data = EmitterEditorData {
+            texture_region: self.emitter_texture_regions.remove(&key),
+            acceleration_override: self.emitter_acceleration_overrides.remove(&key),
+        };
+        let remap = |i: usize| if i > index { i - 1 } else { i };
+        remap_emitter_keys(&mut self.emitter_texture_regions, particle_system, &remap);
+        remap_emitter_keys(
+            &mut self.emitter_acceleration_overrides,
+            particle_system,
+            &remap,
+        );
+        data
+    }
+
+    /// Puts back editor data of an emitter removed by [`Self::remove_emitter_data`].
+    pub fn insert_emitter_data(
+        &mut self,
+        particle_system: Handle<Node>,
+        index: usize,
+        data: EmitterEditorData,
+    ) {
+        let remap = |i: usize| if i >= index { i + 1 } else { i };
+        remap_emitter_keys(&mut self.emitter_texture_regions, particle_system, &remap);
+        remap_emitter_keys(
+            &mut self.emitter_acceleration_overrides,
+            particle_system,
+            &remap,
+        );
+        let key = (particle_system, index);
+        if let Some(rect) = data.texture_region {
+            self.emitter_texture_regions.insert(key, rect);
+        }
+        if let Some(acceleration) = data.acceleration_override {
+            self.emitter_acceleration_overrides
+                .insert(key, acceleration);
+        }
+    }
+
+    /// Name of a region of saved scene where texture regions of emitters are stored.
+    pub const EMITTER_TEXTURE_REGIONS_REGION_NAME: &'static str = "EmitterTextureRegions";
+
+    /// Reads texture regions of emitters from a saved scene. Scenes saved without regions
+    /// will give empty map.
+    pub fn load_emitter_texture_regions(
+        visitor: &mut Visitor,
+    ) -> HashMap<(Handle<Node>, usize), Rect<f32>> {
+        let mut regions = Vec::<EmitterTextureRegion>::new();
+        if regions
+            .visit(Self::EMITTER_TEXTURE_REGIONS_REGION_NAME, visitor)
+            .is_err()
+        {
+            regions.clear();
+        }
+        regions
+            .into_iter()
+            .map(|r| ((r.particle_system, r.emitter_index as usize), r.rect))
+            .collect()
+    }
+
+    /// Name of a region of saved scene where acceleration overrides of emitters are stored.
+    pub const EMITTER_ACCELERATION_OVERRIDES_REGION_NAME: &'static str =
+        "EmitterAccelerationOverrides";
+
+    /// Reads acceleration overrides of emitters from a saved scene. Scenes saved without
+    /// overrides will give empty map.
+    pub fn load_emitter_acceleration_overrides(
+        visitor: &mut Visitor,
+    ) -> HashMap<(Handle<Node>, usize), Vector3<f32>> {
+        let mut overrides = Vec::<EmitterAccelerationOverride>::new();
+        if overrides
+            .visit(Self::EMITTER_ACCELERATION_OVERRIDES_REGION_NAME, visitor)
+            .is_err()
+        {
+            overrides.clear();
+        }
+        overrides
+            .into_iter()
+            .map(|o| {
+                (
+                    (o.particle_system, o.emitter_index as usize),
+                    o.acceleration,
                 )
-                .unwrap();
-                valid = false;
+            })
+            .collect()
+    }
+
+    /// Name of a region of saved scene where blend shape weights of meshes are stored.
+    pub const BLEND_SHAPE_WEIGHTS_REGION_NAME: &'static str = "BlendShapeWeights";
+
+    /// Reads blend shape weights of meshes from a saved scene. Scenes saved without weights
+    /// will give empty map.
+    pub fn load_blend_shape_weights(visitor: &mut Visitor) -> HashMap<(Handle<Node>, usize), f32> {
+        let mut weights = Vec::<BlendShapeWeight>::new();
+        if weights
+            .visit(Self::BLEND_SHAPE_WEIGHTS_REGION_NAME, visitor)
+            .is_err()
+        {
+            weights.clear();
+        }
+        weights
+            .into_iter()
+            .map(|w| ((w.mesh, w.shape_index as usize), w.weight))
+            .collect()
+    }
+
+    /// Name of a region of saved scene where render path overrides of surfaces are stored.
+    pub const SURFACE_RENDER_PATHS_REGION_NAME: &'static str = "SurfaceRenderPaths";
+
+    /// Reads render path overrides of surfaces from a saved scene. Scenes saved without
+    /// overrides will give empty map.
+    pub fn load_surface_render_paths(
+        visitor: &mut Visitor,
+    ) -> HashMap<(Handle<Node>, usize), RenderPath> {
+        let mut overrides = Vec::<SurfaceRenderPathOverride>::new();
+        if overrides
+            .visit(Self::SURFACE_RENDER_PATHS_REGION_NAME, visitor)
+            .is_err()
+        {
+            overrides.clear();
+        }
+        overrides
+            .into_iter()
+            .map(|o| ((o.mesh, o.surface_index as usize), o.render_path))
+            .collect()
+    }
+
+    /// Returns true if given node is editor's own node (camera, gizmos, etc.) - the editor
+    /// root or any of its descendants. Such nodes must never be copied or saved.
+    pub fn is_editor_node(&self, handle: Handle<Node>, graph: &Graph) -> bool {
+        let mut ancestor = handle;
+        while ancestor.is_some() {
+            if ancestor == self.root {
+                return true;
             }
+            ancestor = graph[ancestor].parent();
         }
+        false
+    }
 
-        if valid {
-            self.path = Some(path.clone());
+    /// Dumps hierarchy of the scene (names, kinds, local transforms, parents and physics
+    /// bindings) in RON format. Geometry and other properties are not included, the dump is
+    /// meant for reviewing and diffing, it can't be loaded back.
+    pub fn export_hierarchy_ron(&self, scene: &Scene) -> String {
+        let graph = &scene.graph;
+
+        let mut entries = Vec::new();
+        let mut stack = graph[graph.get_root()]
+            .children()
+            .iter()
+            .rev()
+            .cloned()
+            .collect::<Vec<_>>();
+        while let Some(handle) = stack.pop() {
+            if self.is_editor_node(handle, graph) {
+                continue;
+            }
+
+            let node = &graph[handle];
+            let transform = node.local_transform();
+            let position = **transform.position();
+            let rotation = **transform.rotation();
+            let scale = **transform.scale();
+            entries.push(HierarchyEntry {
+                index: handle.index(),
+                name: node.name().to_owned(),
+                kind: NodeKind::of(node),
+                parent: if node.parent() == graph.get_root() {
+                    None
+                } else {
+                    Some(node.parent().index())
+                },
+                position: (position.x, position.y, position.z),
+                rotation: (rotation.i, rotation.j, rotation.k, rotation.w),
+                scale: (scale.x, scale.y, scale.z),
+                body: self
+                    .physics
+                    .binder
+                    .value_of(&handle)
+                    .map(|body| body.index()),
+            });
+
+            // Reverse order, so children will be popped in natural order.
+            stack.extend(node.children().iter().rev());
+        }
+
+        ron::ser::to_string_pretty(&entries, PrettyConfig::default()).unwrap()
+    }
 
-            let editor_root = self.root;
-            let (mut pure_scene, old_to_new) = scene.clone(&mut |node, _| node != editor_root);
+    /// Fills clipboard with selected nodes, editor nodes are never copied.
+    pub fn copy_selection_to_clipboard(&mut self, graph: &Graph) {
+        if let Selection::Graph(selection) = &self.selection {
+            let selection =
+                selection.filtered(graph, |handle, _| !self.is_editor_node(handle, graph));
+            self.clipboard
+                .fill_from_selection(&selection, graph, &self.physics);
+        }
+    }
+
+    pub fn is_node_static(&self, node: Handle<Node>) -> bool {
+        self.static_nodes.get(&node).cloned().unwrap_or_default()
+    }
 
-            // Reset state of nodes. For some nodes (such as particles systems) we use scene as preview
-            // so before saving scene, we have to reset state of such nodes.
-            for node in pure_scene.graph.linear_iter_mut() {
-                if let Node::ParticleSystem(particle_system) = node {
-                    // Particle system must not save generated vertices.
-                    particle_system.clear_particles();
+    /// Draws edges of triangles of every wireframe mesh. Wireframe is drawn as debug lines,
+    /// so it never gets into saved scene.
+    pub fn draw_wireframes(&self, context: &mut SceneDrawingContext, graph: &Graph) {
+        for &node in self.wireframe_nodes.iter() {
+            if !graph.is_valid_handle(node) {
+                continue;
+            }
+            if let Node::Mesh(mesh) = &graph[node] {
+                let transform = mesh.global_transform();
+                for surface in mesh.surfaces() {
+                    let data = surface.data();
+                    let data = data.read().unwrap();
+                    let vertices = data.get_vertices();
+                    for triangle in data.triangles() {
+                        for &(a, b) in [(0, 1), (1, 2), (2, 0)].iter() {
+                            let begin = vertices[triangle[a] as usize].position;
+                            let end = vertices[triangle[b] as usize].position;
+                            context.add_line(Line {
+                                begin: transform.transform_point(&Point3::from(begin)).coords,
+                                end: transform.transform_point(&Point3::from(end)).coords,
+                                color: Color::opaque(255, 255, 255),
+                            });
+                        }
+                    }
                 }
             }
+        }
+    }
 
-            pure_scene.navmeshes.clear();
+    /// Moves surfaces with overridden render path out of their meshes into child meshes with
+    /// desired render path, so the engine will honor the overrides. Returns new location (mesh
+    /// and surface index) of every surface of changed meshes, keyed by handles of the editor's
+    /// scene.
+    fn split_overridden_surfaces(
+        &self,
+        graph: &mut Graph,
+        old_to_new: &HashMap<Handle<Node>, Handle<Node>>,
+    ) -> HashMap<(Handle<Node>, usize), (Handle<Node>, usize)> {
+        let mut overrides: HashMap<Handle<Node>, Vec<(usize, RenderPath)>> = HashMap::new();
+        for (&(node, surface), &render_path) in self.surface_render_paths.iter() {
+            if old_to_new.contains_key(&node) {
+                overrides
+                    .entry(node)
+                    .or_default()
+                    .push((surface, render_path));
+            }
+        }
 
-            for navmesh in self.navmeshes.iter() {
-                // Sparse-to-dense mapping - handle to index.
-                let mut vertex_map = HashMap::new();
+        let mut locations = HashMap::new();
+        for (node, surface_overrides) in overrides {
+            let new_node = old_to_new[&node];
+            let (name, mesh_render_path, surfaces) = match &graph[new_node] {
+                Node::Mesh(mesh) => (
+                    mesh.name().to_owned(),
+                    mesh.render_path(),
+                    mesh.surfaces().to_vec(),
+                ),
+                _ => continue,
+            };
 
-                let vertices = navmesh
-                    .vertices
-                    .pair_iter()
-                    .enumerate()
-                    .map(|(i, (handle, vertex))| {
-                        vertex_map.insert(handle, i);
-                        vertex.position
-                    })
-                    .collect::<Vec<_>>();
+            // Surfaces which will stay in the mesh and surfaces which will be moved out, grouped
+            // by render path. Original indices are kept to report new locations.
+            let mut kept = Vec::new();
+            let mut moved: Vec<(RenderPath, Vec<(usize, Surface)>)> = Vec::new();
+            for (i, surface) in surfaces.into_iter().enumerate() {
+                match surface_overrides.iter().find(|(index, _)| *index == i) {
+                    Some(&(_, render_path)) if render_path != mesh_render_path => {
+                        match moved.iter_mut().find(|(path, _)| *path == render_path) {
+                            Some((_, group)) => group.push((i, surface)),
+                            None => moved.push((render_path, vec![(i, surface)])),
+                        }
+                    }
+                    _ => kept.push((i, surface)),
+                }
+            }
 
-                let triangles = navmesh
-                    .triangles
-                    .iter()
-                    .map(|triangle| {
-                        TriangleDefinition([
-                            vertex_map[&triangle.a] as u32,
-                            vertex_map[&triangle.b] as u32,
-                            vertex_map[&triangle.c] as u32,
-                        ])
-                    })
-                    .collect::<Vec<_>>();
+            if moved.is_empty() {
+                continue;
+            }
 
-                pure_scene
-                    .navmeshes
-                    .add(rg3d::utils::navmesh::Navmesh::new(&triangles, &vertices));
+            let mesh = graph[new_node].as_mesh_mut();
+            mesh.clear_surfaces();
+            for (new_index, (i, surface)) in kept.into_iter().enumerate() {
+                mesh.add_surface(surface);
+                locations.insert((node, i), (new_node, new_index));
             }
 
-            let (desc, binder) = self.physics.generate_engine_desc();
-            pure_scene.physics.desc = Some(desc);
-            pure_scene.physics_binder.enabled = true;
-            pure_scene.physics_binder.clear();
-            for (node, body) in binder {
-                pure_scene
-                    .physics_binder
-                    .bind(*old_to_new.get(&node).unwrap(), body);
+            for (render_path, surfaces) in moved {
+                let part = MeshBuilder::new(
+                    BaseBuilder::new().with_name(format!("{}_{:?}", name, render_path)),
+                )
+                .with_render_path(render_path)
+                .with_surfaces(
+                    surfaces
+                        .iter()
+                        .map(|(_, surface)| surface.clone())
+                        .collect(),
+                )
+                .build(graph);
+                graph.link_nodes(part, new_node);
+                for (new_index, (i, _)) in surfaces.into_iter().enumerate() {
+                    locations.insert((node, i), (part, new_index));
+                }
             }
-            let mut visitor = Visitor::new();
-            pure_scene.visit("Scene", &mut visitor).unwrap();
-            if let Err(e) = visitor.save_binary(&path) {
-                Err(format!("Failed to save scene! Reason: {}", e.to_string()))
-            } else {
-                Ok(format!("Scene {} was successfully saved!", path.display()))
+        }
+        locations
+    }
+
+    /// Moves emitters with overridden acceleration out of their particle systems into child
+    /// particle systems with desired acceleration, so the engine will honor the overrides.
+    /// Child systems are copies of the parent system (texture, etc.) with single emitter.
+    /// Returns new location (particle system and emitter index) of every emitter of changed
+    /// particle systems, keyed by handles of the editor's scene.
+    fn split_overridden_emitters(
+        &self,
+        graph: &mut Graph,
+        old_to_new: &HashMap<Handle<Node>, Handle<Node>>,
+    ) -> HashMap<(Handle<Node>, usize), (Handle<Node>, usize)> {
+        let mut overrides: HashMap<Handle<Node>, Vec<(usize, Vector3<f32>)>> = HashMap::new();
+        for (&(node, index), &acceleration) in self.emitter_acceleration_overrides.iter() {
+            if old_to_new.contains_key(&node) {
+                overrides
+                    .entry(node)
+                    .or_default()
+                    .push((index, acceleration));
             }
-        } else {
-            writeln!(&mut reason, "\nPlease fix errors and try again.").unwrap();
+        }
 
-            Err(reason)
+        let mut locations = HashMap::new();
+        for (node, mut emitter_overrides) in overrides {
+            let new_node = old_to_new[&node];
+            let (emitter_count, system_acceleration) = match &graph[new_node] {
+                Node::ParticleSystem(particle_system) => (
+                    particle_system.emitters.len(),
+                    particle_system.acceleration(),
+                ),
+                _ => continue,
+            };
+
+            // Overrides equal to acceleration of the system are no-op.
+            emitter_overrides.retain(|&(index, acceleration)| {
+                index < emitter_count && acceleration != system_acceleration
+            });
+            if emitter_overrides.is_empty() {
+                continue;
+            }
+
+            let emitters = std::mem::take(&mut graph[new_node].as_particle_system_mut().emitters);
+            let template = graph[new_node].raw_copy();
+            let name = template.name().to_owned();
+
+            let mut kept = Vec::new();
+            for (index, emitter) in emitters.into_iter().enumerate() {
+                match emitter_overrides.iter().find(|(i, _)| *i == index) {
+                    Some(&(_, acceleration)) => {
+                        let mut part = template.raw_copy();
+                        part.set_name(format!("{}_Emitter{}", name, index));
+                        // Part is attached to the system, so it must not be moved again.
+                        *part.local_transform_mut() = TransformBuilder::new().build();
+                        let particle_system = part.as_particle_system_mut();
+                        particle_system.emitters = vec![emitter];
+                        particle_system.set_acceleration(acceleration);
+                        let part = graph.add_node(part);
+                        graph.link_nodes(part, new_node);
+                        locations.insert((node, index), (part, 0));
+                    }
+                    None => {
+                        locations.insert((node, index), (new_node, kept.len()));
+                        kept.push(emitter);
+                    }
+                }
+            }
+            graph[new_node].as_particle_system_mut().emitters = kept;
         }
+        locations
     }
-}
 
-#[derive(Debug)]
-pub enum SceneCommand {
-    CommandGroup(CommandGroup),
-    Paste(PasteCommand),
-    AddNode(AddNodeCommand),
-    DeleteNode(DeleteNodeCommand),
-    DeleteSubGraph(DeleteSubGraphCommand),
-    ChangeSelection(ChangeSelectionCommand),
-    MoveNode(MoveNodeCommand),
-    ScaleNode(ScaleNodeCommand),
+    /// Checks the scene for problems without modifying anything. Scene with issues of
+    /// [`ValidationSeverity::Error`] severity can't be saved.
+    pub fn validate(&self, scene: &Scene) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+        let graph = &scene.graph;
+        let physics = &self.physics;
+
+        let body_name = |body: ErasedHandle| -> String {
+            physics
+                .binder
+                .key_of(&body.into())
+                .filter(|&&node| graph.is_valid_handle(node))
+                .map(|&node| {
+                    format!(
+                        "{} ({}:{})",
+                        graph[node].name(),
+                        node.index(),
+                        node.generation()
+                    )
+                })
+                .unwrap_or_else(|| "<unknown>".to_owned())
+        };
+
+        for (handle, joint) in physics.joints.pair_iter() {
+            let is_valid_body =
+                |body: ErasedHandle| body.is_some() && physics.bodies.is_valid_handle(body.into());
+            if !is_valid_body(joint.body1) || !is_valid_body(joint.body2) {
+                issues.push(ValidationIssue {
+                    severity: ValidationSeverity::Error,
+                    subject: ValidationSubject::Joint(handle),
+                    message: format!(
+                        "Invalid joint on node {}. Associated body is missing!",
+                        body_name(joint.body1)
+                    ),
+                });
+            } else if joint.body1 == joint.body2 {
+                issues.push(ValidationIssue {
+                    severity: ValidationSeverity::Error,
+                    subject: ValidationSubject::Joint(handle),
+                    message: format!(
+                        "Joint on node {} connects body with itself!",
+                        body_name(joint.body1)
+                    ),
+                });
+            }
+        }
+
+        for (handle, collider) in physics.colliders.pair_iter() {
+            if collider.parent.is_none() || !physics.bodies.is_valid_handle(collider.parent.into())
+            {
+                issues.push(ValidationIssue {
+                    severity: ValidationSeverity::Error,
+                    subject: ValidationSubject::Collider(handle),
+                    message: format!(
+                        "Collider {}:{} has no parent body!",
+                        handle.index(),
+                        handle.generation()
+                    ),
+                });
+            }
+        }
+
+        for (handle, body) in physics.bodies.pair_iter() {
+            if body.mass <= 0.0 {
+                issues.push(ValidationIssue {
+                    severity: ValidationSeverity::Warning,
+                    subject: ValidationSubject::Body(handle),
+                    message: format!(
+                        "Body of node {} has zero mass.",
+                        body_name(ErasedHandle::from(handle))
+                    ),
+                });
+            }
+        }
+
+        for (handle, navmesh) in self.navmeshes.pair_iter() {
+            if navmesh.triangles.alive_count() == 0 {
+                issues.push(ValidationIssue {
+                    severity: ValidationSeverity::Warning,
+                    subject: ValidationSubject::Navmesh(handle),
+                    message: format!(
+                        "Navmesh {}:{} has no triangles.",
+                        handle.index(),
+                        handle.generation()
+                    ),
+                });
+            }
+        }
+
+        let mut stack = graph[graph.get_root()]
+            .children()
+            .iter()
+            .cloned()
+            .filter(|&n| n != self.root)
+            .collect::<Vec<_>>();
+        while let Some(handle) = stack.pop() {
+            let node = &graph[handle];
+            let transform = node.local_transform();
+            if transform.position().iter().any(|v| v.is_nan())
+                || transform.rotation().coords.iter().any(|v| v.is_nan())
+                || transform.scale().iter().any(|v| v.is_nan())
+            {
+                issues.push(ValidationIssue {
+                    severity: ValidationSeverity::Error,
+                    subject: ValidationSubject::Node(handle),
+                    message: format!(
+                        "Node {} ({}:{}) has NaN in its transform!",
+                        node.name(),
+                        handle.index(),
+                        handle.generation()
+                    ),
+                });
+            }
+            stack.extend_from_slice(node.children());
+        }
+
+        issues
+    }
+
+    /// Creates command that selects every node of given kind in the scene, editor nodes are
+    /// never selected.
+    pub fn select_all_of_type(&self, graph: &Graph, kind: NodeKind) -> ChangeSelectionCommand {
+        let nodes = graph
+            .pair_iter()
+            .filter(|&(handle, node)| {
+                NodeKind::of(node) == kind
+                    && handle != graph.get_root()
+                    && !self.is_editor_node(handle, graph)
+            })
+            .map(|(handle, _)| handle)
+            .collect::<Vec<_>>();
+
+        ChangeSelectionCommand::new(
+            Selection::Graph(GraphSelection::from_list(nodes)),
+            self.selection.clone(),
+        )
+    }
+
+    /// Searches for nodes which name contains text of the query or which tag is equal to
+    /// text of the query. Results are in depth-first traversal order, editor nodes are
+    /// never returned.
+    pub fn find_nodes(&self, graph: &Graph, query: &NodeQuery) -> Vec<Handle<Node>> {
+        let normalize = |s: &str| {
+            if query.case_sensitive {
+                s.to_owned()
+            } else {
+                s.to_lowercase()
+            }
+        };
+        let text = normalize(&query.text);
+
+        let mut result = Vec::new();
+        if text.is_empty() {
+            return result;
+        }
+
+        let mut stack = graph[graph.get_root()]
+            .children()
+            .iter()
+            .rev()
+            .cloned()
+            .filter(|&n| n != self.root)
+            .collect::<Vec<_>>();
+        while let Some(handle) = stack.pop() {
+            let node = &graph[handle];
+            if normalize(node.name()).contains(&text) || normalize(node.tag()) == text {
+                result.push(handle);
+            }
+            // Reverse order, so children will be popped in natural order.
+            stack.extend(node.children().iter().rev());
+        }
+        result
+    }
+
+    /// Creates command that selects nodes inside of given frustum (for example frustum of a
+    /// selection rectangle), editor nodes are never selected.
+    pub fn select_in_frustum(
+        &self,
+        graph: &Graph,
+        frustum: &Frustum,
+        mode: FrustumSelectionMode,
+    ) -> ChangeSelectionCommand {
+        let selection = GraphSelection::from_frustum(graph, frustum, mode)
+            .filtered(graph, |handle, _| !self.is_editor_node(handle, graph));
+
+        ChangeSelectionCommand::new(Selection::Graph(selection), self.selection.clone())
+    }
+
+    /// Gathers statistics of the scene, `scene` must be the engine scene of this editor scene.
+    pub fn statistics(&self, scene: &Scene) -> SceneStatistics {
+        let graph = &scene.graph;
+
+        let mut stats = SceneStatistics {
+            bodies: self.physics.bodies.alive_count(),
+            colliders: self.physics.colliders.alive_count(),
+            joints: self.physics.joints.alive_count(),
+            navmesh_triangles: self
+                .navmeshes
+                .iter()
+                .map(|navmesh| navmesh.triangles.alive_count())
+                .sum(),
+            ..Default::default()
+        };
+
+        let mut textures = Vec::new();
+        let mut add_texture = |texture: Option<Texture>| {
+            if let Some(texture) = texture {
+                if !textures.contains(&texture) {
+                    textures.push(texture);
+                }
+            }
+        };
+
+        let mut stack = graph[graph.get_root()]
+            .children()
+            .iter()
+            .cloned()
+            .filter(|&n| n != self.root)
+            .collect::<Vec<_>>();
+        while let Some(handle) = stack.pop() {
+            let node = &graph[handle];
+            match node {
+                Node::Base(_) => stats.base_nodes += 1,
+                Node::Light(Light::Point(_)) => stats.point_lights += 1,
+                Node::Light(Light::Spot(_)) => stats.spot_lights += 1,
+                Node::Light(Light::Directional(_)) => stats.directional_lights += 1,
+                Node::Camera(_) => stats.cameras += 1,
+                Node::Mesh(mesh) => {
+                    stats.meshes += 1;
+                    for surface in mesh.surfaces() {
+                        stats.triangles += surface.data().read().unwrap().triangles().len();
+                        add_texture(surface.diffuse_texture());
+                        add_texture(surface.normal_texture());
+                    }
+                }
+                Node::Sprite(sprite) => {
+                    stats.sprites += 1;
+                    add_texture(sprite.texture());
+                }
+                Node::ParticleSystem(particle_system) => {
+                    stats.particle_systems += 1;
+                    stats.emitters += particle_system.emitters.len();
+                    add_texture(particle_system.texture());
+                }
+            }
+            stack.extend_from_slice(node.children());
+        }
+
+        for texture in textures {
+            if let TextureState::Ok(data) = &*texture.state() {
+                stats.texture_memory += data.data().len();
+            }
+        }
+
+        stats
+    }
+
+    pub fn save(&mut self, path: PathBuf, engine: &mut GameEngine) -> Result<String, String> {
+        let scene = &mut engine.scenes[self.scene];
+
+        // Validate first.
+        let mut valid = true;
+        let mut reason = "Scene is not saved, because validation failed:\n".to_owned();
+
+        for issue in self.validate(scene) {
+            if issue.severity == ValidationSeverity::Error {
+                writeln!(&mut reason, "{}", issue.message).unwrap();
+                valid = false;
+            }
+        }
+
+        if valid {
+            self.path = Some(path.clone());
+
+            let result = self.write(scene, &path);
+            if let Err(e) = result {
+                Err(format!("Failed to save scene! Reason: {}", e))
+            } else {
+                Ok(format!("Scene {} was successfully saved!", path.display()))
+            }
+        } else {
+            writeln!(&mut reason, "\nPlease fix errors and try again.").unwrap();
+
+            Err(reason)
+        }
+    }
+
+    /// Prefix of names of autosave files.
+    pub const AUTOSAVE_PREFIX: &'static str = "autosave_";
+
+    /// Writes current scene to a timestamped file in given directory and removes oldest
+    /// autosaves so only `keep` most recent ones remain. Unlike `save` it does not change path
+    /// of the scene. Returns path of the new autosave.
+    pub fn autosave(
+        &self,
+        engine: &GameEngine,
+        dir: &Path,
+        keep: usize,
+    ) -> Result<PathBuf, String> {
+        let scene = &engine.scenes[self.scene];
+
+        if self
+            .validate(scene)
+            .iter()
+            .any(|issue| issue.severity == ValidationSeverity::Error)
+        {
+            return Err("Scene is not autosaved, because validation failed.".to_owned());
+        }
+
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_err(|e| e.to_string())?
+            .as_millis();
+        let path = dir.join(format!("{}{}.rgs", Self::AUTOSAVE_PREFIX, timestamp));
+        self.write(scene, &path)?;
+
+        Self::prune_autosaves(dir, keep)?;
+
+        Ok(path)
+    }
+
+    /// Removes oldest autosaves in given directory so only `keep` (at least one) most recent
+    /// ones remain. Other files are never removed.
+    pub fn prune_autosaves(dir: &Path, keep: usize) -> Result<(), String> {
+        for old_autosave in Self::list_autosaves(dir).into_iter().skip(keep.max(1)) {
+            std::fs::remove_file(old_autosave).map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    /// Returns paths of autosaves in given directory, newest first. Autosave could be restored
+    /// by loading it as a regular scene.
+    pub fn list_autosaves(dir: &Path) -> Vec<PathBuf> {
+        let mut autosaves = std::fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .filter_map(|path| {
+                        let timestamp = path
+                            .file_stem()?
+                            .to_str()?
+                            .strip_prefix(Self::AUTOSAVE_PREFIX)?
+                            .parse::<u128>()
+                            .ok()?;
+                        Some((timestamp, path))
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        autosaves.sort_by(|(a, _), (b, _)| b.cmp(a));
+        autosaves.into_iter().map(|(_, path)| path).collect()
+    }
+
+    /// Prepares a copy of editor's scene (or of a part of it) for saving: resets preview state
+    /// of nodes, bakes overrides from side tables into the copy and generates physics of the
+    /// engine from `physics`. `old_to_new` maps nodes of editor's scene to their copies, nodes
+    /// in binder of `physics` are remapped through it, unless `binder_in_copy` is set, which
+    /// means that `physics` was copied together with the nodes and is bound to the copies.
+    fn prepare_pure_scene(
+        &self,
+        mut scene: Scene,
+        old_to_new: HashMap<Handle<Node>, Handle<Node>>,
+        physics: &Physics,
+        binder_in_copy: bool,
+    ) -> PureScene {
+        // Reset state of nodes. For some nodes (such as particles systems) we use scene as preview
+        // so before saving scene, we have to reset state of such nodes.
+        for node in scene.graph.linear_iter_mut() {
+            if let Node::ParticleSystem(particle_system) = node {
+                // Particle system must not save generated vertices.
+                particle_system.clear_particles();
+            }
+        }
+
+        // Particle systems disabled by preview pause must be saved enabled.
+        for handle in self.paused_particle_systems.iter() {
+            if let Some(&new_handle) = old_to_new.get(handle) {
+                scene.graph[new_handle]
+                    .as_particle_system_mut()
+                    .set_enabled(true);
+            }
+        }
+
+        // Same for animations, they keep their handles in a copy of the scene.
+        for &handle in self.paused_animations.iter() {
+            if scene.animations.pair_iter().any(|(h, _)| h == handle) {
+                scene.animations[handle].set_enabled(true);
+            }
+        }
+
+        let surface_locations = self.split_overridden_surfaces(&mut scene.graph, &old_to_new);
+        let emitter_locations = self.split_overridden_emitters(&mut scene.graph, &old_to_new);
+
+        let (desc, binder) = physics.generate_engine_desc();
+        scene.physics.desc = Some(desc);
+        scene.physics_binder.enabled = true;
+        scene.physics_binder.clear();
+        for (node, body) in binder {
+            let node = if binder_in_copy {
+                node
+            } else {
+                *old_to_new.get(&node).unwrap()
+            };
+            scene.physics_binder.bind(node, body);
+        }
+
+        PureScene {
+            scene,
+            old_to_new,
+            surface_locations,
+            emitter_locations,
+        }
+    }
+
+    /// Writes prepared scene and editor-only data remapped to handles of the copy. Data of nodes
+    /// that are not in the copy is skipped.
+    fn visit_pure_scene(&self, pure_scene: &mut PureScene, visitor: &mut Visitor) -> VisitResult {
+        pure_scene.scene.visit("Scene", visitor)?;
+        let old_to_new = &pure_scene.old_to_new;
+        self.collision_layers.visit("CollisionLayers", visitor)?;
+        // Handles of nodes are changed after clone, so remap them the same way as physics
+        // binder does.
+        let mut static_nodes = self
+            .static_nodes
+            .iter()
+            .filter_map(|(node, &is_static)| {
+                old_to_new.get(node).map(|&new_node| (new_node, is_static))
+            })
+            .collect::<HashMap<_, _>>();
+        static_nodes.visit(Self::STATIC_NODES_REGION_NAME, visitor)?;
+        // Links are saved only if both the instance and its source are saved.
+        let mut linked_instances = self
+            .linked_instances
+            .iter()
+            .filter_map(|(instance, source)| {
+                Some((*old_to_new.get(instance)?, *old_to_new.get(source)?))
+            })
+            .collect::<HashMap<_, _>>();
+        linked_instances.visit(Self::LINKED_INSTANCES_REGION_NAME, visitor)?;
+        let mut emitter_texture_regions = self
+            .emitter_texture_regions
+            .iter()
+            .filter_map(|(&(node, emitter_index), &rect)| {
+                pure_scene.emitter_location(node, emitter_index).map(
+                    |(particle_system, emitter_index)| EmitterTextureRegion {
+                        particle_system,
+                        emitter_index: emitter_index as u32,
+                        rect,
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+        emitter_texture_regions.visit(Self::EMITTER_TEXTURE_REGIONS_REGION_NAME, visitor)?;
+        let mut emitter_acceleration_overrides = self
+            .emitter_acceleration_overrides
+            .iter()
+            .filter_map(|(&(node, emitter_index), &acceleration)| {
+                pure_scene.emitter_location(node, emitter_index).map(
+                    |(particle_system, emitter_index)| EmitterAccelerationOverride {
+                        particle_system,
+                        emitter_index: emitter_index as u32,
+                        acceleration,
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+        emitter_acceleration_overrides
+            .visit(Self::EMITTER_ACCELERATION_OVERRIDES_REGION_NAME, visitor)?;
+        let mut blend_shape_weights = self
+            .blend_shape_weights
+            .iter()
+            .filter_map(|(&(node, shape_index), &weight)| {
+                old_to_new.get(&node).map(|&mesh| BlendShapeWeight {
+                    mesh,
+                    shape_index: shape_index as u32,
+                    weight,
+                })
+            })
+            .collect::<Vec<_>>();
+        blend_shape_weights.visit(Self::BLEND_SHAPE_WEIGHTS_REGION_NAME, visitor)?;
+        // Overrides are saved at new locations of surfaces, there they match render path of
+        // their meshes, so next save will leave the surfaces in place.
+        let mut surface_render_paths = self
+            .surface_render_paths
+            .iter()
+            .filter_map(|(&(node, surface_index), &render_path)| {
+                pure_scene
+                    .surface_location(node, surface_index)
+                    .map(|(mesh, surface_index)| SurfaceRenderPathOverride {
+                        mesh,
+                        surface_index: surface_index as u32,
+                        render_path,
+                    })
+            })
+            .collect::<Vec<_>>();
+        surface_render_paths.visit(Self::SURFACE_RENDER_PATHS_REGION_NAME, visitor)
+    }
+
+    /// Writes scene without editor nodes together with editor-only data to given path.
+    /// Live scene is not modified, everything is done on its copy.
+    fn write(&self, scene: &Scene, path: &Path) -> Result<(), String> {
+        let graph = &scene.graph;
+        let (pure_scene, old_to_new) =
+            scene.clone(&mut |node, _| !self.is_editor_node(node, graph));
+        let mut pure_scene = self.prepare_pure_scene(pure_scene, old_to_new, &self.physics, false);
+
+        pure_scene.scene.navmeshes.clear();
+
+        let mut navmesh_regions = Vec::new();
+
+        for navmesh in self.navmeshes.iter() {
+            // Sparse-to-dense mapping - handle to index.
+            let mut vertex_map = HashMap::new();
+
+            let vertices = navmesh
+                .vertices
+                .pair_iter()
+                .enumerate()
+                .map(|(i, (handle, vertex))| {
+                    vertex_map.insert(handle, i);
+                    vertex.position
+                })
+                .collect::<Vec<_>>();
+
+            let triangles = navmesh
+                .triangles
+                .iter()
+                .map(|triangle| {
+                    TriangleDefinition([
+                        vertex_map[&triangle.a] as u32,
+                        vertex_map[&triangle.b] as u32,
+                        vertex_map[&triangle.c] as u32,
+                    ])
+                })
+                .collect::<Vec<_>>();
+
+            pure_scene
+                .scene
+                .navmeshes
+                .add(rg3d::utils::navmesh::Navmesh::new(&triangles, &vertices));
+
+            navmesh_regions.push(
+                navmesh
+                    .triangles
+                    .iter()
+                    .map(|triangle| triangle.region)
+                    .collect::<Vec<_>>(),
+            );
+        }
+
+        let mut visitor = Visitor::new();
+        self.visit_pure_scene(&mut pure_scene, &mut visitor)
+            .unwrap();
+        navmesh_regions
+            .visit(Self::NAVMESH_REGIONS_REGION_NAME, &mut visitor)
+            .unwrap();
+        // Editor camera pose is stored in its own region which is never read by the game.
+        self.camera_controller
+            .pose(&scene.graph)
+            .visit(CameraPose::REGION_NAME, &mut visitor)
+            .unwrap();
+        visitor.save_binary(path).map_err(|e| e.to_string())
+    }
+
+    /// Saves selected sub-graphs together with their physics entities as a separate scene,
+    /// which could be instantiated as a model later. Prefab is prepared the same way as a saved
+    /// scene, editor-only data of selected nodes is saved too.
+    pub fn save_selection_as_prefab(
+        &self,
+        selection: &GraphSelection,
+        scene: &Scene,
+        path: &Path,
+    ) -> Result<String, String> {
+        let graph = &scene.graph;
+        let root_nodes = selection
+            .root_nodes(graph)
+            .into_iter()
+            .filter(|&node| node != graph.get_root() && !self.is_editor_node(node, graph))
+            .collect::<Vec<_>>();
+        if root_nodes.is_empty() {
+            return Err("Prefab is not saved, because selection is empty.".to_owned());
+        }
+
+        let mut prefab = Scene::new();
+        let mut prefab_physics = Physics::default();
+        let result = deep_clone_nodes(
+            &root_nodes,
+            graph,
+            &self.physics,
+            &mut prefab.graph,
+            &mut prefab_physics,
+        );
+
+        // Joints are copied only if both of their bodies are in the prefab, joints that
+        // connect prefab with the rest of the scene can't be saved.
+        if self.physics.joints.iter().any(|joint| {
+            result.old_to_new.contains_key(&joint.body1)
+                != result.old_to_new.contains_key(&joint.body2)
+        }) {
+            return Err(
+                "Prefab is not saved, because selection has joints connected to \
+                bodies outside of selection."
+                    .to_owned(),
+            );
+        }
+
+        // Mapping of deep clone has bodies and colliders too, take only nodes of selection.
+        let old_to_new = root_nodes
+            .iter()
+            .flat_map(|&root_node| graph.traverse_handle_iter(root_node))
+            .filter_map(|node| {
+                result
+                    .old_to_new
+                    .get(&ErasedHandle::from(node))
+                    .map(|&new_node| (node, Handle::<Node>::from(new_node)))
+            })
+            .collect::<HashMap<_, _>>();
+
+        // Bodies of prefab are bound to nodes of prefab already, so no remapping is needed.
+        let mut prefab = self.prepare_pure_scene(prefab, old_to_new, &prefab_physics, true);
+
+        let mut visitor = Visitor::new();
+        self.visit_pure_scene(&mut prefab, &mut visitor).unwrap();
+        if let Err(e) = visitor.save_binary(path) {
+            Err(format!("Failed to save prefab! Reason: {}", e))
+        } else {
+            Ok(format!("Prefab {} was successfully saved!", path.display()))
+        }
+    }
+}
+
+/// Removes models that are not pending anymore from the list and reports them, see
+/// [`EditorScene::poll_pending_models`].
+fn poll_models(
+    pending_models: &mut Vec<(PathBuf, Model)>,
+    scene: Handle<Scene>,
+    sender: &Sender<Message>,
+) {
+    pending_models.retain(|(path, model)| match &*model.state() {
+        ResourceState::Pending { .. } => true,
+        ResourceState::LoadError { error, .. } => {
+            sender
+                .send(Message::Log(format!(
+                    "Unable to load model {}. Reason: {:?}",
+                    path.display(),
+                    error
+                )))
+                .unwrap();
+            false
+        }
+        ResourceState::Ok(_) => {
+            sender
+                .send(Message::ModelLoaded {
+                    scene,
+                    path: path.clone(),
+                    model: model.clone(),
+                })
+                .unwrap();
+            false
+        }
+    });
+}
+
+/// Copy of editor's scene (or of a part of it) prepared for saving, see
+/// [`EditorScene::prepare_pure_scene`].
+struct PureScene {
+    scene: Scene,
+    /// Maps nodes of editor's scene to their copies.
+    old_to_new: HashMap<Handle<Node>, Handle<Node>>,
+    /// New locations of surfaces moved by render path overrides.
+    surface_locations: HashMap<(Handle<Node>, usize), (Handle<Node>, usize)>,
+    /// New locations of emitters moved by acceleration overrides.
+    emitter_locations: HashMap<(Handle<Node>, usize), (Handle<Node>, usize)>,
+}
+
+impl PureScene {
+    /// Returns location of a surface of a mesh of editor's scene in the copy. Surfaces of split
+    /// meshes are moved, other ones keep their indices.
+    fn surface_location(&self, node: Handle<Node>, index: usize) -> Option<(Handle<Node>, usize)> {
+        self.surface_locations
+            .get(&(node, index))
+            .cloned()
+            .or_else(|| {
+                self.old_to_new
+                    .get(&node)
+                    .map(|&new_node| (new_node, index))
+            })
+    }
+
+    /// Same as `surface_location`, but for emitters of particle systems.
+    fn emitter_location(&self, node: Handle<Node>, index: usize) -> Option<(Handle<Node>, usize)> {
+        self.emitter_locations
+            .get(&(node, index))
+            .cloned()
+            .or_else(|| {
+                self.old_to_new
+                    .get(&node)
+                    .map(|&new_node| (new_node, index))
+            })
+    }
+}
+
+#[derive(Debug)]
+pub enum SceneCommand {
+    CommandGroup(CommandGroup),
+    Paste(PasteCommand),
+    AddNode(AddNodeCommand),
+    DeleteNode(DeleteNodeCommand),
+    DeleteSubGraph(DeleteSubGraphCommand),
+    ChangeSelection(ChangeSelectionCommand),
+    MoveNode(MoveNodeCommand),
+    ScaleNode(ScaleNodeCommand),
     RotateNode(RotateNodeCommand),
     LinkNodes(LinkNodesCommand),
     SetVisible(SetVisibleCommand),
@@ -383,6 +2000,58 @@ pub enum SceneCommand {
     DeleteNavmeshVertex(DeleteNavmeshVertexCommand),
     ConnectNavmeshEdges(ConnectNavmeshEdgesCommand),
     SetPhysicsBinding(SetPhysicsBindingCommand),
+    LinkNodesKeepTransform(LinkNodesKeepTransformCommand),
+    ValidatePhysicsConsistency(ValidatePhysicsConsistencyCommand),
+    SetSceneUnitScale(SetSceneUnitScaleCommand),
+    SetColliderMembership(SetColliderMembershipCommand),
+    SetColliderFilter(SetColliderFilterCommand),
+    SetParticleSystemSoftBoundarySharpness(SetParticleSystemSoftBoundarySharpnessCommand),
+    SetEmitterResurrectParticles(SetEmitterResurrectParticlesCommand),
+    DeleteNavmeshVertices(DeleteNavmeshVerticesCommand),
+    RotateNodesAroundPivot(RotateNodesAroundPivotCommand),
+    SetLocalTransform(SetLocalTransformCommand),
+    AddNavmeshTriangleByVertices(AddNavmeshTriangleByVerticesCommand),
+    SetJointBody1(SetJointBody1Command),
+    FlattenTransform(FlattenTransformCommand),
+    SetSurfaceRenderPathOverride(SetSurfaceRenderPathOverrideCommand),
+    SetNodesVisibility(SetNodesVisibilityCommand),
+    SetColliderShape(SetColliderShapeCommand),
+    WeldNavmeshVertices(WeldNavmeshVerticesCommand),
+    SetSpotLightShadowBias(SetSpotLightShadowBiasCommand),
+    SetPointLightShadowBias(SetPointLightShadowBiasCommand),
+    SetNodeStatic(SetNodeStaticCommand),
+    SetCameraExposure(SetCameraExposureCommand),
+    SetCameraColorGradingLut(SetCameraColorGradingLutCommand),
+    SetNavmeshTriangleRegion(SetNavmeshTriangleRegionCommand),
+    SetParticleSystemEnabled(SetParticleSystemEnabledCommand),
+    GroupNodes(GroupNodesCommand),
+    SetColliderSolverGroups(SetColliderSolverGroupsCommand),
+    MoveEmitter(MoveEmitterCommand),
+    SetEmitterTextureRegion(SetEmitterTextureRegionCommand),
+    SetNavmeshVertexPosition(SetNavmeshVertexPositionCommand),
+    MakeCompoundBody(MakeCompoundBodyCommand),
+    SetBodyLockedRotation(SetBodyLockedRotationCommand),
+    SetBodyLockedTranslation(SetBodyLockedTranslationCommand),
+    SetMeshDecalLayerIndex(SetMeshDecalLayerIndexCommand),
+    SetEmitterAccelerationOverride(SetEmitterAccelerationOverrideCommand),
+    ResetTransform(ResetTransformCommand),
+    SetEmitterType(SetEmitterTypeCommand),
+    SetColliderParent(SetColliderParentCommand),
+    SetNodeWireframe(SetNodeWireframeCommand),
+    SetSpotLightCookieTexture(SetSpotLightCookieTextureCommand),
+    SetAnimationSpeed(SetAnimationSpeedCommand),
+    SetAnimationLooping(SetAnimationLoopingCommand),
+    DeleteNavmeshTriangles(DeleteNavmeshTrianglesCommand),
+    AddLinkedInstance(AddLinkedInstanceCommand),
+    SetSceneAmbientColor(SetSceneAmbientColorCommand),
+    SetCameraSkyBox(SetCameraSkyBoxCommand),
+    SplitNavmeshVertex(SplitNavmeshVertexCommand),
+    SetSurfaceDiffuseTexture(SetSurfaceDiffuseTextureCommand),
+    SetSurfaceNormalTexture(SetSurfaceNormalTextureCommand),
+    SetMeshBlendShapeWeight(SetMeshBlendShapeWeightCommand),
+    SetBodyLinearVelocity(SetBodyLinearVelocityCommand),
+    SetBodyAngularVelocity(SetBodyAngularVelocityCommand),
+    FlipNavmeshTriangle(FlipNavmeshTriangleCommand),
 }
 
 pub struct SceneContext<'a> {
@@ -492,6 +2161,58 @@ macro_rules! static_dispatch {
             SceneCommand::DeleteNavmeshVertex(v) => v.$func($($args),*),
             SceneCommand::ConnectNavmeshEdges(v) => v.$func($($args),*),
             SceneCommand::SetPhysicsBinding(v) => v.$func($($args),*),
+            SceneCommand::LinkNodesKeepTransform(v) => v.$func($($args),*),
+            SceneCommand::ValidatePhysicsConsistency(v) => v.$func($($args),*),
+            SceneCommand::SetSceneUnitScale(v) => v.$func($($args),*),
+            SceneCommand::SetColliderMembership(v) => v.$func($($args),*),
+            SceneCommand::SetColliderFilter(v) => v.$func($($args),*),
+            SceneCommand::SetParticleSystemSoftBoundarySharpness(v) => v.$func($($args),*),
+            SceneCommand::SetEmitterResurrectParticles(v) => v.$func($($args),*),
+            SceneCommand::DeleteNavmeshVertices(v) => v.$func($($args),*),
+            SceneCommand::RotateNodesAroundPivot(v) => v.$func($($args),*),
+            SceneCommand::SetLocalTransform(v) => v.$func($($args),*),
+            SceneCommand::AddNavmeshTriangleByVertices(v) => v.$func($($args),*),
+            SceneCommand::SetJointBody1(v) => v.$func($($args),*),
+            SceneCommand::FlattenTransform(v) => v.$func($($args),*),
+            SceneCommand::SetSurfaceRenderPathOverride(v) => v.$func($($args),*),
+            SceneCommand::SetNodesVisibility(v) => v.$func($($args),*),
+            SceneCommand::SetColliderShape(v) => v.$func($($args),*),
+            SceneCommand::WeldNavmeshVertices(v) => v.$func($($args),*),
+            SceneCommand::SetSpotLightShadowBias(v) => v.$func($($args),*),
+            SceneCommand::SetPointLightShadowBias(v) => v.$func($($args),*),
+            SceneCommand::SetNodeStatic(v) => v.$func($($args),*),
+            SceneCommand::SetCameraExposure(v) => v.$func($($args),*),
+            SceneCommand::SetCameraColorGradingLut(v) => v.$func($($args),*),
+            SceneCommand::SetNavmeshTriangleRegion(v) => v.$func($($args),*),
+            SceneCommand::SetParticleSystemEnabled(v) => v.$func($($args),*),
+            SceneCommand::GroupNodes(v) => v.$func($($args),*),
+            SceneCommand::SetColliderSolverGroups(v) => v.$func($($args),*),
+            SceneCommand::MoveEmitter(v) => v.$func($($args),*),
+            SceneCommand::SetEmitterTextureRegion(v) => v.$func($($args),*),
+            SceneCommand::SetNavmeshVertexPosition(v) => v.$func($($args),*),
+            SceneCommand::MakeCompoundBody(v) => v.$func($($args),*),
+            SceneCommand::SetBodyLockedRotation(v) => v.$func($($args),*),
+            SceneCommand::SetBodyLockedTranslation(v) => v.$func($($args),*),
+            SceneCommand::SetMeshDecalLayerIndex(v) => v.$func($($args),*),
+            SceneCommand::SetEmitterAccelerationOverride(v) => v.$func($($args),*),
+            SceneCommand::ResetTransform(v) => v.$func($($args),*),
+            SceneCommand::SetEmitterType(v) => v.$func($($args),*),
+            SceneCommand::SetColliderParent(v) => v.$func($($args),*),
+            SceneCommand::SetNodeWireframe(v) => v.$func($($args),*),
+            SceneCommand::SetSpotLightCookieTexture(v) => v.$func($($args),*),
+            SceneCommand::SetAnimationSpeed(v) => v.$func($($args),*),
+            SceneCommand::SetAnimationLooping(v) => v.$func($($args),*),
+            SceneCommand::DeleteNavmeshTriangles(v) => v.$func($($args),*),
+            SceneCommand::AddLinkedInstance(v) => v.$func($($args),*),
+            SceneCommand::SetSceneAmbientColor(v) => v.$func($($args),*),
+            SceneCommand::SetCameraSkyBox(v) => v.$func($($args),*),
+            SceneCommand::SplitNavmeshVertex(v) => v.$func($($args),*),
+            SceneCommand::SetSurfaceDiffuseTexture(v) => v.$func($($args),*),
+            SceneCommand::SetSurfaceNormalTexture(v) => v.$func($($args),*),
+            SceneCommand::SetMeshBlendShapeWeight(v) => v.$func($($args),*),
+            SceneCommand::SetBodyLinearVelocity(v) => v.$func($($args),*),
+            SceneCommand::SetBodyAngularVelocity(v) => v.$func($($args),*),
+            SceneCommand::FlipNavmeshTriangle(v) => v.$func($($args),*),
         }
     };
 }
@@ -499,15 +2220,26 @@ macro_rules! static_dispatch {
 #[derive(Debug)]
 pub struct CommandGroup {
     commands: Vec<SceneCommand>,
+    name: Option<String>,
 }
 
 impl From<Vec<SceneCommand>> for CommandGroup {
     fn from(commands: Vec<SceneCommand>) -> Self {
-        Self { commands }
+        Self {
+            commands,
+            name: None,
+        }
     }
 }
 
 impl CommandGroup {
+    /// Sets name of the group shown in command stack, by default the name is made of
+    /// names of commands in the group.
+    pub fn with_name(mut self, name: String) -> Self {
+        self.name = Some(name);
+        self
+    }
+
     pub fn push(&mut self, command: SceneCommand) {
         self.commands.push(command)
     }
@@ -515,12 +2247,201 @@ impl CommandGroup {
     pub fn is_empty(&self) -> bool {
         self.commands.is_empty()
     }
+
+    /// Describes effect of the group as a list of replayable records, see [`CommandRecord`].
+    /// The group must be executed (as every command in command stack is), values of property
+    /// commands are taken from the graph, so the group must be described right after it was
+    /// executed. Unsupported commands are skipped.
+    pub fn describe(&self, graph: &Graph) -> Vec<CommandRecord> {
+        let mut records = Vec::new();
+        for command in self.commands.iter() {
+            describe_command(command, graph, &mut records);
+        }
+        records
+    }
+}
+
+fn describe_command(command: &SceneCommand, graph: &Graph, records: &mut Vec<CommandRecord>) {
+    // Commands swap their values on execution, so executed command keeps applied value in
+    // its "old" field.
+    match command {
+        SceneCommand::CommandGroup(group) => {
+            for command in group.commands.iter() {
+                describe_command(command, graph, records);
+            }
+        }
+        SceneCommand::MoveNode(move_node) => {
+            let p = move_node.old_position;
+            records.push(CommandRecord::Move {
+                node: move_node.node.into(),
+                position: [p.x, p.y, p.z],
+            });
+        }
+        SceneCommand::RotateNode(rotate_node) => {
+            let r = rotate_node.old_rotation;
+            records.push(CommandRecord::Rotate {
+                node: rotate_node.node.into(),
+                rotation: [r.i, r.j, r.k, r.w],
+            });
+        }
+        SceneCommand::ScaleNode(scale_node) => {
+            let s = scale_node.old_scale;
+            records.push(CommandRecord::Scale {
+                node: scale_node.node.into(),
+                scale: [s.x, s.y, s.z],
+            });
+        }
+        SceneCommand::SetName(set_name) if graph.is_valid_handle(set_name.handle) => {
+            records.push(CommandRecord::SetName {
+                node: set_name.handle.into(),
+                name: graph[set_name.handle].name().to_owned(),
+            });
+        }
+        SceneCommand::SetTag(set_tag) if graph.is_valid_handle(set_tag.handle) => {
+            records.push(CommandRecord::SetTag {
+                node: set_tag.handle.into(),
+                tag: graph[set_tag.handle].tag().to_owned(),
+            });
+        }
+        SceneCommand::SetVisible(set_visible) if graph.is_valid_handle(set_visible.handle) => {
+            records.push(CommandRecord::SetVisible {
+                node: set_visible.handle.into(),
+                visible: graph[set_visible.handle].visibility(),
+            });
+        }
+        _ => (),
+    }
+}
+
+/// Handle of a node in [`CommandRecord`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordHandle {
+    pub index: u32,
+    pub generation: u32,
+}
+
+impl From<Handle<Node>> for RecordHandle {
+    fn from(handle: Handle<Node>) -> Self {
+        Self {
+            index: handle.index(),
+            generation: handle.generation(),
+        }
+    }
+}
+
+impl From<RecordHandle> for Handle<Node> {
+    fn from(handle: RecordHandle) -> Self {
+        Handle::new(handle.index, handle.generation)
+    }
+}
+
+/// Minimal description of an applied command, which could be serialized (see
+/// [`records_to_ron`]) and applied to other copy of the same scene (see
+/// [`make_command_from_records`]). Only transform and basic property commands are supported.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum CommandRecord {
+    Move {
+        node: RecordHandle,
+        position: [f32; 3],
+    },
+    Rotate {
+        node: RecordHandle,
+        rotation: [f32; 4],
+    },
+    Scale {
+        node: RecordHandle,
+        scale: [f32; 3],
+    },
+    SetName {
+        node: RecordHandle,
+        name: String,
+    },
+    SetTag {
+        node: RecordHandle,
+        tag: String,
+    },
+    SetVisible {
+        node: RecordHandle,
+        visible: bool,
+    },
+}
+
+pub fn records_to_ron(records: &[CommandRecord]) -> String {
+    ron::ser::to_string_pretty(&records, PrettyConfig::default()).unwrap()
+}
+
+pub fn records_from_ron(source: &str) -> Result<Vec<CommandRecord>, String> {
+    ron::de::from_str(source).map_err(|e| e.to_string())
+}
+
+impl CommandRecord {
+    pub fn node(&self) -> RecordHandle {
+        match self {
+            CommandRecord::Move { node, .. }
+            | CommandRecord::Rotate { node, .. }
+            | CommandRecord::Scale { node, .. }
+            | CommandRecord::SetName { node, .. }
+            | CommandRecord::SetTag { node, .. }
+            | CommandRecord::SetVisible { node, .. } => *node,
+        }
+    }
+}
+
+/// Creates command group that applies given records to a scene. Records that refer to
+/// missing nodes are skipped.
+pub fn make_command_from_records(records: &[CommandRecord], graph: &Graph) -> SceneCommand {
+    let mut commands = Vec::new();
+    for record in records {
+        let node = Handle::from(record.node());
+        if !graph.is_valid_handle(node) {
+            continue;
+        }
+
+        let transform = graph[node].local_transform();
+        commands.push(match record {
+            CommandRecord::Move { position, .. } => SceneCommand::MoveNode(MoveNodeCommand::new(
+                node,
+                **transform.position(),
+                Vector3::new(position[0], position[1], position[2]),
+            )),
+            CommandRecord::Rotate { rotation, .. } => {
+                SceneCommand::RotateNode(RotateNodeCommand::new(
+                    node,
+                    **transform.rotation(),
+                    UnitQuaternion::from_quaternion(Quaternion::new(
+                        rotation[3],
+                        rotation[0],
+                        rotation[1],
+                        rotation[2],
+                    )),
+                ))
+            }
+            CommandRecord::Scale { scale, .. } => SceneCommand::ScaleNode(ScaleNodeCommand::new(
+                node,
+                **transform.scale(),
+                Vector3::new(scale[0], scale[1], scale[2]),
+            )),
+            CommandRecord::SetName { name, .. } => {
+                SceneCommand::SetName(SetNameCommand::new(node, name.clone()))
+            }
+            CommandRecord::SetTag { tag, .. } => {
+                SceneCommand::SetTag(SetTagCommand::new(node, tag.clone()))
+            }
+            CommandRecord::SetVisible { visible, .. } => {
+                SceneCommand::SetVisible(SetVisibleCommand::new(node, *visible))
+            }
+        });
+    }
+    SceneCommand::CommandGroup(CommandGroup::from(commands))
 }
 
 impl<'a> Command<'a> for CommandGroup {
     type Context = SceneContext<'a>;
 
     fn name(&mut self, context: &Self::Context) -> String {
+        if let Some(name) = self.name.as_ref() {
+            return name.clone();
+        }
         let mut name = String::from("Command group: ");
         for cmd in self.commands.iter_mut() {
             name.push_str(&cmd.name(context));
@@ -567,6 +2488,15 @@ impl<'a> Command<'a> for SceneCommand {
     fn finalize(&mut self, context: &mut Self::Context) {
         static_dispatch!(self, finalize, context);
     }
+
+    fn merge(&mut self, next: &mut Self, context: &mut Self::Context) -> bool {
+        match (self, next) {
+            (SceneCommand::SetSceneAmbientColor(v), SceneCommand::SetSceneAmbientColor(next)) => {
+                v.merge(next, context)
+            }
+            _ => false,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -623,32 +2553,87 @@ impl<'a> Command<'a> for AddNodeCommand {
     }
 }
 
+/// Adds a node as a linked duplicate of a source node, see [`make_linked_duplicate_command`].
 #[derive(Debug)]
-pub struct AddParticleSystemEmitterCommand {
-    particle_system: Handle<Node>,
-    emitter: Option<Emitter>,
-}
-
-impl AddParticleSystemEmitterCommand {
-    pub fn new(particle_system: Handle<Node>, emitter: Emitter) -> Self {
-        Self {
-            particle_system,
-            emitter: Some(emitter),
-        }
-    }
+pub struct AddLinkedInstanceCommand {
+    source: Handle<Node>,
+    add: AddNodeCommand,
 }
 
-impl<'a> Command<'a> for AddParticleSystemEmitterCommand {
+impl<'a> Command<'a> for AddLinkedInstanceCommand {
     type Context = SceneContext<'a>;
 
     fn name(&mut self, _context: &Self::Context) -> String {
-        "Add Particle System Emitter".to_owned()
+        "Add Linked Duplicate".to_owned()
     }
 
     fn execute(&mut self, context: &mut Self::Context) {
-        context.scene.graph[self.particle_system]
-            .as_particle_system_mut()
-            .emitters
+        self.add.execute(context);
+        context
+            .editor_scene
+            .linked_instances
+            .insert(self.add.handle, self.source);
+    }
+
+    fn revert(&mut self, context: &mut Self::Context) {
+        context
+            .editor_scene
+            .linked_instances
+            .remove(&self.add.handle);
+        self.add.revert(context);
+    }
+
+    fn finalize(&mut self, context: &mut Self::Context) {
+        self.add.finalize(context);
+    }
+}
+
+/// Creates command that adds linked duplicate of a mesh. Linked duplicate shares geometry
+/// (surface data) with its source, textures set by [`SetMeshTextureCommand`] to any mesh of
+/// the link group are set to every mesh of the group. Limitations: transform and other properties
+/// of the duplicate are independent, children of the source are not duplicated, and changes
+/// of topology of shared surface data affect every duplicate. Returns `None` if the node is
+/// not a mesh.
+pub fn make_linked_duplicate_command(node: Handle<Node>, graph: &Graph) -> Option<SceneCommand> {
+    if let Node::Mesh(_) = &graph[node] {
+        // Raw copy clones surfaces, surface data is shared between copies.
+        let mut copy = graph[node].raw_copy();
+        copy.set_name(format!("{} (Linked)", graph[node].name()));
+        Some(SceneCommand::AddLinkedInstance(AddLinkedInstanceCommand {
+            source: node,
+            add: AddNodeCommand::new(copy),
+        }))
+    } else {
+        None
+    }
+}
+
+#[derive(Debug)]
+pub struct AddParticleSystemEmitterCommand {
+    particle_system: Handle<Node>,
+    emitter: Option<Emitter>,
+}
+
+impl AddParticleSystemEmitterCommand {
+    pub fn new(particle_system: Handle<Node>, emitter: Emitter) -> Self {
+        Self {
+            particle_system,
+            emitter: Some(emitter),
+        }
+    }
+}
+
+impl<'a> Command<'a> for AddParticleSystemEmitterCommand {
+    type Context = SceneContext<'a>;
+
+    fn name(&mut self, _context: &Self::Context) -> String {
+        "Add Particle System Emitter".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut Self::Context) {
+        context.scene.graph[self.particle_system]
+            .as_particle_system_mut()
+            .emitters
             .push(self.emitter.take().unwrap());
     }
 
@@ -703,6 +2688,79 @@ impl AddNavmeshEdgeCommand {
             new_selection: Default::default(),
         }
     }
+
+    /// Creates command that extrudes given edge by `distance` along `direction`. If no
+    /// direction is specified, edge will be extruded perpendicular to itself in the plane
+    /// of a triangle it belongs to, away from that triangle.
+    pub fn extrude(
+        navmesh_handle: Handle<Navmesh>,
+        navmesh: &Navmesh,
+        edge: NavmeshEdge,
+        direction: Option<Vector3<f32>>,
+        distance: f32,
+        select: bool,
+    ) -> Self {
+        let begin = navmesh.vertices[edge.begin].position;
+        let end = navmesh.vertices[edge.end].position;
+
+        let direction = direction
+            .and_then(|d| d.try_normalize(std::f32::EPSILON))
+            .unwrap_or_else(|| extrusion_direction(navmesh, edge));
+
+        let offset = direction.scale(distance);
+
+        Self::new(
+            navmesh_handle,
+            (
+                NavmeshVertex {
+                    position: begin + offset,
+                },
+                NavmeshVertex {
+                    position: end + offset,
+                },
+            ),
+            edge,
+            select,
+        )
+    }
+}
+
+fn extrusion_direction(navmesh: &Navmesh, edge: NavmeshEdge) -> Vector3<f32> {
+    let begin = navmesh.vertices[edge.begin].position;
+    let end = navmesh.vertices[edge.end].position;
+    let edge_vector = end - begin;
+
+    // Find third vertex of a triangle that shares given edge.
+    let opposite = navmesh.triangles.iter().find_map(|triangle| {
+        let vertices = triangle.vertices();
+        if vertices.contains(&edge.begin) && vertices.contains(&edge.end) {
+            vertices
+                .iter()
+                .find(|&&v| v != edge.begin && v != edge.end)
+                .map(|&v| navmesh.vertices[v].position)
+        } else {
+            None
+        }
+    });
+
+    let direction = match opposite {
+        Some(opposite) => {
+            let to_opposite = opposite - begin;
+            let normal = edge_vector.cross(&to_opposite);
+            let perpendicular = normal.cross(&edge_vector);
+            if perpendicular.dot(&to_opposite) > 0.0 {
+                -perpendicular
+            } else {
+                perpendicular
+            }
+        }
+        // Lone edge, extrude it in horizontal plane.
+        None => edge_vector.cross(&Vector3::y()),
+    };
+
+    direction
+        .try_normalize(std::f32::EPSILON)
+        .unwrap_or_else(Vector3::z)
 }
 
 impl<'a> Command<'a> for AddNavmeshEdgeCommand {
@@ -722,11 +2780,13 @@ impl<'a> Command<'a> for AddNavmeshEdgeCommand {
                     a: self.opposite_edge.begin,
                     b: begin_handle,
                     c: self.opposite_edge.end,
+                    region: 0,
                 });
                 let triangle_b = navmesh.triangles.spawn(NavmeshTriangle {
                     a: begin_handle,
                     b: end_handle,
                     c: self.opposite_edge.end,
+                    region: 0,
                 });
                 self.state = AddNavmeshEdgeCommandState::Executed {
                     triangles: [triangle_a, triangle_b],
@@ -859,11 +2919,13 @@ impl<'a> Command<'a> for ConnectNavmeshEdgesCommand {
                     a: edges[0].begin,
                     b: edges[0].end,
                     c: edges[1].begin,
+                    region: 0,
                 });
                 let tb = navmesh.triangles.spawn(NavmeshTriangle {
                     a: edges[1].begin,
                     b: edges[1].end,
                     c: edges[0].begin,
+                    region: 0,
                 });
 
                 self.state = ConnectNavmeshEdgesCommandState::Executed {
@@ -917,6 +2979,7 @@ pub struct DeleteEmitterCommand {
     particle_system: Handle<Node>,
     emitter: Option<Emitter>,
     emitter_index: usize,
+    editor_data: Option<EmitterEditorData>,
 }
 
 impl DeleteEmitterCommand {
@@ -925,6 +2988,7 @@ impl DeleteEmitterCommand {
             particle_system,
             emitter: None,
             emitter_index,
+            editor_data: None,
         }
     }
 }
@@ -943,17 +3007,99 @@ impl<'a> Command<'a> for DeleteEmitterCommand {
                 .emitters
                 .remove(self.emitter_index),
         );
+        self.editor_data = Some(
+            context
+                .editor_scene
+                .remove_emitter_data(self.particle_system, self.emitter_index),
+        );
     }
 
     fn revert(&mut self, context: &mut Self::Context) {
         let particle_system: &mut ParticleSystem =
             context.scene.graph[self.particle_system].as_particle_system_mut();
-        if self.emitter_index == 0 {
-            particle_system.emitters.push(self.emitter.take().unwrap());
+        particle_system
+            .emitters
+            .insert(self.emitter_index, self.emitter.take().unwrap());
+        context.editor_scene.insert_emitter_data(
+            self.particle_system,
+            self.emitter_index,
+            self.editor_data.take().unwrap_or_default(),
+        );
+    }
+}
+
+/// Moves emitter of a particle system to other position, emitters between old and new
+/// positions are shifted.
+#[derive(Debug)]
+pub struct MoveEmitterCommand {
+    particle_system: Handle<Node>,
+    from_index: usize,
+    to_index: usize,
+    moved: bool,
+}
+
+impl MoveEmitterCommand {
+    pub fn new(particle_system: Handle<Node>, from_index: usize, to_index: usize) -> Self {
+        Self {
+            particle_system,
+            from_index,
+            to_index,
+            moved: false,
+        }
+    }
+
+    fn move_emitter(emitters: &mut Vec<Emitter>, from: usize, to: usize) {
+        let emitter = emitters.remove(from);
+        emitters.insert(to, emitter);
+    }
+}
+
+impl<'a> Command<'a> for MoveEmitterCommand {
+    type Context = SceneContext<'a>;
+
+    fn name(&mut self, _context: &Self::Context) -> String {
+        "Move Particle System Emitter".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut Self::Context) {
+        let emitters = &mut context.scene.graph[self.particle_system]
+            .as_particle_system_mut()
+            .emitters;
+        self.moved = self.from_index < emitters.len() && self.to_index < emitters.len();
+        if self.moved {
+            Self::move_emitter(emitters, self.from_index, self.to_index);
+            context.editor_scene.move_emitter_data(
+                self.particle_system,
+                self.from_index,
+                self.to_index,
+            );
         } else {
-            particle_system
-                .emitters
-                .insert(self.emitter_index, self.emitter.take().unwrap());
+            context
+                .message_sender
+                .send(Message::Log(format!(
+                    "Unable to move emitter from {} to {}, particle system has only {} emitters.",
+                    self.from_index,
+                    self.to_index,
+                    emitters.len()
+                )))
+                .unwrap();
+        }
+    }
+
+    fn revert(&mut self, context: &mut Self::Context) {
+        if self.moved {
+            Self::move_emitter(
+                &mut context.scene.graph[self.particle_system]
+                    .as_particle_system_mut()
+                    .emitters,
+                self.to_index,
+                self.from_index,
+            );
+            context.editor_scene.move_emitter_data(
+                self.particle_system,
+                self.to_index,
+                self.from_index,
+            );
         }
     }
 }
@@ -973,6 +3119,11 @@ impl AddNavmeshCommand {
             navmesh: Some(navmesh),
         }
     }
+
+    /// Creates command that adds a deep copy of given navmesh.
+    pub fn clone_of(navmesh: &Navmesh) -> Self {
+        Self::new(navmesh.deep_clone())
+    }
 }
 
 impl<'a> Command<'a> for AddNavmeshCommand {
@@ -1082,135 +3233,236 @@ define_pool_command!(
     navmesh: Handle<Navmesh>
 );
 
+/// Duplicates a vertex of a navmesh and makes given triangles use the duplicate instead of
+/// the vertex, so the triangles can be edited separately. Inverse of welding.
 #[derive(Debug)]
-pub struct DeleteNavmeshCommand {
-    handle: Handle<Navmesh>,
-    ticket: Option<Ticket<Navmesh>>,
-    node: Option<Navmesh>,
+pub struct SplitNavmeshVertexCommand {
+    navmesh: Handle<Navmesh>,
+    vertex: Handle<NavmeshVertex>,
+    triangles: Vec<Handle<NavmeshTriangle>>,
+    state: SplitNavmeshVertexCommandState,
 }
 
-impl DeleteNavmeshCommand {
-    pub fn new(handle: Handle<Navmesh>) -> Self {
+#[derive(Debug)]
+pub enum SplitNavmeshVertexCommandState {
+    Undefined,
+    NonExecuted,
+    Executed {
+        duplicate: Handle<NavmeshVertex>,
+        // Rewired triangles with their original vertices.
+        original_triangles: Vec<(Handle<NavmeshTriangle>, NavmeshTriangle)>,
+    },
+    Reverted {
+        duplicate: (Ticket<NavmeshVertex>, NavmeshVertex),
+    },
+}
+
+impl SplitNavmeshVertexCommand {
+    pub fn new(
+        navmesh: Handle<Navmesh>,
+        vertex: Handle<NavmeshVertex>,
+        triangles: Vec<Handle<NavmeshTriangle>>,
+    ) -> Self {
         Self {
-            handle,
-            ticket: None,
-            node: None,
+            navmesh,
+            vertex,
+            triangles,
+            state: SplitNavmeshVertexCommandState::NonExecuted,
         }
     }
 }
 
-impl<'a> Command<'a> for DeleteNavmeshCommand {
+impl<'a> Command<'a> for SplitNavmeshVertexCommand {
     type Context = SceneContext<'a>;
 
     fn name(&mut self, _context: &Self::Context) -> String {
-        "Delete Navmesh".to_owned()
+        "Split Navmesh Vertex".to_owned()
     }
 
     fn execute(&mut self, context: &mut Self::Context) {
-        let (ticket, node) = context.editor_scene.navmeshes.take_reserve(self.handle);
-        self.node = Some(node);
-        self.ticket = Some(ticket);
+        let navmesh = &mut context.editor_scene.navmeshes[self.navmesh];
+
+        let duplicate =
+            match std::mem::replace(&mut self.state, SplitNavmeshVertexCommandState::Undefined) {
+                SplitNavmeshVertexCommandState::NonExecuted => {
+                    let vertex = navmesh.vertices[self.vertex].clone();
+                    navmesh.vertices.spawn(vertex)
+                }
+                SplitNavmeshVertexCommandState::Reverted { duplicate } => {
+                    navmesh.vertices.put_back(duplicate.0, duplicate.1)
+                }
+                _ => unreachable!(),
+            };
+
+        let mut original_triangles = Vec::new();
+        for &handle in self.triangles.iter() {
+            let triangle = &mut navmesh.triangles[handle];
+            if triangle.vertices().contains(&self.vertex) {
+                original_triangles.push((handle, triangle.clone()));
+                for v in [&mut triangle.a, &mut triangle.b, &mut triangle.c].iter_mut() {
+                    if **v == self.vertex {
+                        **v = duplicate;
+                    }
+                }
+            }
+        }
+
+        self.state = SplitNavmeshVertexCommandState::Executed {
+            duplicate,
+            original_triangles,
+        };
     }
 
     fn revert(&mut self, context: &mut Self::Context) {
-        self.handle = context
-            .editor_scene
-            .navmeshes
-            .put_back(self.ticket.take().unwrap(), self.node.take().unwrap());
+        let navmesh = &mut context.editor_scene.navmeshes[self.navmesh];
+
+        match std::mem::replace(&mut self.state, SplitNavmeshVertexCommandState::Undefined) {
+            SplitNavmeshVertexCommandState::Executed {
+                duplicate,
+                original_triangles,
+            } => {
+                for (handle, triangle) in original_triangles {
+                    navmesh.triangles[handle] = triangle;
+                }
+                self.state = SplitNavmeshVertexCommandState::Reverted {
+                    duplicate: navmesh.vertices.take_reserve(duplicate),
+                };
+            }
+            _ => unreachable!(),
+        }
     }
 
     fn finalize(&mut self, context: &mut Self::Context) {
-        if let Some(ticket) = self.ticket.take() {
-            context.editor_scene.navmeshes.forget_ticket(ticket)
+        if let SplitNavmeshVertexCommandState::Reverted { duplicate } =
+            std::mem::replace(&mut self.state, SplitNavmeshVertexCommandState::Undefined)
+        {
+            if let Some(navmesh) = context.editor_scene.navmeshes.try_borrow_mut(self.navmesh) {
+                navmesh.vertices.forget_ticket(duplicate.0);
+            }
         }
     }
 }
 
+/// Merges vertices of a navmesh that are closer than `epsilon` to each other into one,
+/// triangles that become degenerate after merge are removed.
 #[derive(Debug)]
-pub struct DeleteNavmeshVertexCommand {
+pub struct WeldNavmeshVerticesCommand {
     navmesh: Handle<Navmesh>,
-    state: DeleteNavmeshVertexCommandState,
+    epsilon: f32,
+    state: WeldNavmeshVerticesCommandState,
 }
 
 #[derive(Debug)]
-pub enum DeleteNavmeshVertexCommandState {
-    Undefined,
-    NonExecuted {
-        vertex: Handle<NavmeshVertex>,
-    },
+pub enum WeldNavmeshVerticesCommandState {
+    NonExecuted,
     Executed {
-        vertex: (Ticket<NavmeshVertex>, NavmeshVertex),
+        vertices: Vec<(Ticket<NavmeshVertex>, NavmeshVertex)>,
         triangles: Vec<(Ticket<NavmeshTriangle>, NavmeshTriangle)>,
+        // Triangles that were rewritten to use merged vertices with their original vertices.
+        rewritten_triangles: Vec<(Handle<NavmeshTriangle>, NavmeshTriangle)>,
     },
-    Reverted {
-        vertex: Handle<NavmeshVertex>,
-    },
+    Reverted,
 }
 
-impl DeleteNavmeshVertexCommand {
-    pub fn new(navmesh: Handle<Navmesh>, vertex: Handle<NavmeshVertex>) -> Self {
+impl WeldNavmeshVerticesCommand {
+    pub fn new(navmesh: Handle<Navmesh>, epsilon: f32) -> Self {
         Self {
             navmesh,
-            state: DeleteNavmeshVertexCommandState::NonExecuted { vertex },
+            epsilon,
+            state: WeldNavmeshVerticesCommandState::NonExecuted,
         }
     }
 }
 
-impl<'a> Command<'a> for DeleteNavmeshVertexCommand {
+impl<'a> Command<'a> for WeldNavmeshVerticesCommand {
     type Context = SceneContext<'a>;
 
     fn name(&mut self, _context: &Self::Context) -> String {
-        "Delete Navmesh Vertex".to_owned()
+        "Weld Navmesh Vertices".to_owned()
     }
 
     fn execute(&mut self, context: &mut Self::Context) {
         let navmesh = &mut context.editor_scene.navmeshes[self.navmesh];
 
-        match std::mem::replace(&mut self.state, DeleteNavmeshVertexCommandState::Undefined) {
-            DeleteNavmeshVertexCommandState::NonExecuted { vertex }
-            | DeleteNavmeshVertexCommandState::Reverted { vertex } => {
-                // Find each triangle that shares the same vertex and move them out of pool.
-                let mut triangles = Vec::new();
-                for (handle, triangle) in navmesh.triangles.pair_iter() {
-                    if triangle.vertices().contains(&vertex) {
-                        triangles.push(handle);
-                    }
+        // Find representative for each vertex, first vertex of a cluster is representative.
+        let mut representatives: Vec<(Handle<NavmeshVertex>, Vector3<f32>)> = Vec::new();
+        let mut replacements = HashMap::new();
+        for (handle, vertex) in navmesh.vertices.pair_iter() {
+            match representatives
+                .iter()
+                .find(|(_, position)| position.metric_distance(&vertex.position) <= self.epsilon)
+            {
+                Some(&(representative, _)) => {
+                    replacements.insert(handle, representative);
                 }
+                None => representatives.push((handle, vertex.position)),
+            }
+        }
 
-                self.state = DeleteNavmeshVertexCommandState::Executed {
-                    vertex: navmesh.vertices.take_reserve(vertex),
-                    triangles: triangles
-                        .iter()
-                        .map(|&t| navmesh.triangles.take_reserve(t))
-                        .collect(),
-                };
+        let mut degenerate_triangles = Vec::new();
+        let mut rewritten_triangles = Vec::new();
+        for (handle, triangle) in navmesh.triangles.pair_iter_mut() {
+            let replace = |v: Handle<NavmeshVertex>| *replacements.get(&v).unwrap_or(&v);
+            let (a, b, c) = (
+                replace(triangle.a),
+                replace(triangle.b),
+                replace(triangle.c),
+            );
+            if a == b || b == c || c == a {
+                degenerate_triangles.push(handle);
+            } else if a != triangle.a || b != triangle.b || c != triangle.c {
+                rewritten_triangles.push((handle, triangle.clone()));
+                triangle.a = a;
+                triangle.b = b;
+                triangle.c = c;
             }
-            _ => unreachable!(),
         }
+
+        self.state = WeldNavmeshVerticesCommandState::Executed {
+            vertices: replacements
+                .keys()
+                .map(|&v| navmesh.vertices.take_reserve(v))
+                .collect(),
+            triangles: degenerate_triangles
+                .into_iter()
+                .map(|t| navmesh.triangles.take_reserve(t))
+                .collect(),
+            rewritten_triangles,
+        };
     }
 
     fn revert(&mut self, context: &mut Self::Context) {
         let navmesh = &mut context.editor_scene.navmeshes[self.navmesh];
 
-        match std::mem::replace(&mut self.state, DeleteNavmeshVertexCommandState::Undefined) {
-            DeleteNavmeshVertexCommandState::Executed { vertex, triangles } => {
-                let vertex = navmesh.vertices.put_back(vertex.0, vertex.1);
-                for (ticket, triangle) in triangles {
-                    navmesh.triangles.put_back(ticket, triangle);
-                }
-
-                self.state = DeleteNavmeshVertexCommandState::Reverted { vertex };
+        if let WeldNavmeshVerticesCommandState::Executed {
+            vertices,
+            triangles,
+            rewritten_triangles,
+        } = std::mem::replace(&mut self.state, WeldNavmeshVerticesCommandState::Reverted)
+        {
+            for (ticket, vertex) in vertices {
+                navmesh.vertices.put_back(ticket, vertex);
+            }
+            for (ticket, triangle) in triangles {
+                navmesh.triangles.put_back(ticket, triangle);
+            }
+            for (handle, triangle) in rewritten_triangles {
+                navmesh.triangles[handle] = triangle;
             }
-            _ => unreachable!(),
         }
     }
 
     fn finalize(&mut self, context: &mut Self::Context) {
-        if let DeleteNavmeshVertexCommandState::Executed { vertex, triangles } =
-            std::mem::replace(&mut self.state, DeleteNavmeshVertexCommandState::Undefined)
+        if let WeldNavmeshVerticesCommandState::Executed {
+            vertices,
+            triangles,
+            ..
+        } = std::mem::replace(&mut self.state, WeldNavmeshVerticesCommandState::Reverted)
         {
             if let Some(navmesh) = context.editor_scene.navmeshes.try_borrow_mut(self.navmesh) {
-                navmesh.vertices.forget_ticket(vertex.0);
+                for (ticket, _) in vertices {
+                    navmesh.vertices.forget_ticket(ticket);
+                }
                 for (ticket, _) in triangles {
                     navmesh.triangles.forget_ticket(ticket);
                 }
@@ -1219,76 +3471,139 @@ impl<'a> Command<'a> for DeleteNavmeshVertexCommand {
     }
 }
 
+/// Checks whether a triangle could be added to a navmesh, returns reason why it can't.
+fn validate_navmesh_triangle(
+    navmesh: &Navmesh,
+    vertices: [Handle<NavmeshVertex>; 3],
+) -> Result<(), String> {
+    let [a, b, c] = vertices;
+
+    if vertices
+        .iter()
+        .any(|&v| !navmesh.vertices.is_valid_handle(v))
+    {
+        return Err("One or more vertices of the triangle do not exist".to_owned());
+    }
+
+    if a == b || b == c || c == a {
+        return Err("Triangle vertices must be distinct".to_owned());
+    }
+
+    let (pa, pb, pc) = (
+        navmesh.vertices[a].position,
+        navmesh.vertices[b].position,
+        navmesh.vertices[c].position,
+    );
+    if (pb - pa).cross(&(pc - pa)).norm() <= f32::EPSILON {
+        return Err("Triangle has zero area".to_owned());
+    }
+
+    if navmesh.triangles.iter().any(|triangle| {
+        let existing = triangle.vertices();
+        vertices.iter().all(|v| existing.contains(v))
+    }) {
+        return Err("Identical triangle already exists".to_owned());
+    }
+
+    Ok(())
+}
+
+/// Adds a triangle built on existing vertices of a navmesh. Unlike [`AddNavmeshTriangleCommand`]
+/// it validates the triangle first and does nothing if the triangle is degenerate or a
+/// duplicate of existing one.
 #[derive(Debug)]
-pub struct AddJointCommand {
-    ticket: Option<Ticket<Joint>>,
-    handle: Handle<Joint>,
-    joint: Option<Joint>,
+pub struct AddNavmeshTriangleByVerticesCommand {
+    navmesh: Handle<Navmesh>,
+    vertices: [Handle<NavmeshVertex>; 3],
+    ticket: Option<Ticket<NavmeshTriangle>>,
+    handle: Handle<NavmeshTriangle>,
+    triangle: Option<NavmeshTriangle>,
 }
 
-impl AddJointCommand {
-    pub fn new(node: Joint) -> Self {
+impl AddNavmeshTriangleByVerticesCommand {
+    pub fn new(
+        navmesh: Handle<Navmesh>,
+        a: Handle<NavmeshVertex>,
+        b: Handle<NavmeshVertex>,
+        c: Handle<NavmeshVertex>,
+    ) -> Self {
         Self {
+            navmesh,
+            vertices: [a, b, c],
             ticket: None,
             handle: Default::default(),
-            joint: Some(node),
+            triangle: None,
         }
     }
 }
 
-impl<'a> Command<'a> for AddJointCommand {
+impl<'a> Command<'a> for AddNavmeshTriangleByVerticesCommand {
     type Context = SceneContext<'a>;
 
     fn name(&mut self, _context: &Self::Context) -> String {
-        "Add Joint".to_owned()
+        "Add Navmesh Triangle".to_owned()
     }
 
     fn execute(&mut self, context: &mut Self::Context) {
+        let navmesh = &mut context.editor_scene.navmeshes[self.navmesh];
+
         match self.ticket.take() {
             None => {
-                self.handle = context
-                    .editor_scene
-                    .physics
-                    .joints
-                    .spawn(self.joint.take().unwrap());
+                if let Err(reason) = validate_navmesh_triangle(navmesh, self.vertices) {
+                    context
+                        .message_sender
+                        .send(Message::Log(format!(
+                            "Navmesh triangle was not added: {}.",
+                            reason
+                        )))
+                        .unwrap();
+                    return;
+                }
+
+                let [a, b, c] = self.vertices;
+                self.handle = navmesh
+                    .triangles
+                    .spawn(NavmeshTriangle { a, b, c, region: 0 });
             }
             Some(ticket) => {
-                let handle = context
-                    .editor_scene
-                    .physics
-                    .joints
-                    .put_back(ticket, self.joint.take().unwrap());
+                let handle = navmesh
+                    .triangles
+                    .put_back(ticket, self.triangle.take().unwrap());
                 assert_eq!(handle, self.handle);
             }
         }
     }
 
     fn revert(&mut self, context: &mut Self::Context) {
-        let (ticket, node) = context
-            .editor_scene
-            .physics
-            .joints
-            .take_reserve(self.handle);
+        // Command was refused, nothing to revert.
+        if self.handle.is_none() {
+            return;
+        }
+
+        let navmesh = &mut context.editor_scene.navmeshes[self.navmesh];
+        let (ticket, triangle) = navmesh.triangles.take_reserve(self.handle);
         self.ticket = Some(ticket);
-        self.joint = Some(node);
+        self.triangle = Some(triangle);
     }
 
     fn finalize(&mut self, context: &mut Self::Context) {
         if let Some(ticket) = self.ticket.take() {
-            context.editor_scene.physics.joints.forget_ticket(ticket)
+            if let Some(navmesh) = context.editor_scene.navmeshes.try_borrow_mut(self.navmesh) {
+                navmesh.triangles.forget_ticket(ticket);
+            }
         }
     }
 }
 
 #[derive(Debug)]
-pub struct DeleteJointCommand {
-    handle: Handle<Joint>,
-    ticket: Option<Ticket<Joint>>,
-    node: Option<Joint>,
+pub struct DeleteNavmeshCommand {
+    handle: Handle<Navmesh>,
+    ticket: Option<Ticket<Navmesh>>,
+    node: Option<Navmesh>,
 }
 
-impl DeleteJointCommand {
-    pub fn new(handle: Handle<Joint>) -> Self {
+impl DeleteNavmeshCommand {
+    pub fn new(handle: Handle<Navmesh>) -> Self {
         Self {
             handle,
             ticket: None,
@@ -1297,19 +3612,15 @@ impl DeleteJointCommand {
     }
 }
 
-impl<'a> Command<'a> for DeleteJointCommand {
+impl<'a> Command<'a> for DeleteNavmeshCommand {
     type Context = SceneContext<'a>;
 
     fn name(&mut self, _context: &Self::Context) -> String {
-        "Delete Joint".to_owned()
+        "Delete Navmesh".to_owned()
     }
 
     fn execute(&mut self, context: &mut Self::Context) {
-        let (ticket, node) = context
-            .editor_scene
-            .physics
-            .joints
-            .take_reserve(self.handle);
+        let (ticket, node) = context.editor_scene.navmeshes.take_reserve(self.handle);
         self.node = Some(node);
         self.ticket = Some(ticket);
     }
@@ -1317,2210 +3628,8170 @@ impl<'a> Command<'a> for DeleteJointCommand {
     fn revert(&mut self, context: &mut Self::Context) {
         self.handle = context
             .editor_scene
-            .physics
-            .joints
+            .navmeshes
             .put_back(self.ticket.take().unwrap(), self.node.take().unwrap());
     }
 
     fn finalize(&mut self, context: &mut Self::Context) {
         if let Some(ticket) = self.ticket.take() {
-            context.editor_scene.physics.joints.forget_ticket(ticket)
+            context.editor_scene.navmeshes.forget_ticket(ticket)
         }
     }
 }
 
 #[derive(Debug)]
-pub struct ChangeSelectionCommand {
-    new_selection: Selection,
-    old_selection: Selection,
-    cached_name: String,
+pub struct DeleteNavmeshVertexCommand {
+    navmesh: Handle<Navmesh>,
+    state: DeleteNavmeshVertexCommandState,
 }
 
-impl ChangeSelectionCommand {
-    pub fn new(new_selection: Selection, old_selection: Selection) -> Self {
+#[derive(Debug)]
+pub enum DeleteNavmeshVertexCommandState {
+    Undefined,
+    NonExecuted {
+        vertex: Handle<NavmeshVertex>,
+    },
+    Executed {
+        vertex: (Ticket<NavmeshVertex>, NavmeshVertex),
+        triangles: Vec<(Ticket<NavmeshTriangle>, NavmeshTriangle)>,
+    },
+    Reverted {
+        vertex: Handle<NavmeshVertex>,
+    },
+}
+
+impl DeleteNavmeshVertexCommand {
+    pub fn new(navmesh: Handle<Navmesh>, vertex: Handle<NavmeshVertex>) -> Self {
         Self {
-            cached_name: match new_selection {
-                Selection::None => "Change Selection: None",
-                Selection::Graph(_) => "Change Selection: Graph",
-                Selection::Navmesh(_) => "Change Selection: Navmesh",
-            }
-            .to_owned(),
-            new_selection,
-            old_selection,
+            navmesh,
+            state: DeleteNavmeshVertexCommandState::NonExecuted { vertex },
         }
     }
-
-    fn swap(&mut self) -> Selection {
-        let selection = self.new_selection.clone();
-        std::mem::swap(&mut self.new_selection, &mut self.old_selection);
-        selection
-    }
 }
 
-impl<'a> Command<'a> for ChangeSelectionCommand {
+impl<'a> Command<'a> for DeleteNavmeshVertexCommand {
     type Context = SceneContext<'a>;
 
     fn name(&mut self, _context: &Self::Context) -> String {
-        self.cached_name.clone()
+        "Delete Navmesh Vertex".to_owned()
     }
 
     fn execute(&mut self, context: &mut Self::Context) {
-        let new_selection = self.swap();
-        if new_selection != context.editor_scene.selection {
-            context.editor_scene.selection = new_selection;
-            context
-                .message_sender
-                .send(Message::SelectionChanged)
-                .unwrap();
+        let navmesh = &mut context.editor_scene.navmeshes[self.navmesh];
+
+        match std::mem::replace(&mut self.state, DeleteNavmeshVertexCommandState::Undefined) {
+            DeleteNavmeshVertexCommandState::NonExecuted { vertex }
+            | DeleteNavmeshVertexCommandState::Reverted { vertex } => {
+                // Find each triangle that shares the same vertex and move them out of pool.
+                let mut triangles = Vec::new();
+                for (handle, triangle) in navmesh.triangles.pair_iter() {
+                    if triangle.vertices().contains(&vertex) {
+                        triangles.push(handle);
+                    }
+                }
+
+                self.state = DeleteNavmeshVertexCommandState::Executed {
+                    vertex: navmesh.vertices.take_reserve(vertex),
+                    triangles: triangles
+                        .iter()
+                        .map(|&t| navmesh.triangles.take_reserve(t))
+                        .collect(),
+                };
+            }
+            _ => unreachable!(),
         }
     }
 
     fn revert(&mut self, context: &mut Self::Context) {
-        let new_selection = self.swap();
-        if new_selection != context.editor_scene.selection {
-            context.editor_scene.selection = new_selection;
-            context
-                .message_sender
-                .send(Message::SelectionChanged)
-                .unwrap();
+        let navmesh = &mut context.editor_scene.navmeshes[self.navmesh];
+
+        match std::mem::replace(&mut self.state, DeleteNavmeshVertexCommandState::Undefined) {
+            DeleteNavmeshVertexCommandState::Executed { vertex, triangles } => {
+                let vertex = navmesh.vertices.put_back(vertex.0, vertex.1);
+                for (ticket, triangle) in triangles {
+                    navmesh.triangles.put_back(ticket, triangle);
+                }
+
+                self.state = DeleteNavmeshVertexCommandState::Reverted { vertex };
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn finalize(&mut self, context: &mut Self::Context) {
+        if let DeleteNavmeshVertexCommandState::Executed { vertex, triangles } =
+            std::mem::replace(&mut self.state, DeleteNavmeshVertexCommandState::Undefined)
+        {
+            if let Some(navmesh) = context.editor_scene.navmeshes.try_borrow_mut(self.navmesh) {
+                navmesh.vertices.forget_ticket(vertex.0);
+                for (ticket, _) in triangles {
+                    navmesh.triangles.forget_ticket(ticket);
+                }
+            }
         }
     }
 }
 
+/// Deletes triangles of a navmesh together with vertices that are used only by deleted
+/// triangles.
 #[derive(Debug)]
-enum PasteCommandState {
+pub struct DeleteNavmeshTrianglesCommand {
+    navmesh: Handle<Navmesh>,
+    state: DeleteNavmeshTrianglesCommandState,
+}
+
+#[derive(Debug)]
+pub enum DeleteNavmeshTrianglesCommandState {
     Undefined,
-    NonExecuted,
-    Reverted {
-        subgraphs: Vec<SubGraph>,
-        bodies: Vec<(Ticket<RigidBody>, RigidBody)>,
-        colliders: Vec<(Ticket<Collider>, Collider)>,
-        joints: Vec<(Ticket<Joint>, Joint)>,
-        binder: HashMap<Handle<Node>, Handle<RigidBody>>,
-        selection: Selection,
+    NonExecuted {
+        triangles: Vec<Handle<NavmeshTriangle>>,
     },
     Executed {
-        paste_result: DeepCloneResult,
-        last_selection: Selection,
+        vertices: Vec<(Ticket<NavmeshVertex>, NavmeshVertex)>,
+        triangles: Vec<(Ticket<NavmeshTriangle>, NavmeshTriangle)>,
+    },
+    Reverted {
+        triangles: Vec<Handle<NavmeshTriangle>>,
     },
 }
 
-#[derive(Debug)]
-pub struct PasteCommand {
-    state: PasteCommandState,
-}
-
-impl Default for PasteCommand {
-    fn default() -> Self {
-        Self::new()
-    }
-}
+impl DeleteNavmeshTrianglesCommand {
+    pub fn new(navmesh: Handle<Navmesh>, mut triangles: Vec<Handle<NavmeshTriangle>>) -> Self {
+        triangles.sort_by_key(|t| t.index());
+        triangles.dedup();
 
-impl PasteCommand {
-    pub fn new() -> Self {
         Self {
-            state: PasteCommandState::NonExecuted,
+            navmesh,
+            state: DeleteNavmeshTrianglesCommandState::NonExecuted { triangles },
         }
     }
 }
 
-impl<'a> Command<'a> for PasteCommand {
+impl<'a> Command<'a> for DeleteNavmeshTrianglesCommand {
     type Context = SceneContext<'a>;
 
     fn name(&mut self, _context: &Self::Context) -> String {
-        "Paste".to_owned()
+        "Delete Navmesh Triangles".to_owned()
     }
 
     fn execute(&mut self, context: &mut Self::Context) {
-        match std::mem::replace(&mut self.state, PasteCommandState::Undefined) {
-            PasteCommandState::NonExecuted => {
-                let paste_result = context
-                    .editor_scene
-                    .clipboard
-                    .paste(&mut context.scene.graph, &mut context.editor_scene.physics);
+        let navmesh = &mut context.editor_scene.navmeshes[self.navmesh];
 
-                let mut selection =
-                    Selection::Graph(GraphSelection::from_list(paste_result.root_nodes.clone()));
-                std::mem::swap(&mut context.editor_scene.selection, &mut selection);
+        match std::mem::replace(
+            &mut self.state,
+            DeleteNavmeshTrianglesCommandState::Undefined,
+        ) {
+            DeleteNavmeshTrianglesCommandState::NonExecuted { triangles }
+            | DeleteNavmeshTrianglesCommandState::Reverted { triangles } => {
+                let triangles = triangles
+                    .iter()
+                    .map(|&t| navmesh.triangles.take_reserve(t))
+                    .collect::<Vec<_>>();
 
-                self.state = PasteCommandState::Executed {
-                    paste_result,
-                    last_selection: selection,
+                // Vertices of deleted triangles that aren't used by the rest of triangles.
+                let mut vertices = triangles
+                    .iter()
+                    .flat_map(|(_, triangle)| triangle.vertices().to_vec())
+                    .filter(|v| {
+                        !navmesh
+                            .triangles
+                            .iter()
+                            .any(|triangle| triangle.vertices().contains(v))
+                    })
+                    .collect::<Vec<_>>();
+                vertices.sort_by_key(|v| v.index());
+                vertices.dedup();
+
+                self.state = DeleteNavmeshTrianglesCommandState::Executed {
+                    vertices: vertices
+                        .iter()
+                        .map(|&v| navmesh.vertices.take_reserve(v))
+                        .collect(),
+                    triangles,
                 };
             }
-            PasteCommandState::Reverted {
-                subgraphs,
-                bodies,
-                colliders,
-                joints,
-                binder,
-                mut selection,
-            } => {
-                let mut paste_result = DeepCloneResult {
-                    binder,
-                    ..Default::default()
-                };
+            _ => unreachable!(),
+        }
+    }
 
-                for subgraph in subgraphs {
-                    paste_result
-                        .root_nodes
-                        .push(context.scene.graph.put_sub_graph_back(subgraph));
-                }
+    fn revert(&mut self, context: &mut Self::Context) {
+        let navmesh = &mut context.editor_scene.navmeshes[self.navmesh];
 
-                for (ticket, body) in bodies {
-                    paste_result
-                        .bodies
-                        .push(context.editor_scene.physics.bodies.put_back(ticket, body));
+        match std::mem::replace(
+            &mut self.state,
+            DeleteNavmeshTrianglesCommandState::Undefined,
+        ) {
+            DeleteNavmeshTrianglesCommandState::Executed {
+                vertices,
+                triangles,
+            } => {
+                for (ticket, vertex) in vertices {
+                    navmesh.vertices.put_back(ticket, vertex);
                 }
+                let triangles = triangles
+                    .into_iter()
+                    .map(|(ticket, triangle)| navmesh.triangles.put_back(ticket, triangle))
+                    .collect();
 
-                for (ticket, collider) in colliders {
-                    paste_result.colliders.push(
-                        context
-                            .editor_scene
-                            .physics
-                            .colliders
-                            .put_back(ticket, collider),
-                    );
-                }
+                self.state = DeleteNavmeshTrianglesCommandState::Reverted { triangles };
+            }
+            _ => unreachable!(),
+        }
+    }
 
-                for (ticket, joint) in joints {
-                    paste_result
-                        .joints
-                        .push(context.editor_scene.physics.joints.put_back(ticket, joint));
+    fn finalize(&mut self, context: &mut Self::Context) {
+        if let DeleteNavmeshTrianglesCommandState::Executed {
+            vertices,
+            triangles,
+        } = std::mem::replace(
+            &mut self.state,
+            DeleteNavmeshTrianglesCommandState::Undefined,
+        ) {
+            if let Some(navmesh) = context.editor_scene.navmeshes.try_borrow_mut(self.navmesh) {
+                for (ticket, _) in vertices {
+                    navmesh.vertices.forget_ticket(ticket);
                 }
-
-                for (&node, &body) in paste_result.binder.iter() {
-                    context.editor_scene.physics.binder.insert(node, body);
+                for (ticket, _) in triangles {
+                    navmesh.triangles.forget_ticket(ticket);
                 }
-
-                std::mem::swap(&mut context.editor_scene.selection, &mut selection);
-                self.state = PasteCommandState::Executed {
-                    paste_result,
-                    last_selection: selection,
-                };
             }
-            _ => unreachable!(),
         }
     }
+}
 
-    fn revert(&mut self, context: &mut Self::Context) {
-        if let PasteCommandState::Executed {
-            paste_result,
-            mut last_selection,
-        } = std::mem::replace(&mut self.state, PasteCommandState::Undefined)
-        {
-            let mut subgraphs = Vec::new();
-            for root_node in paste_result.root_nodes {
-                subgraphs.push(context.scene.graph.take_reserve_sub_graph(root_node));
-            }
-
-            let mut bodies = Vec::new();
-            for body in paste_result.bodies {
-                bodies.push(context.editor_scene.physics.bodies.take_reserve(body));
+/// Creates command that removes every triangle of a navmesh whose centroid lies outside of
+/// given box, so triangles that cross the box are kept if their centroid is inside. Returns
+/// `None` if there is nothing to remove.
+pub fn make_crop_navmesh_command(
+    navmesh_handle: Handle<Navmesh>,
+    navmesh: &Navmesh,
+    aabb: &AxisAlignedBoundingBox,
+) -> Option<SceneCommand> {
+    let is_inside = |p: Vector3<f32>| (0..3).all(|i| p[i] >= aabb.min[i] && p[i] <= aabb.max[i]);
+
+    let triangles = navmesh
+        .triangles
+        .pair_iter()
+        .filter(|(_, triangle)| {
+            let mut centroid = Vector3::default();
+            for &vertex in triangle.vertices().iter() {
+                centroid += navmesh.vertices[vertex].position;
             }
+            !is_inside(centroid.scale(1.0 / 3.0))
+        })
+        .map(|(handle, _)| handle)
+        .collect::<Vec<_>>();
 
-            let mut colliders = Vec::new();
-            for collider in paste_result.colliders {
-                colliders.push(
-                    context
-                        .editor_scene
-                        .physics
-                        .colliders
-                        .take_reserve(collider),
-                );
-            }
+    if triangles.is_empty() {
+        None
+    } else {
+        Some(SceneCommand::DeleteNavmeshTriangles(
+            DeleteNavmeshTrianglesCommand::new(navmesh_handle, triangles),
+        ))
+    }
+}
 
-            let mut joints = Vec::new();
-            for joint in paste_result.joints {
-                joints.push(context.editor_scene.physics.joints.take_reserve(joint));
-            }
+#[derive(Debug)]
+pub struct DeleteNavmeshVerticesCommand {
+    navmesh: Handle<Navmesh>,
+    state: DeleteNavmeshVerticesCommandState,
+}
 
-            for (node, _) in paste_result.binder.iter() {
-                context.editor_scene.physics.binder.remove_by_key(node);
-            }
+#[derive(Debug)]
+pub enum DeleteNavmeshVerticesCommandState {
+    Undefined,
+    NonExecuted {
+        vertices: Vec<Handle<NavmeshVertex>>,
+    },
+    Executed {
+        vertices: Vec<(Ticket<NavmeshVertex>, NavmeshVertex)>,
+        triangles: Vec<(Ticket<NavmeshTriangle>, NavmeshTriangle)>,
+    },
+    Reverted {
+        vertices: Vec<Handle<NavmeshVertex>>,
+    },
+}
 
-            std::mem::swap(&mut context.editor_scene.selection, &mut last_selection);
+impl DeleteNavmeshVerticesCommand {
+    pub fn new(navmesh: Handle<Navmesh>, mut vertices: Vec<Handle<NavmeshVertex>>) -> Self {
+        vertices.sort_by_key(|v| v.index());
+        vertices.dedup();
 
-            self.state = PasteCommandState::Reverted {
-                subgraphs,
-                bodies,
-                colliders,
-                joints,
-                binder: paste_result.binder,
-                selection: last_selection,
-            };
+        Self {
+            navmesh,
+            state: DeleteNavmeshVerticesCommandState::NonExecuted { vertices },
         }
     }
+}
 
-    fn finalize(&mut self, context: &mut Self::Context) {
-        if let PasteCommandState::Reverted {
-            subgraphs,
-            bodies,
-            colliders,
-            joints,
-            ..
-        } = std::mem::replace(&mut self.state, PasteCommandState::Undefined)
-        {
-            for subgraph in subgraphs {
-                context.scene.graph.forget_sub_graph(subgraph);
-            }
+impl<'a> Command<'a> for DeleteNavmeshVerticesCommand {
+    type Context = SceneContext<'a>;
 
-            for (ticket, _) in bodies {
-                context.editor_scene.physics.bodies.forget_ticket(ticket);
+    fn name(&mut self, _context: &Self::Context) -> String {
+        "Delete Navmesh Vertices".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut Self::Context) {
+        let navmesh = &mut context.editor_scene.navmeshes[self.navmesh];
+
+        match std::mem::replace(
+            &mut self.state,
+            DeleteNavmeshVerticesCommandState::Undefined,
+        ) {
+            DeleteNavmeshVerticesCommandState::NonExecuted { vertices }
+            | DeleteNavmeshVerticesCommandState::Reverted { vertices } => {
+                // Collect union of triangles that share any of deleted vertices, each triangle
+                // must be moved out of pool only once even if it references several vertices.
+                let triangles = navmesh
+                    .triangles
+                    .pair_iter()
+                    .filter(|(_, triangle)| {
+                        triangle.vertices().iter().any(|v| vertices.contains(v))
+                    })
+                    .map(|(handle, _)| handle)
+                    .collect::<Vec<_>>();
+
+                self.state = DeleteNavmeshVerticesCommandState::Executed {
+                    vertices: vertices
+                        .iter()
+                        .map(|&v| navmesh.vertices.take_reserve(v))
+                        .collect(),
+                    triangles: triangles
+                        .iter()
+                        .map(|&t| navmesh.triangles.take_reserve(t))
+                        .collect(),
+                };
             }
+            _ => unreachable!(),
+        }
+    }
 
-            for (ticket, _) in colliders {
-                context.editor_scene.physics.colliders.forget_ticket(ticket)
+    fn revert(&mut self, context: &mut Self::Context) {
+        let navmesh = &mut context.editor_scene.navmeshes[self.navmesh];
+
+        match std::mem::replace(
+            &mut self.state,
+            DeleteNavmeshVerticesCommandState::Undefined,
+        ) {
+            DeleteNavmeshVerticesCommandState::Executed {
+                vertices,
+                triangles,
+            } => {
+                let vertices = vertices
+                    .into_iter()
+                    .map(|(ticket, vertex)| navmesh.vertices.put_back(ticket, vertex))
+                    .collect();
+                for (ticket, triangle) in triangles {
+                    navmesh.triangles.put_back(ticket, triangle);
+                }
+
+                self.state = DeleteNavmeshVerticesCommandState::Reverted { vertices };
             }
+            _ => unreachable!(),
+        }
+    }
 
-            for (ticket, _) in joints {
-                context.editor_scene.physics.joints.forget_ticket(ticket);
+    fn finalize(&mut self, context: &mut Self::Context) {
+        if let DeleteNavmeshVerticesCommandState::Executed {
+            vertices,
+            triangles,
+        } = std::mem::replace(
+            &mut self.state,
+            DeleteNavmeshVerticesCommandState::Undefined,
+        ) {
+            if let Some(navmesh) = context.editor_scene.navmeshes.try_borrow_mut(self.navmesh) {
+                for (ticket, _) in vertices {
+                    navmesh.vertices.forget_ticket(ticket);
+                }
+                for (ticket, _) in triangles {
+                    navmesh.triangles.forget_ticket(ticket);
+                }
             }
         }
     }
 }
 
 #[derive(Debug)]
-pub struct MoveNavmeshVertexCommand {
-    navmesh: Handle<Navmesh>,
-    vertex: Handle<NavmeshVertex>,
-    old_position: Vector3<f32>,
-    new_position: Vector3<f32>,
+pub struct AddJointCommand {
+    ticket: Option<Ticket<Joint>>,
+    handle: Handle<Joint>,
+    joint: Option<Joint>,
 }
 
-impl MoveNavmeshVertexCommand {
-    pub fn new(
-        navmesh: Handle<Navmesh>,
-        vertex: Handle<NavmeshVertex>,
-        old_position: Vector3<f32>,
-        new_position: Vector3<f32>,
-    ) -> Self {
+impl AddJointCommand {
+    pub fn new(node: Joint) -> Self {
         Self {
-            navmesh,
-            vertex,
-            old_position,
-            new_position,
+            ticket: None,
+            handle: Default::default(),
+            joint: Some(node),
         }
     }
-
-    fn swap(&mut self) -> Vector3<f32> {
-        let position = self.new_position;
-        std::mem::swap(&mut self.new_position, &mut self.old_position);
-        position
-    }
-
-    fn set_position(&self, navmesh: &mut Navmesh, position: Vector3<f32>) {
-        navmesh.vertices[self.vertex].position = position;
-    }
 }
 
-impl<'a> Command<'a> for MoveNavmeshVertexCommand {
+impl<'a> Command<'a> for AddJointCommand {
     type Context = SceneContext<'a>;
 
     fn name(&mut self, _context: &Self::Context) -> String {
-        "Move Navmesh Vertex".to_owned()
+        "Add Joint".to_owned()
     }
 
     fn execute(&mut self, context: &mut Self::Context) {
-        let position = self.swap();
-        self.set_position(&mut context.editor_scene.navmeshes[self.navmesh], position);
+        match self.ticket.take() {
+            None => {
+                self.handle = context
+                    .editor_scene
+                    .physics
+                    .joints
+                    .spawn(self.joint.take().unwrap());
+            }
+            Some(ticket) => {
+                let handle = context
+                    .editor_scene
+                    .physics
+                    .joints
+                    .put_back(ticket, self.joint.take().unwrap());
+                assert_eq!(handle, self.handle);
+            }
+        }
     }
 
     fn revert(&mut self, context: &mut Self::Context) {
-        let position = self.swap();
-        self.set_position(&mut context.editor_scene.navmeshes[self.navmesh], position);
+        let (ticket, node) = context
+            .editor_scene
+            .physics
+            .joints
+            .take_reserve(self.handle);
+        self.ticket = Some(ticket);
+        self.joint = Some(node);
+    }
+
+    fn finalize(&mut self, context: &mut Self::Context) {
+        if let Some(ticket) = self.ticket.take() {
+            context.editor_scene.physics.joints.forget_ticket(ticket)
+        }
     }
 }
 
 #[derive(Debug)]
-pub struct MoveNodeCommand {
-    node: Handle<Node>,
-    old_position: Vector3<f32>,
-    new_position: Vector3<f32>,
+pub struct DeleteJointCommand {
+    handle: Handle<Joint>,
+    ticket: Option<Ticket<Joint>>,
+    node: Option<Joint>,
 }
 
-impl MoveNodeCommand {
-    pub fn new(node: Handle<Node>, old_position: Vector3<f32>, new_position: Vector3<f32>) -> Self {
+impl DeleteJointCommand {
+    pub fn new(handle: Handle<Joint>) -> Self {
         Self {
-            node,
-            old_position,
-            new_position,
-        }
-    }
-
-    fn swap(&mut self) -> Vector3<f32> {
-        let position = self.new_position;
-        std::mem::swap(&mut self.new_position, &mut self.old_position);
-        position
-    }
-
-    fn set_position(&self, graph: &mut Graph, physics: &mut Physics, position: Vector3<f32>) {
-        graph[self.node]
-            .local_transform_mut()
-            .set_position(position);
-        if let Some(&body) = physics.binder.value_of(&self.node) {
-            physics.bodies[body].position = position;
+            handle,
+            ticket: None,
+            node: None,
         }
     }
 }
 
-impl<'a> Command<'a> for MoveNodeCommand {
+impl<'a> Command<'a> for DeleteJointCommand {
     type Context = SceneContext<'a>;
 
     fn name(&mut self, _context: &Self::Context) -> String {
-        "Move Node".to_owned()
+        "Delete Joint".to_owned()
     }
 
     fn execute(&mut self, context: &mut Self::Context) {
-        let position = self.swap();
-        self.set_position(
-            &mut context.scene.graph,
-            &mut context.editor_scene.physics,
-            position,
-        );
-    }
+        let (ticket, node) = context
+            .editor_scene
+            .physics
+            .joints
+            .take_reserve(self.handle);
+        self.node = Some(node);
+        self.ticket = Some(ticket);
+    }
 
     fn revert(&mut self, context: &mut Self::Context) {
-        let position = self.swap();
-        self.set_position(
-            &mut context.scene.graph,
-            &mut context.editor_scene.physics,
-            position,
-        );
+        self.handle = context
+            .editor_scene
+            .physics
+            .joints
+            .put_back(self.ticket.take().unwrap(), self.node.take().unwrap());
     }
-}
-
-#[derive(Debug)]
-pub struct ScaleNodeCommand {
-    node: Handle<Node>,
-    old_scale: Vector3<f32>,
-    new_scale: Vector3<f32>,
-}
 
-impl ScaleNodeCommand {
-    pub fn new(node: Handle<Node>, old_scale: Vector3<f32>, new_scale: Vector3<f32>) -> Self {
-        Self {
-            node,
-            old_scale,
-            new_scale,
+    fn finalize(&mut self, context: &mut Self::Context) {
+        if let Some(ticket) = self.ticket.take() {
+            context.editor_scene.physics.joints.forget_ticket(ticket)
         }
     }
-
-    fn swap(&mut self) -> Vector3<f32> {
-        let position = self.new_scale;
-        std::mem::swap(&mut self.new_scale, &mut self.old_scale);
-        position
-    }
-
-    fn set_scale(&self, graph: &mut Graph, scale: Vector3<f32>) {
-        graph[self.node].local_transform_mut().set_scale(scale);
-    }
-}
-
-impl<'a> Command<'a> for ScaleNodeCommand {
-    type Context = SceneContext<'a>;
-
-    fn name(&mut self, _context: &Self::Context) -> String {
-        "Scale Node".to_owned()
-    }
-
-    fn execute(&mut self, context: &mut Self::Context) {
-        let scale = self.swap();
-        self.set_scale(&mut context.scene.graph, scale);
-    }
-
-    fn revert(&mut self, context: &mut Self::Context) {
-        let scale = self.swap();
-        self.set_scale(&mut context.scene.graph, scale);
-    }
 }
 
 #[derive(Debug)]
-pub struct RotateNodeCommand {
-    node: Handle<Node>,
-    old_rotation: UnitQuaternion<f32>,
-    new_rotation: UnitQuaternion<f32>,
+pub struct ChangeSelectionCommand {
+    new_selection: Selection,
+    old_selection: Selection,
+    cached_name: String,
 }
 
-impl RotateNodeCommand {
-    pub fn new(
-        node: Handle<Node>,
-        old_rotation: UnitQuaternion<f32>,
-        new_rotation: UnitQuaternion<f32>,
-    ) -> Self {
+impl ChangeSelectionCommand {
+    pub fn new(new_selection: Selection, old_selection: Selection) -> Self {
         Self {
-            node,
-            old_rotation,
-            new_rotation,
+            cached_name: match new_selection {
+                Selection::None => "Change Selection: None",
+                Selection::Graph(_) => "Change Selection: Graph",
+                Selection::Navmesh(_) => "Change Selection: Navmesh",
+            }
+            .to_owned(),
+            new_selection,
+            old_selection,
         }
     }
 
-    fn swap(&mut self) -> UnitQuaternion<f32> {
-        let position = self.new_rotation;
-        std::mem::swap(&mut self.new_rotation, &mut self.old_rotation);
-        position
-    }
-
-    fn set_rotation(
-        &self,
-        graph: &mut Graph,
-        physics: &mut Physics,
-        rotation: UnitQuaternion<f32>,
-    ) {
-        graph[self.node]
-            .local_transform_mut()
-            .set_rotation(rotation);
-        if let Some(&body) = physics.binder.value_of(&self.node) {
-            physics.bodies[body].rotation = rotation;
-        }
+    fn swap(&mut self) -> Selection {
+        let selection = self.new_selection.clone();
+        std::mem::swap(&mut self.new_selection, &mut self.old_selection);
+        selection
     }
 }
 
-impl<'a> Command<'a> for RotateNodeCommand {
+impl<'a> Command<'a> for ChangeSelectionCommand {
     type Context = SceneContext<'a>;
 
     fn name(&mut self, _context: &Self::Context) -> String {
-        "Rotate Node".to_owned()
+        self.cached_name.clone()
     }
 
     fn execute(&mut self, context: &mut Self::Context) {
-        let rotation = self.swap();
-        self.set_rotation(
-            &mut context.scene.graph,
-            &mut context.editor_scene.physics,
-            rotation,
-        );
+        let new_selection = self.swap();
+        if new_selection != context.editor_scene.selection {
+            context.editor_scene.selection = new_selection;
+            context
+                .message_sender
+                .send(Message::SelectionChanged)
+                .unwrap();
+        }
     }
 
     fn revert(&mut self, context: &mut Self::Context) {
-        let rotation = self.swap();
-        self.set_rotation(
-            &mut context.scene.graph,
-            &mut context.editor_scene.physics,
-            rotation,
-        );
+        let new_selection = self.swap();
+        if new_selection != context.editor_scene.selection {
+            context.editor_scene.selection = new_selection;
+            context
+                .message_sender
+                .send(Message::SelectionChanged)
+                .unwrap();
+        }
     }
 }
 
 #[derive(Debug)]
-pub struct LinkNodesCommand {
-    child: Handle<Node>,
-    parent: Handle<Node>,
+enum PasteCommandState {
+    Undefined,
+    NonExecuted,
+    Reverted {
+        subgraphs: Vec<SubGraph>,
+        bodies: Vec<(Ticket<RigidBody>, RigidBody)>,
+        colliders: Vec<(Ticket<Collider>, Collider)>,
+        joints: Vec<(Ticket<Joint>, Joint)>,
+        binder: HashMap<Handle<Node>, Handle<RigidBody>>,
+        selection: Selection,
+    },
+    Executed {
+        paste_result: DeepCloneResult,
+        last_selection: Selection,
+    },
 }
 
-impl LinkNodesCommand {
-    pub fn new(child: Handle<Node>, parent: Handle<Node>) -> Self {
-        Self { child, parent }
-    }
+#[derive(Debug)]
+pub struct PasteCommand {
+    state: PasteCommandState,
+    // Nodes are pasted from editor's clipboard if there is no own source.
+    source: Option<Clipboard>,
+    // World position where centroid of pasted root nodes will be placed.
+    target: Option<Vector3<f32>>,
+    // Node to which pasted root nodes are attached instead of scene root.
+    parent: Handle<Node>,
+}
 
-    fn link(&mut self, graph: &mut Graph) {
-        let old_parent = graph[self.child].parent();
-        graph.link_nodes(self.child, self.parent);
-        self.parent = old_parent;
+impl Default for PasteCommand {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
-impl<'a> Command<'a> for LinkNodesCommand {
-    type Context = SceneContext<'a>;
-
-    fn name(&mut self, _context: &Self::Context) -> String {
-        "Link Nodes".to_owned()
+impl PasteCommand {
+    pub fn new() -> Self {
+        Self {
+            state: PasteCommandState::NonExecuted,
+            source: None,
+            target: None,
+            parent: Handle::NONE,
+        }
     }
 
-    fn execute(&mut self, context: &mut Self::Context) {
-        self.link(&mut context.scene.graph);
+    /// Creates command that pastes content of given clipboard instead of editor's one.
+    pub fn from_clipboard(clipboard: Clipboard) -> Self {
+        Self {
+            state: PasteCommandState::NonExecuted,
+            source: Some(clipboard),
+            target: None,
+            parent: Handle::NONE,
+        }
     }
 
-    fn revert(&mut self, context: &mut Self::Context) {
-        self.link(&mut context.scene.graph);
+    /// Places centroid of pasted root nodes at given world position instead of the position
+    /// of copied nodes.
+    pub fn with_target(mut self, target: Vector3<f32>) -> Self {
+        self.target = Some(target);
+        self
     }
-}
-
-#[derive(Debug)]
-pub struct DeleteNodeCommand {
-    handle: Handle<Node>,
-    ticket: Option<Ticket<Node>>,
-    node: Option<Node>,
-    parent: Handle<Node>,
-}
 
-impl DeleteNodeCommand {
-    pub fn new(handle: Handle<Node>) -> Self {
-        Self {
-            handle,
-            ticket: None,
-            node: None,
-            parent: Default::default(),
-        }
+    /// Attaches pasted root nodes to given node instead of scene root. Local transforms of
+    /// pasted nodes are kept, so they become relative to the parent.
+    pub fn with_parent(mut self, parent: Handle<Node>) -> Self {
+        self.parent = parent;
+        self
     }
 }
 
-impl<'a> Command<'a> for DeleteNodeCommand {
+impl<'a> Command<'a> for PasteCommand {
     type Context = SceneContext<'a>;
 
     fn name(&mut self, _context: &Self::Context) -> String {
-        "Delete Node".to_owned()
+        "Paste".to_owned()
     }
 
     fn execute(&mut self, context: &mut Self::Context) {
-        self.parent = context.scene.graph[self.handle].parent();
-        let (ticket, node) = context.scene.graph.take_reserve(self.handle);
-        self.node = Some(node);
-        self.ticket = Some(ticket);
-    }
+        match std::mem::replace(&mut self.state, PasteCommandState::Undefined) {
+            PasteCommandState::NonExecuted => {
+                let clipboard = match self.source.as_mut() {
+                    Some(source) => source,
+                    None => &mut context.editor_scene.clipboard,
+                };
+                let paste_result =
+                    clipboard.paste(&mut context.scene.graph, &mut context.editor_scene.physics);
 
-    fn revert(&mut self, context: &mut Self::Context) {
-        self.handle = context
-            .scene
-            .graph
-            .put_back(self.ticket.take().unwrap(), self.node.take().unwrap());
-        context.scene.graph.link_nodes(self.handle, self.parent);
-    }
+                if self.parent.is_some() {
+                    for &root in paste_result.root_nodes.iter() {
+                        context.scene.graph.link_nodes(root, self.parent);
+                    }
+                }
 
-    fn finalize(&mut self, context: &mut Self::Context) {
-        if let Some(ticket) = self.ticket.take() {
-            context.scene.graph.forget_ticket(ticket)
-        }
-    }
-}
+                if let Some(target) = self.target {
+                    // Pasted roots share the same parent, so their local positions are in
+                    // space of the parent and the target is converted to this space as well.
+                    // Offset is applied only once - reverted sub-graphs are put back as they
+                    // were.
+                    let graph = &mut context.scene.graph;
+                    let physics = &mut context.editor_scene.physics;
+                    let parent = if self.parent.is_some() {
+                        self.parent
+                    } else {
+                        graph.get_root()
+                    };
+                    let local_target = graph[parent]
+                        .global_transform()
+                        .try_inverse()
+                        .unwrap_or_else(Matrix4::identity)
+                        .transform_point(&Point3::from(target))
+                        .coords;
+                    let roots = &paste_result.root_nodes;
+                    if !roots.is_empty() {
+                        let centroid = roots
+                            .iter()
+                            .map(|&root| **graph[root].local_transform().position())
+                            .sum::<Vector3<f32>>()
+                            .scale(1.0 / roots.len() as f32);
+                        let offset = local_target - centroid;
+                        for &root in roots {
+                            graph[root].local_transform_mut().offset(offset);
+                            if let Some(&body) = paste_result.binder.get(&root) {
+                                physics.bodies[body].position += offset;
+                            }
+                        }
+                    }
+                }
 
-#[derive(Debug)]
-pub struct SetBodyCommand {
-    node: Handle<Node>,
-    ticket: Option<Ticket<RigidBody>>,
-    handle: Handle<RigidBody>,
-    body: Option<RigidBody>,
-}
+                let mut selection =
+                    Selection::Graph(GraphSelection::from_list(paste_result.root_nodes.clone()));
+                std::mem::swap(&mut context.editor_scene.selection, &mut selection);
 
-impl SetBodyCommand {
-    pub fn new(node: Handle<Node>, body: RigidBody) -> Self {
-        Self {
-            node,
-            ticket: None,
-            handle: Default::default(),
-            body: Some(body),
-        }
-    }
-}
+                context
+                    .message_sender
+                    .send(Message::NodesPasted {
+                        mapping: paste_result.old_to_new.clone(),
+                    })
+                    .unwrap();
 
-impl<'a> Command<'a> for SetBodyCommand {
-    type Context = SceneContext<'a>;
+                self.state = PasteCommandState::Executed {
+                    paste_result,
+                    last_selection: selection,
+                };
+            }
+            PasteCommandState::Reverted {
+                subgraphs,
+                bodies,
+                colliders,
+                joints,
+                binder,
+                mut selection,
+            } => {
+                let mut paste_result = DeepCloneResult {
+                    binder,
+                    ..Default::default()
+                };
 
-    fn name(&mut self, _context: &Self::Context) -> String {
-        "Set Node Body".to_owned()
-    }
+                for subgraph in subgraphs {
+                    let root = context.scene.graph.put_sub_graph_back(subgraph);
+                    if self.parent.is_some() {
+                        context.scene.graph.link_nodes(root, self.parent);
+                    }
+                    paste_result.root_nodes.push(root);
+                }
 
-    fn execute(&mut self, context: &mut Self::Context) {
-        match self.ticket.take() {
-            None => {
-                self.handle = context
-                    .editor_scene
-                    .physics
-                    .bodies
-                    .spawn(self.body.take().unwrap());
-            }
-            Some(ticket) => {
-                context
-                    .editor_scene
-                    .physics
-                    .bodies
-                    .put_back(ticket, self.body.take().unwrap());
+                for (ticket, body) in bodies {
+                    paste_result
+                        .bodies
+                        .push(context.editor_scene.physics.bodies.put_back(ticket, body));
+                }
+
+                for (ticket, collider) in colliders {
+                    paste_result.colliders.push(
+                        context
+                            .editor_scene
+                            .physics
+                            .colliders
+                            .put_back(ticket, collider),
+                    );
+                }
+
+                for (ticket, joint) in joints {
+                    paste_result
+                        .joints
+                        .push(context.editor_scene.physics.joints.put_back(ticket, joint));
+                }
+
+                for (&node, &body) in paste_result.binder.iter() {
+                    context.editor_scene.physics.binder.insert(node, body);
+                }
+
+                std::mem::swap(&mut context.editor_scene.selection, &mut selection);
+                self.state = PasteCommandState::Executed {
+                    paste_result,
+                    last_selection: selection,
+                };
             }
+            _ => unreachable!(),
         }
-        context
-            .editor_scene
-            .physics
-            .binder
-            .insert(self.node, self.handle);
     }
 
     fn revert(&mut self, context: &mut Self::Context) {
-        let (ticket, node) = context
-            .editor_scene
-            .physics
-            .bodies
-            .take_reserve(self.handle);
-        self.ticket = Some(ticket);
-        self.body = Some(node);
-        context
-            .editor_scene
-            .physics
-            .binder
-            .remove_by_key(&self.node);
+        if let PasteCommandState::Executed {
+            paste_result,
+            mut last_selection,
+        } = std::mem::replace(&mut self.state, PasteCommandState::Undefined)
+        {
+            let mut subgraphs = Vec::new();
+            for root_node in paste_result.root_nodes {
+                subgraphs.push(context.scene.graph.take_reserve_sub_graph(root_node));
+            }
+
+            let mut bodies = Vec::new();
+            for body in paste_result.bodies {
+                bodies.push(context.editor_scene.physics.bodies.take_reserve(body));
+            }
+
+            let mut colliders = Vec::new();
+            for collider in paste_result.colliders {
+                colliders.push(
+                    context
+                        .editor_scene
+                        .physics
+                        .colliders
+                        .take_reserve(collider),
+                );
+            }
+
+            let mut joints = Vec::new();
+            for joint in paste_result.joints {
+                joints.push(context.editor_scene.physics.joints.take_reserve(joint));
+            }
+
+            for (node, _) in paste_result.binder.iter() {
+                context.editor_scene.physics.binder.remove_by_key(node);
+            }
+
+            std::mem::swap(&mut context.editor_scene.selection, &mut last_selection);
+
+            self.state = PasteCommandState::Reverted {
+                subgraphs,
+                bodies,
+                colliders,
+                joints,
+                binder: paste_result.binder,
+                selection: last_selection,
+            };
+        }
     }
 
     fn finalize(&mut self, context: &mut Self::Context) {
-        if let Some(ticket) = self.ticket.take() {
-            context.editor_scene.physics.bodies.forget_ticket(ticket);
-            context
-                .editor_scene
-                .physics
-                .binder
-                .remove_by_key(&self.node);
+        if let PasteCommandState::Reverted {
+            subgraphs,
+            bodies,
+            colliders,
+            joints,
+            ..
+        } = std::mem::replace(&mut self.state, PasteCommandState::Undefined)
+        {
+            for subgraph in subgraphs {
+                context.scene.graph.forget_sub_graph(subgraph);
+            }
+
+            for (ticket, _) in bodies {
+                context.editor_scene.physics.bodies.forget_ticket(ticket);
+            }
+
+            for (ticket, _) in colliders {
+                context.editor_scene.physics.colliders.forget_ticket(ticket)
+            }
+
+            for (ticket, _) in joints {
+                context.editor_scene.physics.joints.forget_ticket(ticket);
+            }
         }
     }
 }
 
 #[derive(Debug)]
-pub struct SetColliderCommand {
-    body: Handle<RigidBody>,
-    ticket: Option<Ticket<Collider>>,
-    handle: Handle<Collider>,
-    collider: Option<Collider>,
+pub struct MoveNavmeshVertexCommand {
+    navmesh: Handle<Navmesh>,
+    vertex: Handle<NavmeshVertex>,
+    old_position: Vector3<f32>,
+    new_position: Vector3<f32>,
 }
 
-impl SetColliderCommand {
-    pub fn new(body: Handle<RigidBody>, collider: Collider) -> Self {
+impl MoveNavmeshVertexCommand {
+    pub fn new(
+        navmesh: Handle<Navmesh>,
+        vertex: Handle<NavmeshVertex>,
+        old_position: Vector3<f32>,
+        new_position: Vector3<f32>,
+    ) -> Self {
         Self {
-            body,
-            ticket: None,
-            handle: Default::default(),
-            collider: Some(collider),
+            navmesh,
+            vertex,
+            old_position,
+            new_position,
         }
     }
+
+    fn swap(&mut self) -> Vector3<f32> {
+        let position = self.new_position;
+        std::mem::swap(&mut self.new_position, &mut self.old_position);
+        position
+    }
+
+    fn set_position(&self, navmesh: &mut Navmesh, position: Vector3<f32>) {
+        navmesh.vertices[self.vertex].position = position;
+    }
 }
 
-impl<'a> Command<'a> for SetColliderCommand {
+impl<'a> Command<'a> for MoveNavmeshVertexCommand {
     type Context = SceneContext<'a>;
 
     fn name(&mut self, _context: &Self::Context) -> String {
-        "Set Collider".to_owned()
+        "Move Navmesh Vertex".to_owned()
     }
 
     fn execute(&mut self, context: &mut Self::Context) {
-        match self.ticket.take() {
-            None => {
-                self.handle = context
-                    .editor_scene
-                    .physics
-                    .colliders
-                    .spawn(self.collider.take().unwrap());
-            }
-            Some(ticket) => {
-                context
-                    .editor_scene
-                    .physics
-                    .colliders
-                    .put_back(ticket, self.collider.take().unwrap());
-            }
-        }
-        context.editor_scene.physics.colliders[self.handle].parent = self.body.into();
-        context.editor_scene.physics.bodies[self.body]
-            .colliders
-            .push(self.handle.into());
+        let position = self.swap();
+        self.set_position(&mut context.editor_scene.navmeshes[self.navmesh], position);
     }
 
     fn revert(&mut self, context: &mut Self::Context) {
-        let (ticket, mut collider) = context
-            .editor_scene
-            .physics
-            .colliders
-            .take_reserve(self.handle);
-        collider.parent = Default::default();
-        self.ticket = Some(ticket);
-        self.collider = Some(collider);
-
-        let body = &mut context.editor_scene.physics.bodies[self.body];
-        body.colliders.remove(
-            body.colliders
-                .iter()
-                .position(|&c| c == ErasedHandle::from(self.handle))
-                .unwrap(),
-        );
+        let position = self.swap();
+        self.set_position(&mut context.editor_scene.navmeshes[self.navmesh], position);
     }
+}
 
-    fn finalize(&mut self, context: &mut Self::Context) {
-        if let Some(ticket) = self.ticket.take() {
-            context.editor_scene.physics.colliders.forget_ticket(ticket);
+/// Sets absolute position of a navmesh vertex, unlike [`MoveNavmeshVertexCommand`] which
+/// is driven by the gizmo, this one is meant to be used by numeric fields. Position could be
+/// optionally snapped to a grid with given step. Non-finite positions are rejected.
+#[derive(Debug)]
+pub struct SetNavmeshVertexPositionCommand {
+    navmesh: Handle<Navmesh>,
+    vertex: Handle<NavmeshVertex>,
+    position: Vector3<f32>,
+    valid: bool,
+}
+
+impl SetNavmeshVertexPositionCommand {
+    pub fn new(
+        navmesh: Handle<Navmesh>,
+        vertex: Handle<NavmeshVertex>,
+        position: Vector3<f32>,
+        grid_step: Option<f32>,
+    ) -> Self {
+        let position = match grid_step {
+            Some(step) if step > 0.0 => position.map(|c| (c / step).round() * step),
+            _ => position,
+        };
+
+        Self {
+            navmesh,
+            vertex,
+            valid: position.iter().all(|c| c.is_finite()),
+            position,
+        }
+    }
+
+    fn swap(&mut self, navmesh: &mut Navmesh) {
+        std::mem::swap(
+            &mut navmesh.vertices[self.vertex].position,
+            &mut self.position,
+        );
+    }
+}
+
+impl<'a> Command<'a> for SetNavmeshVertexPositionCommand {
+    type Context = SceneContext<'a>;
+
+    fn name(&mut self, _context: &Self::Context) -> String {
+        "Set Navmesh Vertex Position".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut Self::Context) {
+        if self.valid {
+            self.swap(&mut context.editor_scene.navmeshes[self.navmesh]);
+        } else {
+            context
+                .message_sender
+                .send(Message::Log(format!(
+                    "Navmesh vertex position {:?} is invalid, it was not changed.",
+                    self.position
+                )))
+                .unwrap();
+        }
+    }
+
+    fn revert(&mut self, context: &mut Self::Context) {
+        if self.valid {
+            self.swap(&mut context.editor_scene.navmeshes[self.navmesh]);
         }
     }
 }
 
 #[derive(Debug)]
-pub struct LoadModelCommand {
-    path: PathBuf,
-    model: Handle<Node>,
-    animations: Vec<Handle<Animation>>,
-    sub_graph: Option<SubGraph>,
-    animations_container: Vec<(Ticket<Animation>, Animation)>,
+pub struct SetNavmeshTriangleRegionCommand {
+    navmesh: Handle<Navmesh>,
+    triangle: Handle<NavmeshTriangle>,
+    value: u8,
 }
 
-impl LoadModelCommand {
-    pub fn new(path: PathBuf) -> Self {
+impl SetNavmeshTriangleRegionCommand {
+    pub fn new(navmesh: Handle<Navmesh>, triangle: Handle<NavmeshTriangle>, value: u8) -> Self {
         Self {
-            path,
-            model: Default::default(),
-            animations: Default::default(),
-            sub_graph: None,
-            animations_container: Default::default(),
+            navmesh,
+            triangle,
+            value,
         }
     }
+
+    fn swap(&mut self, navmesh: &mut Navmesh) {
+        std::mem::swap(
+            &mut navmesh.triangles[self.triangle].region,
+            &mut self.value,
+        );
+    }
 }
 
-impl<'a> Command<'a> for LoadModelCommand {
+impl<'a> Command<'a> for SetNavmeshTriangleRegionCommand {
     type Context = SceneContext<'a>;
 
     fn name(&mut self, _context: &Self::Context) -> String {
-        "Load Model".to_owned()
+        "Set Navmesh Triangle Region".to_owned()
     }
 
     fn execute(&mut self, context: &mut Self::Context) {
-        if self.model.is_none() {
-            // No model was loaded yet, do it.
-            if let Ok(model) = rg3d::core::futures::executor::block_on(
-                context.resource_manager.request_model(&self.path),
-            ) {
-                let instance = model.instantiate(context.scene);
-                self.model = instance.root;
-                self.animations = instance.animations;
+        self.swap(&mut context.editor_scene.navmeshes[self.navmesh]);
+    }
 
-                // Enable instantiated animations.
-                for &animation in self.animations.iter() {
-                    context.scene.animations[animation].set_enabled(true);
-                }
-            }
+    fn revert(&mut self, context: &mut Self::Context) {
+        self.swap(&mut context.editor_scene.navmeshes[self.navmesh]);
+    }
+}
+
+/// Reverses winding (and so normal) of a single navmesh triangle by swapping two of its
+/// vertices. Invalid triangle handles are reported in log and left untouched.
+#[derive(Debug)]
+pub struct FlipNavmeshTriangleCommand {
+    navmesh: Handle<Navmesh>,
+    triangle: Handle<NavmeshTriangle>,
+    applied: bool,
+}
+
+impl FlipNavmeshTriangleCommand {
+    pub fn new(navmesh: Handle<Navmesh>, triangle: Handle<NavmeshTriangle>) -> Self {
+        Self {
+            navmesh,
+            triangle,
+            applied: false,
+        }
+    }
+
+    fn flip(&mut self, navmesh: &mut Navmesh) {
+        let triangle = &mut navmesh.triangles[self.triangle];
+        std::mem::swap(&mut triangle.b, &mut triangle.c);
+    }
+}
+
+impl<'a> Command<'a> for FlipNavmeshTriangleCommand {
+    type Context = SceneContext<'a>;
+
+    fn name(&mut self, _context: &Self::Context) -> String {
+        "Flip Navmesh Triangle".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut Self::Context) {
+        let navmesh = &mut context.editor_scene.navmeshes[self.navmesh];
+        self.applied = navmesh.triangles.is_valid_handle(self.triangle);
+        if self.applied {
+            self.flip(navmesh);
         } else {
-            // A model was loaded, but change was reverted and here we must put all nodes
-            // back to graph.
-            self.model = context
-                .scene
-                .graph
-                .put_sub_graph_back(self.sub_graph.take().unwrap());
-            for (ticket, animation) in self.animations_container.drain(..) {
-                context.scene.animations.put_back(ticket, animation);
-            }
+            context
+                .message_sender
+                .send(Message::Log(format!(
+                    "Navmesh triangle {:?} does not exist, it was not flipped.",
+                    self.triangle
+                )))
+                .unwrap();
         }
     }
 
     fn revert(&mut self, context: &mut Self::Context) {
-        self.sub_graph = Some(context.scene.graph.take_reserve_sub_graph(self.model));
-        self.animations_container = self
-            .animations
-            .iter()
-            .map(|&anim| context.scene.animations.take_reserve(anim))
-            .collect();
+        if self.applied {
+            self.flip(&mut context.editor_scene.navmeshes[self.navmesh]);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct MoveNodeCommand {
+    node: Handle<Node>,
+    old_position: Vector3<f32>,
+    new_position: Vector3<f32>,
+}
+
+impl MoveNodeCommand {
+    pub fn new(node: Handle<Node>, old_position: Vector3<f32>, new_position: Vector3<f32>) -> Self {
+        Self {
+            node,
+            old_position,
+            new_position,
+        }
+    }
+
+    fn swap(&mut self) -> Vector3<f32> {
+        let position = self.new_position;
+        std::mem::swap(&mut self.new_position, &mut self.old_position);
+        position
+    }
+
+    fn set_position(&self, graph: &mut Graph, physics: &mut Physics, position: Vector3<f32>) {
+        graph[self.node]
+            .local_transform_mut()
+            .set_position(position);
+        if let Some(&body) = physics.binder.value_of(&self.node) {
+            physics.bodies[body].position = position;
+        }
+    }
+}
+
+impl<'a> Command<'a> for MoveNodeCommand {
+    type Context = SceneContext<'a>;
+
+    fn name(&mut self, _context: &Self::Context) -> String {
+        "Move Node".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut Self::Context) {
+        let position = self.swap();
+        self.set_position(
+            &mut context.scene.graph,
+            &mut context.editor_scene.physics,
+            position,
+        );
+    }
+
+    fn revert(&mut self, context: &mut Self::Context) {
+        let position = self.swap();
+        self.set_position(
+            &mut context.scene.graph,
+            &mut context.editor_scene.physics,
+            position,
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct ScaleNodeCommand {
+    node: Handle<Node>,
+    old_scale: Vector3<f32>,
+    new_scale: Vector3<f32>,
+}
+
+impl ScaleNodeCommand {
+    pub fn new(node: Handle<Node>, old_scale: Vector3<f32>, new_scale: Vector3<f32>) -> Self {
+        Self {
+            node,
+            old_scale,
+            new_scale,
+        }
+    }
+
+    fn swap(&mut self) -> Vector3<f32> {
+        let position = self.new_scale;
+        std::mem::swap(&mut self.new_scale, &mut self.old_scale);
+        position
+    }
+
+    fn set_scale(&self, graph: &mut Graph, scale: Vector3<f32>) {
+        graph[self.node].local_transform_mut().set_scale(scale);
+    }
+}
+
+impl<'a> Command<'a> for ScaleNodeCommand {
+    type Context = SceneContext<'a>;
+
+    fn name(&mut self, _context: &Self::Context) -> String {
+        "Scale Node".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut Self::Context) {
+        let scale = self.swap();
+        self.set_scale(&mut context.scene.graph, scale);
+    }
+
+    fn revert(&mut self, context: &mut Self::Context) {
+        let scale = self.swap();
+        self.set_scale(&mut context.scene.graph, scale);
+    }
+}
+
+#[derive(Debug)]
+pub struct RotateNodeCommand {
+    node: Handle<Node>,
+    old_rotation: UnitQuaternion<f32>,
+    new_rotation: UnitQuaternion<f32>,
+}
+
+impl RotateNodeCommand {
+    pub fn new(
+        node: Handle<Node>,
+        old_rotation: UnitQuaternion<f32>,
+        new_rotation: UnitQuaternion<f32>,
+    ) -> Self {
+        Self {
+            node,
+            old_rotation,
+            new_rotation,
+        }
+    }
+
+    fn swap(&mut self) -> UnitQuaternion<f32> {
+        let position = self.new_rotation;
+        std::mem::swap(&mut self.new_rotation, &mut self.old_rotation);
+        position
+    }
+
+    fn set_rotation(
+        &self,
+        graph: &mut Graph,
+        physics: &mut Physics,
+        rotation: UnitQuaternion<f32>,
+    ) {
+        graph[self.node]
+            .local_transform_mut()
+            .set_rotation(rotation);
+        if let Some(&body) = physics.binder.value_of(&self.node) {
+            physics.bodies[body].rotation = rotation;
+        }
     }
+}
+
+impl<'a> Command<'a> for RotateNodeCommand {
+    type Context = SceneContext<'a>;
+
+    fn name(&mut self, _context: &Self::Context) -> String {
+        "Rotate Node".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut Self::Context) {
+        let rotation = self.swap();
+        self.set_rotation(
+            &mut context.scene.graph,
+            &mut context.editor_scene.physics,
+            rotation,
+        );
+    }
+
+    fn revert(&mut self, context: &mut Self::Context) {
+        let rotation = self.swap();
+        self.set_rotation(
+            &mut context.scene.graph,
+            &mut context.editor_scene.physics,
+            rotation,
+        );
+    }
+}
+
+/// Defines which parts of local transform are reset by [`ResetTransformCommand`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ResetTransformMode {
+    All,
+    Position,
+    Rotation,
+    Scale,
+}
+
+/// Resets local position of a node to zero, rotation to identity and scale to one (or only
+/// one of them, depending on mode).
+#[derive(Debug)]
+pub struct ResetTransformCommand {
+    node: Handle<Node>,
+    mode: ResetTransformMode,
+    old_transform: Option<Transform>,
+}
+
+impl ResetTransformCommand {
+    pub fn new(node: Handle<Node>, mode: ResetTransformMode) -> Self {
+        Self {
+            node,
+            mode,
+            old_transform: None,
+        }
+    }
+
+    fn sync_body(&self, graph: &Graph, physics: &mut Physics) {
+        if let Some(&body) = physics.binder.value_of(&self.node) {
+            let transform = graph[self.node].local_transform();
+            let body = &mut physics.bodies[body];
+            body.position = **transform.position();
+            body.rotation = **transform.rotation();
+        }
+    }
+}
+
+impl<'a> Command<'a> for ResetTransformCommand {
+    type Context = SceneContext<'a>;
+
+    fn name(&mut self, _context: &Self::Context) -> String {
+        match self.mode {
+            ResetTransformMode::All => "Reset Transform",
+            ResetTransformMode::Position => "Reset Position",
+            ResetTransformMode::Rotation => "Reset Rotation",
+            ResetTransformMode::Scale => "Reset Scale",
+        }
+        .to_owned()
+    }
+
+    fn execute(&mut self, context: &mut Self::Context) {
+        let graph = &mut context.scene.graph;
+        let transform = graph[self.node].local_transform_mut();
+        self.old_transform = Some(transform.clone());
+
+        if let ResetTransformMode::All | ResetTransformMode::Position = self.mode {
+            transform.set_position(Vector3::default());
+        }
+        if let ResetTransformMode::All | ResetTransformMode::Rotation = self.mode {
+            transform.set_rotation(UnitQuaternion::identity());
+        }
+        if let ResetTransformMode::All | ResetTransformMode::Scale = self.mode {
+            transform.set_scale(Vector3::new(1.0, 1.0, 1.0));
+        }
+
+        self.sync_body(graph, &mut context.editor_scene.physics);
+    }
+
+    fn revert(&mut self, context: &mut Self::Context) {
+        if let Some(old_transform) = self.old_transform.take() {
+            let graph = &mut context.scene.graph;
+            *graph[self.node].local_transform_mut() = old_transform;
+            self.sync_body(graph, &mut context.editor_scene.physics);
+        }
+    }
+}
+
+/// Sets ambient lighting color of the scene, it is a part of the scene itself, so it is
+/// saved with the scene. Color field sends new color on every change while dragging, so
+/// consecutive changes are merged into one command.
+#[derive(Debug)]
+pub struct SetSceneAmbientColorCommand {
+    value: Color,
+}
+
+impl SetSceneAmbientColorCommand {
+    pub fn new(value: Color) -> Self {
+        Self { value }
+    }
+
+    fn swap(&mut self, scene: &mut Scene) {
+        std::mem::swap(&mut scene.ambient_lighting_color, &mut self.value);
+    }
+}
+
+impl<'a> Command<'a> for SetSceneAmbientColorCommand {
+    type Context = SceneContext<'a>;
+
+    fn name(&mut self, _context: &Self::Context) -> String {
+        "Set Scene Ambient Color".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut Self::Context) {
+        self.swap(context.scene);
+    }
+
+    fn revert(&mut self, context: &mut Self::Context) {
+        self.swap(context.scene);
+    }
+
+    fn merge(&mut self, _next: &mut Self, _context: &mut Self::Context) -> bool {
+        // Color before the first change is kept, so revert restores it, while the scene
+        // already has the color of the last change.
+        true
+    }
+}
+
+/// Replaces whole local transform of a node (position, rotation, scale and pivots) in
+/// a single undoable step.
+#[derive(Debug)]
+pub struct SetLocalTransformCommand {
+    node: Handle<Node>,
+    transform: Transform,
+}
+
+impl SetLocalTransformCommand {
+    pub fn new(node: Handle<Node>, transform: Transform) -> Self {
+        Self { node, transform }
+    }
+
+    fn swap(&mut self, graph: &mut Graph, physics: &mut Physics) {
+        std::mem::swap(graph[self.node].local_transform_mut(), &mut self.transform);
+
+        if let Some(&body) = physics.binder.value_of(&self.node) {
+            let transform = graph[self.node].local_transform();
+            let body = &mut physics.bodies[body];
+            body.position = **transform.position();
+            body.rotation = **transform.rotation();
+        }
+    }
+}
+
+impl<'a> Command<'a> for SetLocalTransformCommand {
+    type Context = SceneContext<'a>;
+
+    fn name(&mut self, _context: &Self::Context) -> String {
+        "Set Local Transform".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut Self::Context) {
+        self.swap(&mut context.scene.graph, &mut context.editor_scene.physics);
+    }
+
+    fn revert(&mut self, context: &mut Self::Context) {
+        self.swap(&mut context.scene.graph, &mut context.editor_scene.physics);
+    }
+}
+
+/// Sets visibility of many nodes at once, it is much cheaper than a group of
+/// `SetVisibleCommand`s for large selections.
+#[derive(Debug)]
+pub struct SetNodesVisibilityCommand {
+    // Nodes with their visibility before execution.
+    nodes: Vec<(Handle<Node>, bool)>,
+    visibility: bool,
+}
+
+impl SetNodesVisibilityCommand {
+    pub fn new(graph: &Graph, nodes: &[Handle<Node>], visibility: bool) -> Self {
+        Self {
+            nodes: nodes
+                .iter()
+                .map(|&node| (node, graph[node].visibility()))
+                .collect(),
+            visibility,
+        }
+    }
+}
+
+impl<'a> Command<'a> for SetNodesVisibilityCommand {
+    type Context = SceneContext<'a>;
+
+    fn name(&mut self, _context: &Self::Context) -> String {
+        "Set Nodes Visibility".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut Self::Context) {
+        for &(node, _) in self.nodes.iter() {
+            context.scene.graph[node].set_visibility(self.visibility);
+        }
+    }
+
+    fn revert(&mut self, context: &mut Self::Context) {
+        for &(node, visibility) in self.nodes.iter() {
+            context.scene.graph[node].set_visibility(visibility);
+        }
+    }
+}
+
+/// Rotates a group of nodes rigidly around common pivot, this is what group rotation
+/// with a gizmo does - positions of nodes are rotated around the pivot as well as their
+/// orientations.
+#[derive(Debug)]
+pub struct RotateNodesAroundPivotCommand {
+    pivot: Vector3<f32>,
+    rotation: UnitQuaternion<f32>,
+    nodes: Vec<Handle<Node>>,
+    // Transforms to be applied on next execute/revert, calculated on first execution.
+    transforms: Option<Vec<Transform>>,
+}
+
+impl RotateNodesAroundPivotCommand {
+    /// Creates command that rotates root nodes of the selection by `rotation` (in world
+    /// space) around the center of the selection.
+    pub fn new(graph: &Graph, selection: &GraphSelection, rotation: UnitQuaternion<f32>) -> Self {
+        let pivot = selection
+            .global_rotation_position(graph)
+            .map(|(_, position)| position)
+            .unwrap_or_default();
+
+        Self {
+            pivot,
+            rotation,
+            nodes: selection.root_nodes(graph),
+            transforms: None,
+        }
+    }
+
+    fn calculate_transforms(&self, graph: &Graph) -> Vec<Transform> {
+        let pivot_rotation = Matrix4::new_translation(&self.pivot)
+            * self.rotation.to_homogeneous()
+            * Matrix4::new_translation(&-self.pivot);
+
+        self.nodes
+            .iter()
+            .map(|&node| {
+                let parent_inv_transform = graph[graph[node].parent()]
+                    .global_transform()
+                    .try_inverse()
+                    .unwrap_or_else(Matrix4::identity);
+
+                let (position, rotation, scale) = decompose_transform_matrix(
+                    &(parent_inv_transform * pivot_rotation * graph[node].global_transform()),
+                );
+
+                TransformBuilder::new()
+                    .with_local_position(position)
+                    .with_local_rotation(rotation)
+                    .with_local_scale(scale)
+                    .build()
+            })
+            .collect()
+    }
+
+    fn swap(&mut self, graph: &mut Graph, physics: &mut Physics) {
+        let new_transforms = match self.transforms.take() {
+            Some(transforms) => transforms,
+            None => self.calculate_transforms(graph),
+        };
+
+        let mut old_transforms = Vec::with_capacity(self.nodes.len());
+        for (&node, transform) in self.nodes.iter().zip(new_transforms) {
+            old_transforms.push(graph[node].local_transform().clone());
+
+            if let Some(&body) = physics.binder.value_of(&node) {
+                let body = &mut physics.bodies[body];
+                body.position = **transform.position();
+                body.rotation = **transform.rotation();
+            }
+
+            *graph[node].local_transform_mut() = transform;
+        }
+
+        self.transforms = Some(old_transforms);
+    }
+}
+
+impl<'a> Command<'a> for RotateNodesAroundPivotCommand {
+    type Context = SceneContext<'a>;
+
+    fn name(&mut self, _context: &Self::Context) -> String {
+        "Rotate Nodes Around Pivot".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut Self::Context) {
+        self.swap(&mut context.scene.graph, &mut context.editor_scene.physics);
+    }
+
+    fn revert(&mut self, context: &mut Self::Context) {
+        self.swap(&mut context.scene.graph, &mut context.editor_scene.physics);
+    }
+}
+
+#[derive(Debug)]
+pub struct LinkNodesCommand {
+    child: Handle<Node>,
+    parent: Handle<Node>,
+}
+
+impl LinkNodesCommand {
+    pub fn new(child: Handle<Node>, parent: Handle<Node>) -> Self {
+        Self { child, parent }
+    }
+
+    fn link(&mut self, graph: &mut Graph) {
+        let old_parent = graph[self.child].parent();
+        graph.link_nodes(self.child, self.parent);
+        self.parent = old_parent;
+    }
+}
+
+impl<'a> Command<'a> for LinkNodesCommand {
+    type Context = SceneContext<'a>;
+
+    fn name(&mut self, _context: &Self::Context) -> String {
+        "Link Nodes".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut Self::Context) {
+        self.link(&mut context.scene.graph);
+    }
+
+    fn revert(&mut self, context: &mut Self::Context) {
+        self.link(&mut context.scene.graph);
+    }
+}
+
+/// Splits global transform matrix into position, rotation and scale. Shear cannot
+/// be represented by node's transform so it is discarded.
+fn decompose_transform_matrix(
+    matrix: &Matrix4<f32>,
+) -> (Vector3<f32>, UnitQuaternion<f32>, Vector3<f32>) {
+    let side = matrix.side();
+    let up = matrix.up();
+    let look = matrix.look();
+
+    let scale = Vector3::new(side.norm(), up.norm(), look.norm());
+
+    let rotation = UnitQuaternion::from_rotation_matrix(&Rotation3::from_matrix_unchecked(
+        Matrix3::from_columns(&[
+            side.try_normalize(f32::EPSILON).unwrap_or_else(Vector3::x),
+            up.try_normalize(f32::EPSILON).unwrap_or_else(Vector3::y),
+            look.try_normalize(f32::EPSILON).unwrap_or_else(Vector3::z),
+        ]),
+    ));
+
+    (matrix.position(), rotation, scale)
+}
+
+/// Calculates local transform for `node` which will keep its global transform
+/// unchanged when the node is attached to `new_parent`.
+fn calculate_keep_global_transform(
+    graph: &Graph,
+    node: Handle<Node>,
+    new_parent: Handle<Node>,
+) -> Transform {
+    let parent_inv_transform = graph[new_parent]
+        .global_transform()
+        .try_inverse()
+        .unwrap_or_else(Matrix4::identity);
+
+    let (position, rotation, scale) =
+        decompose_transform_matrix(&(parent_inv_transform * graph[node].global_transform()));
+
+    TransformBuilder::new()
+        .with_local_position(position)
+        .with_local_rotation(rotation)
+        .with_local_scale(scale)
+        .build()
+}
+
+/// Same as [`LinkNodesCommand`], but recalculates local transform of the child so it
+/// stays at the same place in the world after it is attached to a new parent.
+#[derive(Debug)]
+pub struct LinkNodesKeepTransformCommand {
+    child: Handle<Node>,
+    parent: Handle<Node>,
+    transform: Option<Transform>,
+}
+
+impl LinkNodesKeepTransformCommand {
+    pub fn new(child: Handle<Node>, parent: Handle<Node>) -> Self {
+        Self {
+            child,
+            parent,
+            transform: None,
+        }
+    }
+
+    fn link(&mut self, graph: &mut Graph, physics: &mut Physics) {
+        let old_parent = graph[self.child].parent();
+        let old_transform = graph[self.child].local_transform().clone();
+
+        // New transform is calculated only once, on first execution. After that we just
+        // swap old and new transforms back and forth.
+        let new_transform = match self.transform.take() {
+            Some(transform) => transform,
+            None => calculate_keep_global_transform(graph, self.child, self.parent),
+        };
+
+        graph.link_nodes(self.child, self.parent);
+
+        if let Some(&body) = physics.binder.value_of(&self.child) {
+            let body = &mut physics.bodies[body];
+            body.position = **new_transform.position();
+            body.rotation = **new_transform.rotation();
+        }
+
+        *graph[self.child].local_transform_mut() = new_transform;
+
+        self.parent = old_parent;
+        self.transform = Some(old_transform);
+    }
+}
+
+impl<'a> Command<'a> for LinkNodesKeepTransformCommand {
+    type Context = SceneContext<'a>;
+
+    fn name(&mut self, _context: &Self::Context) -> String {
+        "Link Nodes".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut Self::Context) {
+        self.link(&mut context.scene.graph, &mut context.editor_scene.physics);
+    }
+
+    fn revert(&mut self, context: &mut Self::Context) {
+        self.link(&mut context.scene.graph, &mut context.editor_scene.physics);
+    }
+}
+
+/// Adds a pivot and attaches given nodes to it keeping their world transforms, the pivot
+/// becomes selected. Handle of the pivot is unknown until it is added, so this can't be a
+/// plain group of commands. Use [`make_group_command`] to create the command.
+#[derive(Debug)]
+pub struct GroupNodesCommand {
+    add_pivot: AddNodeCommand,
+    // Nodes with their local transforms relative to the pivot.
+    children: Vec<(Handle<Node>, Transform)>,
+    links: Vec<LinkNodesKeepTransformCommand>,
+    selection: Selection,
+}
+
+impl GroupNodesCommand {
+    fn swap_selection(&mut self, context: &mut SceneContext) {
+        std::mem::swap(&mut context.editor_scene.selection, &mut self.selection);
+        context
+            .message_sender
+            .send(Message::SelectionChanged)
+            .unwrap();
+    }
+}
+
+impl<'a> Command<'a> for GroupNodesCommand {
+    type Context = SceneContext<'a>;
+
+    fn name(&mut self, _context: &Self::Context) -> String {
+        "Group Nodes".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut Self::Context) {
+        self.add_pivot.execute(context);
+
+        let pivot = self.add_pivot.handle;
+        if self.links.is_empty() {
+            self.links = self
+                .children
+                .iter()
+                .map(|(child, transform)| LinkNodesKeepTransformCommand {
+                    child: *child,
+                    parent: pivot,
+                    transform: Some(transform.clone()),
+                })
+                .collect();
+            self.selection = Selection::Graph(GraphSelection::single_or_empty(pivot));
+        }
+
+        for link in self.links.iter_mut() {
+            link.execute(context);
+        }
+
+        self.swap_selection(context);
+    }
+
+    fn revert(&mut self, context: &mut Self::Context) {
+        self.swap_selection(context);
+
+        for link in self.links.iter_mut().rev() {
+            link.revert(context);
+        }
+
+        self.add_pivot.revert(context);
+    }
+
+    fn finalize(&mut self, context: &mut Self::Context) {
+        self.add_pivot.finalize(context);
+    }
+}
+
+/// Creates command that groups selected root nodes under a new pivot placed at the center
+/// of the selection. World transforms of the nodes are preserved.
+pub fn make_group_command(selection: &GraphSelection, graph: &Graph) -> Option<SceneCommand> {
+    make_group_nodes_command(selection, graph).map(SceneCommand::GroupNodes)
+}
+
+fn make_group_nodes_command(
+    selection: &GraphSelection,
+    graph: &Graph,
+) -> Option<GroupNodesCommand> {
+    let (_, center) = selection.global_rotation_position(graph)?;
+
+    let pivot_inv_transform = Matrix4::new_translation(&-center);
+
+    let children = selection
+        .root_nodes(graph)
+        .into_iter()
+        .filter(|&node| node != graph.get_root())
+        .map(|node| {
+            let (position, rotation, scale) =
+                decompose_transform_matrix(&(pivot_inv_transform * graph[node].global_transform()));
+            let transform = TransformBuilder::new()
+                .with_local_position(position)
+                .with_local_rotation(rotation)
+                .with_local_scale(scale)
+                .build();
+            (node, transform)
+        })
+        .collect::<Vec<_>>();
+
+    if children.is_empty() {
+        return None;
+    }
+
+    let pivot = BaseBuilder::new()
+        .with_name("Group")
+        .with_local_transform(TransformBuilder::new().with_local_position(center).build())
+        .build_node();
+
+    Some(GroupNodesCommand {
+        add_pivot: AddNodeCommand::new(pivot),
+        children,
+        links: Default::default(),
+        selection: Default::default(),
+    })
+}
+
+/// Groups nodes under a new pivot (see [`GroupNodesCommand`]) and binds a single rigid body
+/// to the pivot, the body has one collider per mesh of the group. Use
+/// [`make_compound_body_command`] to create the command.
+#[derive(Debug)]
+pub struct MakeCompoundBodyCommand {
+    group: GroupNodesCommand,
+    body: Option<RigidBody>,
+    colliders: Vec<Collider>,
+    // Commands below are created on first execution, when handles of pivot and body are known.
+    set_body: Option<SetBodyCommand>,
+    set_colliders: Vec<SetColliderCommand>,
+}
+
+impl<'a> Command<'a> for MakeCompoundBodyCommand {
+    type Context = SceneContext<'a>;
+
+    fn name(&mut self, _context: &Self::Context) -> String {
+        "Make Compound Body".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut Self::Context) {
+        self.group.execute(context);
+
+        match self.set_body.as_mut() {
+            None => {
+                let mut set_body =
+                    SetBodyCommand::new(self.group.add_pivot.handle, self.body.take().unwrap());
+                set_body.execute(context);
+
+                for collider in self.colliders.drain(..) {
+                    let mut set_collider = SetColliderCommand::new(set_body.handle, collider);
+                    set_collider.execute(context);
+                    self.set_colliders.push(set_collider);
+                }
+
+                self.set_body = Some(set_body);
+            }
+            Some(set_body) => {
+                set_body.execute(context);
+                for set_collider in self.set_colliders.iter_mut() {
+                    set_collider.execute(context);
+                }
+            }
+        }
+    }
+
+    fn revert(&mut self, context: &mut Self::Context) {
+        for set_collider in self.set_colliders.iter_mut().rev() {
+            set_collider.revert(context);
+        }
+        if let Some(set_body) = self.set_body.as_mut() {
+            set_body.revert(context);
+        }
+        self.group.revert(context);
+    }
+
+    fn finalize(&mut self, context: &mut Self::Context) {
+        for set_collider in self.set_colliders.iter_mut() {
+            set_collider.finalize(context);
+        }
+        if let Some(set_body) = self.set_body.as_mut() {
+            set_body.finalize(context);
+        }
+        self.group.finalize(context);
+    }
+}
+
+/// Creates command that turns selected nodes into a single rigid body: nodes are grouped
+/// under a new pivot placed at the center of the selection, the pivot gets a body with a
+/// cuboid collider per mesh of selected sub-graphs, fitted to bounding box of the mesh.
+/// Convex hulls are not supported by the editor's physics data model, so bounding boxes are
+/// used even for complex meshes - use [`SetColliderShapeCommand`] to refine colliders
+/// afterwards.
+///
+/// Nodes that already have bodies keep them, their meshes (and meshes of their descendants,
+/// which are moved by these bodies) get no colliders. Returns `None` if there is no mesh to
+/// make a collider for.
+pub fn make_compound_body_command(
+    selection: &GraphSelection,
+    graph: &Graph,
+    physics: &Physics,
+) -> Option<SceneCommand> {
+    let (_, center) = selection.global_rotation_position(graph)?;
+
+    let mut colliders = Vec::new();
+    let mut stack = selection.root_nodes(graph);
+    stack.retain(|&node| node != graph.get_root());
+    while let Some(handle) = stack.pop() {
+        if physics.binder.value_of(&handle).is_some() {
+            continue;
+        }
+        let node = &graph[handle];
+        stack.extend_from_slice(node.children());
+        if let Node::Mesh(mesh) = node {
+            let transform = mesh.global_transform();
+            let scale = Vector3::new(
+                transform.side().norm(),
+                transform.up().norm(),
+                transform.look().norm(),
+            );
+            let (_, rotation, _) = decompose_transform_matrix(&transform);
+            let aabb = mesh.bounding_box();
+            let local_center = (aabb.min + aabb.max).scale(0.5);
+            let world_center = transform
+                .transform_point(&Point3::from(local_center))
+                .coords;
+
+            colliders.push(Collider {
+                shape: ColliderShapeDesc::Cuboid(CuboidDesc {
+                    half_extents: (aabb.max - aabb.min).scale(0.5).component_mul(&scale),
+                }),
+                // Pivot is not rotated, so offset in world space is offset in pivot space.
+                translation: world_center - center,
+                rotation,
+                ..Default::default()
+            });
+        }
+    }
+
+    if colliders.is_empty() {
+        return None;
+    }
+
+    let group = make_group_nodes_command(selection, graph)?;
+
+    let body = RigidBody {
+        position: center,
+        ..Default::default()
+    };
+
+    Some(SceneCommand::MakeCompoundBody(MakeCompoundBodyCommand {
+        group,
+        body: Some(body),
+        colliders,
+        set_body: None,
+        set_colliders: Default::default(),
+    }))
+}
+
+/// Creates command that makes collider of a body bound to given node match geometry of the
+/// node: shape of the first collider of the body is replaced with a triangle mesh, a new
+/// collider is added if the body has none. Triangle mesh descriptor stores no vertices, the
+/// engine builds the mesh from surfaces of the node and its descendants (with their global
+/// transforms baked in) when the scene is loaded, so the collider follows edits of geometry.
+///
+/// Convex hulls are not supported by the editor's physics data model (the engine has no
+/// descriptor for them), so only triangle meshes can be made. Triangle meshes have no volume
+/// and should be used with static bodies only.
+pub fn make_trimesh_collider_command(
+    node: Handle<Node>,
+    graph: &Graph,
+    physics: &Physics,
+    message_sender: &Sender<Message>,
+) -> Option<SceneCommand> {
+    let node_ref = &graph[node];
+
+    let mut has_geometry = false;
+    let mut stack = vec![node];
+    while let Some(handle) = stack.pop() {
+        let descendant = &graph[handle];
+        if let Node::Mesh(mesh) = descendant {
+            has_geometry |= mesh
+                .surfaces()
+                .iter()
+                .any(|s| !s.data().read().unwrap().triangles().is_empty());
+        }
+        stack.extend_from_slice(descendant.children());
+    }
+
+    let reason = if !has_geometry {
+        Some("it has no mesh geometry")
+    } else if physics.binder.value_of(&node).is_none() {
+        Some("it has no physical body")
+    } else {
+        None
+    };
+    if let Some(reason) = reason {
+        message_sender
+            .send(Message::Log(format!(
+                "Triangle mesh collider was not made for node {}, because {}.",
+                node_ref.name(),
+                reason
+            )))
+            .unwrap();
+        return None;
+    }
+
+    let body = *physics.binder.value_of(&node).unwrap();
+    let shape = ColliderShapeDesc::Trimesh(TrimeshDesc);
+
+    Some(match physics.bodies[body].colliders.first() {
+        Some(&collider) => {
+            SceneCommand::SetColliderShape(SetColliderShapeCommand::new(collider.into(), shape))
+        }
+        None => SceneCommand::SetCollider(SetColliderCommand::new(
+            body,
+            Collider {
+                shape,
+                ..Default::default()
+            },
+        )),
+    })
+}
+
+/// Creates command group that attaches children of given pivot to the parent of the pivot
+/// keeping their world transforms, and deletes the pivot. Children become selected.
+pub fn make_ungroup_command(
+    pivot: Handle<Node>,
+    graph: &Graph,
+    current_selection: &Selection,
+) -> SceneCommand {
+    let parent = graph[pivot].parent();
+    let children = graph[pivot].children().to_vec();
+
+    let mut commands = vec![SceneCommand::ChangeSelection(ChangeSelectionCommand::new(
+        Selection::Graph(GraphSelection::from_list(children.clone())),
+        current_selection.clone(),
+    ))];
+    for child in children {
+        commands.push(SceneCommand::LinkNodesKeepTransform(
+            LinkNodesKeepTransformCommand::new(child, parent),
+        ));
+    }
+    commands.push(SceneCommand::DeleteNode(DeleteNodeCommand::new(pivot)));
+
+    SceneCommand::CommandGroup(CommandGroup::from(commands))
+}
+
+/// Detaches a node from its parent and attaches it to the scene root, keeping world position
+/// and rotation of the node. Unlike [`LinkNodesKeepTransformCommand`] scale inherited from
+/// ancestors is discarded - node keeps only its own local scale. This is useful for imported
+/// models with scaled root, which confuses physics.
+#[derive(Debug)]
+pub struct FlattenTransformCommand {
+    node: Handle<Node>,
+    parent: Handle<Node>,
+    transform: Option<Transform>,
+}
+
+impl FlattenTransformCommand {
+    pub fn new(node: Handle<Node>) -> Self {
+        Self {
+            node,
+            parent: Default::default(),
+            transform: None,
+        }
+    }
+
+    fn swap(&mut self, graph: &mut Graph, physics: &mut Physics) {
+        let old_parent = graph[self.node].parent();
+        let old_transform = graph[self.node].local_transform().clone();
+
+        let new_transform = match self.transform.take() {
+            Some(transform) => transform,
+            None => {
+                let (position, rotation, _) =
+                    decompose_transform_matrix(&graph[self.node].global_transform());
+                TransformBuilder::new()
+                    .with_local_position(position)
+                    .with_local_rotation(rotation)
+                    .with_local_scale(**old_transform.scale())
+                    .build()
+            }
+        };
+
+        // Parent is none only on first execution.
+        let new_parent = if self.parent.is_some() {
+            self.parent
+        } else {
+            graph.get_root()
+        };
+        graph.link_nodes(self.node, new_parent);
+
+        if let Some(&body) = physics.binder.value_of(&self.node) {
+            let body = &mut physics.bodies[body];
+            body.position = **new_transform.position();
+            body.rotation = **new_transform.rotation();
+        }
+
+        *graph[self.node].local_transform_mut() = new_transform;
+
+        self.parent = old_parent;
+        self.transform = Some(old_transform);
+    }
+}
+
+impl<'a> Command<'a> for FlattenTransformCommand {
+    type Context = SceneContext<'a>;
+
+    fn name(&mut self, _context: &Self::Context) -> String {
+        "Flatten Transform".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut Self::Context) {
+        self.swap(&mut context.scene.graph, &mut context.editor_scene.physics);
+    }
+
+    fn revert(&mut self, context: &mut Self::Context) {
+        self.swap(&mut context.scene.graph, &mut context.editor_scene.physics);
+    }
+}
+
+#[derive(Debug)]
+pub struct DeleteNodeCommand {
+    handle: Handle<Node>,
+    ticket: Option<Ticket<Node>>,
+    node: Option<Node>,
+    parent: Handle<Node>,
+}
+
+impl DeleteNodeCommand {
+    pub fn new(handle: Handle<Node>) -> Self {
+        Self {
+            handle,
+            ticket: None,
+            node: None,
+            parent: Default::default(),
+        }
+    }
+}
+
+impl<'a> Command<'a> for DeleteNodeCommand {
+    type Context = SceneContext<'a>;
+
+    fn name(&mut self, _context: &Self::Context) -> String {
+        "Delete Node".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut Self::Context) {
+        self.parent = context.scene.graph[self.handle].parent();
+        let (ticket, node) = context.scene.graph.take_reserve(self.handle);
+        self.node = Some(node);
+        self.ticket = Some(ticket);
+    }
+
+    fn revert(&mut self, context: &mut Self::Context) {
+        self.handle = context
+            .scene
+            .graph
+            .put_back(self.ticket.take().unwrap(), self.node.take().unwrap());
+        context.scene.graph.link_nodes(self.handle, self.parent);
+    }
+
+    fn finalize(&mut self, context: &mut Self::Context) {
+        if let Some(ticket) = self.ticket.take() {
+            context.scene.graph.forget_ticket(ticket)
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct SetBodyCommand {
+    node: Handle<Node>,
+    ticket: Option<Ticket<RigidBody>>,
+    handle: Handle<RigidBody>,
+    body: Option<RigidBody>,
+}
+
+impl SetBodyCommand {
+    pub fn new(node: Handle<Node>, body: RigidBody) -> Self {
+        Self {
+            node,
+            ticket: None,
+            handle: Default::default(),
+            body: Some(body),
+        }
+    }
+}
+
+impl<'a> Command<'a> for SetBodyCommand {
+    type Context = SceneContext<'a>;
+
+    fn name(&mut self, _context: &Self::Context) -> String {
+        "Set Node Body".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut Self::Context) {
+        match self.ticket.take() {
+            None => {
+                self.handle = context
+                    .editor_scene
+                    .physics
+                    .bodies
+                    .spawn(self.body.take().unwrap());
+            }
+            Some(ticket) => {
+                context
+                    .editor_scene
+                    .physics
+                    .bodies
+                    .put_back(ticket, self.body.take().unwrap());
+            }
+        }
+        context
+            .editor_scene
+            .physics
+            .binder
+            .insert(self.node, self.handle);
+    }
+
+    fn revert(&mut self, context: &mut Self::Context) {
+        let (ticket, node) = context
+            .editor_scene
+            .physics
+            .bodies
+            .take_reserve(self.handle);
+        self.ticket = Some(ticket);
+        self.body = Some(node);
+        context
+            .editor_scene
+            .physics
+            .binder
+            .remove_by_key(&self.node);
+    }
+
+    fn finalize(&mut self, context: &mut Self::Context) {
+        if let Some(ticket) = self.ticket.take() {
+            context.editor_scene.physics.bodies.forget_ticket(ticket);
+            context
+                .editor_scene
+                .physics
+                .binder
+                .remove_by_key(&self.node);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct SetColliderCommand {
+    body: Handle<RigidBody>,
+    ticket: Option<Ticket<Collider>>,
+    handle: Handle<Collider>,
+    collider: Option<Collider>,
+}
+
+impl SetColliderCommand {
+    pub fn new(body: Handle<RigidBody>, collider: Collider) -> Self {
+        Self {
+            body,
+            ticket: None,
+            handle: Default::default(),
+            collider: Some(collider),
+        }
+    }
+}
+
+impl<'a> Command<'a> for SetColliderCommand {
+    type Context = SceneContext<'a>;
+
+    fn name(&mut self, _context: &Self::Context) -> String {
+        "Set Collider".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut Self::Context) {
+        match self.ticket.take() {
+            None => {
+                self.handle = context
+                    .editor_scene
+                    .physics
+                    .colliders
+                    .spawn(self.collider.take().unwrap());
+            }
+            Some(ticket) => {
+                context
+                    .editor_scene
+                    .physics
+                    .colliders
+                    .put_back(ticket, self.collider.take().unwrap());
+            }
+        }
+        context.editor_scene.physics.colliders[self.handle].parent = self.body.into();
+        context.editor_scene.physics.bodies[self.body]
+            .colliders
+            .push(self.handle.into());
+    }
+
+    fn revert(&mut self, context: &mut Self::Context) {
+        let (ticket, mut collider) = context
+            .editor_scene
+            .physics
+            .colliders
+            .take_reserve(self.handle);
+        collider.parent = Default::default();
+        self.ticket = Some(ticket);
+        self.collider = Some(collider);
+
+        let body = &mut context.editor_scene.physics.bodies[self.body];
+        body.colliders.remove(
+            body.colliders
+                .iter()
+                .position(|&c| c == ErasedHandle::from(self.handle))
+                .unwrap(),
+        );
+    }
+
+    fn finalize(&mut self, context: &mut Self::Context) {
+        if let Some(ticket) = self.ticket.take() {
+            context.editor_scene.physics.colliders.forget_ticket(ticket);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct LoadModelCommand {
+    path: PathBuf,
+    resource: Option<Model>,
+    model: Handle<Node>,
+    animations: Vec<Handle<Animation>>,
+    sub_graph: Option<SubGraph>,
+    animations_container: Vec<(Ticket<Animation>, Animation)>,
+    retarget_to: Option<Handle<Node>>,
+    transform: Option<Transform>,
+    select_instance: bool,
+    loaded: bool,
+}
+
+impl LoadModelCommand {
+    /// Creates command that loads model from given path on first execution. It blocks until
+    /// the model is loaded, use `EditorScene::request_model` to load large models.
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            resource: None,
+            model: Default::default(),
+            animations: Default::default(),
+            sub_graph: None,
+            animations_container: Default::default(),
+            retarget_to: None,
+            transform: None,
+            select_instance: false,
+            loaded: false,
+        }
+    }
+
+    /// Creates command that instantiates already loaded model, so execution never blocks.
+    pub fn from_model(path: PathBuf, model: Model) -> Self {
+        Self {
+            resource: Some(model),
+            ..Self::new(path)
+        }
+    }
+
+    /// Sets local transform of instance root.
+    pub fn with_transform(mut self, transform: Transform) -> Self {
+        self.transform = Some(transform);
+        self
+    }
+
+    /// Adds instance root to current graph selection when executed, and removes it from
+    /// selection when reverted.
+    pub fn with_instance_selected(mut self) -> Self {
+        self.select_instance = true;
+        self
+    }
+
+    fn set_instance_selected(&self, editor_scene: &mut EditorScene, selected: bool) {
+        if !self.select_instance || self.model.is_none() {
+            return;
+        }
+
+        if let Selection::Graph(selection) = &mut editor_scene.selection {
+            if selection.contains(self.model) != selected {
+                selection.insert_or_exclude(self.model);
+            }
+        } else if selected {
+            editor_scene.selection = Selection::Graph(GraphSelection::single_or_empty(self.model));
+        }
+    }
+
+    /// Instead of instantiating the model, its animations will be retargeted to existing
+    /// hierarchy with given root. Tracks are mapped onto nodes by name, nodes of the model
+    /// itself are not added to the scene, so there will be no duplicate meshes.
+    pub fn with_retarget_to(mut self, root: Handle<Node>) -> Self {
+        self.retarget_to = Some(root);
+        self
+    }
+}
+
+impl<'a> Command<'a> for LoadModelCommand {
+    type Context = SceneContext<'a>;
+
+    fn name(&mut self, _context: &Self::Context) -> String {
+        "Load Model".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut Self::Context) {
+        if !self.loaded {
+            // No model was loaded yet, do it.
+            let model = match self.resource.clone() {
+                Some(model) => Ok(model),
+                None => rg3d::core::futures::executor::block_on(
+                    context.resource_manager.request_model(&self.path),
+                ),
+            };
+            if let Ok(model) = model {
+                match self.retarget_to {
+                    Some(root) => {
+                        self.animations = model.retarget_animations(root, context.scene);
+                    }
+                    None => {
+                        let instance = model.instantiate(context.scene);
+                        self.model = instance.root;
+                        self.animations = instance.animations;
+
+                        if let Some(transform) = self.transform.clone() {
+                            *context.scene.graph[self.model].local_transform_mut() = transform;
+                        }
+                    }
+                }
+
+                // Enable instantiated animations.
+                for &animation in self.animations.iter() {
+                    context.scene.animations[animation].set_enabled(true);
+                }
+
+                self.loaded = true;
+            }
+        } else {
+            // A model was loaded, but change was reverted and here we must put all nodes
+            // back to graph.
+            if let Some(sub_graph) = self.sub_graph.take() {
+                self.model = context.scene.graph.put_sub_graph_back(sub_graph);
+            }
+            for (ticket, animation) in self.animations_container.drain(..) {
+                context.scene.animations.put_back(ticket, animation);
+            }
+        }
+
+        self.set_instance_selected(context.editor_scene, true);
+    }
+
+    fn revert(&mut self, context: &mut Self::Context) {
+        self.set_instance_selected(context.editor_scene, false);
+
+        if self.model.is_some() {
+            self.sub_graph = Some(context.scene.graph.take_reserve_sub_graph(self.model));
+        }
+        self.animations_container = self
+            .animations
+            .iter()
+            .map(|&anim| context.scene.animations.take_reserve(anim))
+            .collect();
+    }
+
+    fn finalize(&mut self, context: &mut Self::Context) {
+        if let Some(sub_graph) = self.sub_graph.take() {
+            context.scene.graph.forget_sub_graph(sub_graph)
+        }
+        for (ticket, _) in self.animations_container.drain(..) {
+            context.scene.animations.forget_ticket(ticket);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct DeleteSubGraphCommand {
+    sub_graph_root: Handle<Node>,
+    sub_graph: Option<SubGraph>,
+    parent: Handle<Node>,
+}
+
+impl DeleteSubGraphCommand {
+    pub fn new(sub_graph_root: Handle<Node>) -> Self {
+        Self {
+            sub_graph_root,
+            sub_graph: None,
+            parent: Handle::NONE,
+        }
+    }
+}
+
+impl<'a> Command<'a> for DeleteSubGraphCommand {
+    type Context = SceneContext<'a>;
+
+    fn name(&mut self, _context: &Self::Context) -> String {
+        "Delete Sub Graph".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut Self::Context) {
+        self.parent = context.scene.graph[self.sub_graph_root].parent();
+        self.sub_graph = Some(
+            context
+                .scene
+                .graph
+                .take_reserve_sub_graph(self.sub_graph_root),
+        );
+    }
+
+    fn revert(&mut self, context: &mut Self::Context) {
+        context
+            .scene
+            .graph
+            .put_sub_graph_back(self.sub_graph.take().unwrap());
+        context
+            .scene
+            .graph
+            .link_nodes(self.sub_graph_root, self.parent);
+    }
+
+    fn finalize(&mut self, context: &mut Self::Context) {
+        if let Some(sub_graph) = self.sub_graph.take() {
+            context.scene.graph.forget_sub_graph(sub_graph)
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct DeleteBodyCommand {
+    handle: Handle<RigidBody>,
+    ticket: Option<Ticket<RigidBody>>,
+    body: Option<RigidBody>,
+    node: Handle<Node>,
+}
+
+impl DeleteBodyCommand {
+    pub fn new(handle: Handle<RigidBody>) -> Self {
+        Self {
+            handle,
+            ticket: None,
+            body: None,
+            node: Handle::NONE,
+        }
+    }
+}
+
+impl<'a> Command<'a> for DeleteBodyCommand {
+    type Context = SceneContext<'a>;
+
+    fn name(&mut self, _context: &Self::Context) -> String {
+        "Delete Body".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut Self::Context) {
+        let (ticket, node) = context
+            .editor_scene
+            .physics
+            .bodies
+            .take_reserve(self.handle);
+        self.body = Some(node);
+        self.ticket = Some(ticket);
+        self.node = context.editor_scene.physics.unbind_by_body(self.handle);
+    }
+
+    fn revert(&mut self, context: &mut Self::Context) {
+        self.handle = context
+            .editor_scene
+            .physics
+            .bodies
+            .put_back(self.ticket.take().unwrap(), self.body.take().unwrap());
+        context
+            .editor_scene
+            .physics
+            .binder
+            .insert(self.node, self.handle);
+    }
+
+    fn finalize(&mut self, context: &mut Self::Context) {
+        if let Some(ticket) = self.ticket.take() {
+            context.editor_scene.physics.bodies.forget_ticket(ticket)
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct DeleteColliderCommand {
+    handle: Handle<Collider>,
+    ticket: Option<Ticket<Collider>>,
+    collider: Option<Collider>,
+    body: Handle<RigidBody>,
+}
+
+impl DeleteColliderCommand {
+    pub fn new(handle: Handle<Collider>) -> Self {
+        Self {
+            handle,
+            ticket: None,
+            collider: None,
+            body: Handle::NONE,
+        }
+    }
+}
+
+impl<'a> Command<'a> for DeleteColliderCommand {
+    type Context = SceneContext<'a>;
+
+    fn name(&mut self, _context: &Self::Context) -> String {
+        "Delete Collider".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut Self::Context) {
+        let (ticket, collider) = context
+            .editor_scene
+            .physics
+            .colliders
+            .take_reserve(self.handle);
+        self.body = collider.parent.into();
+        self.collider = Some(collider);
+        self.ticket = Some(ticket);
+
+        let body = &mut context.editor_scene.physics.bodies[self.body];
+        body.colliders.remove(
+            body.colliders
+                .iter()
+                .position(|&c| c == ErasedHandle::from(self.handle))
+                .unwrap(),
+        );
+    }
+
+    fn revert(&mut self, context: &mut Self::Context) {
+        self.handle = context
+            .editor_scene
+            .physics
+            .colliders
+            .put_back(self.ticket.take().unwrap(), self.collider.take().unwrap());
+
+        let body = &mut context.editor_scene.physics.bodies[self.body];
+        body.colliders.push(self.handle.into());
+    }
+
+    fn finalize(&mut self, context: &mut Self::Context) {
+        if let Some(ticket) = self.ticket.take() {
+            context.editor_scene.physics.colliders.forget_ticket(ticket)
+        }
+    }
+}
+
+/// Moves a collider to another body, collider keeps its settings.
+#[derive(Debug)]
+pub struct SetColliderParentCommand {
+    handle: Handle<Collider>,
+    value: Handle<RigidBody>,
+    applied: bool,
+}
+
+impl SetColliderParentCommand {
+    pub fn new(handle: Handle<Collider>, body: Handle<RigidBody>) -> Self {
+        Self {
+            handle,
+            value: body,
+            applied: false,
+        }
+    }
+
+    fn swap(&mut self, physics: &mut Physics) {
+        let collider = &mut physics.colliders[self.handle];
+        let old_parent: Handle<RigidBody> = collider.parent.into();
+        collider.parent = self.value.into();
+
+        if physics.bodies.is_valid_handle(old_parent) {
+            let body = &mut physics.bodies[old_parent];
+            if let Some(position) = body
+                .colliders
+                .iter()
+                .position(|&c| c == ErasedHandle::from(self.handle))
+            {
+                body.colliders.remove(position);
+            }
+        }
+        // Parent could be none only on revert, if the collider had no parent before.
+        if physics.bodies.is_valid_handle(self.value) {
+            physics.bodies[self.value]
+                .colliders
+                .push(self.handle.into());
+        }
+
+        self.value = old_parent;
+    }
+}
+
+impl<'a> Command<'a> for SetColliderParentCommand {
+    type Context = SceneContext<'a>;
+
+    fn name(&mut self, _context: &Self::Context) -> String {
+        "Set Collider Parent".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut Self::Context) {
+        if context
+            .editor_scene
+            .physics
+            .bodies
+            .is_valid_handle(self.value)
+        {
+            self.swap(&mut context.editor_scene.physics);
+            self.applied = true;
+        } else {
+            context
+                .message_sender
+                .send(Message::Log(
+                    "Collider parent was not changed, because new body is invalid.".to_owned(),
+                ))
+                .unwrap();
+        }
+    }
+
+    fn revert(&mut self, context: &mut Self::Context) {
+        if self.applied {
+            self.swap(&mut context.editor_scene.physics);
+            self.applied = false;
+        }
+    }
+}
+
+/// Searches for mismatches between `body.colliders` and `collider.parent` and fixes them:
+/// colliders that point to a dead body lose their parent, bodies get their lists of colliders
+/// rebuilt so they contain each collider that points to them exactly once. Prior state is
+/// remembered, so the command can be reverted.
+#[derive(Debug, Default)]
+pub struct ValidatePhysicsConsistencyCommand {
+    body_colliders: Vec<(Handle<RigidBody>, Vec<ErasedHandle>)>,
+    collider_parents: Vec<(Handle<Collider>, ErasedHandle)>,
+}
+
+impl ValidatePhysicsConsistencyCommand {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<'a> Command<'a> for ValidatePhysicsConsistencyCommand {
+    type Context = SceneContext<'a>;
+
+    fn name(&mut self, _context: &Self::Context) -> String {
+        "Validate Physics Consistency".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut Self::Context) {
+        let physics = &mut context.editor_scene.physics;
+
+        self.body_colliders.clear();
+        self.collider_parents.clear();
+
+        let mut report = String::new();
+
+        // Clear dangling parents first, so colliders of dead bodies won't be added anywhere.
+        for (handle, collider) in physics.colliders.pair_iter_mut() {
+            if collider.parent.is_some() && !physics.bodies.is_valid_handle(collider.parent.into())
+            {
+                writeln!(
+                    &mut report,
+                    "Collider {}:{} had dangling parent - cleared.",
+                    handle.index(),
+                    handle.generation()
+                )
+                .unwrap();
+                self.collider_parents
+                    .push((handle, std::mem::take(&mut collider.parent)));
+            }
+        }
+
+        for (body_handle, body) in physics.bodies.pair_iter_mut() {
+            let body_erased_handle = ErasedHandle::from(body_handle);
+
+            // Keep only alive colliders which do point to this body.
+            let mut colliders = Vec::new();
+            for &collider in body.colliders.iter() {
+                if let Some(collider_ref) = physics.colliders.try_borrow(collider.into()) {
+                    if collider_ref.parent == body_erased_handle && !colliders.contains(&collider) {
+                        colliders.push(collider);
+                    }
+                }
+            }
+
+            // Then add every collider which points to this body, but is missing in the list.
+            for (collider_handle, collider) in physics.colliders.pair_iter() {
+                let collider_handle = ErasedHandle::from(collider_handle);
+                if collider.parent == body_erased_handle && !colliders.contains(&collider_handle) {
+                    colliders.push(collider_handle);
+                }
+            }
+
+            if colliders != body.colliders {
+                writeln!(
+                    &mut report,
+                    "Body {}:{} had inconsistent list of colliders - rebuilt.",
+                    body_handle.index(),
+                    body_handle.generation()
+                )
+                .unwrap();
+                self.body_colliders.push((
+                    body_handle,
+                    std::mem::replace(&mut body.colliders, colliders),
+                ));
+            }
+        }
+
+        if report.is_empty() {
+            report.push_str("Physics is consistent, nothing to fix.");
+        }
+
+        context.message_sender.send(Message::Log(report)).unwrap();
+    }
+
+    fn revert(&mut self, context: &mut Self::Context) {
+        let physics = &mut context.editor_scene.physics;
+
+        for (body, colliders) in self.body_colliders.drain(..) {
+            physics.bodies[body].colliders = colliders;
+        }
+
+        for (collider, parent) in self.collider_parents.drain(..) {
+            physics.colliders[collider].parent = parent;
+        }
+    }
+}
+
+/// Converts whole scene to other units by multiplying every distance in it by `factor`, for
+/// example `0.01` converts a scene made in centimeters to meters. Root nodes are scaled around
+/// scene origin, so every descendant is converted too. Reverting applies inverse factor.
+#[derive(Debug)]
+pub struct SetSceneUnitScaleCommand {
+    factor: f32,
+}
+
+impl SetSceneUnitScaleCommand {
+    /// Returns `None` if `factor` is not a finite positive number: zero factor can't be
+    /// reverted and negative one would mirror the scene.
+    pub fn new(factor: f32) -> Option<Self> {
+        if factor.is_finite() && factor > f32::EPSILON {
+            Some(Self { factor })
+        } else {
+            None
+        }
+    }
+
+    fn apply(&self, context: &mut SceneContext, factor: f32) {
+        let graph = &mut context.scene.graph;
+        let editor_root = context.editor_scene.root;
+        let root_nodes = graph[graph.get_root()]
+            .children()
+            .iter()
+            .cloned()
+            .filter(|&n| n != editor_root)
+            .collect::<Vec<_>>();
+        for root_node in root_nodes {
+            let transform = graph[root_node].local_transform_mut();
+            let position = **transform.position();
+            let scale = **transform.scale();
+            transform
+                .set_position(position.scale(factor))
+                .set_scale(scale.scale(factor));
+        }
+
+        for navmesh in context.editor_scene.navmeshes.iter_mut() {
+            for vertex in navmesh.vertices.iter_mut() {
+                vertex.position = vertex.position.scale(factor);
+            }
+        }
+
+        // Positions of bodies bound to descendants are relative to their parents, which are
+        // scaled already, so only bodies of root nodes (and free ones) are moved.
+        let graph = &context.scene.graph;
+        let physics = &mut context.editor_scene.physics;
+        for (handle, body) in physics.bodies.pair_iter_mut() {
+            let is_root = match physics.binder.key_of(&handle) {
+                Some(&node) => graph[node].parent() == graph.get_root(),
+                None => true,
+            };
+            if is_root {
+                body.position = body.position.scale(factor);
+            }
+        }
+        for collider in physics.colliders.iter_mut() {
+            collider.translation = collider.translation.scale(factor);
+            scale_collider_shape(&mut collider.shape, factor);
+        }
+    }
+}
+
+impl<'a> Command<'a> for SetSceneUnitScaleCommand {
+    type Context = SceneContext<'a>;
+
+    fn name(&mut self, _context: &Self::Context) -> String {
+        format!("Set Scene Unit Scale {}", self.factor)
+    }
+
+    fn execute(&mut self, context: &mut Self::Context) {
+        self.apply(context, self.factor);
+    }
+
+    fn revert(&mut self, context: &mut Self::Context) {
+        self.apply(context, 1.0 / self.factor);
+    }
+}
+
+#[derive(Debug)]
+pub struct AddLodGroupLevelCommand {
+    handle: Handle<Node>,
+    level: LevelOfDetail,
+}
+
+impl AddLodGroupLevelCommand {
+    pub fn new(handle: Handle<Node>, level: LevelOfDetail) -> Self {
+        Self { handle, level }
+    }
+}
+
+impl<'a> Command<'a> for AddLodGroupLevelCommand {
+    type Context = SceneContext<'a>;
+
+    fn name(&mut self, _context: &Self::Context) -> String {
+        "Add Lod Group Level".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut Self::Context) {
+        context.scene.graph[self.handle]
+            .lod_group_mut()
+            .unwrap()
+            .levels
+            .push(self.level.clone());
+    }
+
+    fn revert(&mut self, context: &mut Self::Context) {
+        context.scene.graph[self.handle]
+            .lod_group_mut()
+            .unwrap()
+            .levels
+            .pop();
+    }
+}
+
+#[derive(Debug)]
+pub struct RemoveLodGroupLevelCommand {
+    handle: Handle<Node>,
+    level: Option<LevelOfDetail>,
+    index: usize,
+}
+
+impl RemoveLodGroupLevelCommand {
+    pub fn new(handle: Handle<Node>, index: usize) -> Self {
+        Self {
+            handle,
+            level: None,
+            index,
+        }
+    }
+}
+
+impl<'a> Command<'a> for RemoveLodGroupLevelCommand {
+    type Context = SceneContext<'a>;
+
+    fn name(&mut self, _context: &Self::Context) -> String {
+        "Remove Lod Group Level".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut Self::Context) {
+        self.level = Some(
+            context.scene.graph[self.handle]
+                .lod_group_mut()
+                .unwrap()
+                .levels
+                .remove(self.index),
+        );
+    }
+
+    fn revert(&mut self, context: &mut Self::Context) {
+        let group = context.scene.graph[self.handle].lod_group_mut().unwrap();
+        let level = self.level.take().unwrap();
+        if group.levels.is_empty() {
+            group.levels.push(level);
+        } else {
+            group.levels.insert(self.index, level)
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct AddLodObjectCommand {
+    handle: Handle<Node>,
+    lod_index: usize,
+    object: Handle<Node>,
+    object_index: usize,
+}
+
+impl AddLodObjectCommand {
+    pub fn new(handle: Handle<Node>, lod_index: usize, object: Handle<Node>) -> Self {
+        Self {
+            handle,
+            lod_index,
+            object,
+            object_index: 0,
+        }
+    }
+}
+
+impl<'a> Command<'a> for AddLodObjectCommand {
+    type Context = SceneContext<'a>;
+
+    fn name(&mut self, _context: &Self::Context) -> String {
+        "Add Lod Object".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut Self::Context) {
+        let objects = &mut context.scene.graph[self.handle]
+            .lod_group_mut()
+            .unwrap()
+            .levels[self.lod_index]
+            .objects;
+        self.object_index = objects.len();
+        objects.push(self.object);
+    }
+
+    fn revert(&mut self, context: &mut Self::Context) {
+        context.scene.graph[self.handle]
+            .lod_group_mut()
+            .unwrap()
+            .levels[self.lod_index]
+            .objects
+            .remove(self.object_index);
+    }
+}
+
+#[derive(Debug)]
+pub struct RemoveLodObjectCommand {
+    handle: Handle<Node>,
+    lod_index: usize,
+    object: Handle<Node>,
+    object_index: usize,
+}
+
+impl RemoveLodObjectCommand {
+    pub fn new(handle: Handle<Node>, lod_index: usize, object_index: usize) -> Self {
+        Self {
+            handle,
+            lod_index,
+            object: Default::default(),
+            object_index,
+        }
+    }
+}
+
+impl<'a> Command<'a> for RemoveLodObjectCommand {
+    type Context = SceneContext<'a>;
+
+    fn name(&mut self, _context: &Self::Context) -> String {
+        "Remove Lod Object".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut Self::Context) {
+        self.object = context.scene.graph[self.handle]
+            .lod_group_mut()
+            .unwrap()
+            .levels[self.lod_index]
+            .objects
+            .remove(self.object_index);
+    }
+
+    fn revert(&mut self, context: &mut Self::Context) {
+        let objects = &mut context.scene.graph[self.handle]
+            .lod_group_mut()
+            .unwrap()
+            .levels[self.lod_index]
+            .objects;
+        if objects.is_empty() {
+            objects.push(self.object);
+        } else {
+            objects.insert(self.object_index, self.object);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ChangeLodRangeBeginCommand {
+    handle: Handle<Node>,
+    lod_index: usize,
+    new_value: f32,
+}
+
+impl ChangeLodRangeBeginCommand {
+    pub fn new(handle: Handle<Node>, lod_index: usize, new_value: f32) -> Self {
+        Self {
+            handle,
+            lod_index,
+            new_value,
+        }
+    }
+
+    fn swap(&mut self, context: &mut SceneContext) {
+        let level = &mut context.scene.graph[self.handle]
+            .lod_group_mut()
+            .unwrap()
+            .levels[self.lod_index];
+        let old = level.begin();
+        level.set_begin(self.new_value);
+        self.new_value = old;
+    }
+}
+
+impl<'a> Command<'a> for ChangeLodRangeBeginCommand {
+    type Context = SceneContext<'a>;
+
+    fn name(&mut self, _context: &Self::Context) -> String {
+        "Change Lod Range Begin".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut Self::Context) {
+        self.swap(context);
+    }
+
+    fn revert(&mut self, context: &mut Self::Context) {
+        self.swap(context);
+    }
+}
+
+#[derive(Debug)]
+pub struct ChangeLodRangeEndCommand {
+    handle: Handle<Node>,
+    lod_index: usize,
+    new_value: f32,
+}
+
+impl ChangeLodRangeEndCommand {
+    pub fn new(handle: Handle<Node>, lod_index: usize, new_value: f32) -> Self {
+        Self {
+            handle,
+            lod_index,
+            new_value,
+        }
+    }
+
+    fn swap(&mut self, context: &mut SceneContext) {
+        let level = &mut context.scene.graph[self.handle]
+            .lod_group_mut()
+            .unwrap()
+            .levels[self.lod_index];
+        let old = level.end();
+        level.set_end(self.new_value);
+        self.new_value = old;
+    }
+}
+
+impl<'a> Command<'a> for ChangeLodRangeEndCommand {
+    type Context = SceneContext<'a>;
+
+    fn name(&mut self, _context: &Self::Context) -> String {
+        "Change Lod Range End".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut Self::Context) {
+        self.swap(context);
+    }
+
+    fn revert(&mut self, context: &mut Self::Context) {
+        self.swap(context);
+    }
+}
+
+/// Sets diffuse texture of every surface of a mesh. Linked duplicates of the mesh (and its
+/// source, if the mesh is a linked duplicate itself) get the texture too, see
+/// [`make_linked_duplicate_command`].
+#[derive(Debug)]
+pub struct SetMeshTextureCommand {
+    node: Handle<Node>,
+    texture: Texture,
+    // Diffuse textures of surfaces of every changed mesh before the change.
+    old_textures: Vec<(Handle<Node>, Vec<Option<Texture>>)>,
+}
+
+impl SetMeshTextureCommand {
+    pub fn new(node: Handle<Node>, texture: Texture) -> Self {
+        Self {
+            node,
+            texture,
+            old_textures: Default::default(),
+        }
+    }
+}
+
+impl<'a> Command<'a> for SetMeshTextureCommand {
+    type Context = SceneContext<'a>;
+
+    fn name(&mut self, _context: &Self::Context) -> String {
+        "Set Texture".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut Self::Context) {
+        let graph = &mut context.scene.graph;
+        for node in context.editor_scene.linked_group(self.node, graph) {
+            let mesh: &mut Mesh = graph[node].as_mesh_mut();
+            self.old_textures.push((
+                node,
+                mesh.surfaces()
+                    .iter()
+                    .map(|s| s.diffuse_texture())
+                    .collect(),
+            ));
+            for surface in mesh.surfaces_mut() {
+                surface.set_diffuse_texture(Some(self.texture.clone()));
+            }
+        }
+    }
+
+    fn revert(&mut self, context: &mut Self::Context) {
+        for (node, old_textures) in self.old_textures.drain(..) {
+            let mesh: &mut Mesh = context.scene.graph[node].as_mesh_mut();
+            for (surface, old_texture) in mesh.surfaces_mut().iter_mut().zip(old_textures) {
+                surface.set_diffuse_texture(old_texture);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub enum EmitterNumericParameter {
+    SpawnRate,
+    MaxParticles,
+    MinLifetime,
+    MaxLifetime,
+    MinSizeModifier,
+    MaxSizeModifier,
+    MinXVelocity,
+    MaxXVelocity,
+    MinYVelocity,
+    MaxYVelocity,
+    MinZVelocity,
+    MaxZVelocity,
+    MinRotationSpeed,
+    MaxRotationSpeed,
+    MinRotation,
+    MaxRotation,
+}
+
+impl EmitterNumericParameter {
+    fn name(self) -> &'static str {
+        match self {
+            EmitterNumericParameter::SpawnRate => "SpawnRate",
+            EmitterNumericParameter::MaxParticles => "MaxParticles",
+            EmitterNumericParameter::MinLifetime => "MinLifetime",
+            EmitterNumericParameter::MaxLifetime => "MaxLifetime",
+            EmitterNumericParameter::MinSizeModifier => "MinSizeModifier",
+            EmitterNumericParameter::MaxSizeModifier => "MaxSizeModifier",
+            EmitterNumericParameter::MinXVelocity => "MinXVelocity",
+            EmitterNumericParameter::MaxXVelocity => "MaxXVelocity",
+            EmitterNumericParameter::MinYVelocity => "MinYVelocity",
+            EmitterNumericParameter::MaxYVelocity => "MaxYVelocity",
+            EmitterNumericParameter::MinZVelocity => "MinZVelocity",
+            EmitterNumericParameter::MaxZVelocity => "MaxZVelocity",
+            EmitterNumericParameter::MinRotationSpeed => "MinRotationSpeed",
+            EmitterNumericParameter::MaxRotationSpeed => "MaxRotationSpeed",
+            EmitterNumericParameter::MinRotation => "MinRotation",
+            EmitterNumericParameter::MaxRotation => "MaxRotation",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct SetEmitterNumericParameterCommand {
+    node: Handle<Node>,
+    parameter: EmitterNumericParameter,
+    value: f32,
+    emitter_index: usize,
+}
+
+impl SetEmitterNumericParameterCommand {
+    pub fn new(
+        node: Handle<Node>,
+        emitter_index: usize,
+        parameter: EmitterNumericParameter,
+        value: f32,
+    ) -> Self {
+        Self {
+            node,
+            parameter,
+            value,
+            emitter_index,
+        }
+    }
+
+    fn swap(&mut self, context: &mut SceneContext) {
+        let emitter: &mut Emitter = &mut context.scene.graph[self.node]
+            .as_particle_system_mut()
+            .emitters[self.emitter_index];
+        match self.parameter {
+            EmitterNumericParameter::SpawnRate => {
+                let old = emitter.spawn_rate();
+                emitter.set_spawn_rate(self.value as u32);
+                self.value = old as f32;
+            }
+            EmitterNumericParameter::MaxParticles => {
+                let old = emitter.max_particles();
+                emitter.set_max_particles(if self.value < 0.0 {
+                    ParticleLimit::Unlimited
+                } else {
+                    ParticleLimit::Strict(self.value as u32)
+                });
+                self.value = match old {
+                    ParticleLimit::Unlimited => -1.0,
+                    ParticleLimit::Strict(value) => value as f32,
+                };
+            }
+            EmitterNumericParameter::MinLifetime => {
+                let old = emitter.life_time_range();
+                emitter.set_life_time_range(NumericRange::new(self.value, old.bounds[1]));
+                self.value = old.bounds[0];
+            }
+            EmitterNumericParameter::MaxLifetime => {
+                let old = emitter.life_time_range();
+                emitter.set_life_time_range(NumericRange::new(old.bounds[0], self.value));
+                self.value = old.bounds[1];
+            }
+            EmitterNumericParameter::MinSizeModifier => {
+                let old = emitter.size_modifier_range();
+                emitter.set_size_modifier_range(NumericRange::new(self.value, old.bounds[1]));
+                self.value = old.bounds[0];
+            }
+            EmitterNumericParameter::MaxSizeModifier => {
+                let old = emitter.size_modifier_range();
+                emitter.set_size_modifier_range(NumericRange::new(old.bounds[0], self.value));
+                self.value = old.bounds[1];
+            }
+            EmitterNumericParameter::MinXVelocity => {
+                let old = emitter.x_velocity_range();
+                emitter.set_x_velocity_range(NumericRange::new(self.value, old.bounds[1]));
+                self.value = old.bounds[0];
+            }
+            EmitterNumericParameter::MaxXVelocity => {
+                let old = emitter.x_velocity_range();
+                emitter.set_x_velocity_range(NumericRange::new(old.bounds[0], self.value));
+                self.value = old.bounds[1];
+            }
+            EmitterNumericParameter::MinYVelocity => {
+                let old = emitter.y_velocity_range();
+                emitter.set_y_velocity_range(NumericRange::new(self.value, old.bounds[1]));
+                self.value = old.bounds[0];
+            }
+            EmitterNumericParameter::MaxYVelocity => {
+                let old = emitter.y_velocity_range();
+                emitter.set_y_velocity_range(NumericRange::new(old.bounds[0], self.value));
+                self.value = old.bounds[1];
+            }
+            EmitterNumericParameter::MinZVelocity => {
+                let old = emitter.z_velocity_range();
+                emitter.set_z_velocity_range(NumericRange::new(self.value, old.bounds[1]));
+                self.value = old.bounds[0];
+            }
+            EmitterNumericParameter::MaxZVelocity => {
+                let old = emitter.z_velocity_range();
+                emitter.set_z_velocity_range(NumericRange::new(old.bounds[0], self.value));
+                self.value = old.bounds[1];
+            }
+            EmitterNumericParameter::MinRotationSpeed => {
+                let old = emitter.rotation_speed_range();
+                emitter.set_rotation_speed_range(NumericRange::new(self.value, old.bounds[1]));
+                self.value = old.bounds[0];
+            }
+            EmitterNumericParameter::MaxRotationSpeed => {
+                let old = emitter.rotation_speed_range();
+                emitter.set_rotation_speed_range(NumericRange::new(old.bounds[0], self.value));
+                self.value = old.bounds[1];
+            }
+            EmitterNumericParameter::MinRotation => {
+                let old = emitter.rotation_range();
+                emitter.set_rotation_range(NumericRange::new(self.value, old.bounds[1]));
+                self.value = old.bounds[0];
+            }
+            EmitterNumericParameter::MaxRotation => {
+                let old = emitter.rotation_range();
+                emitter.set_rotation_range(NumericRange::new(old.bounds[0], self.value));
+                self.value = old.bounds[1];
+            }
+        };
+    }
+}
+
+impl<'a> Command<'a> for SetEmitterNumericParameterCommand {
+    type Context = SceneContext<'a>;
+
+    fn name(&mut self, _context: &Self::Context) -> String {
+        format!("Set Emitter F32 Parameter: {}", self.parameter.name())
+    }
+
+    fn execute(&mut self, context: &mut Self::Context) {
+        self.swap(context);
+    }
+
+    fn revert(&mut self, context: &mut Self::Context) {
+        self.swap(context);
+    }
+}
+
+macro_rules! define_node_command {
+    ($name:ident($human_readable_name:expr, $value_type:ty) where fn swap($self:ident, $node:ident) $apply_method:block ) => {
+        #[derive(Debug)]
+        pub struct $name {
+            handle: Handle<Node>,
+            value: $value_type,
+        }
+
+        impl $name {
+            pub fn new(handle: Handle<Node>, value: $value_type) -> Self {
+                Self { handle, value }
+            }
+
+            fn swap(&mut $self, graph: &mut Graph) {
+                let $node = &mut graph[$self.handle];
+                $apply_method
+            }
+        }
+
+        impl<'a> Command<'a> for $name {
+            type Context = SceneContext<'a>;
+
+            fn name(&mut self, _context: &Self::Context) -> String {
+                $human_readable_name.to_owned()
+            }
+
+            fn execute(&mut self, context: &mut Self::Context) {
+                self.swap(&mut context.scene.graph);
+            }
+
+            fn revert(&mut self, context: &mut Self::Context) {
+                self.swap(&mut context.scene.graph);
+            }
+        }
+    };
+}
+
+macro_rules! define_physics_command {
+    ($name:ident($human_readable_name:expr, $handle_type:ty, $value_type:ty) where fn swap($self:ident, $physics:ident) $apply_method:block ) => {
+        #[derive(Debug)]
+        pub struct $name {
+            handle: Handle<$handle_type>,
+            value: $value_type,
+        }
+
+        impl $name {
+            pub fn new(handle: Handle<$handle_type>, value: $value_type) -> Self {
+                Self { handle, value }
+            }
+
+            fn swap(&mut $self, $physics: &mut Physics) {
+                 $apply_method
+            }
+        }
+
+        impl<'a> Command<'a> for $name {
+            type Context = SceneContext<'a>;
+
+            fn name(&mut self, _context: &Self::Context) -> String {
+                $human_readable_name.to_owned()
+            }
+
+            fn execute(&mut self, context: &mut Self::Context) {
+                self.swap(&mut context.editor_scene.physics);
+            }
+
+            fn revert(&mut self, context: &mut Self::Context) {
+                self.swap(&mut context.editor_scene.physics);
+            }
+        }
+    };
+}
+
+macro_rules! define_body_command {
+    ($name:ident($human_readable_name:expr, $value_type:ty) where fn swap($self:ident, $physics: ident, $body:ident) $apply_method:block ) => {
+        define_physics_command!($name($human_readable_name, RigidBody, $value_type) where fn swap($self, $physics) {
+            let $body = &mut $physics.bodies[$self.handle];
+            $apply_method
+        });
+    };
+}
+
+macro_rules! define_collider_command {
+    ($name:ident($human_readable_name:expr, $value_type:ty) where fn swap($self:ident, $physics:ident, $collider:ident) $apply_method:block ) => {
+        define_physics_command!($name($human_readable_name, Collider, $value_type) where fn swap($self, $physics) {
+            let $collider = &mut $physics.colliders[$self.handle];
+            $apply_method
+        });
+    };
+}
+
+macro_rules! define_joint_command {
+    ($name:ident($human_readable_name:expr, $value_type:ty) where fn swap($self:ident, $physics:ident, $joint:ident) $apply_method:block ) => {
+        define_physics_command!($name($human_readable_name, Joint, $value_type) where fn swap($self, $physics) {
+            let $joint = &mut $physics.joints[$self.handle];
+            $apply_method
+        });
+    };
+}
+
+macro_rules! define_joint_variant_command {
+    ($name:ident($human_readable_name:expr, $value_type:ty) where fn swap($self:ident, $physics:ident, $variant:ident, $var:ident) $apply_method:block ) => {
+        define_physics_command!($name($human_readable_name, Joint, $value_type) where fn swap($self, $physics) {
+            let joint = &mut $physics.joints[$self.handle];
+            if let JointParamsDesc::$variant($var) = &mut joint.params {
+                $apply_method
+            } else {
+                unreachable!();
+            }
+        });
+    };
+}
+
+macro_rules! define_collider_variant_command {
+    ($name:ident($human_readable_name:expr, $value_type:ty) where fn swap($self:ident, $physics:ident, $variant:ident, $var:ident) $apply_method:block ) => {
+        define_physics_command!($name($human_readable_name, Collider, $value_type) where fn swap($self, $physics) {
+            let collider = &mut $physics.colliders[$self.handle];
+            if let ColliderShapeDesc::$variant($var) = &mut collider.shape {
+                $apply_method
+            } else {
+                unreachable!();
+            }
+        });
+    };
+}
+
+macro_rules! define_emitter_command {
+    ($name:ident($human_readable_name:expr, $value_type:ty) where fn swap($self:ident, $emitter:ident) $apply_method:block ) => {
+        #[derive(Debug)]
+        pub struct $name {
+            handle: Handle<Node>,
+            value: $value_type,
+            index: usize
+        }
+
+        impl $name {
+            pub fn new(handle: Handle<Node>, index: usize, value: $value_type) -> Self {
+                Self { handle, index, value }
+            }
+
+            fn swap(&mut $self, graph: &mut Graph) {
+                let $emitter = &mut graph[$self.handle].as_particle_system_mut().emitters[$self.index];
+                $apply_method
+            }
+        }
+
+        impl<'a> Command<'a> for $name {
+            type Context = SceneContext<'a>;
+
+            fn name(&mut self, _context: &Self::Context) -> String {
+                $human_readable_name.to_owned()
+            }
+
+            fn execute(&mut self, context: &mut Self::Context) {
+                self.swap(&mut context.scene.graph);
+            }
+
+            fn revert(&mut self, context: &mut Self::Context) {
+                self.swap(&mut context.scene.graph);
+            }
+        }
+    };
+    ($name:ident($human_readable_name:expr, $value_type:ty) where fn swap($self:ident, editor_scene: $editor_scene:ident) $apply_method:block ) => {
+        // Emitter data that the engine has no place for, it is stored in editor's side tables
+        // keyed by emitter index.
+        #[derive(Debug)]
+        pub struct $name {
+            handle: Handle<Node>,
+            value: $value_type,
+            index: usize
+        }
+
+        impl $name {
+            pub fn new(handle: Handle<Node>, index: usize, value: $value_type) -> Self {
+                Self { handle, index, value }
+            }
+
+            fn swap(&mut $self, $editor_scene: &mut EditorScene) {
+                $apply_method
+            }
+        }
+
+        impl<'a> Command<'a> for $name {
+            type Context = SceneContext<'a>;
+
+            fn name(&mut self, _context: &Self::Context) -> String {
+                $human_readable_name.to_owned()
+            }
+
+            fn execute(&mut self, context: &mut Self::Context) {
+                self.swap(context.editor_scene);
+            }
+
+            fn revert(&mut self, context: &mut Self::Context) {
+                self.swap(context.editor_scene);
+            }
+        }
+    };
+}
+
+macro_rules! define_surface_command {
+    ($name:ident($human_readable_name:expr, $value_type:ty) where fn swap($self:ident, $surface:ident) $apply_method:block ) => {
+        #[derive(Debug)]
+        pub struct $name {
+            handle: Handle<Node>,
+            value: $value_type,
+            index: usize
+        }
+
+        impl $name {
+            pub fn new(handle: Handle<Node>, index: usize, value: $value_type) -> Self {
+                Self { handle, index, value }
+            }
+
+            fn swap(&mut $self, graph: &mut Graph) {
+                let $surface = &mut graph[$self.handle].as_mesh_mut().surfaces_mut()[$self.index];
+                $apply_method
+            }
+        }
+
+        impl<'a> Command<'a> for $name {
+            type Context = SceneContext<'a>;
+
+            fn name(&mut self, _context: &Self::Context) -> String {
+                $human_readable_name.to_owned()
+            }
+
+            fn execute(&mut self, context: &mut Self::Context) {
+                self.swap(&mut context.scene.graph);
+            }
+
+            fn revert(&mut self, context: &mut Self::Context) {
+                self.swap(&mut context.scene.graph);
+            }
+        }
+    };
+}
+
+macro_rules! define_animation_command {
+    ($name:ident($human_readable_name:expr, $value_type:ty) where fn swap($self:ident, $animation:ident) $apply_method:block ) => {
+        #[derive(Debug)]
+        pub struct $name {
+            handle: Handle<Animation>,
+            value: $value_type,
+        }
+
+        impl $name {
+            pub fn new(handle: Handle<Animation>, value: $value_type) -> Self {
+                Self { handle, value }
+            }
+
+            fn swap(&mut $self, animations: &mut AnimationContainer) {
+                let $animation = &mut animations[$self.handle];
+                $apply_method
+            }
+        }
+
+        impl<'a> Command<'a> for $name {
+            type Context = SceneContext<'a>;
+
+            fn name(&mut self, _context: &Self::Context) -> String {
+                $human_readable_name.to_owned()
+            }
+
+            fn execute(&mut self, context: &mut Self::Context) {
+                self.swap(&mut context.scene.animations);
+            }
+
+            fn revert(&mut self, context: &mut Self::Context) {
+                self.swap(&mut context.scene.animations);
+            }
+        }
+    };
+}
+
+macro_rules! define_emitter_variant_command {
+    ($name:ident($human_readable_name:expr, $value_type:ty) where fn swap($self:ident, $emitter:ident, $variant:ident, $var:ident) $apply_method:block ) => {
+        define_emitter_command!($name($human_readable_name, $value_type) where fn swap($self, $emitter) {
+            if let Emitter::$variant($var) = $emitter {
+                $apply_method
+            } else {
+                unreachable!()
+            }
+        });
+    };
+}
+
+macro_rules! get_set_swap {
+    ($self:ident, $host:expr, $get:ident, $set:ident) => {
+        match $host {
+            host => {
+                let old = host.$get();
+                host.$set($self.value.clone());
+                $self.value = old;
+            }
+        }
+    };
+}
+
+define_node_command!(SetLightScatterCommand("Set Light Scatter", Vector3<f32>) where fn swap(self, node) {
+    get_set_swap!(self, node.as_light_mut(), scatter, set_scatter)
+});
+
+define_node_command!(SetLightScatterEnabledCommand("Set Light Scatter Enabled", bool) where fn swap(self, node) {
+    get_set_swap!(self, node.as_light_mut(), is_scatter_enabled, enable_scatter)
+});
+
+define_node_command!(SetLightCastShadowsCommand("Set Light Cast Shadows", bool) where fn swap(self, node) {
+    get_set_swap!(self, node.as_light_mut(), is_cast_shadows, set_cast_shadows)
+});
+
+define_node_command!(SetPointLightRadiusCommand("Set Point Light Radius", f32) where fn swap(self, node) {
+    get_set_swap!(self, node.as_light_mut().as_point_mut(), radius, set_radius)
+});
+
+define_node_command!(SetSpotLightHotspotCommand("Set Spot Light Hotspot", f32) where fn swap(self, node) {
+    get_set_swap!(self, node.as_light_mut().as_spot_mut(), hotspot_cone_angle, set_hotspot_cone_angle)
+});
+
+define_node_command!(SetSpotLightFalloffAngleDeltaCommand("Set Spot Light Falloff Angle Delta", f32) where fn swap(self, node) {
+    get_set_swap!(self, node.as_light_mut().as_spot_mut(), falloff_angle_delta, set_falloff_angle_delta)
+});
+
+define_node_command!(SetSpotLightDistanceCommand("Set Spot Light Distance", f32) where fn swap(self, node) {
+    get_set_swap!(self, node.as_light_mut().as_spot_mut(), distance, set_distance);
+});
+
+define_node_command!(SetSpotLightShadowBiasCommand("Set Spot Light Shadow Bias", f32) where fn swap(self, node) {
+    get_set_swap!(self, node.as_light_mut().as_spot_mut(), shadow_bias, set_shadow_bias);
+});
+
+// Cookie is a texture projected by the light, it is a resource, so the texture is saved
+// as a reference.
+define_node_command!(SetSpotLightCookieTextureCommand("Set Spot Light Cookie Texture", Option<Texture>) where fn swap(self, node) {
+    get_set_swap!(self, node.as_light_mut().as_spot_mut(), cookie_texture, set_cookie_texture);
+});
+
+define_node_command!(SetPointLightShadowBiasCommand("Set Point Light Shadow Bias", f32) where fn swap(self, node) {
+    get_set_swap!(self, node.as_light_mut().as_point_mut(), shadow_bias, set_shadow_bias);
+});
+
+define_node_command!(SetLightColorCommand("Set Light Color", Color) where fn swap(self, node) {
+    get_set_swap!(self, node.as_light_mut(), color, set_color)
+});
+
+define_node_command!(SetNameCommand("Set Name", String) where fn swap(self, node) {
+    get_set_swap!(self, node, name_owned, set_name);
+});
+
+define_node_command!(SetLodGroupCommand("Set Lod Group", Option<LodGroup>) where fn swap(self, node) {
+    get_set_swap!(self, node, take_lod_group, set_lod_group);
+});
+
+/// Creates command that sets lod group to given node, where each of `children` (ordered from
+/// most to least detailed) forms own level. Levels evenly divide normalized 0..1 range.
+pub fn make_auto_lod_group_command(
+    handle: Handle<Node>,
+    children: &[Handle<Node>],
+) -> SceneCommand {
+    let step = 1.0 / children.len().max(1) as f32;
+
+    let mut lod_group = LodGroup::default();
+    for (i, &child) in children.iter().enumerate() {
+        let mut level = LevelOfDetail::default();
+        // Begin is clamped by end, so end is set first. Last level ends exactly at 1.0
+        // regardless of rounding errors.
+        level.set_end(if i + 1 == children.len() {
+            1.0
+        } else {
+            (i + 1) as f32 * step
+        });
+        level.set_begin(i as f32 * step);
+        level.objects.push(child);
+        lod_group.levels.push(level);
+    }
+
+    SceneCommand::SetLodGroup(SetLodGroupCommand::new(handle, Some(lod_group)))
+}
+
+define_node_command!(SetPhysicsBindingCommand("Set Physics Binding", PhysicsBinding) where fn swap(self, node) {
+    get_set_swap!(self, node, physics_binding, set_physics_binding);
+});
+
+define_node_command!(SetTagCommand("Set Tag", String) where fn swap(self, node) {
+    get_set_swap!(self, node, tag_owned, set_tag);
+});
+
+define_node_command!(SetVisibleCommand("Set Visible", bool) where fn swap(self, node) {
+    get_set_swap!(self, node, visibility, set_visibility)
+});
+
+define_node_command!(SetFovCommand("Set Fov", f32) where fn swap(self, node) {
+    get_set_swap!(self, node.as_camera_mut(), fov, set_fov);
+});
+
+define_node_command!(SetZNearCommand("Set Camera Z Near", f32) where fn swap(self, node) {
+    get_set_swap!(self, node.as_camera_mut(), z_near, set_z_near);
+});
+
+define_node_command!(SetZFarCommand("Set Camera Z Far", f32) where fn swap(self, node) {
+    get_set_swap!(self, node.as_camera_mut(), z_far, set_z_far);
+});
+
+define_node_command!(SetCameraExposureCommand("Set Camera Exposure", f32) where fn swap(self, node) {
+    get_set_swap!(self, node.as_camera_mut(), exposure, set_exposure);
+});
+
+define_node_command!(SetCameraColorGradingLutCommand("Set Camera Color Grading Lut", Option<Texture>) where fn swap(self, node) {
+    get_set_swap!(self, node.as_camera_mut(), color_grading_lut, set_color_grading_lut);
+});
+
+// Textures of sky box are resources, so they're saved as references.
+define_node_command!(SetCameraSkyBoxCommand("Set Camera Sky Box", Option<SkyBox>) where fn swap(self, node) {
+    self.value = node.as_camera_mut().replace_skybox(self.value.take());
+});
+
+define_node_command!(SetParticleSystemAccelerationCommand("Set Particle System Acceleration", Vector3<f32>) where fn swap(self, node) {
+    get_set_swap!(self, node.as_particle_system_mut(), acceleration, set_acceleration);
+});
+
+define_node_command!(SetSpriteSizeCommand("Set Sprite Size", f32) where fn swap(self, node) {
+    get_set_swap!(self, node.as_sprite_mut(), size, set_size);
+});
+
+define_node_command!(SetSpriteRotationCommand("Set Sprite Rotation", f32) where fn swap(self, node) {
+    get_set_swap!(self, node.as_sprite_mut(), rotation, set_rotation);
+});
+
+define_node_command!(SetSpriteColorCommand("Set Sprite Color", Color) where fn swap(self, node) {
+    get_set_swap!(self, node.as_sprite_mut(), color, set_color);
+});
+
+define_surface_command!(SetSurfaceDiffuseTextureCommand("Set Surface Diffuse Texture", Option<Texture>) where fn swap(self, surface) {
+    get_set_swap!(self, surface, diffuse_texture, set_diffuse_texture)
+});
+
+define_surface_command!(SetSurfaceNormalTextureCommand("Set Surface Normal Texture", Option<Texture>) where fn swap(self, surface) {
+    get_set_swap!(self, surface, normal_texture, set_normal_texture)
+});
+
+define_node_command!(SetSpriteTextureCommand("Set Sprite Texture", Option<Texture>) where fn swap(self, node) {
+    get_set_swap!(self, node.as_sprite_mut(), texture, set_texture);
+});
 
-    fn finalize(&mut self, context: &mut Self::Context) {
-        if let Some(sub_graph) = self.sub_graph.take() {
-            context.scene.graph.forget_sub_graph(sub_graph)
-        }
-        for (ticket, _) in self.animations_container.drain(..) {
-            context.scene.animations.forget_ticket(ticket);
-        }
-    }
-}
+define_node_command!(SetParticleSystemTextureCommand("Set Particle System Texture", Option<Texture>) where fn swap(self, node) {
+    get_set_swap!(self, node.as_particle_system_mut(), texture, set_texture);
+});
 
+/// Enables or disables a particle system. Disabling a particle system that was paused by
+/// preview pause also takes it out of the paused list, otherwise resume (or save) would turn it
+/// back on; revert puts it back in the list.
 #[derive(Debug)]
-pub struct DeleteSubGraphCommand {
-    sub_graph_root: Handle<Node>,
-    sub_graph: Option<SubGraph>,
-    parent: Handle<Node>,
+pub struct SetParticleSystemEnabledCommand {
+    handle: Handle<Node>,
+    value: bool,
+    was_paused: bool,
 }
 
-impl DeleteSubGraphCommand {
-    pub fn new(sub_graph_root: Handle<Node>) -> Self {
+impl SetParticleSystemEnabledCommand {
+    pub fn new(handle: Handle<Node>, value: bool) -> Self {
         Self {
-            sub_graph_root,
-            sub_graph: None,
-            parent: Handle::NONE,
+            handle,
+            value,
+            was_paused: false,
+        }
+    }
+
+    fn swap(&mut self, graph: &mut Graph) {
+        let particle_system = graph[self.handle].as_particle_system_mut();
+        let old = particle_system.is_enabled();
+        particle_system.set_enabled(self.value);
+        if !self.value {
+            particle_system.clear_particles();
         }
+        self.value = old;
     }
 }
 
-impl<'a> Command<'a> for DeleteSubGraphCommand {
+impl<'a> Command<'a> for SetParticleSystemEnabledCommand {
     type Context = SceneContext<'a>;
 
     fn name(&mut self, _context: &Self::Context) -> String {
-        "Delete Sub Graph".to_owned()
+        "Set Particle System Enabled".to_owned()
     }
 
     fn execute(&mut self, context: &mut Self::Context) {
-        self.parent = context.scene.graph[self.sub_graph_root].parent();
-        self.sub_graph = Some(
-            context
-                .scene
-                .graph
-                .take_reserve_sub_graph(self.sub_graph_root),
-        );
+        let paused = &mut context.editor_scene.paused_particle_systems;
+        self.was_paused = false;
+        if !self.value {
+            if let Some(position) = paused.iter().position(|&h| h == self.handle) {
+                paused.remove(position);
+                self.was_paused = true;
+            }
+        }
+        self.swap(&mut context.scene.graph);
     }
 
     fn revert(&mut self, context: &mut Self::Context) {
-        context
-            .scene
-            .graph
-            .put_sub_graph_back(self.sub_graph.take().unwrap());
-        context
-            .scene
-            .graph
-            .link_nodes(self.sub_graph_root, self.parent);
-    }
-
-    fn finalize(&mut self, context: &mut Self::Context) {
-        if let Some(sub_graph) = self.sub_graph.take() {
-            context.scene.graph.forget_sub_graph(sub_graph)
+        self.swap(&mut context.scene.graph);
+        if self.was_paused {
+            // Paused system is already disabled, so it only has to be put back in the list.
+            context
+                .editor_scene
+                .paused_particle_systems
+                .push(self.handle);
         }
     }
 }
 
+define_node_command!(SetParticleSystemSoftBoundarySharpnessCommand("Set Particle System Soft Boundary Sharpness", f32) where fn swap(self, node) {
+    get_set_swap!(self, node.as_particle_system_mut(), soft_boundary_sharpness_factor, set_soft_boundary_sharpness_factor);
+});
+
+define_node_command!(SetMeshCastShadowsCommand("Set Mesh Cast Shadows", bool) where fn swap(self, node) {
+    get_set_swap!(self, node.as_mesh_mut(), cast_shadows, set_cast_shadows);
+});
+
+define_node_command!(SetMeshRenderPathCommand("Set Mesh Render Path", RenderPath) where fn swap(self, node) {
+    get_set_swap!(self, node.as_mesh_mut(), render_path, set_render_path);
+});
+
+define_node_command!(SetMeshDecalLayerIndexCommand("Set Mesh Decal Layer Index", u8) where fn swap(self, node) {
+    get_set_swap!(self, node.as_mesh_mut(), decal_layer_index, set_decal_layer_index);
+});
+
+/// Overrides render path of a single surface of a mesh (`None` removes the override). Render
+/// path is a property of whole mesh in the engine, so overrides live in the editor only and
+/// are baked into the scene on save: each overridden surface is moved out of its mesh into a
+/// child mesh with desired render path, which has identity local transform and therefore
+/// renders at the same place. This means that:
+/// - saved scene has extra child mesh nodes, they will show up as ordinary nodes when the scene
+///   is loaded again, overrides are stored as a side table against moved surfaces, so they are
+///   still shown after load, but removing them will not move the surfaces back;
+/// - surfaces that override render path to the one the mesh already has are left in place;
+/// - surfaces with the same render path are moved into a single child mesh;
+/// - overrides of surfaces that do not exist anymore are ignored;
+/// - if every surface is moved out, the mesh stays in the scene without surfaces, so its
+///   children and physics binding are preserved.
 #[derive(Debug)]
-pub struct DeleteBodyCommand {
-    handle: Handle<RigidBody>,
-    ticket: Option<Ticket<RigidBody>>,
-    body: Option<RigidBody>,
+pub struct SetSurfaceRenderPathOverrideCommand {
     node: Handle<Node>,
+    surface: usize,
+    value: Option<RenderPath>,
 }
 
-impl DeleteBodyCommand {
-    pub fn new(handle: Handle<RigidBody>) -> Self {
+impl SetSurfaceRenderPathOverrideCommand {
+    pub fn new(node: Handle<Node>, surface: usize, value: Option<RenderPath>) -> Self {
         Self {
-            handle,
-            ticket: None,
-            body: None,
-            node: Handle::NONE,
+            node,
+            surface,
+            value,
         }
     }
+
+    fn swap(&mut self, editor_scene: &mut EditorScene) {
+        let key = (self.node, self.surface);
+        self.value = match self.value.take() {
+            Some(render_path) => editor_scene.surface_render_paths.insert(key, render_path),
+            None => editor_scene.surface_render_paths.remove(&key),
+        };
+    }
 }
 
-impl<'a> Command<'a> for DeleteBodyCommand {
+impl<'a> Command<'a> for SetSurfaceRenderPathOverrideCommand {
     type Context = SceneContext<'a>;
 
     fn name(&mut self, _context: &Self::Context) -> String {
-        "Delete Body".to_owned()
+        "Set Surface Render Path Override".to_owned()
     }
 
     fn execute(&mut self, context: &mut Self::Context) {
-        let (ticket, node) = context
-            .editor_scene
-            .physics
-            .bodies
-            .take_reserve(self.handle);
-        self.body = Some(node);
-        self.ticket = Some(ticket);
-        self.node = context.editor_scene.physics.unbind_by_body(self.handle);
+        self.swap(context.editor_scene);
     }
 
     fn revert(&mut self, context: &mut Self::Context) {
-        self.handle = context
-            .editor_scene
-            .physics
-            .bodies
-            .put_back(self.ticket.take().unwrap(), self.body.take().unwrap());
-        context
-            .editor_scene
-            .physics
-            .binder
-            .insert(self.node, self.handle);
-    }
-
-    fn finalize(&mut self, context: &mut Self::Context) {
-        if let Some(ticket) = self.ticket.take() {
-            context.editor_scene.physics.bodies.forget_ticket(ticket)
-        }
+        self.swap(context.editor_scene);
     }
 }
 
-#[derive(Debug)]
-pub struct DeleteColliderCommand {
-    handle: Handle<Collider>,
-    ticket: Option<Ticket<Collider>>,
-    collider: Option<Collider>,
-    body: Handle<RigidBody>,
-}
+// Sets UV sub-rectangle of particle system texture used by an emitter (`None` means whole
+// texture). Regions follow emitters when they're moved or deleted.
+define_emitter_command!(SetEmitterTextureRegionCommand("Set Emitter Texture Region", Option<Rect<f32>>) where fn swap(self, editor_scene: editor_scene) {
+    let key = (self.handle, self.index);
+    self.value = match self.value.take() {
+        Some(rect) => editor_scene.emitter_texture_regions.insert(key, rect),
+        None => editor_scene.emitter_texture_regions.remove(&key),
+    };
+});
 
-impl DeleteColliderCommand {
-    pub fn new(handle: Handle<Collider>) -> Self {
-        Self {
-            handle,
-            ticket: None,
-            collider: None,
-            body: Handle::NONE,
+// Sets acceleration used by particles of an emitter instead of the acceleration of particle
+// system (`None` means that system-wide acceleration is used). Override takes precedence over
+// the system-wide acceleration, see `EditorScene::split_overridden_emitters`.
+define_emitter_command!(SetEmitterAccelerationOverrideCommand("Set Emitter Acceleration Override", Option<Vector3<f32>>) where fn swap(self, editor_scene: editor_scene) {
+    let key = (self.handle, self.index);
+    self.value = match self.value.take() {
+        Some(acceleration) => editor_scene
+            .emitter_acceleration_overrides
+            .insert(key, acceleration),
+        None => editor_scene.emitter_acceleration_overrides.remove(&key),
+    };
+});
+
+/// Sets weight of a blend shape of a mesh, weight is clamped to `[0; 1]` range. Meshes of the
+/// engine have no blend shapes, so the weight is not applied to the mesh and has no visible
+/// effect in the editor: it is only stored in `EditorScene::blend_shape_weights` and saved
+/// with the scene as a separate table (see [`BlendShapeWeight`]), which could be applied by
+/// a game.
+#[derive(Debug)]
+pub struct SetMeshBlendShapeWeightCommand {
+    mesh: Handle<Node>,
+    shape_index: usize,
+    value: Option<f32>,
+}
+
+impl SetMeshBlendShapeWeightCommand {
+    /// Returns `None` if the node is not a mesh or the weight is not a number. Meshes of the
+    /// engine have no blend shapes, so the editor does not know how many shapes a mesh has and
+    /// can't check the shape index.
+    pub fn new(graph: &Graph, mesh: Handle<Node>, shape_index: usize, weight: f32) -> Option<Self> {
+        if !graph.is_valid_handle(mesh) || !matches!(graph[mesh], Node::Mesh(_)) || weight.is_nan()
+        {
+            return None;
         }
+
+        Some(Self {
+            mesh,
+            shape_index,
+            value: Some(weight.max(0.0).min(1.0)),
+        })
+    }
+
+    fn swap(&mut self, editor_scene: &mut EditorScene) {
+        let key = (self.mesh, self.shape_index);
+        self.value = match self.value.take() {
+            Some(weight) => editor_scene.blend_shape_weights.insert(key, weight),
+            None => editor_scene.blend_shape_weights.remove(&key),
+        };
     }
 }
 
-impl<'a> Command<'a> for DeleteColliderCommand {
+impl<'a> Command<'a> for SetMeshBlendShapeWeightCommand {
     type Context = SceneContext<'a>;
 
     fn name(&mut self, _context: &Self::Context) -> String {
-        "Delete Collider".to_owned()
+        "Set Mesh Blend Shape Weight".to_owned()
     }
 
     fn execute(&mut self, context: &mut Self::Context) {
-        let (ticket, collider) = context
-            .editor_scene
-            .physics
-            .colliders
-            .take_reserve(self.handle);
-        self.body = collider.parent.into();
-        self.collider = Some(collider);
-        self.ticket = Some(ticket);
-
-        let body = &mut context.editor_scene.physics.bodies[self.body];
-        body.colliders.remove(
-            body.colliders
-                .iter()
-                .position(|&c| c == ErasedHandle::from(self.handle))
-                .unwrap(),
-        );
+        self.swap(context.editor_scene);
     }
 
     fn revert(&mut self, context: &mut Self::Context) {
-        self.handle = context
-            .editor_scene
-            .physics
-            .colliders
-            .put_back(self.ticket.take().unwrap(), self.collider.take().unwrap());
-
-        let body = &mut context.editor_scene.physics.bodies[self.body];
-        body.colliders.push(self.handle.into());
-    }
-
-    fn finalize(&mut self, context: &mut Self::Context) {
-        if let Some(ticket) = self.ticket.take() {
-            context.editor_scene.physics.colliders.forget_ticket(ticket)
-        }
+        self.swap(context.editor_scene);
     }
 }
 
 #[derive(Debug)]
-pub struct AddLodGroupLevelCommand {
-    handle: Handle<Node>,
-    level: LevelOfDetail,
+pub struct SetNodeStaticCommand {
+    node: Handle<Node>,
+    value: bool,
 }
 
-impl AddLodGroupLevelCommand {
-    pub fn new(handle: Handle<Node>, level: LevelOfDetail) -> Self {
-        Self { handle, level }
+impl SetNodeStaticCommand {
+    pub fn new(node: Handle<Node>, value: bool) -> Self {
+        Self { node, value }
+    }
+
+    fn swap(&mut self, editor_scene: &mut EditorScene) {
+        self.value = editor_scene
+            .static_nodes
+            .insert(self.node, self.value)
+            .unwrap_or_default();
     }
 }
 
-impl<'a> Command<'a> for AddLodGroupLevelCommand {
+impl<'a> Command<'a> for SetNodeStaticCommand {
     type Context = SceneContext<'a>;
 
     fn name(&mut self, _context: &Self::Context) -> String {
-        "Add Lod Group Level".to_owned()
+        "Set Node Static".to_owned()
     }
 
     fn execute(&mut self, context: &mut Self::Context) {
-        context.scene.graph[self.handle]
-            .lod_group_mut()
-            .unwrap()
-            .levels
-            .push(self.level.clone());
+        self.swap(context.editor_scene);
     }
 
     fn revert(&mut self, context: &mut Self::Context) {
-        context.scene.graph[self.handle]
-            .lod_group_mut()
-            .unwrap()
-            .levels
-            .pop();
+        self.swap(context.editor_scene);
     }
 }
 
+/// Toggles wireframe overlay of a node, it affects only how the node is shown in the editor.
 #[derive(Debug)]
-pub struct RemoveLodGroupLevelCommand {
-    handle: Handle<Node>,
-    level: Option<LevelOfDetail>,
-    index: usize,
+pub struct SetNodeWireframeCommand {
+    node: Handle<Node>,
+    value: bool,
 }
 
-impl RemoveLodGroupLevelCommand {
-    pub fn new(handle: Handle<Node>, index: usize) -> Self {
-        Self {
-            handle,
-            level: None,
-            index,
+impl SetNodeWireframeCommand {
+    pub fn new(node: Handle<Node>, value: bool) -> Self {
+        Self { node, value }
+    }
+
+    fn swap(&mut self, editor_scene: &mut EditorScene) {
+        let old = editor_scene.wireframe_nodes.contains(&self.node);
+        if self.value {
+            editor_scene.wireframe_nodes.insert(self.node);
+        } else {
+            editor_scene.wireframe_nodes.remove(&self.node);
         }
+        self.value = old;
     }
 }
 
-impl<'a> Command<'a> for RemoveLodGroupLevelCommand {
+impl<'a> Command<'a> for SetNodeWireframeCommand {
     type Context = SceneContext<'a>;
 
     fn name(&mut self, _context: &Self::Context) -> String {
-        "Remove Lod Group Level".to_owned()
+        "Set Node Wireframe".to_owned()
     }
 
     fn execute(&mut self, context: &mut Self::Context) {
-        self.level = Some(
-            context.scene.graph[self.handle]
-                .lod_group_mut()
-                .unwrap()
-                .levels
-                .remove(self.index),
-        );
+        self.swap(context.editor_scene);
     }
 
     fn revert(&mut self, context: &mut Self::Context) {
-        let group = context.scene.graph[self.handle].lod_group_mut().unwrap();
-        let level = self.level.take().unwrap();
-        if group.levels.is_empty() {
-            group.levels.push(level);
-        } else {
-            group.levels.insert(self.index, level)
-        }
+        self.swap(context.editor_scene);
     }
 }
 
+define_body_command!(SetBodyMassCommand("Set Body Mass", f32) where fn swap(self, physics, body) {
+    std::mem::swap(&mut body.mass, &mut self.value);
+});
+
+// Mask of locked rotation axes: X, Y, Z.
+define_body_command!(SetBodyLockedRotationCommand("Set Body Locked Rotation", [bool; 3]) where fn swap(self, physics, body) {
+    let old = [body.x_rotation_locked, body.y_rotation_locked, body.z_rotation_locked];
+    body.x_rotation_locked = self.value[0];
+    body.y_rotation_locked = self.value[1];
+    body.z_rotation_locked = self.value[2];
+    self.value = old;
+});
+
+// Engine allows to lock translation only on all axes at once, so there is single flag.
+define_body_command!(SetBodyLockedTranslationCommand("Set Body Locked Translation", bool) where fn swap(self, physics, body) {
+    std::mem::swap(&mut body.translation_locked, &mut self.value);
+});
+
+// Initial velocities matter only at simulation start, physics is not simulated in editor.
+define_body_command!(SetBodyLinearVelocityCommand("Set Body Linear Velocity", Vector3<f32>) where fn swap(self, physics, body) {
+    std::mem::swap(&mut body.linvel, &mut self.value);
+});
+
+define_body_command!(SetBodyAngularVelocityCommand("Set Body Angular Velocity", Vector3<f32>) where fn swap(self, physics, body) {
+    std::mem::swap(&mut body.angvel, &mut self.value);
+});
+
+define_collider_command!(SetColliderFrictionCommand("Set Collider Friction", f32) where fn swap(self, physics, collider) {
+    std::mem::swap(&mut collider.friction, &mut self.value);
+});
+
+define_collider_command!(SetColliderRestitutionCommand("Set Collider Restitution", f32) where fn swap(self, physics, collider) {
+    std::mem::swap(&mut collider.restitution, &mut self.value);
+});
+
+define_collider_command!(SetColliderPositionCommand("Set Collider Position", Vector3<f32>) where fn swap(self, physics, collider) {
+    std::mem::swap(&mut collider.translation, &mut self.value);
+});
+
+define_collider_command!(SetColliderRotationCommand("Set Collider Rotation", UnitQuaternion<f32>) where fn swap(self, physics, collider) {
+    std::mem::swap(&mut collider.rotation, &mut self.value);
+});
+
+/// Sensors have no mass, so if mass of parent body is calculated from its colliders, it is
+/// recalculated when collider becomes sensor (or vice versa).
 #[derive(Debug)]
-pub struct AddLodObjectCommand {
-    handle: Handle<Node>,
-    lod_index: usize,
-    object: Handle<Node>,
-    object_index: usize,
+pub struct SetColliderIsSensorCommand {
+    handle: Handle<Collider>,
+    value: bool,
+    old_body_mass: Option<f32>,
 }
 
-impl AddLodObjectCommand {
-    pub fn new(handle: Handle<Node>, lod_index: usize, object: Handle<Node>) -> Self {
+impl SetColliderIsSensorCommand {
+    pub fn new(handle: Handle<Collider>, value: bool) -> Self {
         Self {
             handle,
-            lod_index,
-            object,
-            object_index: 0,
+            value,
+            old_body_mass: None,
+        }
+    }
+
+    fn swap(&mut self, physics: &mut Physics) {
+        std::mem::swap(
+            &mut physics.colliders[self.handle].is_sensor,
+            &mut self.value,
+        );
+    }
+
+    fn parent_body(&self, physics: &Physics) -> Option<Handle<RigidBody>> {
+        let body: Handle<RigidBody> = physics.colliders[self.handle].parent.into();
+        if physics.bodies.is_valid_handle(body) {
+            Some(body)
+        } else {
+            None
         }
     }
 }
 
-impl<'a> Command<'a> for AddLodObjectCommand {
+impl<'a> Command<'a> for SetColliderIsSensorCommand {
     type Context = SceneContext<'a>;
 
     fn name(&mut self, _context: &Self::Context) -> String {
-        "Add Lod Object".to_owned()
+        "Set Collider Is Sensor".to_owned()
     }
 
     fn execute(&mut self, context: &mut Self::Context) {
-        let objects = &mut context.scene.graph[self.handle]
-            .lod_group_mut()
-            .unwrap()
-            .levels[self.lod_index]
-            .objects;
-        self.object_index = objects.len();
-        objects.push(self.object);
+        let physics = &mut context.editor_scene.physics;
+        match self.parent_body(physics) {
+            Some(body) if physics.is_body_mass_calculated(body) => {
+                self.swap(physics);
+                self.old_body_mass = Some(physics.recompute_body_mass(body));
+            }
+            _ => self.swap(physics),
+        }
     }
 
     fn revert(&mut self, context: &mut Self::Context) {
-        context.scene.graph[self.handle]
-            .lod_group_mut()
-            .unwrap()
-            .levels[self.lod_index]
-            .objects
-            .remove(self.object_index);
+        let physics = &mut context.editor_scene.physics;
+        self.swap(physics);
+        if let Some(mass) = self.old_body_mass.take() {
+            if let Some(body) = self.parent_body(physics) {
+                physics.bodies[body].mass = mass;
+            }
+        }
     }
 }
 
-#[derive(Debug)]
-pub struct RemoveLodObjectCommand {
-    handle: Handle<Node>,
-    lod_index: usize,
-    object: Handle<Node>,
-    object_index: usize,
-}
+define_collider_command!(SetColliderShapeCommand("Set Collider Shape", ColliderShapeDesc) where fn swap(self, physics, collider) {
+    std::mem::swap(&mut collider.shape, &mut self.value);
+});
 
-impl RemoveLodObjectCommand {
-    pub fn new(handle: Handle<Node>, lod_index: usize, object_index: usize) -> Self {
-        Self {
-            handle,
-            lod_index,
-            object: Default::default(),
-            object_index,
+define_collider_command!(SetColliderCollisionGroupsCommand("Set Collider Collision Groups", u32) where fn swap(self, physics, collider) {
+    std::mem::swap(&mut collider.collision_groups, &mut self.value);
+});
+
+define_collider_command!(SetColliderSolverGroupsCommand("Set Collider Solver Groups", u32) where fn swap(self, physics, collider) {
+    std::mem::swap(&mut collider.solver_groups, &mut self.value);
+});
+
+// Named layer commands take layer index, it could be obtained by name from
+// `EditorScene::collision_layers`.
+#[derive(Debug)]
+pub struct SetColliderMembershipCommand {
+    handle: Handle<Collider>,
+    layer: u32,
+    enabled: bool,
+}
+
+impl SetColliderMembershipCommand {
+    /// Returns `None` if layer index is out of range, see `MAX_COLLISION_LAYERS`.
+    pub fn new(handle: Handle<Collider>, layer: u32, enabled: bool) -> Option<Self> {
+        if layer < MAX_COLLISION_LAYERS {
+            Some(Self {
+                handle,
+                layer,
+                enabled,
+            })
+        } else {
+            None
         }
     }
+
+    fn swap(&mut self, physics: &mut Physics) {
+        let collider = &mut physics.colliders[self.handle];
+        self.enabled =
+            set_collision_membership(&mut collider.collision_groups, self.layer, self.enabled);
+    }
 }
 
-impl<'a> Command<'a> for RemoveLodObjectCommand {
+impl<'a> Command<'a> for SetColliderMembershipCommand {
     type Context = SceneContext<'a>;
 
     fn name(&mut self, _context: &Self::Context) -> String {
-        "Remove Lod Object".to_owned()
+        "Set Collider Membership".to_owned()
     }
 
     fn execute(&mut self, context: &mut Self::Context) {
-        self.object = context.scene.graph[self.handle]
-            .lod_group_mut()
-            .unwrap()
-            .levels[self.lod_index]
-            .objects
-            .remove(self.object_index);
+        self.swap(&mut context.editor_scene.physics);
     }
 
     fn revert(&mut self, context: &mut Self::Context) {
-        let objects = &mut context.scene.graph[self.handle]
-            .lod_group_mut()
-            .unwrap()
-            .levels[self.lod_index]
-            .objects;
-        if objects.is_empty() {
-            objects.push(self.object);
-        } else {
-            objects.insert(self.object_index, self.object);
-        }
+        self.swap(&mut context.editor_scene.physics);
     }
 }
 
 #[derive(Debug)]
-pub struct ChangeLodRangeBeginCommand {
-    handle: Handle<Node>,
-    lod_index: usize,
-    new_value: f32,
-}
-
-impl ChangeLodRangeBeginCommand {
-    pub fn new(handle: Handle<Node>, lod_index: usize, new_value: f32) -> Self {
-        Self {
-            handle,
-            lod_index,
-            new_value,
+pub struct SetColliderFilterCommand {
+    handle: Handle<Collider>,
+    layer: u32,
+    enabled: bool,
+}
+
+impl SetColliderFilterCommand {
+    /// Returns `None` if layer index is out of range, see `MAX_COLLISION_LAYERS`.
+    pub fn new(handle: Handle<Collider>, layer: u32, enabled: bool) -> Option<Self> {
+        if layer < MAX_COLLISION_LAYERS {
+            Some(Self {
+                handle,
+                layer,
+                enabled,
+            })
+        } else {
+            None
         }
     }
 
-    fn swap(&mut self, context: &mut SceneContext) {
-        let level = &mut context.scene.graph[self.handle]
-            .lod_group_mut()
-            .unwrap()
-            .levels[self.lod_index];
-        let old = level.begin();
-        level.set_begin(self.new_value);
-        self.new_value = old;
+    fn swap(&mut self, physics: &mut Physics) {
+        let collider = &mut physics.colliders[self.handle];
+        self.enabled =
+            set_collision_filter(&mut collider.collision_groups, self.layer, self.enabled);
     }
 }
 
-impl<'a> Command<'a> for ChangeLodRangeBeginCommand {
+impl<'a> Command<'a> for SetColliderFilterCommand {
     type Context = SceneContext<'a>;
 
     fn name(&mut self, _context: &Self::Context) -> String {
-        "Change Lod Range Begin".to_owned()
+        "Set Collider Filter".to_owned()
     }
 
     fn execute(&mut self, context: &mut Self::Context) {
-        self.swap(context);
+        self.swap(&mut context.editor_scene.physics);
     }
 
     fn revert(&mut self, context: &mut Self::Context) {
-        self.swap(context);
+        self.swap(&mut context.editor_scene.physics);
     }
 }
 
+define_collider_variant_command!(SetCylinderHalfHeightCommand("Set Cylinder Half Height", f32) where fn swap(self, physics, Cylinder, cylinder) {
+    std::mem::swap(&mut cylinder.half_height, &mut self.value);
+});
+
+define_collider_variant_command!(SetCylinderRadiusCommand("Set Cylinder Radius", f32) where fn swap(self, physics, Cylinder, cylinder) {
+    std::mem::swap(&mut cylinder.radius, &mut self.value);
+});
+
+define_collider_variant_command!(SetConeHalfHeightCommand("Set Cone Half Height", f32) where fn swap(self, physics, Cone, cone) {
+    std::mem::swap(&mut cone.half_height, &mut self.value);
+});
+
+define_collider_variant_command!(SetConeRadiusCommand("Set Cone Radius", f32) where fn swap(self, physics, Cone, cone) {
+    std::mem::swap(&mut cone.radius, &mut self.value);
+});
+
+define_collider_variant_command!(SetCuboidHalfExtentsCommand("Set Cuboid Half Extents", Vector3<f32>) where fn swap(self, physics, Cuboid, cuboid) {
+    std::mem::swap(&mut cuboid.half_extents, &mut self.value);
+});
+
+define_collider_variant_command!(SetCapsuleRadiusCommand("Set Capsule Radius", f32) where fn swap(self, physics, Capsule, capsule) {
+    std::mem::swap(&mut capsule.radius, &mut self.value);
+});
+
+define_collider_variant_command!(SetCapsuleBeginCommand("Set Capsule Begin", Vector3<f32>) where fn swap(self, physics, Capsule, capsule) {
+    std::mem::swap(&mut capsule.begin, &mut self.value);
+});
+
+define_collider_variant_command!(SetCapsuleEndCommand("Set Capsule End", Vector3<f32>) where fn swap(self, physics, Capsule, capsule) {
+    std::mem::swap(&mut capsule.end, &mut self.value);
+});
+
+define_collider_variant_command!(SetBallRadiusCommand("Set Ball Radius", f32) where fn swap(self, physics, Ball, ball) {
+    std::mem::swap(&mut ball.radius, &mut self.value);
+});
+
+define_joint_variant_command!(SetBallJointAnchor1Command("Set Ball Joint Anchor 1", Vector3<f32>) where fn swap(self, physics, BallJoint, ball) {
+    std::mem::swap(&mut ball.local_anchor1, &mut self.value);
+});
+
+define_joint_variant_command!(SetBallJointAnchor2Command("Set Ball Joint Anchor 2", Vector3<f32>) where fn swap(self, physics, BallJoint, ball) {
+    std::mem::swap(&mut ball.local_anchor2, &mut self.value);
+});
+
+define_joint_variant_command!(SetFixedJointAnchor1TranslationCommand("Set Fixed Joint Anchor 1 Translation", Vector3<f32>) where fn swap(self, physics, FixedJoint, fixed) {
+    std::mem::swap(&mut fixed.local_anchor1_translation, &mut self.value);
+});
+
+define_joint_variant_command!(SetFixedJointAnchor2TranslationCommand("Set Fixed Joint Anchor 2 Translation", Vector3<f32>) where fn swap(self, physics, FixedJoint, fixed) {
+    std::mem::swap(&mut fixed.local_anchor2_translation, &mut self.value);
+});
+
+define_joint_variant_command!(SetFixedJointAnchor1RotationCommand("Set Fixed Joint Anchor 1 Rotation", UnitQuaternion<f32>) where fn swap(self, physics, FixedJoint, fixed) {
+    std::mem::swap(&mut fixed.local_anchor1_rotation, &mut self.value);
+});
+
+define_joint_variant_command!(SetFixedJointAnchor2RotationCommand("Set Fixed Joint Anchor 2 Rotation", UnitQuaternion<f32>) where fn swap(self, physics, FixedJoint, fixed) {
+    std::mem::swap(&mut fixed.local_anchor2_rotation, &mut self.value);
+});
+
+define_joint_variant_command!(SetRevoluteJointAnchor1Command("Set Revolute Joint Anchor 1", Vector3<f32>) where fn swap(self, physics, RevoluteJoint, revolute) {
+    std::mem::swap(&mut revolute.local_anchor1, &mut self.value);
+});
+
+define_joint_variant_command!(SetRevoluteJointAxis1Command("Set Revolute Joint Axis 1", Vector3<f32>) where fn swap(self, physics, RevoluteJoint, revolute) {
+    std::mem::swap(&mut revolute.local_axis1, &mut self.value);
+});
+
+define_joint_variant_command!(SetRevoluteJointAnchor2Command("Set Revolute Joint Anchor 2", Vector3<f32>) where fn swap(self, physics, RevoluteJoint, revolute) {
+    std::mem::swap(&mut revolute.local_anchor2, &mut self.value);
+});
+
+define_joint_variant_command!(SetRevoluteJointAxis2Command("Set Prismatic Joint Axis 2", Vector3<f32>) where fn swap(self, physics, RevoluteJoint, revolute) {
+    std::mem::swap(&mut revolute.local_axis2, &mut self.value);
+});
+
+define_joint_variant_command!(SetPrismaticJointAnchor1Command("Set Prismatic Joint Anchor 1", Vector3<f32>) where fn swap(self, physics, PrismaticJoint, prismatic) {
+    std::mem::swap(&mut prismatic.local_anchor1, &mut self.value);
+});
+
+define_joint_variant_command!(SetPrismaticJointAxis1Command("Set Prismatic Joint Axis 1", Vector3<f32>) where fn swap(self, physics, PrismaticJoint, prismatic) {
+    std::mem::swap(&mut prismatic.local_axis1, &mut self.value);
+});
+
+define_joint_variant_command!(SetPrismaticJointAnchor2Command("Set Prismatic Joint Anchor 2", Vector3<f32>) where fn swap(self, physics, PrismaticJoint, prismatic) {
+    std::mem::swap(&mut prismatic.local_anchor2, &mut self.value);
+});
+
+define_joint_variant_command!(SetPrismaticJointAxis2Command("Set Prismatic Joint Axis 2", Vector3<f32>) where fn swap(self, physics, PrismaticJoint, prismatic) {
+    std::mem::swap(&mut prismatic.local_axis2, &mut self.value);
+});
+
+define_joint_command!(SetJointConnectedBodyCommand("Set Joint Connected Body", ErasedHandle) where fn swap(self, physics, joint) {
+    std::mem::swap(&mut joint.body2, &mut self.value);
+});
+
+define_joint_command!(SetJointBody1Command("Set Joint Body 1", ErasedHandle) where fn swap(self, physics, joint) {
+    std::mem::swap(&mut joint.body1, &mut self.value);
+});
+
+define_emitter_command!(SetEmitterPositionCommand("Set Emitter Position", Vector3<f32>) where fn swap(self, emitter) {
+    get_set_swap!(self, emitter, position, set_position);
+});
+
+define_emitter_command!(SetEmitterResurrectParticlesCommand("Set Emitter Resurrect Particles", bool) where fn swap(self, emitter) {
+    get_set_swap!(self, emitter, is_particles_resurrects, enable_particle_resurrection);
+});
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EmitterKind {
+    Box,
+    Sphere,
+    Cylinder,
+}
+
+/// Changes shape of an emitter in place. Parameters shared by all emitters (spawn rate,
+/// ranges, position, etc.) are preserved, shape-specific ones get default values.
 #[derive(Debug)]
-pub struct ChangeLodRangeEndCommand {
+pub struct SetEmitterTypeCommand {
     handle: Handle<Node>,
-    lod_index: usize,
-    new_value: f32,
+    index: usize,
+    kind: EmitterKind,
+    old_emitter: Option<Emitter>,
 }
 
-impl ChangeLodRangeEndCommand {
-    pub fn new(handle: Handle<Node>, lod_index: usize, new_value: f32) -> Self {
+impl SetEmitterTypeCommand {
+    pub fn new(handle: Handle<Node>, index: usize, kind: EmitterKind) -> Self {
         Self {
             handle,
-            lod_index,
-            new_value,
+            index,
+            kind,
+            old_emitter: None,
         }
     }
+}
 
-    fn swap(&mut self, context: &mut SceneContext) {
-        let level = &mut context.scene.graph[self.handle]
-            .lod_group_mut()
-            .unwrap()
-            .levels[self.lod_index];
-        let old = level.end();
-        level.set_end(self.new_value);
-        self.new_value = old;
+impl<'a> Command<'a> for SetEmitterTypeCommand {
+    type Context = SceneContext<'a>;
+
+    fn name(&mut self, _context: &Self::Context) -> String {
+        "Set Emitter Type".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut Self::Context) {
+        let emitter = &mut context.scene.graph[self.handle]
+            .as_particle_system_mut()
+            .emitters[self.index];
+
+        let mut new_emitter = match self.kind {
+            EmitterKind::Box => BoxEmitterBuilder::new(BaseEmitterBuilder::new()).build(),
+            EmitterKind::Sphere => SphereEmitterBuilder::new(BaseEmitterBuilder::new()).build(),
+            EmitterKind::Cylinder => CylinderEmitterBuilder::new(BaseEmitterBuilder::new()).build(),
+        };
+        let base: &mut BaseEmitter = &mut new_emitter;
+        *base = (**emitter).clone();
+
+        self.old_emitter = Some(std::mem::replace(emitter, new_emitter));
+    }
+
+    fn revert(&mut self, context: &mut Self::Context) {
+        if let Some(old_emitter) = self.old_emitter.take() {
+            context.scene.graph[self.handle]
+                .as_particle_system_mut()
+                .emitters[self.index] = old_emitter;
+        }
     }
 }
 
-impl<'a> Command<'a> for ChangeLodRangeEndCommand {
-    type Context = SceneContext<'a>;
+define_animation_command!(SetAnimationSpeedCommand("Set Animation Speed", f32) where fn swap(self, animation) {
+    get_set_swap!(self, animation, speed, set_speed);
+});
+
+define_animation_command!(SetAnimationLoopingCommand("Set Animation Looping", bool) where fn swap(self, animation) {
+    get_set_swap!(self, animation, is_loop, set_loop);
+});
+
+define_emitter_variant_command!(SetSphereEmitterRadiusCommand("Set Sphere Emitter Radius", f32) where fn swap(self, emitter, Sphere, sphere) {
+    get_set_swap!(self, sphere, radius, set_radius);
+});
+
+define_emitter_variant_command!(SetCylinderEmitterRadiusCommand("Set Cylinder Emitter Radius", f32) where fn swap(self, emitter, Cylinder, cylinder) {
+    get_set_swap!(self, cylinder, radius, set_radius);
+});
+
+define_emitter_variant_command!(SetCylinderEmitterHeightCommand("Set Cylinder Emitter Radius", f32) where fn swap(self, emitter, Cylinder, cylinder) {
+    get_set_swap!(self, cylinder, height, set_height);
+});
+
+define_emitter_variant_command!(SetBoxEmitterHalfWidthCommand("Set Box Emitter Half Width", f32) where fn swap(self, emitter, Box, box_emitter) {
+    get_set_swap!(self, box_emitter, half_width, set_half_width);
+});
+
+define_emitter_variant_command!(SetBoxEmitterHalfHeightCommand("Set Box Emitter Half Height", f32) where fn swap(self, emitter, Box, box_emitter) {
+    get_set_swap!(self, box_emitter, half_height, set_half_height);
+});
 
-    fn name(&mut self, _context: &Self::Context) -> String {
-        "Change Lod Range End".to_owned()
-    }
+define_emitter_variant_command!(SetBoxEmitterHalfDepthCommand("Set Box Emitter Half Depth", f32) where fn swap(self, emitter, Box, box_emitter) {
+    get_set_swap!(self, box_emitter, half_depth, set_half_depth);
+});
 
-    fn execute(&mut self, context: &mut Self::Context) {
-        self.swap(context);
-    }
+/// Converts world-space offset of a node to local-space offset, taking scale of parent
+/// chain into account.
+fn world_offset_to_local(
+    graph: &Graph,
+    handle: Handle<Node>,
+    offset: Vector3<f32>,
+) -> Vector3<f32> {
+    let mut chain_scale = Vector3::new(1.0, 1.0, 1.0);
+    let mut parent_handle = graph[handle].parent();
+    while parent_handle.is_some() {
+        let parent = &graph[parent_handle];
+        let parent_scale = parent.local_transform().scale();
+        chain_scale.x *= parent_scale.x;
+        chain_scale.y *= parent_scale.y;
+        chain_scale.z *= parent_scale.z;
+        parent_handle = parent.parent();
+    }
+
+    Vector3::new(
+        if chain_scale.x.abs() > 0.0 {
+            offset.x / chain_scale.x
+        } else {
+            offset.x
+        },
+        if chain_scale.y.abs() > 0.0 {
+            offset.y / chain_scale.y
+        } else {
+            offset.y
+        },
+        if chain_scale.z.abs() > 0.0 {
+            offset.z / chain_scale.z
+        } else {
+            offset.z
+        },
+    )
+}
 
-    fn revert(&mut self, context: &mut Self::Context) {
-        self.swap(context);
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Selection {
+    None,
+    Graph(GraphSelection),
+    Navmesh(NavmeshSelection),
+}
+
+impl Default for Selection {
+    fn default() -> Self {
+        Self::None
     }
 }
 
-#[derive(Debug)]
-enum TextureSet {
-    Single(Texture),
-    Multiple(Vec<Option<Texture>>),
+impl Selection {
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Selection::None => true,
+            Selection::Graph(graph) => graph.is_empty(),
+            Selection::Navmesh(navmesh) => navmesh.is_empty(),
+        }
+    }
 }
 
-#[derive(Debug)]
-pub struct SetMeshTextureCommand {
-    node: Handle<Node>,
-    set: TextureSet,
+#[derive(Debug, Default, Clone, Eq)]
+pub struct GraphSelection {
+    nodes: Vec<Handle<Node>>,
 }
 
-impl SetMeshTextureCommand {
-    pub fn new(node: Handle<Node>, texture: Texture) -> Self {
-        Self {
-            node,
-            set: TextureSet::Single(texture),
+impl PartialEq for GraphSelection {
+    fn eq(&self, other: &Self) -> bool {
+        if self.nodes.is_empty() && !other.nodes.is_empty() {
+            false
+        } else {
+            // Selection is equal even when order of elements is different.
+            // TODO: Find a way to do this faster.
+            for &node in self.nodes.iter() {
+                let mut found = false;
+                for &other_node in other.nodes.iter() {
+                    if other_node == node {
+                        found = true;
+                        break;
+                    }
+                }
+                if !found {
+                    return false;
+                }
+            }
+            true
         }
     }
 }
 
-impl<'a> Command<'a> for SetMeshTextureCommand {
-    type Context = SceneContext<'a>;
-
-    fn name(&mut self, _context: &Self::Context) -> String {
-        "Set Texture".to_owned()
+impl GraphSelection {
+    pub fn from_list(nodes: Vec<Handle<Node>>) -> Self {
+        Self {
+            nodes: nodes.into_iter().filter(|h| h.is_some()).collect(),
+        }
     }
 
-    fn execute(&mut self, context: &mut Self::Context) {
-        if let TextureSet::Single(texture) = &self.set {
-            let mesh: &mut Mesh = context.scene.graph[self.node].as_mesh_mut();
-            let old_set = mesh
-                .surfaces_mut()
-                .iter()
-                .map(|s| s.diffuse_texture())
-                .collect();
-            for surface in mesh.surfaces_mut() {
-                surface.set_diffuse_texture(Some(texture.clone()));
+    /// Creates new selection as single if node handle is not none, and empty if it is.
+    pub fn single_or_empty(node: Handle<Node>) -> Self {
+        if node.is_none() {
+            Self {
+                nodes: Default::default(),
             }
-            self.set = TextureSet::Multiple(old_set);
         } else {
-            unreachable!()
+            Self { nodes: vec![node] }
         }
     }
 
-    fn revert(&mut self, context: &mut Self::Context) {
-        if let TextureSet::Multiple(set) = &self.set {
-            let mesh: &mut Mesh = context.scene.graph[self.node].as_mesh_mut();
-            let new_value = mesh.surfaces_mut()[0].diffuse_texture().unwrap();
-            assert_eq!(mesh.surfaces_mut().len(), set.len());
-            for (surface, old_texture) in mesh.surfaces_mut().iter_mut().zip(set) {
-                surface.set_diffuse_texture(old_texture.clone());
-            }
-            self.set = TextureSet::Single(new_value);
+    /// Adds new selected node, or removes it if it is already in the list of selected nodes.
+    pub fn insert_or_exclude(&mut self, handle: Handle<Node>) {
+        if let Some(position) = self.nodes.iter().position(|&h| h == handle) {
+            self.nodes.remove(position);
         } else {
-            unreachable!()
+            self.nodes.push(handle);
         }
     }
-}
 
-#[derive(Debug, Copy, Clone)]
-pub enum EmitterNumericParameter {
-    SpawnRate,
-    MaxParticles,
-    MinLifetime,
-    MaxLifetime,
-    MinSizeModifier,
-    MaxSizeModifier,
-    MinXVelocity,
-    MaxXVelocity,
-    MinYVelocity,
-    MaxYVelocity,
-    MinZVelocity,
-    MaxZVelocity,
-    MinRotationSpeed,
-    MaxRotationSpeed,
-    MinRotation,
-    MaxRotation,
-}
+    pub fn contains(&self, handle: Handle<Node>) -> bool {
+        self.nodes.iter().any(|&h| h == handle)
+    }
 
-impl EmitterNumericParameter {
-    fn name(self) -> &'static str {
-        match self {
-            EmitterNumericParameter::SpawnRate => "SpawnRate",
-            EmitterNumericParameter::MaxParticles => "MaxParticles",
-            EmitterNumericParameter::MinLifetime => "MinLifetime",
-            EmitterNumericParameter::MaxLifetime => "MaxLifetime",
-            EmitterNumericParameter::MinSizeModifier => "MinSizeModifier",
-            EmitterNumericParameter::MaxSizeModifier => "MaxSizeModifier",
-            EmitterNumericParameter::MinXVelocity => "MinXVelocity",
-            EmitterNumericParameter::MaxXVelocity => "MaxXVelocity",
-            EmitterNumericParameter::MinYVelocity => "MinYVelocity",
-            EmitterNumericParameter::MaxYVelocity => "MaxYVelocity",
-            EmitterNumericParameter::MinZVelocity => "MinZVelocity",
-            EmitterNumericParameter::MaxZVelocity => "MaxZVelocity",
-            EmitterNumericParameter::MinRotationSpeed => "MinRotationSpeed",
-            EmitterNumericParameter::MaxRotationSpeed => "MaxRotationSpeed",
-            EmitterNumericParameter::MinRotation => "MinRotation",
-            EmitterNumericParameter::MaxRotation => "MaxRotation",
+    /// Creates selection of every node (except graph root) which world bounding box is inside
+    /// of given frustum, see [`FrustumSelectionMode`].
+    pub fn from_frustum(graph: &Graph, frustum: &Frustum, mode: FrustumSelectionMode) -> Self {
+        let mut nodes = Vec::new();
+        let mut stack = graph[graph.get_root()].children().to_vec();
+        while let Some(handle) = stack.pop() {
+            let aabb = world_aabb(graph, handle);
+            let selected = match mode {
+                FrustumSelectionMode::Intersection => frustum.is_intersects_aabb(&aabb),
+                FrustumSelectionMode::Containment => aabb
+                    .corners()
+                    .iter()
+                    .all(|&corner| frustum.is_contains_point(corner)),
+            };
+            if selected {
+                nodes.push(handle);
+            }
+            stack.extend_from_slice(graph[handle].children());
         }
+        Self { nodes }
     }
-}
 
-#[derive(Debug)]
-pub struct SetEmitterNumericParameterCommand {
-    node: Handle<Node>,
-    parameter: EmitterNumericParameter,
-    value: f32,
-    emitter_index: usize,
-}
+    /// Creates new selection which contains selected nodes and all their descendants, every
+    /// node is listed once.
+    pub fn with_descendants(&self, graph: &Graph) -> Self {
+        let mut nodes = Vec::new();
+        for &handle in self.nodes.iter() {
+            for descendant in graph.traverse_handle_iter(handle) {
+                if !nodes.contains(&descendant) {
+                    nodes.push(descendant);
+                }
+            }
+        }
+        Self { nodes }
+    }
 
-impl SetEmitterNumericParameterCommand {
-    pub fn new(
-        node: Handle<Node>,
-        emitter_index: usize,
-        parameter: EmitterNumericParameter,
-        value: f32,
-    ) -> Self {
+    /// Creates new selection with only those nodes that satisfy given predicate.
+    pub fn filtered<P>(&self, graph: &Graph, mut predicate: P) -> Self
+    where
+        P: FnMut(Handle<Node>, &Node) -> bool,
+    {
         Self {
-            node,
-            parameter,
-            value,
-            emitter_index,
+            nodes: self
+                .nodes
+                .iter()
+                .cloned()
+                .filter(|&h| h.is_some() && predicate(h, &graph[h]))
+                .collect(),
         }
     }
 
-    fn swap(&mut self, context: &mut SceneContext) {
-        let emitter: &mut Emitter = &mut context.scene.graph[self.node]
-            .as_particle_system_mut()
-            .emitters[self.emitter_index];
-        match self.parameter {
-            EmitterNumericParameter::SpawnRate => {
-                let old = emitter.spawn_rate();
-                emitter.set_spawn_rate(self.value as u32);
-                self.value = old as f32;
-            }
-            EmitterNumericParameter::MaxParticles => {
-                let old = emitter.max_particles();
-                emitter.set_max_particles(if self.value < 0.0 {
-                    ParticleLimit::Unlimited
-                } else {
-                    ParticleLimit::Strict(self.value as u32)
-                });
-                self.value = match old {
-                    ParticleLimit::Unlimited => -1.0,
-                    ParticleLimit::Strict(value) => value as f32,
-                };
-            }
-            EmitterNumericParameter::MinLifetime => {
-                let old = emitter.life_time_range();
-                emitter.set_life_time_range(NumericRange::new(self.value, old.bounds[1]));
-                self.value = old.bounds[0];
-            }
-            EmitterNumericParameter::MaxLifetime => {
-                let old = emitter.life_time_range();
-                emitter.set_life_time_range(NumericRange::new(old.bounds[0], self.value));
-                self.value = old.bounds[1];
-            }
-            EmitterNumericParameter::MinSizeModifier => {
-                let old = emitter.size_modifier_range();
-                emitter.set_size_modifier_range(NumericRange::new(self.value, old.bounds[1]));
-                self.value = old.bounds[0];
-            }
-            EmitterNumericParameter::MaxSizeModifier => {
-                let old = emitter.size_modifier_range();
-                emitter.set_size_modifier_range(NumericRange::new(old.bounds[0], self.value));
-                self.value = old.bounds[1];
-            }
-            EmitterNumericParameter::MinXVelocity => {
-                let old = emitter.x_velocity_range();
-                emitter.set_x_velocity_range(NumericRange::new(self.value, old.bounds[1]));
-                self.value = old.bounds[0];
-            }
-            EmitterNumericParameter::MaxXVelocity => {
-                let old = emitter.x_velocity_range();
-                emitter.set_x_velocity_range(NumericRange::new(old.bounds[0], self.value));
-                self.value = old.bounds[1];
-            }
-            EmitterNumericParameter::MinYVelocity => {
-                let old = emitter.y_velocity_range();
-                emitter.set_y_velocity_range(NumericRange::new(self.value, old.bounds[1]));
-                self.value = old.bounds[0];
-            }
-            EmitterNumericParameter::MaxYVelocity => {
-                let old = emitter.y_velocity_range();
-                emitter.set_y_velocity_range(NumericRange::new(old.bounds[0], self.value));
-                self.value = old.bounds[1];
-            }
-            EmitterNumericParameter::MinZVelocity => {
-                let old = emitter.z_velocity_range();
-                emitter.set_z_velocity_range(NumericRange::new(self.value, old.bounds[1]));
-                self.value = old.bounds[0];
-            }
-            EmitterNumericParameter::MaxZVelocity => {
-                let old = emitter.z_velocity_range();
-                emitter.set_z_velocity_range(NumericRange::new(old.bounds[0], self.value));
-                self.value = old.bounds[1];
-            }
-            EmitterNumericParameter::MinRotationSpeed => {
-                let old = emitter.rotation_speed_range();
-                emitter.set_rotation_speed_range(NumericRange::new(self.value, old.bounds[1]));
-                self.value = old.bounds[0];
-            }
-            EmitterNumericParameter::MaxRotationSpeed => {
-                let old = emitter.rotation_speed_range();
-                emitter.set_rotation_speed_range(NumericRange::new(old.bounds[0], self.value));
-                self.value = old.bounds[1];
+    pub fn only_meshes(&self, graph: &Graph) -> Self {
+        self.filtered(graph, |_, node| matches!(node, Node::Mesh(_)))
+    }
+
+    pub fn only_lights(&self, graph: &Graph) -> Self {
+        self.filtered(graph, |_, node| matches!(node, Node::Light(_)))
+    }
+
+    pub fn nodes(&self) -> &[Handle<Node>] {
+        &self.nodes
+    }
+
+    pub fn is_multi_selection(&self) -> bool {
+        self.nodes.len() > 1
+    }
+
+    pub fn is_single_selection(&self) -> bool {
+        self.nodes.len() == 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn extend(&mut self, other: &GraphSelection) {
+        self.nodes.extend_from_slice(&other.nodes)
+    }
+
+    pub fn root_nodes(&self, graph: &Graph) -> Vec<Handle<Node>> {
+        // Helper function.
+        fn is_descendant_of(handle: Handle<Node>, other: Handle<Node>, graph: &Graph) -> bool {
+            for &child in graph[other].children() {
+                if child == handle {
+                    return true;
+                }
+
+                let inner = is_descendant_of(handle, child, graph);
+                if inner {
+                    return true;
+                }
             }
-            EmitterNumericParameter::MinRotation => {
-                let old = emitter.rotation_range();
-                emitter.set_rotation_range(NumericRange::new(self.value, old.bounds[1]));
-                self.value = old.bounds[0];
+            false
+        }
+
+        let mut root_nodes = Vec::new();
+        for &node in self.nodes().iter() {
+            let mut descendant = false;
+            for &other_node in self.nodes().iter() {
+                if is_descendant_of(node, other_node, graph) {
+                    descendant = true;
+                    break;
+                }
             }
-            EmitterNumericParameter::MaxRotation => {
-                let old = emitter.rotation_range();
-                emitter.set_rotation_range(NumericRange::new(old.bounds[0], self.value));
-                self.value = old.bounds[1];
+            if !descendant {
+                root_nodes.push(node);
             }
-        };
+        }
+        root_nodes
     }
-}
 
-impl<'a> Command<'a> for SetEmitterNumericParameterCommand {
-    type Context = SceneContext<'a>;
+    pub fn global_rotation_position(
+        &self,
+        graph: &Graph,
+    ) -> Option<(UnitQuaternion<f32>, Vector3<f32>)> {
+        if self.is_single_selection() {
+            Some(graph.global_rotation_position_no_scale(self.nodes[0]))
+        } else if self.is_empty() {
+            None
+        } else {
+            let mut position = Vector3::default();
+            let mut rotation = graph.global_rotation(self.nodes[0]);
+            let t = 1.0 / self.nodes.len() as f32;
+            for &handle in self.nodes.iter() {
+                let global_transform = graph[handle].global_transform();
+                position += global_transform.position();
+                rotation = rotation.slerp(&graph.global_rotation(self.nodes[0]), t);
+            }
+            position = position.scale(t);
+            Some((rotation, position))
+        }
+    }
 
-    fn name(&mut self, _context: &Self::Context) -> String {
-        format!("Set Emitter F32 Parameter: {}", self.parameter.name())
+    pub fn offset(&self, graph: &mut Graph, offset: Vector3<f32>) {
+        for &handle in self.nodes.iter() {
+            let offset = world_offset_to_local(graph, handle, offset);
+            graph[handle].local_transform_mut().offset(offset);
+        }
     }
 
-    fn execute(&mut self, context: &mut Self::Context) {
-        self.swap(context);
+    pub fn rotate(&self, graph: &mut Graph, rotation: UnitQuaternion<f32>) {
+        for &handle in self.nodes.iter() {
+            graph[handle].local_transform_mut().set_rotation(rotation);
+        }
     }
 
-    fn revert(&mut self, context: &mut Self::Context) {
-        self.swap(context);
+    pub fn scale(&self, graph: &mut Graph, scale: Vector3<f32>) {
+        for &handle in self.nodes.iter() {
+            graph[handle].local_transform_mut().set_scale(scale);
+        }
     }
-}
 
-macro_rules! define_node_command {
-    ($name:ident($human_readable_name:expr, $value_type:ty) where fn swap($self:ident, $node:ident) $apply_method:block ) => {
-        #[derive(Debug)]
-        pub struct $name {
-            handle: Handle<Node>,
-            value: $value_type,
+    pub fn local_positions(&self, graph: &Graph) -> Vec<Vector3<f32>> {
+        let mut positions = Vec::new();
+        for &handle in self.nodes.iter() {
+            positions.push(**graph[handle].local_transform().position());
         }
+        positions
+    }
 
-        impl $name {
-            pub fn new(handle: Handle<Node>, value: $value_type) -> Self {
-                Self { handle, value }
-            }
+    pub fn local_rotations(&self, graph: &Graph) -> Vec<UnitQuaternion<f32>> {
+        let mut rotations = Vec::new();
+        for &handle in self.nodes.iter() {
+            rotations.push(**graph[handle].local_transform().rotation());
+        }
+        rotations
+    }
 
-            fn swap(&mut $self, graph: &mut Graph) {
-                let $node = &mut graph[$self.handle];
-                $apply_method
-            }
+    pub fn local_scales(&self, graph: &Graph) -> Vec<Vector3<f32>> {
+        let mut scales = Vec::new();
+        for &handle in self.nodes.iter() {
+            scales.push(**graph[handle].local_transform().scale());
         }
+        scales
+    }
+}
 
-        impl<'a> Command<'a> for $name {
-            type Context = SceneContext<'a>;
+/// Creates scene command (command group) which removes current selection in editor's scene.
+/// This is **not** trivial because each node has multiple connections inside engine and
+/// in editor's data model, so we have to thoroughly build command using simple commands.
+pub fn make_delete_selection_command(editor_scene: &EditorScene, graph: &Graph) -> SceneCommand {
+    // Graph's root is non-deletable.
+    let mut selection = if let Selection::Graph(selection) = &editor_scene.selection {
+        selection.clone()
+    } else {
+        Default::default()
+    };
+    if let Some(root_position) = selection.nodes.iter().position(|&n| n == graph.get_root()) {
+        selection.nodes.remove(root_position);
+    }
 
-            fn name(&mut self, _context: &Self::Context) -> String {
-                $human_readable_name.to_owned()
-            }
+    // Change selection first.
+    let mut command_group = CommandGroup::from(vec![SceneCommand::ChangeSelection(
+        ChangeSelectionCommand::new(Default::default(), Selection::Graph(selection.clone())),
+    )]);
 
-            fn execute(&mut self, context: &mut Self::Context) {
-                self.swap(&mut context.scene.graph);
-            }
+    // Find sub-graphs to delete - we need to do this because we can end up in situation like this:
+    // A_
+    //   B_      <-
+    //   | C       | these are selected
+    //   | D_    <-
+    //   |   E
+    //   F
+    // In this case we must deleted only node B, there is no need to delete node D separately because
+    // by engine's design when we delete a node, we also delete all its children. So we have to keep
+    // this behaviour in editor too.
 
-            fn revert(&mut self, context: &mut Self::Context) {
-                self.swap(&mut context.scene.graph);
+    let root_nodes = selection.root_nodes(graph);
+
+    // Delete all associated physics entities in the whole hierarchy starting from root nodes
+    // found above.
+    let physics = &editor_scene.physics;
+    let mut bodies = Vec::new();
+    let mut joints = Vec::new();
+    for &root_node in root_nodes.iter() {
+        let (root_bodies, root_colliders, root_joints) =
+            physics.entities_in_subtree(graph, root_node);
+        for collider in root_colliders {
+            command_group.push(SceneCommand::DeleteCollider(DeleteColliderCommand::new(
+                collider,
+            )))
+        }
+        bodies.extend(root_bodies);
+        // Joint could connect bodies of different sub-graphs.
+        for joint in root_joints {
+            if !joints.contains(&joint) {
+                joints.push(joint);
             }
         }
-    };
+    }
+
+    for &body in bodies.iter() {
+        command_group.push(SceneCommand::DeleteBody(DeleteBodyCommand::new(body)));
+    }
+
+    // Joint that is found by its first body is removed together with the body, other joints
+    // that refer to deleted bodies are just detached from them.
+    for joint in joints {
+        let joint_ref = &physics.joints[joint];
+        let is_deleted = |body: ErasedHandle| bodies.iter().any(|&b| body == ErasedHandle::from(b));
+        if is_deleted(joint_ref.body1) && physics.find_joint(joint_ref.body1.into()) == joint {
+            command_group.push(SceneCommand::DeleteJoint(DeleteJointCommand::new(joint)));
+            continue;
+        }
+        if is_deleted(joint_ref.body1) {
+            command_group.push(SceneCommand::SetJointBody1(SetJointBody1Command::new(
+                joint,
+                ErasedHandle::none(),
+            )));
+        }
+        if is_deleted(joint_ref.body2) {
+            command_group.push(SceneCommand::SetJointConnectedBody(
+                SetJointConnectedBodyCommand::new(joint, ErasedHandle::none()),
+            ));
+        }
+    }
+
+    for root_node in root_nodes {
+        command_group.push(SceneCommand::DeleteSubGraph(DeleteSubGraphCommand::new(
+            root_node,
+        )));
+    }
+
+    SceneCommand::CommandGroup(command_group)
 }
 
-macro_rules! define_physics_command {
-    ($name:ident($human_readable_name:expr, $handle_type:ty, $value_type:ty) where fn swap($self:ident, $physics:ident) $apply_method:block ) => {
-        #[derive(Debug)]
-        pub struct $name {
-            handle: Handle<$handle_type>,
-            value: $value_type,
+/// Creates scene command (command group) which moves current selection into clipboard. Clipboard
+/// is filled **before** the command is executed, so every physics entity is still alive at this
+/// moment and will be captured too. Undoing the command restores deleted nodes, but keeps the
+/// clipboard filled, so cut content still can be pasted elsewhere.
+pub fn make_cut_selection_command(editor_scene: &mut EditorScene, graph: &Graph) -> SceneCommand {
+    editor_scene.copy_selection_to_clipboard(graph);
+
+    make_delete_selection_command(editor_scene, graph)
+}
+
+/// Creates command that merges content of a saved scene into the current one. Nodes of the
+/// merged scene are attached to the scene root together with their bodies and joints. Nodes
+/// with names that already exist in the current scene get numeric suffix, duplicate names
+/// within the merged scene itself are kept. Navmeshes of the merged scene are added to the
+/// editor's navmeshes.
+pub fn make_merge_scene_command(
+    path: &Path,
+    dest_graph: &Graph,
+    resource_manager: ResourceManager,
+) -> Result<SceneCommand, String> {
+    let mut scene =
+        rg3d::core::futures::executor::block_on(Scene::from_file(path, resource_manager))
+            .map_err(|e| e.to_string())?;
+
+    let physics = Physics::new(&scene);
+
+    let mut graph = std::mem::replace(&mut scene.graph, Graph::new());
+
+    // Resolve name collisions with the current scene. New names must not collide with any
+    // name of both scenes.
+    let dest_names = dest_graph
+        .linear_iter()
+        .map(|n| n.name().to_owned())
+        .collect::<HashSet<_>>();
+    let root = graph.get_root();
+    let handles = graph
+        .traverse_handle_iter(root)
+        .filter(|&h| h != root)
+        .collect::<Vec<_>>();
+    let mut taken_names = dest_names.clone();
+    taken_names.extend(handles.iter().map(|&h| graph[h].name().to_owned()));
+    for handle in handles {
+        let node = &mut graph[handle];
+        if !dest_names.contains(node.name()) {
+            continue;
         }
 
-        impl $name {
-            pub fn new(handle: Handle<$handle_type>, value: $value_type) -> Self {
-                Self { handle, value }
+        let mut suffix = 1;
+        let new_name = loop {
+            let candidate = format!("{}_{}", node.name(), suffix);
+            if !taken_names.contains(&candidate) {
+                break candidate;
             }
+            suffix += 1;
+        };
+        node.set_name(&new_name);
+        taken_names.insert(new_name);
+    }
 
-            fn swap(&mut $self, $physics: &mut Physics) {
-                 $apply_method
-            }
-        }
+    let mut commands = vec![SceneCommand::Paste(PasteCommand::from_clipboard(
+        Clipboard {
+            graph,
+            physics,
+            empty: false,
+            ..Default::default()
+        },
+    ))];
+
+    for navmesh in scene.navmeshes.iter() {
+        commands.push(SceneCommand::AddNavmesh(AddNavmeshCommand::new(
+            Navmesh::from_engine_navmesh(navmesh),
+        )));
+    }
 
-        impl<'a> Command<'a> for $name {
-            type Context = SceneContext<'a>;
+    Ok(SceneCommand::CommandGroup(CommandGroup::from(commands)))
+}
 
-            fn name(&mut self, _context: &Self::Context) -> String {
-                $human_readable_name.to_owned()
-            }
+/// Sets light properties through light API, so they will be serialized correctly.
+fn setup_light(mut node: Node, color: Color, cast_shadows: bool) -> Node {
+    let light = node.as_light_mut();
+    light.set_color(color);
+    light.set_cast_shadows(cast_shadows);
+    node
+}
 
-            fn execute(&mut self, context: &mut Self::Context) {
-                self.swap(&mut context.editor_scene.physics);
-            }
+/// Creates command that adds directional light oriented like sunlight - pointing 45 degrees
+/// downwards with warm color.
+pub fn make_add_directional_light_command() -> AddNodeCommand {
+    let node = DirectionalLightBuilder::new(BaseLightBuilder::new(
+        BaseBuilder::new()
+            .with_name("DirectionalLight")
+            .with_local_transform(
+                TransformBuilder::new()
+                    .with_local_rotation(UnitQuaternion::from_axis_angle(
+                        &Vector3::x_axis(),
+                        45.0f32.to_radians(),
+                    ))
+                    .build(),
+            ),
+    ))
+    .build_node();
 
-            fn revert(&mut self, context: &mut Self::Context) {
-                self.swap(&mut context.editor_scene.physics);
-            }
+    AddNodeCommand::new(setup_light(node, Color::opaque(255, 244, 214), true))
+}
+
+pub fn make_add_point_light_command() -> AddNodeCommand {
+    let node = PointLightBuilder::new(BaseLightBuilder::new(
+        BaseBuilder::new().with_name("PointLight"),
+    ))
+    .with_radius(10.0)
+    .build_node();
+
+    AddNodeCommand::new(setup_light(node, Color::WHITE, true))
+}
+
+pub fn make_add_spot_light_command() -> AddNodeCommand {
+    let node = SpotLightBuilder::new(BaseLightBuilder::new(
+        BaseBuilder::new().with_name("SpotLight"),
+    ))
+    .with_distance(10.0)
+    .with_hotspot_cone_angle(45.0f32.to_radians())
+    .with_falloff_angle_delta(2.0f32.to_radians())
+    .build_node();
+
+    AddNodeCommand::new(setup_light(node, Color::WHITE, true))
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+impl Axis {
+    fn index(self) -> usize {
+        match self {
+            Axis::X => 0,
+            Axis::Y => 1,
+            Axis::Z => 2,
         }
-    };
+    }
 }
 
-macro_rules! define_body_command {
-    ($name:ident($human_readable_name:expr, $value_type:ty) where fn swap($self:ident, $physics: ident, $body:ident) $apply_method:block ) => {
-        define_physics_command!($name($human_readable_name, RigidBody, $value_type) where fn swap($self, $physics) {
-            let $body = &mut $physics.bodies[$self.handle];
-            $apply_method
-        });
-    };
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AlignMode {
+    Min,
+    Center,
+    Max,
 }
 
-macro_rules! define_collider_command {
-    ($name:ident($human_readable_name:expr, $value_type:ty) where fn swap($self:ident, $physics:ident, $collider:ident) $apply_method:block ) => {
-        define_physics_command!($name($human_readable_name, Collider, $value_type) where fn swap($self, $physics) {
-            let $collider = &mut $physics.colliders[$self.handle];
-            $apply_method
-        });
-    };
+/// Space in which transform deltas are given: in axes of a node itself or in world axes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TransformSpace {
+    Local,
+    World,
 }
 
-macro_rules! define_joint_command {
-    ($name:ident($human_readable_name:expr, $value_type:ty) where fn swap($self:ident, $physics:ident, $joint:ident) $apply_method:block ) => {
-        define_physics_command!($name($human_readable_name, Joint, $value_type) where fn swap($self, $physics) {
-            let $joint = &mut $physics.joints[$self.handle];
-            $apply_method
-        });
-    };
+impl Default for TransformSpace {
+    fn default() -> Self {
+        Self::Local
+    }
 }
 
-macro_rules! define_joint_variant_command {
-    ($name:ident($human_readable_name:expr, $value_type:ty) where fn swap($self:ident, $physics:ident, $variant:ident, $var:ident) $apply_method:block ) => {
-        define_physics_command!($name($human_readable_name, Joint, $value_type) where fn swap($self, $physics) {
-            let joint = &mut $physics.joints[$self.handle];
-            if let JointParamsDesc::$variant($var) = &mut joint.params {
-                $apply_method
+/// Converts translation delta given in specified space to the space of node's parent, so it
+/// could be added to local position of the node.
+pub fn delta_to_parent_space(
+    graph: &Graph,
+    node: Handle<Node>,
+    delta: Vector3<f32>,
+    space: TransformSpace,
+) -> Vector3<f32> {
+    match space {
+        TransformSpace::Local => **graph[node].local_transform().rotation() * delta,
+        TransformSpace::World => {
+            let parent = graph[node].parent();
+            if parent.is_some() {
+                graph[parent]
+                    .global_transform()
+                    .try_inverse()
+                    .unwrap_or_else(Matrix4::identity)
+                    .transform_vector(&delta)
             } else {
-                unreachable!();
+                delta
             }
-        });
-    };
+        }
+    }
 }
 
-macro_rules! define_collider_variant_command {
-    ($name:ident($human_readable_name:expr, $value_type:ty) where fn swap($self:ident, $physics:ident, $variant:ident, $var:ident) $apply_method:block ) => {
-        define_physics_command!($name($human_readable_name, Collider, $value_type) where fn swap($self, $physics) {
-            let collider = &mut $physics.colliders[$self.handle];
-            if let ColliderShapeDesc::$variant($var) = &mut collider.shape {
-                $apply_method
+/// Applies rotation delta given in specified space to local rotation of a node. In world
+/// space the delta is composed with global rotation of node's parent, so the node rotates
+/// around world axes.
+pub fn rotate_in_space(
+    graph: &Graph,
+    node: Handle<Node>,
+    rotation: UnitQuaternion<f32>,
+    delta: UnitQuaternion<f32>,
+    space: TransformSpace,
+) -> UnitQuaternion<f32> {
+    match space {
+        TransformSpace::Local => rotation * delta,
+        TransformSpace::World => {
+            let parent = graph[node].parent();
+            let parent_rotation = if parent.is_some() {
+                graph.global_rotation_position_no_scale(parent).0
             } else {
-                unreachable!();
-            }
-        });
-    };
+                UnitQuaternion::identity()
+            };
+            parent_rotation.inverse() * delta * parent_rotation * rotation
+        }
+    }
 }
 
-macro_rules! define_emitter_command {
-    ($name:ident($human_readable_name:expr, $value_type:ty) where fn swap($self:ident, $emitter:ident) $apply_method:block ) => {
-        #[derive(Debug)]
-        pub struct $name {
-            handle: Handle<Node>,
-            value: $value_type,
-            index: usize
-        }
+/// Creates command that moves a node by `delta` given in specified space.
+pub fn make_move_node_command(
+    graph: &Graph,
+    node: Handle<Node>,
+    delta: Vector3<f32>,
+    space: TransformSpace,
+) -> MoveNodeCommand {
+    let old_position = **graph[node].local_transform().position();
+    let new_position = old_position + delta_to_parent_space(graph, node, delta, space);
+    MoveNodeCommand::new(node, old_position, new_position)
+}
 
-        impl $name {
-            pub fn new(handle: Handle<Node>, index: usize, value: $value_type) -> Self {
-                Self { handle, index, value }
-            }
+/// Creates command that rotates a node by `delta` given in specified space.
+pub fn make_rotate_node_command(
+    graph: &Graph,
+    node: Handle<Node>,
+    delta: UnitQuaternion<f32>,
+    space: TransformSpace,
+) -> RotateNodeCommand {
+    let old_rotation = **graph[node].local_transform().rotation();
+    let new_rotation = rotate_in_space(graph, node, old_rotation, delta, space);
+    RotateNodeCommand::new(node, old_rotation, new_rotation)
+}
+
+/// Creates command that moves a node so its world position coordinate on given axis
+/// becomes `target`.
+fn make_move_along_axis_command(
+    graph: &Graph,
+    node: Handle<Node>,
+    axis: Axis,
+    target: f32,
+) -> SceneCommand {
+    let mut world_offset = Vector3::default();
+    world_offset[axis.index()] = target - graph[node].global_position()[axis.index()];
 
-            fn swap(&mut $self, graph: &mut Graph) {
-                let $emitter = &mut graph[$self.handle].as_particle_system_mut().emitters[$self.index];
-                $apply_method
-            }
-        }
+    let old_position = **graph[node].local_transform().position();
+    let new_position = old_position + world_offset_to_local(graph, node, world_offset);
 
-        impl<'a> Command<'a> for $name {
-            type Context = SceneContext<'a>;
+    SceneCommand::MoveNode(MoveNodeCommand::new(node, old_position, new_position))
+}
 
-            fn name(&mut self, _context: &Self::Context) -> String {
-                $human_readable_name.to_owned()
-            }
+/// Creates command that moves a node down so the lowest point of its bounding box rests on
+/// a surface below node's origin. Nodes without geometry have no extent, so their origin is
+/// put on the surface. Colliders of the node's own body are ignored.
+pub fn make_drop_to_floor_command(
+    node: Handle<Node>,
+    graph: &Graph,
+    physics: &Physics,
+    message_sender: &Sender<Message>,
+) -> Option<SceneCommand> {
+    let origin = graph[node].global_position();
+
+    match raycast_down(node, graph, physics, origin) {
+        Some(distance) => {
+            let floor = origin.y - distance;
+            let bottom = match graph[node] {
+                Node::Mesh(_) => world_aabb(graph, node).min.y,
+                _ => origin.y,
+            };
+            let world_offset = Vector3::new(0.0, floor - bottom, 0.0);
 
-            fn execute(&mut self, context: &mut Self::Context) {
-                self.swap(&mut context.scene.graph);
-            }
+            let old_position = **graph[node].local_transform().position();
+            let new_position = old_position + world_offset_to_local(graph, node, world_offset);
 
-            fn revert(&mut self, context: &mut Self::Context) {
-                self.swap(&mut context.scene.graph);
-            }
+            Some(SceneCommand::MoveNode(MoveNodeCommand::new(
+                node,
+                old_position,
+                new_position,
+            )))
         }
-    };
+        None => {
+            message_sender
+                .send(Message::Log(format!(
+                    "Node {} was not dropped, because there is no surface below it.",
+                    graph[node].name()
+                )))
+                .unwrap();
+            None
+        }
+    }
 }
 
-macro_rules! define_emitter_variant_command {
-    ($name:ident($human_readable_name:expr, $value_type:ty) where fn swap($self:ident, $emitter:ident, $variant:ident, $var:ident) $apply_method:block ) => {
-        define_emitter_command!($name($human_readable_name, $value_type) where fn swap($self, $emitter) {
-            if let Emitter::$variant($var) = $emitter {
-                $apply_method
-            } else {
-                unreachable!()
-            }
-        });
-    };
+/// Casts a ray down from given point and returns distance to nearest surface. Colliders of
+/// the node's own body are ignored.
+fn raycast_down(
+    node: Handle<Node>,
+    graph: &Graph,
+    physics: &Physics,
+    origin: Vector3<f32>,
+) -> Option<f32> {
+    let own_body: ErasedHandle = physics
+        .binder
+        .value_of(&node)
+        .map(|&body| body.into())
+        .unwrap_or_else(ErasedHandle::none);
+
+    physics
+        .raycast(
+            graph,
+            origin,
+            Vector3::new(0.0, -1.0, 0.0),
+            |_, collider| own_body.is_none() || collider.parent != own_body,
+        )
+        .map(|(_, distance)| distance)
 }
 
-macro_rules! get_set_swap {
-    ($self:ident, $host:expr, $get:ident, $set:ident) => {
-        match $host {
-            host => {
-                let old = host.$get();
-                host.$set($self.value.clone());
-                $self.value = old;
-            }
+/// Creates command that rotates a node so its up axis matches normal of a surface below
+/// node's origin, rotation around vertical axis (yaw) is kept. Normal is found by casting
+/// two more rays near the origin, so it works for any shape of collider. If `drop_to_floor`
+/// is set, the node is also moved so the lowest point of its rotated bounding box (or its
+/// origin, if the node has no geometry) rests on the surface, see
+/// `make_drop_to_floor_command`.
+pub fn make_align_to_surface_command(
+    node: Handle<Node>,
+    graph: &Graph,
+    physics: &Physics,
+    drop_to_floor: bool,
+    message_sender: &Sender<Message>,
+) -> Option<SceneCommand> {
+    const PROBE_OFFSET: f32 = 0.01;
+
+    let origin = graph[node].global_position();
+    let hit_point = |offset: Vector3<f32>| {
+        raycast_down(node, graph, physics, origin + offset)
+            .map(|distance| origin + offset - Vector3::new(0.0, distance, 0.0))
+    };
+
+    let hit = match (
+        hit_point(Vector3::default()),
+        hit_point(Vector3::new(PROBE_OFFSET, 0.0, 0.0)),
+        hit_point(Vector3::new(0.0, 0.0, PROBE_OFFSET)),
+    ) {
+        (Some(a), Some(b), Some(c)) => (c - a)
+            .cross(&(b - a))
+            .try_normalize(f32::EPSILON)
+            .map(|normal| (a, normal)),
+        _ => None,
+    };
+
+    let (surface_point, normal) = match hit {
+        Some(hit) => hit,
+        None => {
+            message_sender
+                .send(Message::Log(format!(
+                    "Node {} was not aligned, because there is no surface below it.",
+                    graph[node].name()
+                )))
+                .unwrap();
+            return None;
         }
     };
-}
 
-define_node_command!(SetLightScatterCommand("Set Light Scatter", Vector3<f32>) where fn swap(self, node) {
-    get_set_swap!(self, node.as_light_mut(), scatter, set_scatter)
-});
+    let (world_rotation, _) = graph.global_rotation_position_no_scale(node);
+    let look = world_rotation * Vector3::z();
+    let yaw = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), look.x.atan2(look.z));
+    let tilt = UnitQuaternion::rotation_between(&Vector3::y(), &normal)
+        .unwrap_or_else(UnitQuaternion::identity);
 
-define_node_command!(SetLightScatterEnabledCommand("Set Light Scatter Enabled", bool) where fn swap(self, node) {
-    get_set_swap!(self, node.as_light_mut(), is_scatter_enabled, enable_scatter)
-});
+    let parent = graph[node].parent();
+    let parent_rotation = if parent.is_some() {
+        graph.global_rotation_position_no_scale(parent).0
+    } else {
+        UnitQuaternion::identity()
+    };
 
-define_node_command!(SetLightCastShadowsCommand("Set Light Cast Shadows", bool) where fn swap(self, node) {
-    get_set_swap!(self, node.as_light_mut(), is_cast_shadows, set_cast_shadows)
-});
+    let old_rotation = **graph[node].local_transform().rotation();
+    let new_rotation = parent_rotation.inverse() * tilt * yaw;
 
-define_node_command!(SetPointLightRadiusCommand("Set Point Light Radius", f32) where fn swap(self, node) {
-    get_set_swap!(self, node.as_light_mut().as_point_mut(), radius, set_radius)
-});
+    let rotate = SceneCommand::RotateNode(RotateNodeCommand::new(node, old_rotation, new_rotation));
 
-define_node_command!(SetSpotLightHotspotCommand("Set Spot Light Hotspot", f32) where fn swap(self, node) {
-    get_set_swap!(self, node.as_light_mut().as_spot_mut(), hotspot_cone_angle, set_hotspot_cone_angle)
-});
+    if !drop_to_floor {
+        return Some(rotate);
+    }
 
-define_node_command!(SetSpotLightFalloffAngleDeltaCommand("Set Spot Light Falloff Angle Delta", f32) where fn swap(self, node) {
-    get_set_swap!(self, node.as_light_mut().as_spot_mut(), falloff_angle_delta, set_falloff_angle_delta)
-});
+    // Node rotates around its origin, so rotate corners of its bounding box the same way and
+    // find how far the lowest one is below the origin along the normal.
+    let depth = match &graph[node] {
+        Node::Mesh(mesh) => {
+            let transform = mesh.global_transform();
+            let delta = tilt * yaw * world_rotation.inverse();
+            mesh.bounding_box()
+                .corners()
+                .iter()
+                .map(|corner| {
+                    let offset = transform.transform_point(&Point3::from(*corner)).coords - origin;
+                    (delta * offset).dot(&normal)
+                })
+                .fold(f32::INFINITY, f32::min)
+        }
+        _ => 0.0,
+    };
 
-define_node_command!(SetSpotLightDistanceCommand("Set Spot Light Distance", f32) where fn swap(self, node) {
-    get_set_swap!(self, node.as_light_mut().as_spot_mut(), distance, set_distance);
-});
+    let world_offset = surface_point - normal * depth - origin;
+    let old_position = **graph[node].local_transform().position();
+    let new_position = old_position + world_offset_to_local(graph, node, world_offset);
 
-define_node_command!(SetLightColorCommand("Set Light Color", Color) where fn swap(self, node) {
-    get_set_swap!(self, node.as_light_mut(), color, set_color)
-});
+    Some(SceneCommand::CommandGroup(CommandGroup::from(vec![
+        rotate,
+        SceneCommand::MoveNode(MoveNodeCommand::new(node, old_position, new_position)),
+    ])))
+}
 
-define_node_command!(SetNameCommand("Set Name", String) where fn swap(self, node) {
-    get_set_swap!(self, node, name_owned, set_name);
-});
+/// Creates command group that aligns world positions of selected nodes on given axis to
+/// minimum, average or maximum coordinate of the selection. Only root nodes of the selection
+/// are moved, their selected descendants follow them.
+pub fn make_align_command(
+    selection: &GraphSelection,
+    graph: &Graph,
+    axis: Axis,
+    mode: AlignMode,
+) -> SceneCommand {
+    let root_nodes = selection.root_nodes(graph);
+    let coordinates = root_nodes
+        .iter()
+        .map(|&n| graph[n].global_position()[axis.index()])
+        .collect::<Vec<_>>();
 
-define_node_command!(SetLodGroupCommand("Set Lod Group", Option<LodGroup>) where fn swap(self, node) {
-    get_set_swap!(self, node, take_lod_group, set_lod_group);
-});
+    let mut commands = Vec::new();
+    if !coordinates.is_empty() {
+        let target = match mode {
+            AlignMode::Min => coordinates.iter().cloned().fold(f32::INFINITY, f32::min),
+            AlignMode::Center => coordinates.iter().sum::<f32>() / coordinates.len() as f32,
+            AlignMode::Max => coordinates
+                .iter()
+                .cloned()
+                .fold(f32::NEG_INFINITY, f32::max),
+        };
 
-define_node_command!(SetPhysicsBindingCommand("Set Physics Binding", PhysicsBinding) where fn swap(self, node) {
-    get_set_swap!(self, node, physics_binding, set_physics_binding);
-});
+        for node in root_nodes {
+            commands.push(make_move_along_axis_command(graph, node, axis, target));
+        }
+    }
 
-define_node_command!(SetTagCommand("Set Tag", String) where fn swap(self, node) {
-    get_set_swap!(self, node, tag_owned, set_tag);
-});
+    SceneCommand::CommandGroup(CommandGroup::from(commands))
+}
 
-define_node_command!(SetVisibleCommand("Set Visible", bool) where fn swap(self, node) {
-    get_set_swap!(self, node, visibility, set_visibility)
-});
+/// Creates command group that repositions selected nodes so distances between consecutive
+/// nodes along given axis are equal. Two extreme nodes stay in place. Only root nodes of the
+/// selection are distributed, their selected descendants follow them. Less than three root
+/// nodes give empty group, as well as coincident extremes.
+pub fn make_distribute_command(
+    selection: &GraphSelection,
+    graph: &Graph,
+    axis: Axis,
+) -> SceneCommand {
+    let mut nodes = selection
+        .root_nodes(graph)
+        .into_iter()
+        .map(|n| (n, graph[n].global_position()[axis.index()]))
+        .collect::<Vec<_>>();
 
-define_node_command!(SetFovCommand("Set Fov", f32) where fn swap(self, node) {
-    get_set_swap!(self, node.as_camera_mut(), fov, set_fov);
-});
+    let mut commands = Vec::new();
+    if nodes.len() >= 3 {
+        nodes.sort_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap());
+
+        let first = nodes[0].1;
+        let last = nodes[nodes.len() - 1].1;
+        if (last - first).abs() > f32::EPSILON {
+            let step = (last - first) / (nodes.len() - 1) as f32;
+            for (i, &(node, _)) in nodes.iter().enumerate().skip(1).take(nodes.len() - 2) {
+                commands.push(make_move_along_axis_command(
+                    graph,
+                    node,
+                    axis,
+                    first + step * i as f32,
+                ));
+            }
+        }
+    }
 
-define_node_command!(SetZNearCommand("Set Camera Z Near", f32) where fn swap(self, node) {
-    get_set_swap!(self, node.as_camera_mut(), z_near, set_z_near);
-});
+    SceneCommand::CommandGroup(CommandGroup::from(commands))
+}
+
+/// Creates command that adds a camera placed exactly where editor camera is now, looking
+/// in the same direction.
+pub fn make_add_camera_from_view_command(
+    camera_controller: &CameraController,
+    graph: &Graph,
+) -> AddNodeCommand {
+    // New camera will be attached to the scene root, which has identity transform, so world
+    // transform of editor camera (it includes transforms of camera rig) is used as local one.
+    let (position, rotation, _) =
+        decompose_transform_matrix(&graph[camera_controller.camera].global_transform());
+
+    let node = CameraBuilder::new(
+        BaseBuilder::new().with_name("Camera").with_local_transform(
+            TransformBuilder::new()
+                .with_local_position(position)
+                .with_local_rotation(rotation)
+                .build(),
+        ),
+    )
+    .with_fov(75.0f32.to_radians())
+    .with_z_near(0.025)
+    .with_z_far(2048.0)
+    .build_node();
+
+    AddNodeCommand::new(node)
+}
+
+/// Merged surfaces are split when amount of vertices would exceed this limit, triangles use
+/// 32-bit indices but huge surfaces are bad for culling and GPU uploads anyway.
+const MAX_MERGED_SURFACE_VERTICES: usize = u16::MAX as usize;
+
+/// Material of a surface, surfaces with the same material can be merged into one.
+#[derive(PartialEq)]
+struct SurfaceMaterial {
+    diffuse_texture: Option<Texture>,
+    normal_texture: Option<Texture>,
+    color: Color,
+}
+
+/// Creates command that combines selected static meshes into single mesh node attached to
+/// the scene root. Vertices are transformed into world space and surfaces with the same
+/// material are concatenated. Only meshes without children, bones and physical bodies are
+/// merged, other nodes are skipped and reported in log. Returns `None` if there is less than
+/// two meshes to merge.
+pub fn make_merge_meshes_command(
+    selection: &GraphSelection,
+    graph: &Graph,
+    physics: &Physics,
+    message_sender: &Sender<Message>,
+) -> Option<SceneCommand> {
+    let mut meshes = Vec::new();
+    for &handle in selection.nodes() {
+        let node = &graph[handle];
+        let reason = match node {
+            Node::Mesh(mesh) => {
+                if !mesh.children().is_empty() {
+                    Some("it has children")
+                } else if physics.binder.value_of(&handle).is_some() {
+                    Some("it has physical body")
+                } else if mesh.surfaces().iter().any(|s| !s.bones().is_empty()) {
+                    Some("it is skinned")
+                } else {
+                    meshes.push(handle);
+                    None
+                }
+            }
+            _ => Some("it is not a mesh"),
+        };
+        if let Some(reason) = reason {
+            message_sender
+                .send(Message::Log(format!(
+                    "Node {} was not merged, because {}.",
+                    node.name(),
+                    reason
+                )))
+                .unwrap();
+        }
+    }
 
-define_node_command!(SetZFarCommand("Set Camera Z Far", f32) where fn swap(self, node) {
-    get_set_swap!(self, node.as_camera_mut(), z_far, set_z_far);
-});
+    if meshes.len() < 2 {
+        return None;
+    }
 
-define_node_command!(SetParticleSystemAccelerationCommand("Set Particle System Acceleration", Vector3<f32>) where fn swap(self, node) {
-    get_set_swap!(self, node.as_particle_system_mut(), acceleration, set_acceleration);
-});
+    let mut groups: Vec<(SurfaceMaterial, Vec<SurfaceSharedData>)> = Vec::new();
+    for &handle in meshes.iter() {
+        let mesh = graph[handle].as_mesh();
+        let transform = mesh.global_transform();
+        let basis = Matrix3::from_columns(&[transform.side(), transform.up(), transform.look()]);
+        let normal_matrix = basis
+            .try_inverse()
+            .unwrap_or_else(Matrix3::identity)
+            .transpose();
 
-define_node_command!(SetSpriteSizeCommand("Set Sprite Size", f32) where fn swap(self, node) {
-    get_set_swap!(self, node.as_sprite_mut(), size, set_size);
-});
+        for surface in mesh.surfaces() {
+            let material = SurfaceMaterial {
+                diffuse_texture: surface.diffuse_texture(),
+                normal_texture: surface.normal_texture(),
+                color: surface.color(),
+            };
 
-define_node_command!(SetSpriteRotationCommand("Set Sprite Rotation", f32) where fn swap(self, node) {
-    get_set_swap!(self, node.as_sprite_mut(), rotation, set_rotation);
-});
+            let data = surface.data();
+            let data = data.read().unwrap();
 
-define_node_command!(SetSpriteColorCommand("Set Sprite Color", Color) where fn swap(self, node) {
-    get_set_swap!(self, node.as_sprite_mut(), color, set_color);
-});
+            let index = match groups.iter().position(|(m, _)| *m == material) {
+                Some(index) => index,
+                None => {
+                    groups.push((material, Vec::new()));
+                    groups.len() - 1
+                }
+            };
+            let datas = &mut groups[index].1;
+
+            // Start new surface if current one would become too big.
+            let fits = datas.last().map_or(false, |last| {
+                last.get_vertices().len() + data.get_vertices().len() <= MAX_MERGED_SURFACE_VERTICES
+            });
+            if !fits {
+                datas.push(SurfaceSharedData::new(Vec::new(), Vec::new(), true));
+            }
+            let merged = datas.last_mut().unwrap();
+
+            let base_index = merged.get_vertices().len() as u32;
+            let mut vertices = merged.get_vertices().to_vec();
+            vertices.extend(data.get_vertices().iter().map(|vertex| {
+                let mut vertex = *vertex;
+                vertex.position = transform
+                    .transform_point(&Point3::from(vertex.position))
+                    .coords;
+                vertex.normal = (normal_matrix * vertex.normal)
+                    .try_normalize(f32::EPSILON)
+                    .unwrap_or(vertex.normal);
+                let tangent = (basis * vertex.tangent.xyz())
+                    .try_normalize(f32::EPSILON)
+                    .unwrap_or_else(|| vertex.tangent.xyz());
+                vertex.tangent = tangent.push(vertex.tangent.w);
+                vertex
+            }));
+            let mut triangles = merged.triangles().to_vec();
+            triangles.extend(data.triangles().iter().map(|triangle| {
+                TriangleDefinition([
+                    triangle[0] + base_index,
+                    triangle[1] + base_index,
+                    triangle[2] + base_index,
+                ])
+            }));
+            *merged = SurfaceSharedData::new(vertices, triangles, true);
+        }
+    }
 
-define_node_command!(SetSpriteTextureCommand("Set Sprite Texture", Option<Texture>) where fn swap(self, node) {
-    get_set_swap!(self, node.as_sprite_mut(), texture, set_texture);
-});
+    let mut surfaces = Vec::new();
+    for (material, datas) in groups {
+        for data in datas {
+            let mut surface = Surface::new(Arc::new(RwLock::new(data)));
+            surface.set_diffuse_texture(material.diffuse_texture.clone());
+            surface.set_normal_texture(material.normal_texture.clone());
+            surface.set_color(material.color);
+            surfaces.push(surface);
+        }
+    }
 
-define_node_command!(SetParticleSystemTextureCommand("Set Particle System Texture", Option<Texture>) where fn swap(self, node) {
-    get_set_swap!(self, node.as_particle_system_mut(), texture, set_texture);
-});
+    let merged_mesh = MeshBuilder::new(BaseBuilder::new().with_name("MergedMesh"))
+        .with_surfaces(surfaces)
+        .build_node();
 
-define_node_command!(SetMeshCastShadowsCommand("Set Mesh Cast Shadows", bool) where fn swap(self, node) {
-    get_set_swap!(self, node.as_mesh_mut(), cast_shadows, set_cast_shadows);
-});
+    let mut commands = vec![
+        SceneCommand::ChangeSelection(ChangeSelectionCommand::new(
+            Default::default(),
+            Selection::Graph(selection.clone()),
+        )),
+        SceneCommand::AddNode(AddNodeCommand::new(merged_mesh)),
+    ];
+    for mesh in meshes {
+        commands.push(SceneCommand::DeleteNode(DeleteNodeCommand::new(mesh)));
+    }
 
-define_node_command!(SetMeshRenderPathCommand("Set Mesh Render Path", RenderPath) where fn swap(self, node) {
-    get_set_swap!(self, node.as_mesh_mut(), render_path, set_render_path);
-});
+    Some(SceneCommand::CommandGroup(CommandGroup::from(commands)))
+}
 
-define_body_command!(SetBodyMassCommand("Set Body Mass", f32) where fn swap(self, physics, body) {
-    std::mem::swap(&mut body.mass, &mut self.value);
-});
+/// Creates command group that replaces a mesh with a pivot which has one child mesh per
+/// distinct material of surfaces of the mesh. Pivot takes place of the mesh in hierarchy,
+/// gets its local transform and base properties (name, tag, visibility, lifetime), so
+/// surfaces stay at the same place. Returns `None` if the mesh can't be split.
+pub fn make_explode_mesh_by_material_command(
+    node: Handle<Node>,
+    graph: &Graph,
+    physics: &Physics,
+    message_sender: &Sender<Message>,
+) -> Option<SceneCommand> {
+    let node_ref = &graph[node];
+    let reason = match node_ref {
+        Node::Mesh(mesh) => {
+            if !mesh.children().is_empty() {
+                Some("it has children")
+            } else if physics.binder.value_of(&node).is_some() {
+                Some("it has physical body")
+            } else if mesh.surfaces().iter().any(|s| !s.bones().is_empty()) {
+                Some("it is skinned")
+            } else {
+                None
+            }
+        }
+        _ => Some("it is not a mesh"),
+    };
+    if let Some(reason) = reason {
+        message_sender
+            .send(Message::Log(format!(
+                "Node {} was not split, because {}.",
+                node_ref.name(),
+                reason
+            )))
+            .unwrap();
+        return None;
+    }
+
+    let mesh = node_ref.as_mesh();
+
+    let mut groups: Vec<(SurfaceMaterial, Vec<Surface>)> = Vec::new();
+    for surface in mesh.surfaces() {
+        let material = SurfaceMaterial {
+            diffuse_texture: surface.diffuse_texture(),
+            normal_texture: surface.normal_texture(),
+            color: surface.color(),
+        };
+        match groups.iter().position(|(m, _)| *m == material) {
+            Some(index) => groups[index].1.push(surface.clone()),
+            None => groups.push((material, vec![surface.clone()])),
+        }
+    }
 
-define_collider_command!(SetColliderFrictionCommand("Set Collider Friction", f32) where fn swap(self, physics, collider) {
-    std::mem::swap(&mut collider.friction, &mut self.value);
-});
+    if groups.len() < 2 {
+        message_sender
+            .send(Message::Log(format!(
+                "Node {} was not split, because it has single material.",
+                mesh.name()
+            )))
+            .unwrap();
+        return None;
+    }
+
+    // Build new hierarchy in a clipboard and paste it, this way whole hierarchy will be
+    // added to the scene at once.
+    let mut clipboard = Clipboard::default();
+    let children = groups
+        .into_iter()
+        .enumerate()
+        .map(|(i, (_, surfaces))| {
+            MeshBuilder::new(BaseBuilder::new().with_name(format!("{}_{}", mesh.name(), i)))
+                .with_surfaces(surfaces)
+                .build(&mut clipboard.graph)
+        })
+        .collect::<Vec<_>>();
+    let mut pivot = BaseBuilder::new()
+        .with_name(mesh.name())
+        .with_tag(mesh.tag().to_owned())
+        .with_visibility(mesh.visibility())
+        .with_local_transform(mesh.local_transform().clone())
+        .with_children(&children);
+    if let Some(lifetime) = mesh.lifetime() {
+        pivot = pivot.with_lifetime(lifetime);
+    }
+    pivot.build(&mut clipboard.graph);
+    clipboard.empty = false;
+
+    Some(SceneCommand::CommandGroup(CommandGroup::from(vec![
+        SceneCommand::Paste(PasteCommand::from_clipboard(clipboard).with_parent(mesh.parent())),
+        SceneCommand::DeleteNode(DeleteNodeCommand::new(node)),
+    ])))
+}
+
+/// Creates command group that replaces selected nodes with instances of a model, each
+/// instance gets world transform of the node it replaces. New instances become selected.
+pub fn make_replace_with_model_command(
+    editor_scene: &EditorScene,
+    engine: &GameEngine,
+    path: &Path,
+) -> Result<SceneCommand, String> {
+    // Make sure that the model could be loaded, instances will use the same (cached) resource.
+    rg3d::core::futures::executor::block_on(engine.resource_manager.request_model(path))
+        .map_err(|e| format!("Unable to load model {}. Reason: {:?}", path.display(), e))?;
 
-define_collider_command!(SetColliderRestitutionCommand("Set Collider Restitution", f32) where fn swap(self, physics, collider) {
-    std::mem::swap(&mut collider.restitution, &mut self.value);
-});
+    let graph = &engine.scenes[editor_scene.scene].graph;
 
-define_collider_command!(SetColliderPositionCommand("Set Collider Position", Vector3<f32>) where fn swap(self, physics, collider) {
-    std::mem::swap(&mut collider.translation, &mut self.value);
-});
+    let root_nodes = if let Selection::Graph(selection) = &editor_scene.selection {
+        selection.root_nodes(graph)
+    } else {
+        Default::default()
+    };
 
-define_collider_command!(SetColliderRotationCommand("Set Collider Rotation", UnitQuaternion<f32>) where fn swap(self, physics, collider) {
-    std::mem::swap(&mut collider.rotation, &mut self.value);
-});
+    let mut commands = vec![make_delete_selection_command(editor_scene, graph)];
+    for node in root_nodes {
+        if node == graph.get_root() {
+            continue;
+        }
+        commands.push(SceneCommand::LoadModel(
+            LoadModelCommand::new(path.to_owned())
+                .with_transform(calculate_keep_global_transform(
+                    graph,
+                    node,
+                    graph.get_root(),
+                ))
+                .with_instance_selected(),
+        ));
+    }
+
+    Ok(SceneCommand::CommandGroup(CommandGroup::from(commands)))
+}
+
+/// Mass that is given to bodies without volume (bodies without colliders or with flat
+/// colliders) when total mass is distributed between bodies.
+pub const MIN_DISTRIBUTED_BODY_MASS: f32 = 0.01;
+
+/// Creates command group that sets mass of each given body proportional to volume of its
+/// colliders, so sum of masses will be equal to `total_mass`. Bodies without volume get
+/// [`MIN_DISTRIBUTED_BODY_MASS`].
+pub fn make_distribute_mass_command(
+    total_mass: f32,
+    bodies: &[Handle<RigidBody>],
+    physics: &Physics,
+) -> SceneCommand {
+    let volumes = bodies
+        .iter()
+        .map(|&body| {
+            physics.bodies[body]
+                .colliders
+                .iter()
+                .map(|&collider| {
+                    collider_shape_volume(
+                        &physics.colliders[Handle::<Collider>::from(collider)].shape,
+                    )
+                })
+                .sum::<f32>()
+        })
+        .collect::<Vec<_>>();
 
-define_collider_command!(SetColliderIsSensorCommand("Set Collider Is Sensor", bool) where fn swap(self, physics, collider) {
-    std::mem::swap(&mut collider.is_sensor, &mut self.value);
-});
+    let massless_count = volumes.iter().filter(|&&v| v <= 0.0).count();
+    let total_volume = volumes.iter().sum::<f32>();
+
+    // Do not let minimal masses exceed requested total mass.
+    let min_mass = if massless_count > 0 {
+        MIN_DISTRIBUTED_BODY_MASS.min(total_mass / massless_count as f32)
+    } else {
+        0.0
+    };
+
+    let (distributed_mass, min_mass) = if total_volume > 0.0 {
+        (total_mass - min_mass * massless_count as f32, min_mass)
+    } else {
+        // No body has volume, split mass evenly.
+        (0.0, total_mass / bodies.len().max(1) as f32)
+    };
+
+    let commands = bodies
+        .iter()
+        .zip(volumes.iter())
+        .map(|(&body, &volume)| {
+            let mass = if volume > 0.0 {
+                distributed_mass * volume / total_volume
+            } else {
+                min_mass
+            };
+            SceneCommand::SetBodyMass(SetBodyMassCommand::new(body, mass))
+        })
+        .collect::<Vec<_>>();
 
-define_collider_command!(SetColliderCollisionGroupsCommand("Set Collider Collision Groups", u32) where fn swap(self, physics, collider) {
-    std::mem::swap(&mut collider.collision_groups, &mut self.value);
-});
+    SceneCommand::CommandGroup(CommandGroup::from(commands))
+}
+
+/// Creates command group that sets given texture to every selected node that can have a
+/// texture (meshes, sprites and particle systems). Other nodes are skipped with a message.
+/// Linked duplicates of selected meshes get the texture too.
+pub fn make_set_texture_on_selection_command(
+    selection: &GraphSelection,
+    texture: Texture,
+    graph: &Graph,
+    message_sender: &Sender<Message>,
+) -> Option<SceneCommand> {
+    let mut commands = Vec::new();
+    for &handle in selection.nodes() {
+        let node = &graph[handle];
+        match node {
+            Node::Mesh(_) => {
+                commands.push(SceneCommand::SetMeshTexture(SetMeshTextureCommand::new(
+                    handle,
+                    texture.clone(),
+                )));
+            }
+            Node::Sprite(_) => commands.push(SceneCommand::SetSpriteTexture(
+                SetSpriteTextureCommand::new(handle, Some(texture.clone())),
+            )),
+            Node::ParticleSystem(_) => commands.push(SceneCommand::SetParticleSystemTexture(
+                SetParticleSystemTextureCommand::new(handle, Some(texture.clone())),
+            )),
+            _ => message_sender
+                .send(Message::Log(format!(
+                    "Texture was not set to node {}, because it can't have a texture.",
+                    node.name()
+                )))
+                .unwrap(),
+        }
+    }
 
-define_collider_variant_command!(SetCylinderHalfHeightCommand("Set Cylinder Half Height", f32) where fn swap(self, physics, Cylinder, cylinder) {
-    std::mem::swap(&mut cylinder.half_height, &mut self.value);
-});
+    if commands.is_empty() {
+        None
+    } else {
+        Some(SceneCommand::CommandGroup(CommandGroup::from(commands)))
+    }
+}
 
-define_collider_variant_command!(SetCylinderRadiusCommand("Set Cylinder Radius", f32) where fn swap(self, physics, Cylinder, cylinder) {
-    std::mem::swap(&mut cylinder.radius, &mut self.value);
-});
+/// Textures are the same if they're the same resource or were loaded from the same path,
+/// a texture could be loaded more than once (for example when cache was purged). Procedural
+/// textures (render targets, etc.) have empty path, so they're compared only by identity.
+fn is_same_texture(a: &Texture, b: &Option<Texture>) -> bool {
+    match b {
+        Some(b) => {
+            if a == b {
+                return true;
+            }
+            // Paths are taken one by one, `a` and `b` could share the same lock.
+            let a_path = a.state().path().to_path_buf();
+            let b_path = b.state().path();
+            a_path != Path::new("") && a_path == b_path
+        }
+        None => false,
+    }
+}
 
-define_collider_variant_command!(SetConeHalfHeightCommand("Set Cone Half Height", f32) where fn swap(self, physics, Cone, cone) {
-    std::mem::swap(&mut cone.half_height, &mut self.value);
-});
+/// Creates command group that replaces every use of `old` texture in the scene with `new`
+/// one: diffuse and normal textures of mesh surfaces, textures of sprites, particle systems
+/// and spot light cookies. Editor's own nodes are left untouched. Amount of replaced usages
+/// is reported in log. Returns `None` if the texture is not used.
+pub fn make_replace_texture_command(
+    editor_scene: &EditorScene,
+    graph: &Graph,
+    old: &Texture,
+    new: Texture,
+    message_sender: &Sender<Message>,
+) -> Option<SceneCommand> {
+    let mut commands = Vec::new();
+    let mut stack = vec![graph.get_root()];
+    while let Some(handle) = stack.pop() {
+        if editor_scene.is_editor_node(handle, graph) {
+            continue;
+        }
+        let node = &graph[handle];
+        stack.extend_from_slice(node.children());
+        match node {
+            Node::Mesh(mesh) => {
+                for (index, surface) in mesh.surfaces().iter().enumerate() {
+                    if is_same_texture(old, &surface.diffuse_texture()) {
+                        commands.push(SceneCommand::SetSurfaceDiffuseTexture(
+                            SetSurfaceDiffuseTextureCommand::new(handle, index, Some(new.clone())),
+                        ));
+                    }
+                    if is_same_texture(old, &surface.normal_texture()) {
+                        commands.push(SceneCommand::SetSurfaceNormalTexture(
+                            SetSurfaceNormalTextureCommand::new(handle, index, Some(new.clone())),
+                        ));
+                    }
+                }
+            }
+            Node::Sprite(sprite) => {
+                if is_same_texture(old, &sprite.texture()) {
+                    commands.push(SceneCommand::SetSpriteTexture(
+                        SetSpriteTextureCommand::new(handle, Some(new.clone())),
+                    ));
+                }
+            }
+            Node::ParticleSystem(particle_system) => {
+                if is_same_texture(old, &particle_system.texture()) {
+                    commands.push(SceneCommand::SetParticleSystemTexture(
+                        SetParticleSystemTextureCommand::new(handle, Some(new.clone())),
+                    ));
+                }
+            }
+            Node::Light(Light::Spot(spot)) => {
+                if is_same_texture(old, &spot.cookie_texture()) {
+                    commands.push(SceneCommand::SetSpotLightCookieTexture(
+                        SetSpotLightCookieTextureCommand::new(handle, Some(new.clone())),
+                    ));
+                }
+            }
+            _ => (),
+        }
+    }
 
-define_collider_variant_command!(SetConeRadiusCommand("Set Cone Radius", f32) where fn swap(self, physics, Cone, cone) {
-    std::mem::swap(&mut cone.radius, &mut self.value);
-});
+    // Paths are taken one by one, `old` and `new` could share the same lock.
+    let old_path = old.state().path().to_string_lossy().into_owned();
+    let new_path = new.state().path().to_string_lossy().into_owned();
+    message_sender
+        .send(Message::Log(format!(
+            "{} usages of texture {} were replaced with {}.",
+            commands.len(),
+            old_path,
+            new_path
+        )))
+        .unwrap();
+
+    if commands.is_empty() {
+        None
+    } else {
+        Some(SceneCommand::CommandGroup(CommandGroup::from(commands)))
+    }
+}
 
-define_collider_variant_command!(SetCuboidHalfExtentsCommand("Set Cuboid Half Extents", Vector3<f32>) where fn swap(self, physics, Cuboid, cuboid) {
-    std::mem::swap(&mut cuboid.half_extents, &mut self.value);
-});
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MirrorMode {
+    /// Selected nodes are mirrored.
+    InPlace,
+    /// Mirrored copies of selected nodes are created, selected nodes stay as is.
+    Copy,
+}
 
-define_collider_variant_command!(SetCapsuleRadiusCommand("Set Capsule Radius", f32) where fn swap(self, physics, Capsule, capsule) {
-    std::mem::swap(&mut capsule.radius, &mut self.value);
-});
+/// Calculates local transform of a node which mirrors the node relative to `mirror` plane.
+/// Mirroring flips handedness, which can't be represented by rotation, so the node gets
+/// negative scale on flipped axis (this also keeps correct winding of triangles).
+fn calculate_mirrored_transform(
+    graph: &Graph,
+    node: Handle<Node>,
+    parent_inv_transform: &Matrix4<f32>,
+    mirror: &Matrix4<f32>,
+    axis: Axis,
+) -> Transform {
+    let mut flip = Vector3::repeat(1.0);
+    flip[axis.index()] = -1.0;
+
+    let (position, rotation, mut scale) = decompose_transform_matrix(
+        &(parent_inv_transform
+            * mirror
+            * graph[node].global_transform()
+            * Matrix4::new_nonuniform_scaling(&flip)),
+    );
+    scale[axis.index()] = -scale[axis.index()];
+
+    TransformBuilder::new()
+        .with_local_position(position)
+        .with_local_rotation(rotation)
+        .with_local_scale(scale)
+        .build()
+}
+
+/// Creates command that mirrors selected nodes relative to a plane which passes through
+/// center of the selection and is perpendicular to given world axis.
+pub fn make_mirror_command(
+    editor_scene: &EditorScene,
+    graph: &Graph,
+    axis: Axis,
+    mode: MirrorMode,
+) -> Option<SceneCommand> {
+    let selection = if let Selection::Graph(selection) = &editor_scene.selection {
+        selection
+    } else {
+        return None;
+    };
 
-define_collider_variant_command!(SetCapsuleBeginCommand("Set Capsule Begin", Vector3<f32>) where fn swap(self, physics, Capsule, capsule) {
-    std::mem::swap(&mut capsule.begin, &mut self.value);
-});
+    let (_, center) = selection.global_rotation_position(graph)?;
 
-define_collider_variant_command!(SetCapsuleEndCommand("Set Capsule End", Vector3<f32>) where fn swap(self, physics, Capsule, capsule) {
-    std::mem::swap(&mut capsule.end, &mut self.value);
-});
+    let mut flip = Vector3::repeat(1.0);
+    flip[axis.index()] = -1.0;
+    let mirror = Matrix4::new_translation(&center)
+        * Matrix4::new_nonuniform_scaling(&flip)
+        * Matrix4::new_translation(&-center);
 
-define_collider_variant_command!(SetBallRadiusCommand("Set Ball Radius", f32) where fn swap(self, physics, Ball, ball) {
-    std::mem::swap(&mut ball.radius, &mut self.value);
-});
+    let root_nodes = selection
+        .root_nodes(graph)
+        .into_iter()
+        .filter(|&node| node != graph.get_root() && !editor_scene.is_editor_node(node, graph))
+        .collect::<Vec<_>>();
 
-define_joint_variant_command!(SetBallJointAnchor1Command("Set Ball Joint Anchor 1", Vector3<f32>) where fn swap(self, physics, BallJoint, ball) {
-    std::mem::swap(&mut ball.local_anchor1, &mut self.value);
-});
+    match mode {
+        MirrorMode::InPlace => {
+            let commands = root_nodes
+                .into_iter()
+                .map(|node| {
+                    let parent_inv_transform = graph[graph[node].parent()]
+                        .global_transform()
+                        .try_inverse()
+                        .unwrap_or_else(Matrix4::identity);
+                    SceneCommand::SetLocalTransform(SetLocalTransformCommand::new(
+                        node,
+                        calculate_mirrored_transform(
+                            graph,
+                            node,
+                            &parent_inv_transform,
+                            &mirror,
+                            axis,
+                        ),
+                    ))
+                })
+                .collect::<Vec<_>>();
+
+            Some(SceneCommand::CommandGroup(CommandGroup::from(commands)))
+        }
+        MirrorMode::Copy => {
+            let mut clipboard = Clipboard::default();
+            clipboard.fill_from_selection(
+                &GraphSelection::from_list(root_nodes.clone()),
+                graph,
+                &editor_scene.physics,
+            );
 
-define_joint_variant_command!(SetBallJointAnchor2Command("Set Ball Joint Anchor 2", Vector3<f32>) where fn swap(self, physics, BallJoint, ball) {
-    std::mem::swap(&mut ball.local_anchor2, &mut self.value);
-});
+            // Copies are attached to the root of clipboard (and then to the root of scene),
+            // so their local transforms are global transforms.
+            let copies = clipboard.graph[clipboard.graph.get_root()]
+                .children()
+                .to_vec();
+            for (copy, original) in copies.into_iter().zip(root_nodes) {
+                let transform = calculate_mirrored_transform(
+                    graph,
+                    original,
+                    &Matrix4::identity(),
+                    &mirror,
+                    axis,
+                );
 
-define_joint_variant_command!(SetFixedJointAnchor1TranslationCommand("Set Fixed Joint Anchor 1 Translation", Vector3<f32>) where fn swap(self, physics, FixedJoint, fixed) {
-    std::mem::swap(&mut fixed.local_anchor1_translation, &mut self.value);
-});
+                if let Some(&body) = clipboard.physics.binder.value_of(&copy) {
+                    let body = &mut clipboard.physics.bodies[body];
+                    body.position = **transform.position();
+                    body.rotation = **transform.rotation();
+                }
 
-define_joint_variant_command!(SetFixedJointAnchor2TranslationCommand("Set Fixed Joint Anchor 2 Translation", Vector3<f32>) where fn swap(self, physics, FixedJoint, fixed) {
-    std::mem::swap(&mut fixed.local_anchor2_translation, &mut self.value);
-});
+                *clipboard.graph[copy].local_transform_mut() = transform;
+            }
 
-define_joint_variant_command!(SetFixedJointAnchor1RotationCommand("Set Fixed Joint Anchor 1 Rotation", UnitQuaternion<f32>) where fn swap(self, physics, FixedJoint, fixed) {
-    std::mem::swap(&mut fixed.local_anchor1_rotation, &mut self.value);
-});
+            Some(SceneCommand::Paste(PasteCommand::from_clipboard(clipboard)))
+        }
+    }
+}
 
-define_joint_variant_command!(SetFixedJointAnchor2RotationCommand("Set Fixed Joint Anchor 2 Rotation", UnitQuaternion<f32>) where fn swap(self, physics, FixedJoint, fixed) {
-    std::mem::swap(&mut fixed.local_anchor2_rotation, &mut self.value);
-});
+/// Creates command that selects nodes found by [`EditorScene::find_nodes`].
+pub fn make_select_found_command(
+    editor_scene: &EditorScene,
+    found: Vec<Handle<Node>>,
+) -> ChangeSelectionCommand {
+    ChangeSelectionCommand::new(
+        Selection::Graph(GraphSelection::from_list(found)),
+        editor_scene.selection.clone(),
+    )
+}
 
-define_joint_variant_command!(SetRevoluteJointAnchor1Command("Set Revolute Joint Anchor 1", Vector3<f32>) where fn swap(self, physics, RevoluteJoint, revolute) {
-    std::mem::swap(&mut revolute.local_anchor1, &mut self.value);
-});
+/// Creates command that extends current selection with all descendants of selected nodes,
+/// editor nodes are never selected.
+pub fn make_select_hierarchy_command(
+    editor_scene: &EditorScene,
+    graph: &Graph,
+) -> Option<ChangeSelectionCommand> {
+    if let Selection::Graph(selection) = &editor_scene.selection {
+        let new_selection = selection
+            .with_descendants(graph)
+            .filtered(graph, |handle, _| {
+                !editor_scene.is_editor_node(handle, graph)
+            });
+        Some(ChangeSelectionCommand::new(
+            Selection::Graph(new_selection),
+            editor_scene.selection.clone(),
+        ))
+    } else {
+        None
+    }
+}
 
-define_joint_variant_command!(SetRevoluteJointAxis1Command("Set Revolute Joint Axis 1", Vector3<f32>) where fn swap(self, physics, RevoluteJoint, revolute) {
-    std::mem::swap(&mut revolute.local_axis1, &mut self.value);
-});
+/// Tag of mesh nodes made by [`make_navmesh_debug_mesh_command`].
+pub const NAVMESH_DEBUG_MESH_TAG: &str = "navmesh_debug";
 
-define_joint_variant_command!(SetRevoluteJointAnchor2Command("Set Revolute Joint Anchor 2", Vector3<f32>) where fn swap(self, physics, RevoluteJoint, revolute) {
-    std::mem::swap(&mut revolute.local_anchor2, &mut self.value);
-});
+/// Creates command that adds translucent mesh made of navmesh triangles to the scene, so
+/// the navmesh could be seen in a game that has no navmesh renderer. Unlike editor's
+/// navmesh overlay, the mesh is a regular scene node and it is saved with the scene.
+pub fn make_navmesh_debug_mesh_command(
+    editor_scene: &EditorScene,
+    navmesh: Handle<Navmesh>,
+) -> SceneCommand {
+    let navmesh = &editor_scene.navmeshes[navmesh];
+
+    // Sparse-to-dense mapping - handle to index.
+    let mut vertex_map = HashMap::new();
+    let vertices = navmesh
+        .vertices
+        .pair_iter()
+        .enumerate()
+        .map(|(i, (handle, vertex))| {
+            vertex_map.insert(handle, i);
+            // Lift the mesh a bit to prevent z-fighting with the floor.
+            let mut vertex = Vertex::from_pos_uv(
+                vertex.position + Vector3::new(0.0, 0.01, 0.0),
+                Vector2::default(),
+            );
+            vertex.normal = Vector3::y();
+            vertex
+        })
+        .collect::<Vec<_>>();
 
-define_joint_variant_command!(SetRevoluteJointAxis2Command("Set Prismatic Joint Axis 2", Vector3<f32>) where fn swap(self, physics, RevoluteJoint, revolute) {
-    std::mem::swap(&mut revolute.local_axis2, &mut self.value);
-});
+    let triangles = navmesh
+        .triangles
+        .iter()
+        .map(|triangle| {
+            TriangleDefinition([
+                vertex_map[&triangle.a] as u32,
+                vertex_map[&triangle.b] as u32,
+                vertex_map[&triangle.c] as u32,
+            ])
+        })
+        .collect::<Vec<_>>();
 
-define_joint_variant_command!(SetPrismaticJointAnchor1Command("Set Prismatic Joint Anchor 1", Vector3<f32>) where fn swap(self, physics, PrismaticJoint, prismatic) {
-    std::mem::swap(&mut prismatic.local_anchor1, &mut self.value);
-});
+    let mut surface = Surface::new(Arc::new(RwLock::new(SurfaceSharedData::new(
+        vertices, triangles, true,
+    ))));
+    surface.set_color(Color::from_rgba(0, 200, 0, 120));
 
-define_joint_variant_command!(SetPrismaticJointAxis1Command("Set Prismatic Joint Axis 1", Vector3<f32>) where fn swap(self, physics, PrismaticJoint, prismatic) {
-    std::mem::swap(&mut prismatic.local_axis1, &mut self.value);
-});
+    let mut node = MeshBuilder::new(BaseBuilder::new().with_name("NavmeshDebug"))
+        .with_surfaces(vec![surface])
+        .build_node();
+    node.set_tag(NAVMESH_DEBUG_MESH_TAG.to_owned());
+    // Translucent surfaces are rendered in forward pass only.
+    node.as_mesh_mut().set_render_path(RenderPath::Forward);
 
-define_joint_variant_command!(SetPrismaticJointAnchor2Command("Set Prismatic Joint Anchor 2", Vector3<f32>) where fn swap(self, physics, PrismaticJoint, prismatic) {
-    std::mem::swap(&mut prismatic.local_anchor2, &mut self.value);
-});
+    SceneCommand::AddNode(AddNodeCommand::new(node))
+}
 
-define_joint_variant_command!(SetPrismaticJointAxis2Command("Set Prismatic Joint Axis 2", Vector3<f32>) where fn swap(self, physics, PrismaticJoint, prismatic) {
-    std::mem::swap(&mut prismatic.local_axis2, &mut self.value);
-});
+/// Creates command that deletes every mesh made by [`make_navmesh_debug_mesh_command`].
+/// Returns `None` if there are no such meshes.
+pub fn make_remove_navmesh_debug_meshes_command(graph: &Graph) -> Option<SceneCommand> {
+    let commands = graph
+        .pair_iter()
+        .filter(|(_, node)| node.tag() == NAVMESH_DEBUG_MESH_TAG)
+        .map(|(handle, _)| SceneCommand::DeleteSubGraph(DeleteSubGraphCommand::new(handle)))
+        .collect::<Vec<_>>();
 
-define_joint_command!(SetJointConnectedBodyCommand("Set Joint Connected Body", ErasedHandle) where fn swap(self, physics, joint) {
-    std::mem::swap(&mut joint.body2, &mut self.value);
-});
+    if commands.is_empty() {
+        None
+    } else {
+        Some(SceneCommand::CommandGroup(CommandGroup::from(commands)))
+    }
+}
 
-define_emitter_command!(SetEmitterPositionCommand("Set Emitter Position", Vector3<f32>) where fn swap(self, emitter) {
-    get_set_swap!(self, emitter, position, set_position);
-});
+/// Creates command that sets sky box of the scene. Sky box belongs to a camera, there is no
+/// notion of main camera, so the first camera of the scene (editor camera is not counted)
+/// gets the sky box. Returns `None` if the scene has no cameras.
+pub fn make_set_scene_skybox_command(
+    editor_scene: &EditorScene,
+    graph: &Graph,
+    skybox: Option<SkyBox>,
+    message_sender: &Sender<Message>,
+) -> Option<SceneCommand> {
+    let camera = graph
+        .traverse_handle_iter(graph.get_root())
+        .find(|&handle| {
+            matches!(graph[handle], Node::Camera(_)) && !editor_scene.is_editor_node(handle, graph)
+        });
 
-define_emitter_variant_command!(SetSphereEmitterRadiusCommand("Set Sphere Emitter Radius", f32) where fn swap(self, emitter, Sphere, sphere) {
-    get_set_swap!(self, sphere, radius, set_radius);
-});
+    match camera {
+        Some(camera) => Some(SceneCommand::SetCameraSkyBox(SetCameraSkyBoxCommand::new(
+            camera, skybox,
+        ))),
+        None => {
+            message_sender
+                .send(Message::Log(
+                    "Sky box was not set, because scene has no cameras.".to_owned(),
+                ))
+                .unwrap();
+            None
+        }
+    }
+}
 
-define_emitter_variant_command!(SetCylinderEmitterRadiusCommand("Set Cylinder Emitter Radius", f32) where fn swap(self, emitter, Cylinder, cylinder) {
-    get_set_swap!(self, cylinder, radius, set_radius);
-});
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::command::CommandStack;
+    use rg3d::{
+        core::algebra::Matrix4,
+        renderer::surface::{SurfaceBuilder, SurfaceSharedData},
+        resource::model::ModelData,
+        scene::{particle_system::ParticleSystemBuilder, sprite::SpriteBuilder},
+    };
+    use std::sync::mpsc::{channel, Receiver};
+
+    // Scene together with its editor data, commands are done and undone through command
+    // stack the same way the editor does it.
+    struct TestScene {
+        scene: Scene,
+        editor_scene: EditorScene,
+        command_stack: CommandStack<SceneCommand>,
+        sender: Sender<Message>,
+        receiver: Receiver<Message>,
+        resource_manager: ResourceManager,
+    }
+
+    impl TestScene {
+        fn new() -> Self {
+            let mut scene = Scene::new();
+            let root = BaseBuilder::new().build(&mut scene.graph);
+            let camera_controller = CameraController::new(&mut scene.graph, root);
+            let (sender, receiver) = channel();
+            Self {
+                editor_scene: EditorScene {
+                    path: None,
+                    scene: Handle::NONE,
+                    root,
+                    selection: Default::default(),
+                    clipboard: Default::default(),
+                    camera_controller,
+                    physics: Default::default(),
+                    collision_layers: Default::default(),
+                    navmeshes: Default::default(),
+                    surface_render_paths: Default::default(),
+                    emitter_texture_regions: Default::default(),
+                    emitter_acceleration_overrides: Default::default(),
+                    blend_shape_weights: Default::default(),
+                    linked_instances: Default::default(),
+                    static_nodes: Default::default(),
+                    wireframe_nodes: Default::default(),
+                    paused_particle_systems: Default::default(),
+                    paused_animations: Default::default(),
+                    pending_models: Default::default(),
+                },
+                scene,
+                command_stack: CommandStack::new(false),
+                sender,
+                receiver,
+                resource_manager: ResourceManager::new(),
+            }
+        }
 
-define_emitter_variant_command!(SetCylinderEmitterHeightCommand("Set Cylinder Emitter Radius", f32) where fn swap(self, emitter, Cylinder, cylinder) {
-    get_set_swap!(self, cylinder, height, set_height);
-});
+        fn do_command(&mut self, command: SceneCommand) {
+            self.command_stack.do_command(
+                command,
+                SceneContext {
+                    editor_scene: &mut self.editor_scene,
+                    scene: &mut self.scene,
+                    message_sender: self.sender.clone(),
+                    resource_manager: self.resource_manager.clone(),
+                },
+            );
+        }
 
-define_emitter_variant_command!(SetBoxEmitterHalfWidthCommand("Set Box Emitter Half Width", f32) where fn swap(self, emitter, Box, box_emitter) {
-    get_set_swap!(self, box_emitter, half_width, set_half_width);
-});
+        fn undo(&mut self) {
+            self.command_stack.undo(SceneContext {
+                editor_scene: &mut self.editor_scene,
+                scene: &mut self.scene,
+                message_sender: self.sender.clone(),
+                resource_manager: self.resource_manager.clone(),
+            });
+        }
 
-define_emitter_variant_command!(SetBoxEmitterHalfHeightCommand("Set Box Emitter Half Height", f32) where fn swap(self, emitter, Box, box_emitter) {
-    get_set_swap!(self, box_emitter, half_height, set_half_height);
-});
+        fn redo(&mut self) {
+            self.command_stack.redo(SceneContext {
+                editor_scene: &mut self.editor_scene,
+                scene: &mut self.scene,
+                message_sender: self.sender.clone(),
+                resource_manager: self.resource_manager.clone(),
+            });
+        }
 
-define_emitter_variant_command!(SetBoxEmitterHalfDepthCommand("Set Box Emitter Half Depth", f32) where fn swap(self, emitter, Box, box_emitter) {
-    get_set_swap!(self, box_emitter, half_depth, set_half_depth);
-});
+        // Saves the scene with editor data the same way the editor does it and loads it back
+        // together with visitor of the saved file, so side tables could be read.
+        fn save_and_load(&self, name: &str) -> (Scene, Visitor) {
+            let path = std::env::temp_dir().join(format!(
+                "rusty_editor_{}_{}.rgs",
+                name,
+                std::process::id()
+            ));
+            self.editor_scene.write(&self.scene, &path).unwrap();
+            let scene = rg3d::core::futures::executor::block_on(Scene::from_file(
+                &path,
+                self.resource_manager.clone(),
+            ))
+            .unwrap();
+            let visitor =
+                rg3d::core::futures::executor::block_on(Visitor::load_binary(&path)).unwrap();
+            std::fs::remove_file(&path).unwrap();
+            (scene, visitor)
+        }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub enum Selection {
-    None,
-    Graph(GraphSelection),
-    Navmesh(NavmeshSelection),
-}
+        fn select(&mut self, nodes: Vec<Handle<Node>>) {
+            self.editor_scene.selection = Selection::Graph(GraphSelection::from_list(nodes));
+        }
 
-impl Default for Selection {
-    fn default() -> Self {
-        Self::None
+        // Handles of every scene node with given name.
+        fn find(&self, name: &str) -> Vec<Handle<Node>> {
+            self.scene
+                .graph
+                .pair_iter()
+                .filter(|(_, node)| node.name() == name)
+                .map(|(handle, _)| handle)
+                .collect()
+        }
     }
-}
 
-impl Selection {
-    pub fn is_empty(&self) -> bool {
-        match self {
-            Selection::None => true,
-            Selection::Graph(graph) => graph.is_empty(),
-            Selection::Navmesh(navmesh) => navmesh.is_empty(),
+    // Adds box mesh to the graph and binds a body to it, if needed.
+    fn add_box(graph: &mut Graph, physics: &mut Physics, with_body: bool) -> Handle<Node> {
+        let node = MeshBuilder::new(BaseBuilder::new())
+            .with_surfaces(vec![SurfaceBuilder::new(Arc::new(RwLock::new(
+                SurfaceSharedData::make_cube(Matrix4::identity()),
+            )))
+            .build()])
+            .build(graph);
+        if with_body {
+            let body = physics.bodies.spawn(RigidBody::default());
+            physics.binder.insert(node, body);
+        }
+        node
+    }
+
+    #[test]
+    fn test_poll_pending_model() {
+        let (sender, receiver) = channel();
+        let path = PathBuf::from("model.fbx");
+        let mut pending_models = vec![(
+            path.clone(),
+            Model::new(ResourceState::new_pending(path.clone())),
+        )];
+
+        poll_models(&mut pending_models, Handle::new(1, 1), &sender);
+        assert_eq!(pending_models.len(), 1);
+        assert!(receiver.try_recv().is_err());
+
+        // Stub loader - resolve the model with empty data.
+        *pending_models[0].1.state() = ResourceState::Ok(ModelData::default());
+        poll_models(&mut pending_models, Handle::new(1, 1), &sender);
+        assert!(pending_models.is_empty());
+        match receiver.try_recv() {
+            Ok(Message::ModelLoaded {
+                scene,
+                path: loaded_path,
+                ..
+            }) => {
+                assert_eq!(scene, Handle::new(1, 1));
+                assert_eq!(loaded_path, path);
+            }
+            _ => panic!("ModelLoaded must be sent"),
         }
     }
-}
 
-#[derive(Debug, Default, Clone, Eq)]
-pub struct GraphSelection {
-    nodes: Vec<Handle<Node>>,
-}
+    #[test]
+    fn test_poll_failed_model() {
+        let (sender, receiver) = channel();
+        let path = PathBuf::from("model.fbx");
+        let mut pending_models = vec![(
+            path.clone(),
+            Model::new(ResourceState::LoadError { path, error: None }),
+        )];
+
+        poll_models(&mut pending_models, Handle::new(1, 1), &sender);
+        assert!(pending_models.is_empty());
+        assert!(matches!(receiver.try_recv(), Ok(Message::Log(_))));
+    }
+
+    #[test]
+    fn test_trimesh_collider_replaces_shape() {
+        let (sender, _receiver) = channel();
+        let mut graph = Graph::new();
+        let mut physics = Physics::default();
+        let node = add_box(&mut graph, &mut physics, true);
+        let body = *physics.binder.value_of(&node).unwrap();
+        let collider = physics.colliders.spawn(Collider {
+            parent: body.into(),
+            ..Default::default()
+        });
+        physics.bodies[body].colliders.push(collider.into());
 
-impl PartialEq for GraphSelection {
-    fn eq(&self, other: &Self) -> bool {
-        if self.nodes.is_empty() && !other.nodes.is_empty() {
-            false
-        } else {
-            // Selection is equal even when order of elements is different.
-            // TODO: Find a way to do this faster.
-            for &node in self.nodes.iter() {
-                let mut found = false;
-                for &other_node in other.nodes.iter() {
-                    if other_node == node {
-                        found = true;
-                        break;
-                    }
-                }
-                if !found {
-                    return false;
-                }
+        match make_trimesh_collider_command(node, &graph, &physics, &sender) {
+            Some(SceneCommand::SetColliderShape(command)) => {
+                assert_eq!(command.handle, collider);
+                assert!(matches!(command.value, ColliderShapeDesc::Trimesh(_)));
             }
-            true
+            _ => panic!("Shape of existing collider must be replaced"),
         }
     }
-}
 
-impl GraphSelection {
-    pub fn from_list(nodes: Vec<Handle<Node>>) -> Self {
-        Self {
-            nodes: nodes.into_iter().filter(|h| h.is_some()).collect(),
+    #[test]
+    fn test_trimesh_collider_is_added() {
+        let (sender, _receiver) = channel();
+        let mut graph = Graph::new();
+        let mut physics = Physics::default();
+        let node = add_box(&mut graph, &mut physics, true);
+
+        match make_trimesh_collider_command(node, &graph, &physics, &sender) {
+            Some(SceneCommand::SetCollider(command)) => {
+                assert_eq!(command.body, *physics.binder.value_of(&node).unwrap());
+                assert!(matches!(
+                    command.collider.as_ref().unwrap().shape,
+                    ColliderShapeDesc::Trimesh(_)
+                ));
+            }
+            _ => panic!("Collider must be added to a body without colliders"),
         }
     }
 
-    /// Creates new selection as single if node handle is not none, and empty if it is.
-    pub fn single_or_empty(node: Handle<Node>) -> Self {
-        if node.is_none() {
-            Self {
-                nodes: Default::default(),
-            }
-        } else {
-            Self { nodes: vec![node] }
+    #[test]
+    fn test_trimesh_collider_needs_geometry() {
+        let (sender, receiver) = channel();
+        let mut graph = Graph::new();
+        let mut physics = Physics::default();
+        let node = BaseBuilder::new().build(&mut graph);
+        let body = physics.bodies.spawn(RigidBody::default());
+        physics.binder.insert(node, body);
+
+        assert!(make_trimesh_collider_command(node, &graph, &physics, &sender).is_none());
+        assert!(matches!(receiver.try_recv(), Ok(Message::Log(_))));
+    }
+
+    #[test]
+    fn test_prune_autosaves() {
+        let dir =
+            std::env::temp_dir().join(format!("rusty_editor_autosaves_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for timestamp in 1..=5 {
+            std::fs::write(
+                dir.join(format!("{}{}.rgs", EditorScene::AUTOSAVE_PREFIX, timestamp)),
+                [],
+            )
+            .unwrap();
         }
+        std::fs::write(dir.join("scene.rgs"), []).unwrap();
+
+        EditorScene::prune_autosaves(&dir, 2).unwrap();
+
+        assert_eq!(
+            EditorScene::list_autosaves(&dir),
+            [
+                dir.join(format!("{}5.rgs", EditorScene::AUTOSAVE_PREFIX)),
+                dir.join(format!("{}4.rgs", EditorScene::AUTOSAVE_PREFIX)),
+            ]
+        );
+        assert!(dir.join("scene.rgs").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_blend_shape_weight_needs_mesh() {
+        let mut graph = Graph::new();
+        let mut physics = Physics::default();
+        let mesh = add_box(&mut graph, &mut physics, false);
+        let pivot = BaseBuilder::new().build(&mut graph);
+
+        assert!(SetMeshBlendShapeWeightCommand::new(&graph, pivot, 0, 0.5).is_none());
+        assert!(SetMeshBlendShapeWeightCommand::new(&graph, mesh, 0, f32::NAN).is_none());
+        let command = SetMeshBlendShapeWeightCommand::new(&graph, mesh, 0, 2.0).unwrap();
+        assert_eq!(command.value, Some(1.0));
+    }
+
+    // Saves whatever `write` puts in a visitor to a file and loads it back, the same way
+    // scenes are saved and loaded.
+    fn round_trip<F: FnOnce(&mut Visitor)>(name: &str, write: F) -> Visitor {
+        let path =
+            std::env::temp_dir().join(format!("rusty_editor_{}_{}.rgs", name, std::process::id()));
+        let mut visitor = Visitor::new();
+        write(&mut visitor);
+        visitor.save_binary(&path).unwrap();
+        let visitor = rg3d::core::futures::executor::block_on(Visitor::load_binary(&path)).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        visitor
+    }
+
+    #[test]
+    fn test_collision_layers_round_trip() {
+        let mut visitor = round_trip("collision_layers", |visitor| {
+            let mut collision_layers = CollisionLayers::default();
+            assert!(collision_layers.set("Player", 3));
+            collision_layers.visit("CollisionLayers", visitor).unwrap();
+        });
+
+        let collision_layers = CollisionLayers::load(&mut visitor);
+        assert_eq!(collision_layers.index_of("Player"), Some(3));
+        assert_eq!(collision_layers.iter().count(), 1);
     }
 
-    /// Adds new selected node, or removes it if it is already in the list of selected nodes.
-    pub fn insert_or_exclude(&mut self, handle: Handle<Node>) {
-        if let Some(position) = self.nodes.iter().position(|&h| h == handle) {
-            self.nodes.remove(position);
-        } else {
-            self.nodes.push(handle);
+    #[test]
+    fn test_missing_side_tables() {
+        // Scenes saved by older versions have no side tables at all.
+        let mut visitor = round_trip("no_side_tables", |visitor| {
+            let mut value = 0u32;
+            value.visit("Value", visitor).unwrap();
+        });
+
+        assert_eq!(CollisionLayers::load(&mut visitor).iter().count(), 0);
+        assert!(EditorScene::load_static_nodes(&mut visitor).is_empty());
+        assert!(EditorScene::load_navmesh_regions(&mut visitor).is_empty());
+        assert!(EditorScene::load_blend_shape_weights(&mut visitor).is_empty());
+        assert!(EditorScene::load_surface_render_paths(&mut visitor).is_empty());
+        assert!(EditorScene::load_emitter_texture_regions(&mut visitor).is_empty());
+        assert!(EditorScene::load_emitter_acceleration_overrides(&mut visitor).is_empty());
+    }
+
+    #[test]
+    fn test_collider_layer_commands_validate_layer() {
+        let collider = Handle::new(1, 1);
+        assert!(SetColliderMembershipCommand::new(collider, MAX_COLLISION_LAYERS, true).is_none());
+        assert!(SetColliderFilterCommand::new(collider, MAX_COLLISION_LAYERS, true).is_none());
+        assert!(SetColliderMembershipCommand::new(collider, 15, true).is_some());
+        assert!(SetColliderFilterCommand::new(collider, 0, false).is_some());
+    }
+
+    #[test]
+    fn test_cut_paste_undo() {
+        let mut test = TestScene::new();
+        let child = BaseBuilder::new()
+            .with_name("Child")
+            .build(&mut test.scene.graph);
+        let parent = BaseBuilder::new()
+            .with_name("Parent")
+            .with_children(&[child])
+            .build(&mut test.scene.graph);
+        test.select(vec![parent]);
+
+        let cut = make_cut_selection_command(&mut test.editor_scene, &test.scene.graph);
+        test.do_command(cut);
+        assert!(!test.scene.graph.is_valid_handle(parent));
+        assert!(test.find("Parent").is_empty());
+        assert!(!test.editor_scene.clipboard.is_empty());
+
+        test.do_command(SceneCommand::Paste(PasteCommand::new()));
+        let pasted = test.find("Parent");
+        assert_eq!(pasted.len(), 1);
+        let pasted_children = test.scene.graph[pasted[0]].children();
+        assert_eq!(pasted_children.len(), 1);
+        assert_eq!(test.scene.graph[pasted_children[0]].name(), "Child");
+        assert!(test
+            .receiver
+            .try_iter()
+            .any(|message| matches!(message, Message::NodesPasted { .. })));
+
+        // Undo of paste removes the copy, undo of cut brings the original back.
+        test.undo();
+        assert!(test.find("Parent").is_empty());
+        test.redo();
+        assert_eq!(test.find("Parent").len(), 1);
+        test.undo();
+        test.undo();
+        assert_eq!(test.find("Parent"), [parent]);
+        assert_eq!(test.find("Child"), [child]);
+        assert_eq!(test.scene.graph[parent].children(), [child]);
+        // Cut content is still in clipboard after undo.
+        assert!(!test.editor_scene.clipboard.is_empty());
+    }
+
+    #[test]
+    fn test_scene_unit_scale() {
+        let mut test = TestScene::new();
+        let child = BaseBuilder::new()
+            .with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(100.0, 0.0, 0.0))
+                    .build(),
+            )
+            .build(&mut test.scene.graph);
+        let node = BaseBuilder::new()
+            .with_children(&[child])
+            .with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(100.0, 0.0, 0.0))
+                    .build(),
+            )
+            .build(&mut test.scene.graph);
+        let physics = &mut test.editor_scene.physics;
+        let body = physics.bodies.spawn(RigidBody {
+            position: Vector3::new(100.0, 0.0, 0.0),
+            ..Default::default()
+        });
+        physics.binder.insert(node, body);
+        let collider = physics.colliders.spawn(Collider {
+            shape: ColliderShapeDesc::Cuboid(CuboidDesc {
+                half_extents: Vector3::new(100.0, 100.0, 100.0),
+            }),
+            parent: body.into(),
+            ..Default::default()
+        });
+        physics.bodies[body].colliders.push(collider.into());
+
+        assert!(SetSceneUnitScaleCommand::new(0.0).is_none());
+        assert!(SetSceneUnitScaleCommand::new(-1.0).is_none());
+        test.do_command(SceneCommand::SetSceneUnitScale(
+            SetSceneUnitScaleCommand::new(0.01).unwrap(),
+        ));
+
+        let graph = &mut test.scene.graph;
+        graph.update_hierarchical_data();
+        let physics = &test.editor_scene.physics;
+        assert!((graph[node].global_position().x - 1.0).abs() < 1.0e-4);
+        // Child keeps its local position, but inherits scale of its parent.
+        assert!((graph[child].local_transform().position().x - 100.0).abs() < 1.0e-4);
+        assert!((graph[child].global_position().x - 2.0).abs() < 1.0e-4);
+        assert!((physics.bodies[body].position.x - 1.0).abs() < 1.0e-4);
+        match &physics.colliders[collider].shape {
+            ColliderShapeDesc::Cuboid(cuboid) => {
+                assert!((cuboid.half_extents.x - 1.0).abs() < 1.0e-4)
+            }
+            _ => unreachable!(),
         }
-    }
 
-    pub fn contains(&self, handle: Handle<Node>) -> bool {
-        self.nodes.iter().any(|&h| h == handle)
+        test.undo();
+
+        let graph = &mut test.scene.graph;
+        graph.update_hierarchical_data();
+        let physics = &test.editor_scene.physics;
+        assert!((graph[node].global_position().x - 100.0).abs() < 1.0e-3);
+        assert!((graph[child].global_position().x - 200.0).abs() < 1.0e-3);
+        assert!((physics.bodies[body].position.x - 100.0).abs() < 1.0e-3);
+        match &physics.colliders[collider].shape {
+            ColliderShapeDesc::Cuboid(cuboid) => {
+                assert!((cuboid.half_extents.x - 100.0).abs() < 1.0e-3)
+            }
+            _ => unreachable!(),
+        }
     }
 
-    pub fn nodes(&self) -> &[Handle<Node>] {
-        &self.nodes
-    }
+    #[test]
+    fn test_paste_at_point() {
+        let mut test = TestScene::new();
+        let node = BaseBuilder::new()
+            .with_name("Copied")
+            .with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(1.0, 2.0, 3.0))
+                    .build(),
+            )
+            .build(&mut test.scene.graph);
+        let parent = BaseBuilder::new()
+            .with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(10.0, 0.0, 0.0))
+                    .with_local_rotation(UnitQuaternion::from_axis_angle(
+                        &Vector3::y_axis(),
+                        std::f32::consts::FRAC_PI_2,
+                    ))
+                    .build(),
+            )
+            .build(&mut test.scene.graph);
+        test.scene.graph.update_hierarchical_data();
+        test.editor_scene.clipboard.fill_from_selection(
+            &GraphSelection::from_list(vec![node]),
+            &test.scene.graph,
+            &test.editor_scene.physics,
+        );
 
-    pub fn is_multi_selection(&self) -> bool {
-        self.nodes.len() > 1
-    }
+        let target = Vector3::new(5.0, 1.0, 5.0);
+        test.do_command(SceneCommand::Paste(PasteCommand::new().with_target(target)));
+        test.do_command(SceneCommand::Paste(
+            PasteCommand::new().with_target(target).with_parent(parent),
+        ));
+        test.scene.graph.update_hierarchical_data();
+
+        let copies = test
+            .find("Copied")
+            .into_iter()
+            .filter(|&copy| copy != node)
+            .collect::<Vec<_>>();
+        assert_eq!(copies.len(), 2);
+        for copy in copies {
+            let position = test.scene.graph[copy].global_position();
+            assert!((position - target).norm() < 1.0e-4);
+        }
+        assert_eq!(test.scene.graph[parent].children().len(), 1);
+    }
+
+    #[test]
+    fn test_compound_body_from_model() {
+        let mut test = TestScene::new();
+        let graph = &mut test.scene.graph;
+        let physics = &mut test.editor_scene.physics;
+        let first = add_box(graph, physics, false);
+        let second = add_box(graph, physics, false);
+        let model = BaseBuilder::new().build(graph);
+        graph.link_nodes(first, model);
+        graph.link_nodes(second, model);
+        // Mesh with its own body keeps it and gets no collider in the compound body.
+        let bound = add_box(graph, physics, true);
+        graph.update_hierarchical_data();
+
+        assert!(make_compound_body_command(
+            &GraphSelection::single_or_empty(bound),
+            &test.scene.graph,
+            &test.editor_scene.physics,
+        )
+        .is_none());
 
-    pub fn is_single_selection(&self) -> bool {
-        self.nodes.len() == 1
-    }
+        let command = make_compound_body_command(
+            &GraphSelection::from_list(vec![model, bound]),
+            &test.scene.graph,
+            &test.editor_scene.physics,
+        )
+        .unwrap();
+        test.do_command(command);
 
-    pub fn is_empty(&self) -> bool {
-        self.nodes.is_empty()
-    }
+        let physics = &test.editor_scene.physics;
+        let pivot = match &test.editor_scene.selection {
+            Selection::Graph(selection) => selection.nodes()[0],
+            _ => panic!("Pivot must be selected"),
+        };
+        assert_eq!(test.scene.graph[model].parent(), pivot);
+        assert_eq!(test.scene.graph[bound].parent(), pivot);
+        let body = *physics.binder.value_of(&pivot).unwrap();
+        assert_eq!(physics.bodies[body].colliders.len(), 2);
+        assert_eq!(physics.bodies.iter().count(), 2);
+
+        test.undo();
+        let physics = &test.editor_scene.physics;
+        assert!(!test.scene.graph.is_valid_handle(pivot));
+        assert_eq!(physics.bodies.iter().count(), 1);
+        assert_eq!(physics.colliders.iter().count(), 0);
+    }
+
+    #[test]
+    fn test_sensor_changes_calculated_mass() {
+        let mut test = TestScene::new();
+        let physics = &mut test.editor_scene.physics;
+        let body = physics.bodies.spawn(RigidBody::default());
+        let mut colliders = Vec::new();
+        for _ in 0..2 {
+            let collider = physics.colliders.spawn(Collider {
+                shape: ColliderShapeDesc::Cuboid(CuboidDesc {
+                    half_extents: Vector3::new(0.5, 0.5, 0.5),
+                }),
+                parent: body.into(),
+                ..Default::default()
+            });
+            physics.bodies[body].colliders.push(collider.into());
+            colliders.push(collider);
+        }
+        physics.recompute_body_mass(body);
+        assert!((physics.bodies[body].mass - 2.0).abs() < 1.0e-4);
+
+        test.do_command(SceneCommand::SetColliderIsSensor(
+            SetColliderIsSensorCommand::new(colliders[0], true),
+        ));
+        let physics = &test.editor_scene.physics;
+        assert!(physics.colliders[colliders[0]].is_sensor);
+        assert!((physics.bodies[body].mass - 1.0).abs() < 1.0e-4);
+
+        test.undo();
+        let physics = &test.editor_scene.physics;
+        assert!(!physics.colliders[colliders[0]].is_sensor);
+        assert!((physics.bodies[body].mass - 2.0).abs() < 1.0e-4);
+
+        // Explicitly set mass is kept.
+        test.editor_scene.physics.bodies[body].mass = 10.0;
+        test.do_command(SceneCommand::SetColliderIsSensor(
+            SetColliderIsSensorCommand::new(colliders[1], true),
+        ));
+        assert!(test.editor_scene.physics.colliders[colliders[1]].is_sensor);
+        assert_eq!(test.editor_scene.physics.bodies[body].mass, 10.0);
+    }
+
+    #[test]
+    fn test_blend_shape_weight_undo() {
+        let mut test = TestScene::new();
+        let mesh = add_box(&mut test.scene.graph, &mut test.editor_scene.physics, false);
+
+        for &weight in [0.25, 0.75].iter() {
+            let command =
+                SetMeshBlendShapeWeightCommand::new(&test.scene.graph, mesh, 2, weight).unwrap();
+            test.do_command(SceneCommand::SetMeshBlendShapeWeight(command));
+        }
+        assert_eq!(
+            test.editor_scene.blend_shape_weights.get(&(mesh, 2)),
+            Some(&0.75)
+        );
 
-    pub fn extend(&mut self, other: &GraphSelection) {
-        self.nodes.extend_from_slice(&other.nodes)
+        test.undo();
+        assert_eq!(
+            test.editor_scene.blend_shape_weights.get(&(mesh, 2)),
+            Some(&0.25)
+        );
+        test.undo();
+        assert!(test.editor_scene.blend_shape_weights.is_empty());
+        test.redo();
+        assert_eq!(
+            test.editor_scene.blend_shape_weights.get(&(mesh, 2)),
+            Some(&0.25)
+        );
     }
 
-    pub fn root_nodes(&self, graph: &Graph) -> Vec<Handle<Node>> {
-        // Helper function.
-        fn is_descendant_of(handle: Handle<Node>, other: Handle<Node>, graph: &Graph) -> bool {
-            for &child in graph[other].children() {
-                if child == handle {
-                    return true;
-                }
+    #[test]
+    fn test_blend_shape_weight_round_trip() {
+        let mut test = TestScene::new();
+        let mesh = add_box(&mut test.scene.graph, &mut test.editor_scene.physics, false);
+        test.scene.graph[mesh].set_name("Face");
+        let command =
+            SetMeshBlendShapeWeightCommand::new(&test.scene.graph, mesh, 2, 0.25).unwrap();
+        test.do_command(SceneCommand::SetMeshBlendShapeWeight(command));
+
+        let (scene, mut visitor) = test.save_and_load("blend_shape_weights");
+        let weights = EditorScene::load_blend_shape_weights(&mut visitor);
+        assert_eq!(weights.len(), 1);
+        let (&(saved_mesh, shape_index), &weight) = weights.iter().next().unwrap();
+        assert_eq!(scene.graph[saved_mesh].name(), "Face");
+        assert_eq!(shape_index, 2);
+        assert_eq!(weight, 0.25);
+    }
+
+    #[test]
+    fn test_replace_texture() {
+        let mut test = TestScene::new();
+        let old = Texture::new_render_target(1, 1);
+        let other = Texture::new_render_target(1, 1);
+        let new = Texture::new_render_target(2, 2);
+
+        let graph = &mut test.scene.graph;
+        let mesh = MeshBuilder::new(BaseBuilder::new())
+            .with_surfaces(vec![SurfaceBuilder::new(Arc::new(RwLock::new(
+                SurfaceSharedData::make_cube(Matrix4::identity()),
+            )))
+            .with_diffuse_texture(old.clone())
+            .with_normal_texture(other.clone())
+            .build()])
+            .build(graph);
+        let sprite = SpriteBuilder::new(BaseBuilder::new())
+            .with_texture(old.clone())
+            .build(graph);
+        let particle_system = ParticleSystemBuilder::new(BaseBuilder::new())
+            .with_texture(old.clone())
+            .build(graph);
+        let editor_sprite = SpriteBuilder::new(BaseBuilder::new())
+            .with_texture(old.clone())
+            .build(graph);
+        graph.link_nodes(editor_sprite, test.editor_scene.root);
+
+        let command = make_replace_texture_command(
+            &test.editor_scene,
+            &test.scene.graph,
+            &old,
+            new.clone(),
+            &test.sender,
+        )
+        .unwrap();
+        test.do_command(command);
+
+        let graph = &test.scene.graph;
+        let surface = &graph[mesh].as_mesh().surfaces()[0];
+        assert_eq!(surface.diffuse_texture(), Some(new.clone()));
+        // Other procedural texture has the same (empty) path, but it is a different texture.
+        assert_eq!(surface.normal_texture(), Some(other.clone()));
+        assert_eq!(graph[sprite].as_sprite().texture(), Some(new.clone()));
+        assert_eq!(
+            graph[particle_system].as_particle_system().texture(),
+            Some(new.clone())
+        );
+        assert_eq!(
+            graph[editor_sprite].as_sprite().texture(),
+            Some(old.clone())
+        );
+        assert!(test
+            .receiver
+            .try_iter()
+            .any(|m| matches!(m, Message::Log(text) if text.starts_with("3 usages"))));
+
+        test.undo();
+        let graph = &test.scene.graph;
+        assert_eq!(
+            graph[mesh].as_mesh().surfaces()[0].diffuse_texture(),
+            Some(old.clone())
+        );
+        assert_eq!(graph[sprite].as_sprite().texture(), Some(old.clone()));
+        assert_eq!(
+            graph[particle_system].as_particle_system().texture(),
+            Some(old)
+        );
+    }
 
-                let inner = is_descendant_of(handle, child, graph);
-                if inner {
-                    return true;
-                }
-            }
-            false
+    // Creates a child node under a parent rotated by 90 degrees around Y axis.
+    fn add_child_of_rotated_parent(graph: &mut Graph) -> (Handle<Node>, Handle<Node>) {
+        let child = BaseBuilder::new().build(graph);
+        let parent = BaseBuilder::new()
+            .with_local_transform(
+                TransformBuilder::new()
+                    .with_local_rotation(UnitQuaternion::from_axis_angle(
+                        &Vector3::y_axis(),
+                        std::f32::consts::FRAC_PI_2,
+                    ))
+                    .build(),
+            )
+            .with_children(&[child])
+            .build(graph);
+        graph.update_hierarchical_data();
+        (parent, child)
+    }
+
+    #[test]
+    fn test_move_in_transform_space() {
+        let mut test = TestScene::new();
+        let (_, child) = add_child_of_rotated_parent(&mut test.scene.graph);
+
+        let command = make_move_node_command(
+            &test.scene.graph,
+            child,
+            Vector3::x(),
+            TransformSpace::World,
+        );
+        test.do_command(SceneCommand::MoveNode(command));
+        test.scene.graph.update_hierarchical_data();
+        let position = test.scene.graph[child].global_position();
+        assert!((position - Vector3::x()).norm() < 1.0e-5);
+
+        test.undo();
+        let command = make_move_node_command(
+            &test.scene.graph,
+            child,
+            Vector3::x(),
+            TransformSpace::Local,
+        );
+        test.do_command(SceneCommand::MoveNode(command));
+        test.scene.graph.update_hierarchical_data();
+        // Local X axis of the child is world -Z axis.
+        let position = test.scene.graph[child].global_position();
+        assert!((position - Vector3::new(0.0, 0.0, -1.0)).norm() < 1.0e-5);
+    }
+
+    #[test]
+    fn test_rotate_in_transform_space() {
+        let mut test = TestScene::new();
+        let (parent, child) = add_child_of_rotated_parent(&mut test.scene.graph);
+        let parent_rotation = test.scene.graph.global_rotation_position_no_scale(parent).0;
+        let delta =
+            UnitQuaternion::from_axis_angle(&Vector3::x_axis(), std::f32::consts::FRAC_PI_2);
+
+        let command =
+            make_rotate_node_command(&test.scene.graph, child, delta, TransformSpace::World);
+        test.do_command(SceneCommand::RotateNode(command));
+        test.scene.graph.update_hierarchical_data();
+        // Rotation around world X axis is applied on top of rotation of the parent.
+        let rotation = test.scene.graph.global_rotation_position_no_scale(child).0;
+        assert!(rotation.angle_to(&(delta * parent_rotation)) < 1.0e-4);
+
+        test.undo();
+        let command =
+            make_rotate_node_command(&test.scene.graph, child, delta, TransformSpace::Local);
+        test.do_command(SceneCommand::RotateNode(command));
+        test.scene.graph.update_hierarchical_data();
+        let rotation = test.scene.graph.global_rotation_position_no_scale(child).0;
+        assert!(rotation.angle_to(&(parent_rotation * delta)) < 1.0e-4);
+    }
+
+    #[test]
+    fn test_ambient_color_changes_are_merged() {
+        let mut test = TestScene::new();
+        let original = test.scene.ambient_lighting_color;
+        for &color in [Color::RED, Color::GREEN, Color::BLUE].iter() {
+            test.do_command(SceneCommand::SetSceneAmbientColor(
+                SetSceneAmbientColorCommand::new(color),
+            ));
         }
+        assert_eq!(test.scene.ambient_lighting_color, Color::BLUE);
 
-        let mut root_nodes = Vec::new();
-        for &node in self.nodes().iter() {
-            let mut descendant = false;
-            for &other_node in self.nodes().iter() {
-                if is_descendant_of(node, other_node, graph) {
-                    descendant = true;
-                    break;
-                }
-            }
-            if !descendant {
-                root_nodes.push(node);
-            }
+        test.undo();
+        assert_eq!(test.scene.ambient_lighting_color, original);
+        test.redo();
+        assert_eq!(test.scene.ambient_lighting_color, Color::BLUE);
+
+        // Other command in between stops merging.
+        let node = BaseBuilder::new().build(&mut test.scene.graph);
+        test.do_command(SceneCommand::SetName(SetNameCommand::new(
+            node,
+            "Node".to_owned(),
+        )));
+        test.do_command(SceneCommand::SetSceneAmbientColor(
+            SetSceneAmbientColorCommand::new(Color::RED),
+        ));
+        test.undo();
+        assert_eq!(test.scene.ambient_lighting_color, Color::BLUE);
+    }
+
+    #[test]
+    fn test_paste_into_other_scene() {
+        let mut a = TestScene::new();
+        let texture = Texture::new_render_target(1, 1);
+        let node = MeshBuilder::new(BaseBuilder::new().with_name("Box"))
+            .with_surfaces(vec![SurfaceBuilder::new(Arc::new(RwLock::new(
+                SurfaceSharedData::make_cube(Matrix4::identity()),
+            )))
+            .with_diffuse_texture(texture.clone())
+            .build()])
+            .build(&mut a.scene.graph);
+        let body = a.editor_scene.physics.bodies.spawn(RigidBody::default());
+        a.editor_scene.physics.binder.insert(node, body);
+        a.select(vec![node]);
+        a.editor_scene.copy_selection_to_clipboard(&a.scene.graph);
+
+        // Clipboard is moved to other scene the same way the editor does it.
+        let mut b = TestScene::new();
+        for _ in 0..3 {
+            add_box(&mut b.scene.graph, &mut b.editor_scene.physics, true);
+        }
+        b.editor_scene.clipboard = std::mem::take(&mut a.editor_scene.clipboard);
+        b.editor_scene.clipboard.forget_source();
+        b.do_command(SceneCommand::Paste(PasteCommand::new()));
+
+        let pasted = b.find("Box");
+        assert_eq!(pasted.len(), 1);
+        let pasted = pasted[0];
+        assert!(b.scene.graph.is_valid_handle(pasted));
+        assert_eq!(b.scene.graph[pasted].parent(), b.scene.graph.get_root());
+        let pasted_body = *b.editor_scene.physics.binder.value_of(&pasted).unwrap();
+        assert!(b.editor_scene.physics.bodies.is_valid_handle(pasted_body));
+        assert_eq!(b.editor_scene.physics.bodies.alive_count(), 4);
+        // Resources are shared, not copied.
+        assert_eq!(
+            b.scene.graph[pasted].as_mesh().surfaces()[0].diffuse_texture(),
+            Some(texture)
+        );
+        // Source scene is untouched.
+        assert_eq!(a.find("Box"), [node]);
+    }
+
+    #[test]
+    fn test_prefab_reload() {
+        let mut test = TestScene::new();
+        let graph = &mut test.scene.graph;
+        let child = add_box(graph, &mut test.editor_scene.physics, true);
+        graph[child].set_name("Child");
+        let prefab_root = BaseBuilder::new()
+            .with_name("Prop")
+            .with_children(&[child])
+            .build(graph);
+        BaseBuilder::new().with_name("Other").build(graph);
+        test.select(vec![prefab_root]);
+
+        let path =
+            std::env::temp_dir().join(format!("rusty_editor_prefab_{}.rgs", std::process::id()));
+        let selection = match &test.editor_scene.selection {
+            Selection::Graph(selection) => selection.clone(),
+            _ => unreachable!(),
+        };
+        test.editor_scene
+            .save_selection_as_prefab(&selection, &test.scene, &path)
+            .unwrap();
+        let prefab = rg3d::core::futures::executor::block_on(Scene::from_file(
+            &path,
+            test.resource_manager.clone(),
+        ))
+        .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        // Root of prefab scene, prop and its child; editor nodes and other nodes are not saved.
+        let names = prefab
+            .graph
+            .pair_iter()
+            .map(|(_, node)| node.name().to_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(names.len(), 3);
+        assert!(names.iter().any(|name| name == "Prop"));
+        assert!(names.iter().any(|name| name == "Child"));
+    }
+
+    #[test]
+    fn test_linked_duplicate() {
+        let mut test = TestScene::new();
+        let source = add_box(&mut test.scene.graph, &mut test.editor_scene.physics, false);
+        test.scene.graph[source].set_name("Box");
+
+        let command = make_linked_duplicate_command(source, &test.scene.graph).unwrap();
+        test.do_command(command);
+        let duplicate = test.find("Box (Linked)");
+        assert_eq!(duplicate.len(), 1);
+        let duplicate = duplicate[0];
+        assert_eq!(
+            test.editor_scene.linked_instances.get(&duplicate),
+            Some(&source)
+        );
+        // Geometry is shared, not copied.
+        let graph = &test.scene.graph;
+        assert!(Arc::ptr_eq(
+            &graph[source].as_mesh().surfaces()[0].data(),
+            &graph[duplicate].as_mesh().surfaces()[0].data()
+        ));
+
+        // Texture set to the source is set to the duplicate too.
+        let texture = Texture::new_render_target(1, 1);
+        test.do_command(SceneCommand::SetMeshTexture(SetMeshTextureCommand::new(
+            source,
+            texture.clone(),
+        )));
+        let graph = &test.scene.graph;
+        for &node in [source, duplicate].iter() {
+            assert_eq!(
+                graph[node].as_mesh().surfaces()[0].diffuse_texture(),
+                Some(texture.clone())
+            );
         }
-        root_nodes
-    }
 
-    pub fn global_rotation_position(
-        &self,
-        graph: &Graph,
-    ) -> Option<(UnitQuaternion<f32>, Vector3<f32>)> {
-        if self.is_single_selection() {
-            Some(graph.global_rotation_position_no_scale(self.nodes[0]))
-        } else if self.is_empty() {
+        test.undo();
+        assert_eq!(
+            test.scene.graph[duplicate].as_mesh().surfaces()[0].diffuse_texture(),
             None
-        } else {
-            let mut position = Vector3::default();
-            let mut rotation = graph.global_rotation(self.nodes[0]);
-            let t = 1.0 / self.nodes.len() as f32;
-            for &handle in self.nodes.iter() {
-                let global_transform = graph[handle].global_transform();
-                position += global_transform.position();
-                rotation = rotation.slerp(&graph.global_rotation(self.nodes[0]), t);
+        );
+        test.undo();
+        assert!(test.find("Box (Linked)").is_empty());
+        assert!(test.editor_scene.linked_instances.is_empty());
+    }
+
+    #[test]
+    fn test_command_records_replay() {
+        // Two copies of the same scene, edits of the first one are replayed on the second one.
+        let mut a = TestScene::new();
+        let mut b = TestScene::new();
+        let node = BaseBuilder::new()
+            .with_name("Node")
+            .build(&mut a.scene.graph);
+        assert_eq!(
+            BaseBuilder::new()
+                .with_name("Node")
+                .build(&mut b.scene.graph),
+            node
+        );
+
+        let mut group = CommandGroup::from(vec![
+            SceneCommand::MoveNode(MoveNodeCommand::new(
+                node,
+                Vector3::default(),
+                Vector3::new(1.0, 2.0, 3.0),
+            )),
+            SceneCommand::SetName(SetNameCommand::new(node, "Renamed".to_owned())),
+            SceneCommand::SetVisible(SetVisibleCommand::new(node, false)),
+            // Unsupported commands are skipped.
+            SceneCommand::SetSceneAmbientColor(SetSceneAmbientColorCommand::new(Color::RED)),
+        ]);
+        group.execute(&mut SceneContext {
+            editor_scene: &mut a.editor_scene,
+            scene: &mut a.scene,
+            message_sender: a.sender.clone(),
+            resource_manager: a.resource_manager.clone(),
+        });
+        let records = group.describe(&a.scene.graph);
+        assert_eq!(records.len(), 3);
+
+        let records = records_from_ron(&records_to_ron(&records)).unwrap();
+        b.do_command(make_command_from_records(&records, &b.scene.graph));
+        let replayed = &b.scene.graph[node];
+        assert_eq!(
+            **replayed.local_transform().position(),
+            Vector3::new(1.0, 2.0, 3.0)
+        );
+        assert_eq!(replayed.name(), "Renamed");
+        assert!(!replayed.visibility());
+
+        b.undo();
+        assert_eq!(b.scene.graph[node].name(), "Node");
+    }
+
+    #[test]
+    fn test_paste_reports_mapping() {
+        let mut test = TestScene::new();
+        let node = add_box(&mut test.scene.graph, &mut test.editor_scene.physics, true);
+        test.scene.graph[node].set_name("Box");
+        let body = *test.editor_scene.physics.binder.value_of(&node).unwrap();
+        test.editor_scene.static_nodes.insert(node, true);
+        test.select(vec![node]);
+        test.editor_scene
+            .copy_selection_to_clipboard(&test.scene.graph);
+        test.do_command(SceneCommand::Paste(PasteCommand::new()));
+
+        let mapping = test
+            .receiver
+            .try_iter()
+            .find_map(|message| match message {
+                Message::NodesPasted { mapping } => Some(mapping),
+                _ => None,
+            })
+            .unwrap();
+        let pasted = test
+            .find("Box")
+            .into_iter()
+            .find(|&handle| handle != node)
+            .unwrap();
+        assert_eq!(mapping.get(&node.into()), Some(&pasted.into()));
+        let pasted_body = *test.editor_scene.physics.binder.value_of(&pasted).unwrap();
+        assert_eq!(mapping.get(&body.into()), Some(&pasted_body.into()));
+
+        // Subscribers migrate their data by the mapping.
+        test.editor_scene.copy_pasted_node_data(&mapping);
+        assert!(test.editor_scene.is_node_static(pasted));
+    }
+
+    #[test]
+    fn test_particle_system_flags_round_trip() {
+        let mut test = TestScene::new();
+        let particle_system = ParticleSystemBuilder::new(BaseBuilder::new().with_name("Fire"))
+            .with_emitters(vec![
+                SphereEmitterBuilder::new(BaseEmitterBuilder::new()).build()
+            ])
+            .build(&mut test.scene.graph);
+        let (old_sharpness, old_resurrect) = {
+            let particle_system = test.scene.graph[particle_system].as_particle_system();
+            (
+                particle_system.soft_boundary_sharpness_factor(),
+                particle_system.emitters[0].is_particles_resurrects(),
+            )
+        };
+        let new_sharpness = old_sharpness + 5.0;
+
+        test.do_command(SceneCommand::SetParticleSystemSoftBoundarySharpness(
+            SetParticleSystemSoftBoundarySharpnessCommand::new(particle_system, new_sharpness),
+        ));
+        test.do_command(SceneCommand::SetEmitterResurrectParticles(
+            SetEmitterResurrectParticlesCommand::new(particle_system, 0, !old_resurrect),
+        ));
+
+        let (scene, _) = test.save_and_load("particle_system_flags");
+        let loaded = scene
+            .graph
+            .pair_iter()
+            .find(|(_, node)| node.name() == "Fire")
+            .unwrap()
+            .1
+            .as_particle_system();
+        assert_eq!(loaded.soft_boundary_sharpness_factor(), new_sharpness);
+        assert_eq!(loaded.emitters[0].is_particles_resurrects(), !old_resurrect);
+
+        test.undo();
+        test.undo();
+        let particle_system = test.scene.graph[particle_system].as_particle_system();
+        assert_eq!(
+            particle_system.soft_boundary_sharpness_factor(),
+            old_sharpness
+        );
+        assert_eq!(
+            particle_system.emitters[0].is_particles_resurrects(),
+            old_resurrect
+        );
+    }
+
+    #[test]
+    fn test_statistics() {
+        let mut test = TestScene::new();
+        let graph = &mut test.scene.graph;
+        let physics = &mut test.editor_scene.physics;
+        let a = add_box(graph, physics, true);
+        let b = add_box(graph, physics, true);
+        BaseBuilder::new().with_children(&[a, b]).build(graph);
+        PointLightBuilder::new(BaseLightBuilder::new(BaseBuilder::new())).build(graph);
+        let body = *physics.binder.value_of(&a).unwrap();
+        let collider = physics.colliders.spawn(Collider {
+            parent: body.into(),
+            ..Default::default()
+        });
+        physics.bodies[body].colliders.push(collider.into());
+        physics.joints.spawn(Joint::default());
+        test.editor_scene.navmeshes.spawn(Navmesh::new());
+
+        let stats = test.editor_scene.statistics(&test.scene);
+        assert_eq!(stats.base_nodes, 1);
+        assert_eq!(stats.meshes, 2);
+        assert_eq!(stats.triangles, 24);
+        assert_eq!(stats.point_lights, 1);
+        // Camera of the editor is not counted.
+        assert_eq!(stats.cameras, 0);
+        assert_eq!(stats.node_count(), 4);
+        assert_eq!(stats.bodies, 2);
+        assert_eq!(stats.colliders, 1);
+        assert_eq!(stats.joints, 1);
+        assert_eq!(stats.navmesh_triangles, 2);
+    }
+
+    #[test]
+    fn test_surface_render_path_override_round_trip() {
+        let mut test = TestScene::new();
+        let cube = || {
+            SurfaceBuilder::new(Arc::new(RwLock::new(SurfaceSharedData::make_cube(
+                Matrix4::identity(),
+            ))))
+            .build()
+        };
+        let mesh = MeshBuilder::new(BaseBuilder::new().with_name("Glass"))
+            .with_render_path(RenderPath::Deferred)
+            .with_surfaces(vec![cube(), cube()])
+            .build(&mut test.scene.graph);
+        test.do_command(SceneCommand::SetSurfaceRenderPathOverride(
+            SetSurfaceRenderPathOverrideCommand::new(mesh, 1, Some(RenderPath::Forward)),
+        ));
+
+        let (scene, mut visitor) = test.save_and_load("surface_render_paths");
+        let find = |name: &str| {
+            scene
+                .graph
+                .pair_iter()
+                .find(|(_, node)| node.name() == name)
+                .unwrap()
+                .0
+        };
+        let (mesh, part) = (find("Glass"), find("Glass_Forward"));
+        assert_eq!(scene.graph[mesh].as_mesh().surfaces().len(), 1);
+        assert_eq!(scene.graph[part].parent(), mesh);
+        assert_eq!(
+            scene.graph[part].as_mesh().render_path(),
+            RenderPath::Forward
+        );
+        assert_eq!(scene.graph[part].as_mesh().surfaces().len(), 1);
+
+        // Override is stored against the moved surface.
+        let overrides = EditorScene::load_surface_render_paths(&mut visitor);
+        assert_eq!(overrides.len(), 1);
+        assert_eq!(overrides.get(&(part, 0)), Some(&RenderPath::Forward));
+    }
+
+    #[test]
+    fn test_static_nodes_round_trip() {
+        let mut test = TestScene::new();
+        let graph = &mut test.scene.graph;
+        // Removed node makes handles of saved scene differ from the handles of editor's one.
+        let removed = BaseBuilder::new().build(graph);
+        graph.remove_node(removed);
+        let wall = BaseBuilder::new().with_name("Wall").build(graph);
+        let door = BaseBuilder::new().with_name("Door").build(graph);
+        test.do_command(SceneCommand::SetNodeStatic(SetNodeStaticCommand::new(
+            wall, true,
+        )));
+        test.do_command(SceneCommand::SetNodeStatic(SetNodeStaticCommand::new(
+            door, false,
+        )));
+
+        let (scene, mut visitor) = test.save_and_load("static_nodes");
+        let static_nodes = EditorScene::load_static_nodes(&mut visitor);
+        assert_eq!(static_nodes.len(), 2);
+        for (node, is_static) in static_nodes {
+            match scene.graph[node].name() {
+                "Wall" => assert!(is_static),
+                "Door" => assert!(!is_static),
+                name => panic!("unexpected node {}", name),
             }
-            position = position.scale(t);
-            Some((rotation, position))
         }
-    }
 
-    pub fn offset(&self, graph: &mut Graph, offset: Vector3<f32>) {
-        for &handle in self.nodes.iter() {
-            let mut chain_scale = Vector3::new(1.0, 1.0, 1.0);
-            let mut parent_handle = graph[handle].parent();
-            while parent_handle.is_some() {
-                let parent = &graph[parent_handle];
-                let parent_scale = parent.local_transform().scale();
-                chain_scale.x *= parent_scale.x;
-                chain_scale.y *= parent_scale.y;
-                chain_scale.z *= parent_scale.z;
-                parent_handle = parent.parent();
-            }
-
-            let offset = Vector3::new(
-                if chain_scale.x.abs() > 0.0 {
-                    offset.x / chain_scale.x
-                } else {
-                    offset.x
-                },
-                if chain_scale.y.abs() > 0.0 {
-                    offset.y / chain_scale.y
-                } else {
-                    offset.y
-                },
-                if chain_scale.z.abs() > 0.0 {
-                    offset.z / chain_scale.z
-                } else {
-                    offset.z
-                },
+        test.undo();
+        test.undo();
+        assert!(!test.editor_scene.is_node_static(wall));
+    }
+
+    #[test]
+    fn test_merge_scene() {
+        let mut test = TestScene::new();
+        BaseBuilder::new()
+            .with_name("Crate")
+            .build(&mut test.scene.graph);
+
+        let mut other = Scene::new();
+        BaseBuilder::new()
+            .with_name("Crate")
+            .build(&mut other.graph);
+        BaseBuilder::new()
+            .with_name("Barrel")
+            .build(&mut other.graph);
+        let path =
+            std::env::temp_dir().join(format!("rusty_editor_merge_{}.rgs", std::process::id()));
+        let mut visitor = Visitor::new();
+        other.visit("Scene", &mut visitor).unwrap();
+        visitor.save_binary(&path).unwrap();
+
+        let command =
+            make_merge_scene_command(&path, &test.scene.graph, test.resource_manager.clone())
+                .unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let node_count = test.scene.graph.pair_iter().count();
+        test.do_command(command);
+
+        assert_eq!(test.scene.graph.pair_iter().count(), node_count + 2);
+        assert_eq!(test.find("Crate").len(), 1);
+        for &name in ["Crate_1", "Barrel"].iter() {
+            let merged = test.find(name);
+            assert_eq!(merged.len(), 1);
+            assert_eq!(
+                test.scene.graph[merged[0]].parent(),
+                test.scene.graph.get_root()
             );
-            graph[handle].local_transform_mut().offset(offset);
         }
-    }
 
-    pub fn rotate(&self, graph: &mut Graph, rotation: UnitQuaternion<f32>) {
-        for &handle in self.nodes.iter() {
-            graph[handle].local_transform_mut().set_rotation(rotation);
+        test.undo();
+        assert_eq!(test.scene.graph.pair_iter().count(), node_count);
+        assert!(test.find("Barrel").is_empty());
+    }
+
+    #[test]
+    fn test_delete_body_used_as_body1() {
+        let mut test = TestScene::new();
+        let physics = &mut test.editor_scene.physics;
+        let a = add_box(&mut test.scene.graph, physics, true);
+        let b = add_box(&mut test.scene.graph, physics, true);
+        let body_a = *physics.binder.value_of(&a).unwrap();
+        let body_b = *physics.binder.value_of(&b).unwrap();
+        // First joint of the body is deleted with it, second one is only detached.
+        let first = physics.joints.spawn(Joint {
+            body1: body_a.into(),
+            body2: body_b.into(),
+            ..Default::default()
+        });
+        let second = physics.joints.spawn(Joint {
+            body1: body_a.into(),
+            body2: body_b.into(),
+            ..Default::default()
+        });
+        test.select(vec![a]);
+
+        let command = make_delete_selection_command(&test.editor_scene, &test.scene.graph);
+        test.do_command(command);
+        let physics = &test.editor_scene.physics;
+        assert!(!physics.joints.is_valid_handle(first));
+        assert_eq!(physics.joints[second].body1, ErasedHandle::none());
+        assert_eq!(physics.joints[second].body2, body_b.into());
+
+        test.undo();
+        let physics = &test.editor_scene.physics;
+        assert!(physics.joints.is_valid_handle(first));
+        assert_eq!(physics.joints[second].body1, body_a.into());
+    }
+
+    // Creates a node with given local position.
+    fn add_node_at(graph: &mut Graph, position: Vector3<f32>) -> Handle<Node> {
+        BaseBuilder::new()
+            .with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(position)
+                    .build(),
+            )
+            .build(graph)
+    }
+
+    #[test]
+    fn test_align_to_average() {
+        let mut test = TestScene::new();
+        let graph = &mut test.scene.graph;
+        let a = add_node_at(graph, Vector3::new(1.0, 1.0, 0.0));
+        let b = add_node_at(graph, Vector3::new(4.0, 2.0, 0.0));
+        // Parent is scaled and moved, so local moves must be converted to its space.
+        let c = add_node_at(graph, Vector3::new(2.0, 3.0, 0.0));
+        let parent = add_node_at(graph, Vector3::new(1.0, 0.0, 0.0));
+        graph[parent]
+            .local_transform_mut()
+            .set_scale(Vector3::new(2.0, 2.0, 2.0));
+        graph.link_nodes(c, parent);
+        graph.update_hierarchical_data();
+        // World X coordinates are 1, 4 and 5.
+        let selection = GraphSelection::from_list(vec![a, b, c]);
+
+        let command = make_align_command(&selection, &test.scene.graph, Axis::X, AlignMode::Center);
+        test.do_command(command);
+        test.scene.graph.update_hierarchical_data();
+
+        let graph = &test.scene.graph;
+        for &(node, y) in [(a, 1.0), (b, 2.0), (c, 6.0)].iter() {
+            let position = graph[node].global_position();
+            assert!((position.x - 10.0 / 3.0).abs() < 1.0e-5);
+            // Other coordinates are untouched.
+            assert!((position.y - y).abs() < 1.0e-5);
         }
     }
 
-    pub fn scale(&self, graph: &mut Graph, scale: Vector3<f32>) {
-        for &handle in self.nodes.iter() {
-            graph[handle].local_transform_mut().set_scale(scale);
+    #[test]
+    fn test_auto_lod_group_levels_are_contiguous() {
+        let mut test = TestScene::new();
+        let graph = &mut test.scene.graph;
+        let children = (0..3)
+            .map(|_| BaseBuilder::new().build(graph))
+            .collect::<Vec<_>>();
+        let node = BaseBuilder::new().with_children(&children).build(graph);
+
+        test.do_command(make_auto_lod_group_command(node, &children));
+        let levels = &test.scene.graph[node].lod_group().unwrap().levels;
+        assert_eq!(levels.len(), 3);
+        assert_eq!(levels[0].begin(), 0.0);
+        assert_eq!(levels[2].end(), 1.0);
+        for (level, &child) in levels.iter().zip(children.iter()) {
+            assert!(level.begin() < level.end());
+            assert_eq!(level.objects, [child]);
+        }
+        for pair in levels.windows(2) {
+            assert_eq!(pair[0].end(), pair[1].begin());
         }
+
+        test.undo();
+        assert!(test.scene.graph[node].lod_group().is_none());
+    }
+
+    #[test]
+    fn test_mirror_preserves_centroid() {
+        let mut test = TestScene::new();
+        let a = add_node_at(&mut test.scene.graph, Vector3::new(1.0, 2.0, 0.0));
+        let b = add_node_at(&mut test.scene.graph, Vector3::new(5.0, 0.0, 0.0));
+        test.scene.graph.update_hierarchical_data();
+        test.select(vec![a, b]);
+        let global_x =
+            |test: &TestScene, node: Handle<Node>| test.scene.graph[node].global_position().x;
+
+        let command = make_mirror_command(
+            &test.editor_scene,
+            &test.scene.graph,
+            Axis::X,
+            MirrorMode::InPlace,
+        )
+        .unwrap();
+        test.do_command(command);
+        test.scene.graph.update_hierarchical_data();
+        assert!((global_x(&test, a) - 5.0).abs() < 1.0e-5);
+        assert!((global_x(&test, b) - 1.0).abs() < 1.0e-5);
+        // Mirrored axis gets negative scale, so winding of triangles stays correct.
+        assert!(test.scene.graph[a].local_transform().scale().x < 0.0);
+        test.undo();
+        test.scene.graph.update_hierarchical_data();
+        assert!((global_x(&test, a) - 1.0).abs() < 1.0e-5);
+
+        let command = make_mirror_command(
+            &test.editor_scene,
+            &test.scene.graph,
+            Axis::X,
+            MirrorMode::Copy,
+        )
+        .unwrap();
+        let node_count = test.scene.graph.pair_iter().count();
+        test.do_command(command);
+        test.scene.graph.update_hierarchical_data();
+        assert_eq!(test.scene.graph.pair_iter().count(), node_count + 2);
+        // Originals stay in place, copies are selected after paste.
+        assert!((global_x(&test, a) - 1.0).abs() < 1.0e-5);
+        let copies = match &test.editor_scene.selection {
+            Selection::Graph(selection) => selection.nodes().to_vec(),
+            _ => unreachable!(),
+        };
+        let centroid = copies
+            .iter()
+            .map(|&copy| test.scene.graph[copy].global_position())
+            .sum::<Vector3<f32>>()
+            .scale(0.5);
+        assert!((centroid - Vector3::new(3.0, 1.0, 0.0)).norm() < 1.0e-5);
+    }
+
+    #[test]
+    fn test_find_nodes_by_name_and_tag() {
+        let mut test = TestScene::new();
+        let graph = &mut test.scene.graph;
+        let gun = BaseBuilder::new().with_name("EnemyGun").build(graph);
+        let enemy = BaseBuilder::new()
+            .with_name("Enemy")
+            .with_tag("npc".to_owned())
+            .with_children(&[gun])
+            .build(graph);
+        let tree = BaseBuilder::new()
+            .with_name("Tree")
+            .with_tag("enemy".to_owned())
+            .build(graph);
+        // Tag must match exactly.
+        BaseBuilder::new()
+            .with_name("Rock")
+            .with_tag("enemies".to_owned())
+            .build(graph);
+
+        let find = |text: &str, case_sensitive: bool| {
+            test.editor_scene.find_nodes(
+                &test.scene.graph,
+                &NodeQuery {
+                    text: text.to_owned(),
+                    case_sensitive,
+                },
+            )
+        };
+        assert_eq!(find("enemy", false), [enemy, gun, tree]);
+        assert_eq!(find("Enemy", true), [enemy, gun]);
+        assert_eq!(find("npc", false), [enemy]);
+        // Editor nodes are never found.
+        assert!(find("EditorCamera", false).is_empty());
+        assert!(find("", false).is_empty());
+
+        let found = find("gun", false);
+        test.do_command(SceneCommand::ChangeSelection(make_select_found_command(
+            &test.editor_scene,
+            found,
+        )));
+        assert_eq!(
+            test.editor_scene.selection,
+            Selection::Graph(GraphSelection::from_list(vec![gun]))
+        );
     }
 
-    pub fn local_positions(&self, graph: &Graph) -> Vec<Vector3<f32>> {
-        let mut positions = Vec::new();
-        for &handle in self.nodes.iter() {
-            positions.push(**graph[handle].local_transform().position());
+    #[test]
+    fn test_emitter_texture_region_round_trip() {
+        let mut test = TestScene::new();
+        let emitter = || SphereEmitterBuilder::new(BaseEmitterBuilder::new()).build();
+        let particle_system = ParticleSystemBuilder::new(BaseBuilder::new().with_name("Smoke"))
+            .with_emitters(vec![emitter(), emitter()])
+            .build(&mut test.scene.graph);
+        let rect = Rect::new(0.5, 0.0, 0.5, 0.25);
+        test.do_command(SceneCommand::SetEmitterTextureRegion(
+            SetEmitterTextureRegionCommand::new(particle_system, 1, Some(rect)),
+        ));
+        assert_eq!(
+            test.editor_scene
+                .emitter_texture_regions
+                .get(&(particle_system, 1)),
+            Some(&rect)
+        );
+
+        let (scene, mut visitor) = test.save_and_load("emitter_texture_regions");
+        let regions = EditorScene::load_emitter_texture_regions(&mut visitor);
+        assert_eq!(regions.len(), 1);
+        let (&(saved_particle_system, index), &saved_rect) = regions.iter().next().unwrap();
+        assert_eq!(scene.graph[saved_particle_system].name(), "Smoke");
+        assert_eq!(index, 1);
+        assert_eq!(saved_rect, rect);
+
+        test.undo();
+        assert!(test.editor_scene.emitter_texture_regions.is_empty());
+    }
+
+    #[test]
+    fn test_deep_clone_is_deterministic() {
+        let mut graph = Graph::new();
+        let mut physics = Physics::default();
+        let nodes = (0..4)
+            .map(|_| add_box(&mut graph, &mut physics, true))
+            .collect::<Vec<_>>();
+        for &node in nodes.iter() {
+            let body = *physics.binder.value_of(&node).unwrap();
+            let collider = physics.colliders.spawn(Collider {
+                parent: body.into(),
+                ..Default::default()
+            });
+            physics.bodies[body].colliders.push(collider.into());
         }
-        positions
+        let body = |node| ErasedHandle::from(*physics.binder.value_of(&node).unwrap());
+        let joint = Joint {
+            body1: body(nodes[0]),
+            body2: body(nodes[2]),
+            ..Default::default()
+        };
+        physics.joints.spawn(joint);
+
+        let clone = |root_nodes: &[Handle<Node>]| {
+            let mut dest_graph = Graph::new();
+            let mut dest_physics = Physics::default();
+            deep_clone_nodes(
+                root_nodes,
+                &graph,
+                &physics,
+                &mut dest_graph,
+                &mut dest_physics,
+            )
+        };
+        let first = clone(&nodes);
+        let second = clone(&nodes);
+        assert_eq!(first.bodies.len(), 4);
+        assert_eq!(first.colliders.len(), 4);
+        assert_eq!(first.joints.len(), 1);
+        assert_eq!(first.root_nodes, second.root_nodes);
+        assert_eq!(first.bodies, second.bodies);
+        assert_eq!(first.colliders, second.colliders);
+        assert_eq!(first.joints, second.joints);
+        assert_eq!(first.binder, second.binder);
+        assert_eq!(first.old_to_new, second.old_to_new);
+
+        // Bodies are cloned in the same order whatever order of selection is.
+        let reversed = nodes.iter().rev().cloned().collect::<Vec<_>>();
+        assert_eq!(clone(&reversed).bodies, first.bodies);
+    }
+
+    #[test]
+    fn test_editor_nodes_are_not_copied() {
+        let mut test = TestScene::new();
+        let graph = &mut test.scene.graph;
+        let gizmo_part = BaseBuilder::new().with_name("GizmoPart").build(graph);
+        let gizmo = BaseBuilder::new()
+            .with_name("Gizmo")
+            .with_children(&[gizmo_part])
+            .build(graph);
+        graph.link_nodes(gizmo, test.editor_scene.root);
+        let prop = BaseBuilder::new().with_name("Prop").build(graph);
+
+        let graph = &test.scene.graph;
+        assert!(test
+            .editor_scene
+            .is_editor_node(test.editor_scene.root, graph));
+        assert!(test.editor_scene.is_editor_node(gizmo_part, graph));
+        assert!(!test.editor_scene.is_editor_node(prop, graph));
+        assert!(!test.editor_scene.is_editor_node(graph.get_root(), graph));
+
+        test.select(vec![gizmo, prop]);
+        test.editor_scene
+            .copy_selection_to_clipboard(&test.scene.graph);
+        test.do_command(SceneCommand::Paste(PasteCommand::new()));
+        assert_eq!(test.find("Prop").len(), 2);
+        assert_eq!(test.find("Gizmo"), [gizmo]);
+        assert_eq!(test.find("GizmoPart"), [gizmo_part]);
+    }
+
+    #[test]
+    fn test_export_hierarchy_ron() {
+        let mut test = TestScene::new();
+        let graph = &mut test.scene.graph;
+        let wheel = add_box(graph, &mut test.editor_scene.physics, true);
+        graph[wheel].set_name("Wheel");
+        let car = BaseBuilder::new()
+            .with_name("Car")
+            .with_children(&[wheel])
+            .build(graph);
+
+        let ron = test.editor_scene.export_hierarchy_ron(&test.scene);
+        assert!(ron.contains("name: \"Car\""));
+        assert!(ron.contains("name: \"Wheel\""));
+        assert!(ron.contains(&format!("parent: Some({})", car.index())));
+        let body = test.editor_scene.physics.binder.value_of(&wheel).unwrap();
+        assert!(ron.contains(&format!("body: Some({})", body.index())));
+        // Editor nodes are skipped.
+        assert!(!ron.contains("EditorCamera"));
+    }
+
+    #[test]
+    fn test_body_locked_rotation_round_trip() {
+        let mut test = TestScene::new();
+        let node = add_box(&mut test.scene.graph, &mut test.editor_scene.physics, true);
+        test.scene.graph[node].set_name("Player");
+        let body = *test.editor_scene.physics.binder.value_of(&node).unwrap();
+        test.do_command(SceneCommand::SetBodyLockedRotation(
+            SetBodyLockedRotationCommand::new(body, [false, true, false]),
+        ));
+
+        // Mask follows the body when it is cloned.
+        test.select(vec![node]);
+        test.editor_scene
+            .copy_selection_to_clipboard(&test.scene.graph);
+        test.do_command(SceneCommand::Paste(PasteCommand::new()));
+        let copy = test
+            .find("Player")
+            .into_iter()
+            .find(|&handle| handle != node)
+            .unwrap();
+        let physics = &test.editor_scene.physics;
+        let copy_body = &physics.bodies[*physics.binder.value_of(&copy).unwrap()];
+        assert!(copy_body.y_rotation_locked);
+
+        let (scene, _) = test.save_and_load("locked_rotation");
+        let physics = Physics::new(&scene);
+        assert_eq!(physics.bodies.alive_count(), 2);
+        for body in physics.bodies.iter() {
+            assert!(!body.x_rotation_locked);
+            assert!(body.y_rotation_locked);
+            assert!(!body.z_rotation_locked);
+        }
+
+        test.undo();
+        test.undo();
+        assert!(!test.editor_scene.physics.bodies[body].y_rotation_locked);
     }
 
-    pub fn local_rotations(&self, graph: &Graph) -> Vec<UnitQuaternion<f32>> {
-        let mut rotations = Vec::new();
-        for &handle in self.nodes.iter() {
-            rotations.push(**graph[handle].local_transform().rotation());
+    #[test]
+    fn test_drop_to_floor() {
+        let mut test = TestScene::new();
+        // Top of the floor is at zero height.
+        let physics = &mut test.editor_scene.physics;
+        let floor = physics.bodies.spawn(RigidBody {
+            position: Vector3::new(0.0, -1.0, 0.0),
+            ..Default::default()
+        });
+        let floor_collider = physics.colliders.spawn(Collider {
+            shape: ColliderShapeDesc::Cuboid(CuboidDesc {
+                half_extents: Vector3::new(10.0, 1.0, 10.0),
+            }),
+            parent: floor.into(),
+            ..Default::default()
+        });
+        physics.bodies[floor].colliders.push(floor_collider.into());
+        // Unit cube, its bottom is 0.5 below its origin.
+        let cube = add_box(&mut test.scene.graph, physics, false);
+        test.scene.graph[cube]
+            .local_transform_mut()
+            .set_position(Vector3::new(0.0, 5.0, 0.0));
+        test.scene.graph.update_hierarchical_data();
+
+        let command = make_drop_to_floor_command(
+            cube,
+            &test.scene.graph,
+            &test.editor_scene.physics,
+            &test.sender,
+        )
+        .unwrap();
+        test.do_command(command);
+        test.scene.graph.update_hierarchical_data();
+        assert!((test.scene.graph[cube].global_position().y - 0.5).abs() < 1.0e-4);
+
+        // Nothing below.
+        test.scene.graph[cube]
+            .local_transform_mut()
+            .set_position(Vector3::new(100.0, 5.0, 0.0));
+        test.scene.graph.update_hierarchical_data();
+        assert!(make_drop_to_floor_command(
+            cube,
+            &test.scene.graph,
+            &test.editor_scene.physics,
+            &test.sender,
+        )
+        .is_none());
+        assert!(test
+            .receiver
+            .try_iter()
+            .any(|message| matches!(message, Message::Log(_))));
+    }
+
+    #[test]
+    fn test_partial_transform_reset() {
+        let mut test = TestScene::new();
+        let node = add_box(&mut test.scene.graph, &mut test.editor_scene.physics, true);
+        let position = Vector3::new(1.0, 2.0, 3.0);
+        let rotation = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), 1.0);
+        let scale = Vector3::new(2.0, 3.0, 4.0);
+        test.scene.graph[node]
+            .local_transform_mut()
+            .set_position(position)
+            .set_rotation(rotation)
+            .set_scale(scale);
+
+        let transform = |test: &TestScene| {
+            let transform = test.scene.graph[node].local_transform();
+            (
+                **transform.position(),
+                **transform.rotation(),
+                **transform.scale(),
+            )
+        };
+        let cases = [
+            (
+                ResetTransformMode::Position,
+                (Vector3::default(), rotation, scale),
+            ),
+            (
+                ResetTransformMode::Rotation,
+                (position, UnitQuaternion::identity(), scale),
+            ),
+            (
+                ResetTransformMode::Scale,
+                (position, rotation, Vector3::new(1.0, 1.0, 1.0)),
+            ),
+            (
+                ResetTransformMode::All,
+                (
+                    Vector3::default(),
+                    UnitQuaternion::identity(),
+                    Vector3::new(1.0, 1.0, 1.0),
+                ),
+            ),
+        ];
+        for &(mode, expected) in cases.iter() {
+            test.do_command(SceneCommand::ResetTransform(ResetTransformCommand::new(
+                node, mode,
+            )));
+            assert_eq!(transform(&test), expected);
+            // Bound body follows the node.
+            let physics = &test.editor_scene.physics;
+            let body = &physics.bodies[*physics.binder.value_of(&node).unwrap()];
+            assert_eq!((body.position, body.rotation), (expected.0, expected.1));
+
+            test.undo();
+            assert_eq!(transform(&test), (position, rotation, scale));
         }
-        rotations
     }
 
-    pub fn local_scales(&self, graph: &Graph) -> Vec<Vector3<f32>> {
-        let mut scales = Vec::new();
-        for &handle in self.nodes.iter() {
-            scales.push(**graph[handle].local_transform().scale());
+    #[test]
+    fn test_emitter_type_conversion() {
+        let mut test = TestScene::new();
+        let emitter = SphereEmitterBuilder::new(BaseEmitterBuilder::new().with_spawn_rate(42))
+            .with_radius(3.0)
+            .build();
+        let particle_system = ParticleSystemBuilder::new(BaseBuilder::new())
+            .with_emitters(vec![emitter])
+            .build(&mut test.scene.graph);
+        let emitter = |test: &TestScene| -> Emitter {
+            test.scene.graph[particle_system]
+                .as_particle_system()
+                .emitters[0]
+                .clone()
+        };
+
+        for &kind in [EmitterKind::Box, EmitterKind::Cylinder].iter() {
+            test.do_command(SceneCommand::SetEmitterType(SetEmitterTypeCommand::new(
+                particle_system,
+                0,
+                kind,
+            )));
+            assert_eq!(emitter(&test).spawn_rate(), 42);
+        }
+        assert!(matches!(emitter(&test), Emitter::Cylinder(_)));
+
+        test.undo();
+        assert!(matches!(emitter(&test), Emitter::Box(_)));
+        test.undo();
+        match emitter(&test) {
+            Emitter::Sphere(sphere) => assert_eq!(sphere.radius(), 3.0),
+            _ => panic!("must be a sphere emitter"),
         }
-        scales
     }
-}
 
-/// Creates scene command (command group) which removes current selection in editor's scene.
-/// This is **not** trivial because each node has multiple connections inside engine and
-/// in editor's data model, so we have to thoroughly build command using simple commands.
-pub fn make_delete_selection_command(
-    editor_scene: &EditorScene,
-    engine: &GameEngine,
-) -> SceneCommand {
-    let graph = &engine.scenes[editor_scene.scene].graph;
+    #[test]
+    fn test_collider_parent_is_listed_once() {
+        let mut test = TestScene::new();
+        let physics = &mut test.editor_scene.physics;
+        let first = physics.bodies.spawn(RigidBody::default());
+        let second = physics.bodies.spawn(RigidBody::default());
+        let collider = physics.colliders.spawn(Collider {
+            parent: first.into(),
+            ..Default::default()
+        });
+        physics.bodies[first].colliders.push(collider.into());
 
-    // Graph's root is non-deletable.
-    let mut selection = if let Selection::Graph(selection) = &editor_scene.selection {
-        selection.clone()
-    } else {
-        Default::default()
-    };
-    if let Some(root_position) = selection.nodes.iter().position(|&n| n == graph.get_root()) {
-        selection.nodes.remove(root_position);
-    }
+        let owners = |test: &TestScene| {
+            test.editor_scene
+                .physics
+                .bodies
+                .pair_iter()
+                .flat_map(|(body, b)| {
+                    b.colliders
+                        .iter()
+                        .filter(|&&c| c == ErasedHandle::from(collider))
+                        .map(move |_| body)
+                })
+                .collect::<Vec<_>>()
+        };
 
-    // Change selection first.
-    let mut command_group = CommandGroup::from(vec![SceneCommand::ChangeSelection(
-        ChangeSelectionCommand::new(Default::default(), Selection::Graph(selection.clone())),
-    )]);
+        test.do_command(SceneCommand::SetColliderParent(
+            SetColliderParentCommand::new(collider, second),
+        ));
+        assert_eq!(owners(&test), [second]);
+        assert_eq!(
+            test.editor_scene.physics.colliders[collider].parent,
+            second.into()
+        );
+        test.undo();
+        assert_eq!(owners(&test), [first]);
+        assert_eq!(
+            test.editor_scene.physics.colliders[collider].parent,
+            first.into()
+        );
 
-    // Find sub-graphs to delete - we need to do this because we can end up in situation like this:
-    // A_
-    //   B_      <-
-    //   | C       | these are selected
-    //   | D_    <-
-    //   |   E
-    //   F
-    // In this case we must deleted only node B, there is no need to delete node D separately because
-    // by engine's design when we delete a node, we also delete all its children. So we have to keep
-    // this behaviour in editor too.
+        // Invalid body is rejected.
+        test.do_command(SceneCommand::SetColliderParent(
+            SetColliderParentCommand::new(collider, Handle::NONE),
+        ));
+        assert_eq!(owners(&test), [first]);
+        test.undo();
+        assert_eq!(owners(&test), [first]);
+    }
+
+    #[test]
+    fn test_align_to_tilted_surface() {
+        let mut test = TestScene::new();
+        // Floor slope is tilted around Z axis, its top passes through the origin.
+        let tilt = UnitQuaternion::from_axis_angle(&Vector3::z_axis(), 0.3);
+        let physics = &mut test.editor_scene.physics;
+        let floor = physics.bodies.spawn(RigidBody {
+            position: tilt * Vector3::new(0.0, -1.0, 0.0),
+            rotation: tilt,
+            ..Default::default()
+        });
+        let floor_collider = physics.colliders.spawn(Collider {
+            shape: ColliderShapeDesc::Cuboid(CuboidDesc {
+                half_extents: Vector3::new(10.0, 1.0, 10.0),
+            }),
+            parent: floor.into(),
+            ..Default::default()
+        });
+        physics.bodies[floor].colliders.push(floor_collider.into());
+        let yaw = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), 0.5);
+        let node = add_node_at(&mut test.scene.graph, Vector3::new(0.0, 5.0, 0.0));
+        test.scene.graph[node]
+            .local_transform_mut()
+            .set_rotation(yaw);
+        test.scene.graph.update_hierarchical_data();
 
-    let root_nodes = selection.root_nodes(graph);
+        let command = make_align_to_surface_command(
+            node,
+            &test.scene.graph,
+            &test.editor_scene.physics,
+            false,
+            &test.sender,
+        )
+        .unwrap();
+        test.do_command(command);
+        test.scene.graph.update_hierarchical_data();
+        let rotation = **test.scene.graph[node].local_transform().rotation();
+        // Up axis follows the slope normal, yaw is kept.
+        assert!((rotation * Vector3::y() - tilt * Vector3::y()).norm() < 1.0e-3);
+        assert!(rotation.angle_to(&(tilt * yaw)) < 1.0e-3);
+
+        test.undo();
+        let rotation = **test.scene.graph[node].local_transform().rotation();
+        assert!(rotation.angle_to(&yaw) < 1.0e-6);
+    }
+
+    /// Two unit quads side by side along X, made of four triangles. Vertices are ordered
+    /// row by row: `[(0, 0), (1, 0), (2, 0), (0, 1), (1, 1), (2, 1)]` in XZ plane.
+    fn add_grid_navmesh(test: &mut TestScene) -> (Handle<Navmesh>, Vec<Handle<NavmeshVertex>>) {
+        let mut navmesh = Navmesh::default();
+        let v = (0..6)
+            .map(|i| {
+                navmesh.vertices.spawn(NavmeshVertex {
+                    position: Vector3::new((i % 3) as f32, 0.0, (i / 3) as f32),
+                })
+            })
+            .collect::<Vec<_>>();
+        for &(a, b, c) in &[(0, 1, 4), (0, 4, 3), (1, 2, 5), (1, 5, 4)] {
+            let _ = navmesh.triangles.spawn(NavmeshTriangle {
+                a: v[a],
+                b: v[b],
+                c: v[c],
+                region: 0,
+            });
+        }
+        (test.editor_scene.navmeshes.spawn(navmesh), v)
+    }
 
-    // Delete all associated physics entities in the whole hierarchy starting from root nodes
-    // found above.
-    let mut stack = root_nodes.clone();
-    while let Some(node) = stack.pop() {
-        if let Some(&body) = editor_scene.physics.binder.value_of(&node) {
-            for &collider in editor_scene.physics.bodies[body].colliders.iter() {
-                command_group.push(SceneCommand::DeleteCollider(DeleteColliderCommand::new(
-                    collider.into(),
-                )))
-            }
+    #[test]
+    fn test_crop_navmesh() {
+        let mut test = TestScene::new();
+        let (handle, v) = add_grid_navmesh(&mut test);
+        let left_half = AxisAlignedBoundingBox::from_min_max(
+            Vector3::new(-0.1, -1.0, -0.1),
+            Vector3::new(1.0, 1.0, 1.1),
+        );
 
-            command_group.push(SceneCommand::DeleteBody(DeleteBodyCommand::new(body)));
+        let command =
+            make_crop_navmesh_command(handle, &test.editor_scene.navmeshes[handle], &left_half)
+                .unwrap();
+        test.do_command(command);
+        let navmesh = &test.editor_scene.navmeshes[handle];
+        assert_eq!(navmesh.triangles.alive_count(), 2);
+        // Right column of vertices is not used by any triangle anymore.
+        assert_eq!(navmesh.vertices.alive_count(), 4);
+        assert!(!navmesh.vertices.is_valid_handle(v[2]));
+        assert!(!navmesh.vertices.is_valid_handle(v[5]));
+        assert!(navmesh.vertices.is_valid_handle(v[1]));
+        assert!(navmesh.vertices.is_valid_handle(v[4]));
+
+        // Everything is inside, nothing to crop.
+        assert!(make_crop_navmesh_command(handle, navmesh, &navmesh.bounds()).is_none());
+
+        test.undo();
+        let navmesh = &test.editor_scene.navmeshes[handle];
+        assert_eq!(navmesh.triangles.alive_count(), 4);
+        assert_eq!(navmesh.vertices.alive_count(), 6);
+        assert!(v.iter().all(|&v| navmesh.vertices.is_valid_handle(v)));
+    }
+
+    #[test]
+    fn test_set_scene_skybox() {
+        let mut test = TestScene::new();
+        let skybox = |texture: &Texture| SkyBox {
+            front: Some(texture.clone()),
+            ..Default::default()
+        };
+        let first = Texture::new_render_target(1, 1);
+        let second = Texture::new_render_target(1, 1);
+
+        // Editor camera does not count.
+        assert!(make_set_scene_skybox_command(
+            &test.editor_scene,
+            &test.scene.graph,
+            Some(skybox(&first)),
+            &test.sender,
+        )
+        .is_none());
+        assert!(test
+            .receiver
+            .try_iter()
+            .any(|message| matches!(message, Message::Log(_))));
+
+        let camera = CameraBuilder::new(BaseBuilder::new()).build(&mut test.scene.graph);
+        let front = |test: &TestScene| {
+            test.scene.graph[camera]
+                .as_camera()
+                .skybox_ref()
+                .and_then(|skybox| skybox.front.clone())
+        };
+        for texture in [&first, &second].iter() {
+            let command = make_set_scene_skybox_command(
+                &test.editor_scene,
+                &test.scene.graph,
+                Some(skybox(texture)),
+                &test.sender,
+            )
+            .unwrap();
+            test.do_command(command);
+            assert_eq!(front(&test), Some((*texture).clone()));
+        }
 
-            // Remove any associated joints.
-            let joint = editor_scene.physics.find_joint(body);
-            if joint.is_some() {
-                command_group.push(SceneCommand::DeleteJoint(DeleteJointCommand::new(joint)));
-            }
+        // Prior sky box is restored, then cleared.
+        test.undo();
+        assert_eq!(front(&test), Some(first.clone()));
+        test.undo();
+        assert!(test.scene.graph[camera].as_camera().skybox_ref().is_none());
+    }
+
+    #[test]
+    fn test_split_navmesh_vertex() {
+        let mut test = TestScene::new();
+        let (handle, v) = add_grid_navmesh(&mut test);
+        let navmesh = &test.editor_scene.navmeshes[handle];
+        let triangle_with = |a: Handle<NavmeshVertex>, b: Handle<NavmeshVertex>| {
+            navmesh
+                .triangles
+                .pair_iter()
+                .find(|(_, t)| t.vertices().contains(&a) && t.vertices().contains(&b))
+                .map(|(h, _)| h)
+                .unwrap()
+        };
+        // Both triangles share middle vertex of the top row.
+        let left = triangle_with(v[0], v[4]);
+        let right = triangle_with(v[5], v[4]);
+
+        test.do_command(SceneCommand::SplitNavmeshVertex(
+            SplitNavmeshVertexCommand::new(handle, v[4], vec![right]),
+        ));
+        let navmesh = &test.editor_scene.navmeshes[handle];
+        assert_eq!(navmesh.vertices.alive_count(), 7);
+        assert!(navmesh.triangles[left].vertices().contains(&v[4]));
+        assert!(!navmesh.triangles[right].vertices().contains(&v[4]));
+        let duplicate = navmesh.triangles[right]
+            .vertices()
+            .iter()
+            .copied()
+            .find(|vertex| !v.contains(vertex))
+            .unwrap();
+        assert_eq!(
+            navmesh.vertices[duplicate].position,
+            navmesh.vertices[v[4]].position
+        );
 
-            // Also check if this node is attached to a joint as
-            // "connected body".
-            for (handle, joint) in editor_scene.physics.joints.pair_iter() {
-                if joint.body2 == ErasedHandle::from(body) {
-                    command_group.push(SceneCommand::SetJointConnectedBody(
-                        SetJointConnectedBodyCommand::new(handle, ErasedHandle::none()),
-                    ));
-                }
-            }
+        test.undo();
+        let navmesh = &test.editor_scene.navmeshes[handle];
+        assert_eq!(navmesh.vertices.alive_count(), 6);
+        assert!(navmesh.triangles[right].vertices().contains(&v[4]));
+
+        // Redo puts the same duplicate back.
+        test.redo();
+        let navmesh = &test.editor_scene.navmeshes[handle];
+        assert!(navmesh.triangles[right].vertices().contains(&duplicate));
+    }
+
+    #[test]
+    fn test_body_initial_velocity() {
+        let mut test = TestScene::new();
+        let node = add_box(&mut test.scene.graph, &mut test.editor_scene.physics, true);
+        test.scene.graph[node].set_name("Projectile");
+        let body = *test.editor_scene.physics.binder.value_of(&node).unwrap();
+        let linvel = Vector3::new(0.0, 1.0, 10.0);
+        let angvel = Vector3::new(2.0, 0.0, 0.0);
+        test.do_command(SceneCommand::SetBodyLinearVelocity(
+            SetBodyLinearVelocityCommand::new(body, linvel),
+        ));
+        test.do_command(SceneCommand::SetBodyAngularVelocity(
+            SetBodyAngularVelocityCommand::new(body, angvel),
+        ));
+
+        // Velocities follow the body when it is cloned.
+        test.select(vec![node]);
+        test.editor_scene
+            .copy_selection_to_clipboard(&test.scene.graph);
+        test.do_command(SceneCommand::Paste(PasteCommand::new()));
+        let copy = test
+            .find("Projectile")
+            .into_iter()
+            .find(|&handle| handle != node)
+            .unwrap();
+        let physics = &test.editor_scene.physics;
+        let copy_body = &physics.bodies[*physics.binder.value_of(&copy).unwrap()];
+        assert_eq!(copy_body.linvel, linvel);
+        assert_eq!(copy_body.angvel, angvel);
+
+        let (scene, _) = test.save_and_load("initial_velocity");
+        let physics = Physics::new(&scene);
+        assert_eq!(physics.bodies.alive_count(), 2);
+        for body in physics.bodies.iter() {
+            assert_eq!(body.linvel, linvel);
+            assert_eq!(body.angvel, angvel);
         }
-        stack.extend_from_slice(graph[node].children());
-    }
 
-    for root_node in root_nodes {
-        command_group.push(SceneCommand::DeleteSubGraph(DeleteSubGraphCommand::new(
-            root_node,
-        )));
+        test.undo();
+        test.undo();
+        let original = &test.editor_scene.physics.bodies[body];
+        assert_eq!(original.linvel, linvel);
+        assert_eq!(original.angvel, Vector3::default());
+        test.undo();
+        assert_eq!(
+            test.editor_scene.physics.bodies[body].linvel,
+            Vector3::default()
+        );
     }
 
-    SceneCommand::CommandGroup(command_group)
+    #[test]
+    fn test_flip_navmesh_triangle() {
+        let mut test = TestScene::new();
+        let (handle, _) = add_grid_navmesh(&mut test);
+        let normal = |test: &TestScene, triangle: Handle<NavmeshTriangle>| {
+            let navmesh = &test.editor_scene.navmeshes[handle];
+            let position = |v: Handle<NavmeshVertex>| navmesh.vertices[v].position;
+            let triangle = &navmesh.triangles[triangle];
+            let a = position(triangle.a);
+            (position(triangle.b) - a)
+                .cross(&(position(triangle.c) - a))
+                .normalize()
+        };
+        let triangle = test.editor_scene.navmeshes[handle]
+            .triangles
+            .pair_iter()
+            .next()
+            .map(|(h, _)| h)
+            .unwrap();
+        let original = normal(&test, triangle);
+
+        test.do_command(SceneCommand::FlipNavmeshTriangle(
+            FlipNavmeshTriangleCommand::new(handle, triangle),
+        ));
+        assert!((normal(&test, triangle) + original).norm() < 1.0e-6);
+        test.undo();
+        assert!((normal(&test, triangle) - original).norm() < 1.0e-6);
+
+        // Invalid handle is reported and does nothing, neither on execute nor on undo.
+        test.do_command(SceneCommand::FlipNavmeshTriangle(
+            FlipNavmeshTriangleCommand::new(handle, Handle::NONE),
+        ));
+        assert!(test
+            .receiver
+            .try_iter()
+            .any(|message| matches!(message, Message::Log(_))));
+        test.undo();
+        assert!((normal(&test, triangle) - original).norm() < 1.0e-6);
+    }
 }
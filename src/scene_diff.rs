@@ -0,0 +1,176 @@
+use rg3d::{
+    core::visitor::{Visit, Visitor},
+    scene::{graph::Graph, node::Node, Scene},
+};
+use std::{collections::HashMap, path::Path};
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SceneDiff {
+    /// Names of nodes that exist only in second scene.
+    pub added: Vec<String>,
+    /// Names of nodes that exist only in first scene.
+    pub removed: Vec<String>,
+    /// Names of nodes which local transform (position, rotation or scale) was changed.
+    pub transform_changed: Vec<String>,
+    /// Names of nodes that were attached to other parent.
+    pub reparented: Vec<String>,
+}
+
+impl SceneDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.transform_changed.is_empty()
+            && self.reparented.is_empty()
+    }
+}
+
+fn load_scene(path: &Path) -> Result<Scene, String> {
+    let mut visitor = rg3d::core::futures::executor::block_on(Visitor::load_binary(path))
+        .map_err(|e| format!("Unable to load {}. Reason: {}", path.display(), e))?;
+    let mut scene = Scene::default();
+    scene
+        .visit("Scene", &mut visitor)
+        .map_err(|e| format!("Unable to read {}. Reason: {}", path.display(), e))?;
+    Ok(scene)
+}
+
+/// Groups nodes by name, preserving order of appearance.
+fn nodes_by_name(graph: &Graph) -> HashMap<&str, Vec<&Node>> {
+    let mut map: HashMap<&str, Vec<&Node>> = HashMap::new();
+    let mut stack = vec![graph.get_root()];
+    while let Some(handle) = stack.pop() {
+        let node = &graph[handle];
+        if handle != graph.get_root() {
+            map.entry(node.name()).or_default().push(node);
+        }
+        // Reverse order, so children will be popped in natural order.
+        stack.extend(node.children().iter().rev());
+    }
+    map
+}
+
+fn parent_name(graph: &Graph, node: &Node) -> String {
+    if node.parent().is_some() {
+        graph[node.parent()].name().to_owned()
+    } else {
+        Default::default()
+    }
+}
+
+/// Loads two saved scenes and reports how second one differs from first one. Nodes of
+/// the scenes are matched by their names, nodes with the same name are matched in order
+/// of appearance in the graph.
+pub fn diff_scenes<P: AsRef<Path>>(path_a: P, path_b: P) -> Result<SceneDiff, String> {
+    let scene_a = load_scene(path_a.as_ref())?;
+    let scene_b = load_scene(path_b.as_ref())?;
+
+    let nodes_a = nodes_by_name(&scene_a.graph);
+    let nodes_b = nodes_by_name(&scene_b.graph);
+
+    let mut diff = SceneDiff::default();
+
+    for (&name, list_a) in nodes_a.iter() {
+        let list_b = nodes_b.get(name).map(|l| l.as_slice()).unwrap_or_default();
+
+        for (i, &a) in list_a.iter().enumerate() {
+            match list_b.get(i) {
+                Some(&b) => {
+                    let (ta, tb) = (a.local_transform(), b.local_transform());
+                    if ta.position() != tb.position()
+                        || ta.rotation() != tb.rotation()
+                        || ta.scale() != tb.scale()
+                    {
+                        diff.transform_changed.push(name.to_owned());
+                    }
+
+                    if parent_name(&scene_a.graph, a) != parent_name(&scene_b.graph, b) {
+                        diff.reparented.push(name.to_owned());
+                    }
+                }
+                None => diff.removed.push(name.to_owned()),
+            }
+        }
+    }
+
+    for (&name, list_b) in nodes_b.iter() {
+        let count_a = nodes_a.get(name).map_or(0, |l| l.len());
+        for _ in count_a..list_b.len() {
+            diff.added.push(name.to_owned());
+        }
+    }
+
+    diff.added.sort();
+    diff.removed.sort();
+    diff.transform_changed.sort();
+    diff.reparented.sort();
+
+    Ok(diff)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rg3d::{
+        core::{algebra::Vector3, pool::Handle},
+        scene::{base::BaseBuilder, transform::TransformBuilder},
+    };
+    use std::path::PathBuf;
+
+    fn save_scene(mut scene: Scene, name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "rusty_editor_diff_{}_{}.rgs",
+            name,
+            std::process::id()
+        ));
+        let mut visitor = Visitor::new();
+        scene.visit("Scene", &mut visitor).unwrap();
+        visitor.save_binary(&path).unwrap();
+        path
+    }
+
+    fn make_node(graph: &mut Graph, name: &str, position: Vector3<f32>) -> Handle<Node> {
+        BaseBuilder::new()
+            .with_name(name)
+            .with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(position)
+                    .build(),
+            )
+            .build(graph)
+    }
+
+    #[test]
+    fn test_diff_scenes() {
+        let mut a = Scene::new();
+        make_node(&mut a.graph, "Moved", Vector3::default());
+        make_node(&mut a.graph, "Removed", Vector3::default());
+        make_node(&mut a.graph, "Parent", Vector3::default());
+        make_node(&mut a.graph, "Child", Vector3::default());
+
+        let mut b = Scene::new();
+        make_node(&mut b.graph, "Moved", Vector3::new(1.0, 0.0, 0.0));
+        make_node(&mut b.graph, "Added", Vector3::default());
+        let parent = make_node(&mut b.graph, "Parent", Vector3::default());
+        let child = make_node(&mut b.graph, "Child", Vector3::default());
+        b.graph.link_nodes(child, parent);
+
+        let path_a = save_scene(a, "a");
+        let path_b = save_scene(b, "b");
+        let diff = diff_scenes(&path_a, &path_b).unwrap();
+        let same = diff_scenes(&path_a, &path_a).unwrap();
+        std::fs::remove_file(&path_a).unwrap();
+        std::fs::remove_file(&path_b).unwrap();
+
+        assert_eq!(
+            diff,
+            SceneDiff {
+                added: vec!["Added".to_owned()],
+                removed: vec!["Removed".to_owned()],
+                transform_changed: vec!["Moved".to_owned()],
+                reparented: vec!["Child".to_owned()],
+            }
+        );
+        assert!(same.is_empty());
+    }
+}
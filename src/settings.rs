@@ -1,7 +1,7 @@
 use crate::STARTUP_WORKING_DIR;
 use crate::{
     gui::{BuildContext, Ui, UiMessage, UiNode},
-    scene::EditorScene,
+    scene::{EditorScene, SceneCommand, SetSceneAmbientColorCommand, TransformSpace},
     GameEngine, Message,
 };
 use rg3d::{
@@ -45,6 +45,7 @@ pub struct SettingsWindow {
     show_physics: Handle<UiNode>,
     show_bounds: Handle<UiNode>,
     show_tbn: Handle<UiNode>,
+    world_space_gizmo: Handle<UiNode>,
 }
 
 #[derive(Deserialize, Serialize, PartialEq, Clone)]
@@ -55,6 +56,36 @@ pub struct Settings {
     pub show_tbn: bool,
     pub z_near: f32,
     pub z_far: f32,
+    // Settings saved before gizmo space was added do not have it.
+    #[serde(default)]
+    pub transform_space: TransformSpace,
+    // Max amount of commands in undo history, `None` means no limit.
+    #[serde(default)]
+    pub max_undo_depth: Option<usize>,
+    // Periodic autosave of current scene, `None` disables it.
+    #[serde(default)]
+    pub autosave: Option<AutosaveSettings>,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Clone)]
+pub struct AutosaveSettings {
+    /// Time between autosaves in seconds, see `interval`.
+    pub interval: f32,
+    /// Amount of most recent autosaves to keep, older ones are removed.
+    pub keep: usize,
+    /// Directory where autosaves are written, see `EditorScene::autosave`.
+    pub dir: PathBuf,
+}
+
+impl AutosaveSettings {
+    /// Shortest time between autosaves in seconds, it keeps the editor from writing the
+    /// scene every frame when the settings file has zero (or negative) interval.
+    pub const MIN_INTERVAL: f32 = 10.0;
+
+    /// Returns time between autosaves in seconds, clamped to `MIN_INTERVAL`.
+    pub fn interval(&self) -> f32 {
+        self.interval.max(Self::MIN_INTERVAL)
+    }
 }
 
 impl Default for Settings {
@@ -66,6 +97,9 @@ impl Default for Settings {
             show_tbn: false,
             z_near: 0.025,
             z_far: 128.0,
+            transform_space: TransformSpace::Local,
+            max_undo_depth: None,
+            autosave: None,
         }
     }
 }
@@ -145,6 +179,7 @@ impl SettingsWindow {
         let show_physics;
         let show_bounds;
         let show_tbn;
+        let world_space_gizmo;
         let ctx = &mut engine.user_interface.build_ctx();
         let text =
             "Here you can select graphics settings to improve performance and/or to understand how \
@@ -266,6 +301,15 @@ impl SettingsWindow {
                                         show_tbn =
                                             make_bool_input_field(ctx, 10, settings.show_tbn);
                                         show_tbn
+                                    })
+                                    .with_child(make_text_mark(ctx, "World Space Gizmo", 11))
+                                    .with_child({
+                                        world_space_gizmo = make_bool_input_field(
+                                            ctx,
+                                            11,
+                                            settings.transform_space == TransformSpace::World,
+                                        );
+                                        world_space_gizmo
                                     }),
                             )
                             .add_row(Row::strict(25.0))
@@ -279,6 +323,7 @@ impl SettingsWindow {
                             .add_row(Row::strict(25.0))
                             .add_row(Row::strict(25.0))
                             .add_row(Row::strict(25.0))
+                            .add_row(Row::strict(25.0))
                             .add_row(Row::stretch())
                             .add_row(Row::stretch())
                             .add_column(Column::strict(120.0))
@@ -339,6 +384,7 @@ impl SettingsWindow {
             show_physics,
             show_bounds,
             show_tbn,
+            world_space_gizmo,
         }
     }
 
@@ -383,6 +429,10 @@ impl SettingsWindow {
         sync_check_box(self.show_physics, settings.show_physics);
         sync_check_box(self.show_tbn, settings.show_tbn);
         sync_check_box(self.show_bounds, settings.show_bounds);
+        sync_check_box(
+            self.world_space_gizmo,
+            settings.transform_space == TransformSpace::World,
+        );
     }
 
     pub fn handle_message(
@@ -415,6 +465,12 @@ impl SettingsWindow {
                     settings.show_tbn = value;
                 } else if message.destination() == self.show_physics {
                     settings.show_physics = value;
+                } else if message.destination() == self.world_space_gizmo {
+                    settings.transform_space = if value {
+                        TransformSpace::World
+                    } else {
+                        TransformSpace::Local
+                    };
                 }
             }
             UiMessageData::ColorField(msg)
@@ -423,7 +479,13 @@ impl SettingsWindow {
                 // TODO: Should not be here!
                 if message.destination() == self.ambient_color {
                     if let ColorFieldMessage::Color(color) = *msg {
-                        engine.scenes[editor_scene.scene].ambient_lighting_color = color;
+                        if engine.scenes[editor_scene.scene].ambient_lighting_color != color {
+                            self.sender
+                                .send(Message::DoSceneCommand(SceneCommand::SetSceneAmbientColor(
+                                    SetSceneAmbientColorCommand::new(color),
+                                )))
+                                .unwrap();
+                        }
                     }
                 }
             }
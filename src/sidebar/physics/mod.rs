@@ -1,10 +1,10 @@
 use crate::{
     gui::{BuildContext, Ui, UiMessage, UiNode},
-    physics::{Collider, Joint, RigidBody},
+    physics::{convert_collider_shape, default_collider_shape, Collider, Joint, RigidBody},
     scene::{
         AddJointCommand, CommandGroup, DeleteBodyCommand, DeleteColliderCommand,
         DeleteJointCommand, EditorScene, SceneCommand, Selection, SetBallRadiusCommand,
-        SetBodyCommand, SetColliderCommand, SetColliderPositionCommand,
+        SetBodyCommand, SetColliderCommand, SetColliderPositionCommand, SetColliderShapeCommand,
         SetCuboidHalfExtentsCommand, SetCylinderHalfHeightCommand, SetCylinderRadiusCommand,
     },
     send_sync_message,
@@ -39,10 +39,8 @@ use rg3d::{
         graph::Graph,
         node::Node,
         physics::{
-            BallDesc, BallJointDesc, BodyStatusDesc, CapsuleDesc, ColliderShapeDesc, ConeDesc,
-            CuboidDesc, CylinderDesc, FixedJointDesc, HeightfieldDesc, JointParamsDesc,
-            PrismaticJointDesc, RevoluteJointDesc, RoundCylinderDesc, SegmentDesc, TriangleDesc,
-            TrimeshDesc,
+            BallJointDesc, BodyStatusDesc, ColliderShapeDesc, FixedJointDesc, JointParamsDesc,
+            PrismaticJointDesc, RevoluteJointDesc,
         },
     },
 };
@@ -627,88 +625,28 @@ impl PhysicsSection {
             };
 
             if can_switch {
-                let collider = match index {
-                    0 => Collider {
-                        shape: ColliderShapeDesc::Ball(BallDesc { radius: 0.5 }),
-                        ..Default::default()
-                    },
-                    1 => Collider {
-                        shape: ColliderShapeDesc::Cylinder(CylinderDesc {
-                            half_height: 0.5,
-                            radius: 0.5,
-                        }),
-                        ..Default::default()
-                    },
-                    2 => Collider {
-                        shape: ColliderShapeDesc::RoundCylinder(RoundCylinderDesc {
-                            half_height: 0.5,
-                            radius: 0.5,
-                            border_radius: 0.1,
-                        }),
-                        ..Default::default()
-                    },
-                    3 => Collider {
-                        shape: ColliderShapeDesc::Cone(ConeDesc {
-                            half_height: 0.5,
-                            radius: 0.5,
-                        }),
-                        ..Default::default()
-                    },
-                    4 => Collider {
-                        shape: ColliderShapeDesc::Cuboid(CuboidDesc {
-                            half_extents: Vector3::new(0.5, 0.5, 0.5),
-                        }),
-                        ..Default::default()
-                    },
-                    5 => Collider {
-                        shape: ColliderShapeDesc::Capsule(CapsuleDesc {
-                            begin: Vector3::new(0.0, 0.0, 0.0),
-                            end: Vector3::new(0.0, 1.0, 0.0),
-                            radius: 0.5,
-                        }),
-                        ..Default::default()
-                    },
-                    6 => Collider {
-                        shape: ColliderShapeDesc::Segment(SegmentDesc {
-                            begin: Vector3::new(0.0, 0.0, 0.0),
-                            end: Vector3::new(1.0, 0.0, 0.0),
-                        }),
-                        ..Default::default()
-                    },
-                    7 => Collider {
-                        shape: ColliderShapeDesc::Triangle(TriangleDesc {
-                            a: Vector3::new(0.0, 0.0, 0.0),
-                            b: Vector3::new(1.0, 0.0, 0.0),
-                            c: Vector3::new(1.0, 0.0, 1.0),
-                        }),
-                        ..Default::default()
-                    },
-                    8 => Collider {
-                        shape: ColliderShapeDesc::Trimesh(TrimeshDesc),
-                        ..Default::default()
-                    },
-                    9 => Collider {
-                        shape: ColliderShapeDesc::Heightfield(HeightfieldDesc),
-                        ..Default::default()
-                    },
-                    _ => unreachable!(),
-                };
-                let mut commands = Vec::new();
                 // For now only one collider per body is supported.
                 // It is easy to add more.
-                if let Some(&first_collider) = editor_scene.physics.bodies[body].colliders.first() {
-                    commands.push(SceneCommand::DeleteCollider(DeleteColliderCommand::new(
+                let command = if let Some(&first_collider) =
+                    editor_scene.physics.bodies[body].colliders.first()
+                {
+                    // Keep other properties of the collider, change only its shape.
+                    let shape = convert_collider_shape(
+                        &editor_scene.physics.colliders[first_collider.into()].shape,
+                        index,
+                    );
+                    SceneCommand::SetColliderShape(SetColliderShapeCommand::new(
                         first_collider.into(),
-                    )))
-                }
-                commands.push(SceneCommand::SetCollider(SetColliderCommand::new(
-                    body, collider,
-                )));
-                self.sender
-                    .send(Message::DoSceneCommand(SceneCommand::CommandGroup(
-                        CommandGroup::from(commands),
-                    )))
-                    .unwrap();
+                        shape,
+                    ))
+                } else {
+                    let collider = Collider {
+                        shape: default_collider_shape(index),
+                        ..Default::default()
+                    };
+                    SceneCommand::SetCollider(SetColliderCommand::new(body, collider))
+                };
+                self.sender.send(Message::DoSceneCommand(command)).unwrap();
             }
         }
     }
@@ -5,8 +5,8 @@ use crate::{
     },
     load_image,
     scene::{
-        ChangeSelectionCommand, EditorScene, GraphSelection, LinkNodesCommand, SceneCommand,
-        Selection, SetVisibleCommand,
+        ChangeSelectionCommand, EditorScene, GraphSelection, LinkNodesKeepTransformCommand,
+        SceneCommand, Selection, SetVisibleCommand,
     },
     send_sync_message, GameEngine, Message,
 };
@@ -743,9 +743,11 @@ impl WorldOutliner {
 
                         if attach {
                             self.sender
-                                .send(Message::DoSceneCommand(SceneCommand::LinkNodes(
-                                    LinkNodesCommand::new(child, parent),
-                                )))
+                                .send(Message::DoSceneCommand(
+                                    SceneCommand::LinkNodesKeepTransform(
+                                        LinkNodesKeepTransformCommand::new(child, parent),
+                                    ),
+                                ))
                                 .unwrap();
                         }
                     }